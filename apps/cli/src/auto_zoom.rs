@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use cap_project::{RecordingMeta, ZoomMode, generate_zoom_segments_for_project};
+use cap_rendering::ProjectRecordingsMeta;
+use clap::Args;
+
+#[derive(Args)]
+pub struct AutoZoom {
+    /// Path to the '.cap' project to add zoom segments to
+    project_path: PathBuf,
+    /// Replace the project's existing zoom segments
+    #[arg(long)]
+    replace: bool,
+    /// Print the zoom segments without saving them to the project
+    #[arg(long)]
+    dry_run: bool,
+    /// Print the zoom segments as JSON instead of one per line
+    #[arg(long)]
+    json: bool,
+}
+
+impl AutoZoom {
+    pub async fn run(self) -> Result<(), String> {
+        let meta = RecordingMeta::load_for_project(&self.project_path)
+            .map_err(|e| format!("Failed to load recording meta: {e}"))?;
+        let Some(studio_meta) = meta.studio_meta() else {
+            return Err("Auto zoom only works with studio recordings".to_string());
+        };
+
+        let recordings = ProjectRecordingsMeta::new(&meta.project_path, studio_meta)
+            .map_err(|e| format!("Failed to load recordings: {e}"))?;
+
+        let mut config = meta.project_config();
+        let timeline = config
+            .timeline
+            .get_or_insert_with(|| recordings.default_timeline());
+
+        if !timeline.zoom_segments.is_empty() && !self.replace && !self.dry_run {
+            return Err(format!(
+                "'{}' already has zoom segments, pass --replace to overwrite them",
+                self.project_path.display()
+            ));
+        }
+
+        let zoom_segments = generate_zoom_segments_for_project(&meta, recordings.duration());
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&zoom_segments).unwrap());
+        } else if zoom_segments.is_empty() {
+            println!("No cursor activity to zoom in on");
+        } else {
+            for segment in &zoom_segments {
+                let focus = match segment.mode {
+                    ZoomMode::Auto => "follow cursor".to_string(),
+                    ZoomMode::Manual { x, y } => format!("focus {x:.2},{y:.2}"),
+                };
+                println!(
+                    "{:>8.2}s - {:>8.2}s  {:.2}x  {focus}",
+                    segment.start, segment.end, segment.amount
+                );
+            }
+        }
+
+        if self.dry_run {
+            return Ok(());
+        }
+
+        timeline.zoom_segments = zoom_segments;
+        config
+            .write(&self.project_path)
+            .map_err(|e| format!("Failed to save project config: {e}"))?;
+
+        Ok(())
+    }
+}
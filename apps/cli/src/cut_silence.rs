@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+
+use cap_project::{RecordingMeta, SilenceCutSettings};
+use cap_rendering::ProjectRecordingsMeta;
+use clap::Args;
+
+#[derive(Args)]
+pub struct CutSilence {
+    /// Path to the '.cap' project to cut pauses from
+    project_path: PathBuf,
+    /// Loudness in dBFS below which the microphone counts as silent
+    #[arg(
+        long,
+        default_value_t = SilenceCutSettings::default().threshold_db,
+        allow_negative_numbers = true
+    )]
+    threshold_db: f32,
+    /// Shortest pause, in seconds, that gets cut
+    #[arg(long, default_value_t = SilenceCutSettings::default().min_duration)]
+    min_duration: f64,
+    /// Seconds of each pause to keep next to speech
+    #[arg(long, default_value_t = SilenceCutSettings::default().padding)]
+    padding: f64,
+    /// Speed pauses up by this factor instead of removing them
+    #[arg(long)]
+    speed: Option<f64>,
+    /// Print the new timeline segments without saving them to the project
+    #[arg(long)]
+    dry_run: bool,
+    /// Print the new timeline segments as JSON instead of one per line
+    #[arg(long)]
+    json: bool,
+}
+
+impl CutSilence {
+    pub async fn run(self) -> Result<(), String> {
+        let settings = SilenceCutSettings {
+            threshold_db: self.threshold_db,
+            min_duration: self.min_duration,
+            padding: self.padding,
+            speed: self.speed,
+        };
+        settings.validate()?;
+
+        let meta = RecordingMeta::load_for_project(&self.project_path)
+            .map_err(|e| format!("Failed to load recording meta: {e}"))?;
+        let Some(studio_meta) = meta.studio_meta() else {
+            return Err("Silence cutting only works with studio recordings".to_string());
+        };
+
+        let recordings = ProjectRecordingsMeta::new(&meta.project_path, studio_meta)
+            .map_err(|e| format!("Failed to load recordings: {e}"))?;
+        let mic_audio = cap_editor::load_mic_audio(&meta, studio_meta)?;
+        if mic_audio.iter().all(Option::is_none) {
+            return Err("The recording has no microphone audio to find pauses in".to_string());
+        }
+
+        let mut config = meta.project_config();
+        let mic_offsets = cap_editor::mic_offsets(&config, studio_meta);
+        let timeline = config
+            .timeline
+            .get_or_insert_with(|| recordings.default_timeline());
+
+        let previous_duration = timeline.duration();
+        let segments =
+            cap_editor::silence_cuts(&mic_audio, &mic_offsets, &timeline.segments, &settings);
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&segments).unwrap());
+        } else {
+            for segment in &segments {
+                println!(
+                    "clip {:>2}  {:>8.2}s - {:>8.2}s  {:.2}x",
+                    segment.recording_clip, segment.start, segment.end, segment.timescale
+                );
+            }
+        }
+
+        let duration = segments.iter().map(|s| s.duration()).sum::<f64>();
+        eprintln!("Duration: {previous_duration:.2}s -> {duration:.2}s");
+
+        if self.dry_run {
+            return Ok(());
+        }
+
+        if !timeline.zoom_segments.is_empty()
+            || !timeline.scene_segments.is_empty()
+            || !timeline.mask_segments.is_empty()
+            || !timeline.text_segments.is_empty()
+        {
+            eprintln!(
+                "Zoom, scene, mask and text segments keep their times and may need adjusting"
+            );
+        }
+
+        timeline.segments = segments;
+        config
+            .write(&self.project_path)
+            .map_err(|e| format!("Failed to save project config: {e}"))?;
+
+        Ok(())
+    }
+}
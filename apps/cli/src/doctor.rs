@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+use cap_project::{repair_project, validate_project};
+use clap::Args;
+
+#[derive(Args)]
+pub struct Doctor {
+    /// Path to the '.cap' project to check
+    project_path: PathBuf,
+    /// Fix the problems that can be fixed by dropping broken references, and save the project
+    #[arg(long)]
+    repair: bool,
+    /// Print the issues as JSON instead of one per line
+    #[arg(long)]
+    json: bool,
+}
+
+impl Doctor {
+    pub async fn run(self) -> Result<(), String> {
+        let report = if self.repair {
+            repair_project(&self.project_path)
+                .map_err(|e| format!("Failed to save repaired project: {e}"))?
+        } else {
+            validate_project(&self.project_path)
+        };
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        } else if report.issues.is_empty() {
+            println!("No problems found");
+        } else {
+            for issue in &report.issues {
+                println!("{issue}");
+            }
+        }
+
+        if report.has_errors() {
+            return Err(format!(
+                "'{}' has errors that need fixing",
+                self.project_path.display()
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+use std::{
+    io::{Write, stdout},
+    path::PathBuf,
+};
+
+use cap_export::{
+    ExportOutput, ExportRange, ExporterBase, ExporterBuilder,
+    audio::{AudioExportFormat, AudioExportSettings},
+    copy_sidecars,
+    gif::{GifExportSettings, GifQuality},
+    loudness::LoudnessTarget,
+    mp4::{ExportCodec, ExportCompression, Mp4ExportSettings},
+    webm::WebMExportSettings,
+};
+use cap_project::XY;
+use clap::{Args, ValueEnum};
+use serde_json::json;
+use tracing::*;
+
+#[derive(Args)]
+pub struct Export {
+    /// Path to the '.cap' project to export
+    project_path: PathBuf,
+    /// Path to copy the exported file to
+    output_path: Option<PathBuf>,
+    /// Container format to export
+    #[arg(long, value_enum, default_value_t = ExportFormat::Mp4)]
+    format: ExportFormat,
+    /// Frames per second to render at (default: 60 for mp4 and webm, 30 for gif)
+    #[arg(long)]
+    fps: Option<u32>,
+    /// Width of the resolution the output is scaled to fit
+    #[arg(long, default_value_t = 1920)]
+    width: u32,
+    /// Height of the resolution the output is scaled to fit
+    #[arg(long, default_value_t = 1080)]
+    height: u32,
+    /// Compression preset for mp4 and webm exports
+    #[arg(long, value_enum, default_value_t = Compression::Maximum)]
+    compression: Compression,
+    /// Video codec for mp4 exports
+    #[arg(long, value_enum, default_value_t = Codec::H264)]
+    codec: Codec,
+    /// Custom bits per pixel for mp4 and webm exports, overrides --compression
+    #[arg(long)]
+    bpp: Option<f32>,
+    /// Encoding quality for gif exports, from 1-100
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=100))]
+    gif_quality: Option<u8>,
+    /// Prioritize encoding speed over quality for gif exports
+    #[arg(long)]
+    gif_fast: bool,
+    /// Decode recordings with FFmpeg instead of the platform decoder
+    #[arg(long)]
+    force_ffmpeg_decoder: bool,
+    /// Timeline time in seconds to start exporting from
+    #[arg(long)]
+    start: Option<f64>,
+    /// Timeline time in seconds to stop exporting at
+    #[arg(long)]
+    end: Option<f64>,
+    /// Normalize the audio to this integrated loudness in LUFS, e.g. -16 for podcasts or -14 for YouTube
+    #[arg(long, allow_negative_numbers = true)]
+    loudness: Option<f64>,
+    /// True peak ceiling in dBTP when normalizing with --loudness (default: -1)
+    #[arg(long, allow_negative_numbers = true, requires = "loudness")]
+    true_peak: Option<f64>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    Mp4,
+    Webm,
+    Gif,
+    Wav,
+    M4a,
+    Opus,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Compression {
+    Maximum,
+    Social,
+    Web,
+    Potato,
+}
+
+impl From<Compression> for ExportCompression {
+    fn from(value: Compression) -> Self {
+        match value {
+            Compression::Maximum => Self::Maximum,
+            Compression::Social => Self::Social,
+            Compression::Web => Self::Web,
+            Compression::Potato => Self::Potato,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Codec {
+    H264,
+    Hevc,
+}
+
+impl From<Codec> for ExportCodec {
+    fn from(value: Codec) -> Self {
+        match value {
+            Codec::H264 => Self::H264,
+            Codec::Hevc => Self::Hevc,
+        }
+    }
+}
+
+impl Export {
+    pub async fn run(self) -> Result<(), String> {
+        let builder = ExporterBase::builder(self.project_path.clone())
+            .with_force_ffmpeg_decoder(self.force_ffmpeg_decoder)
+            .with_range(ExportRange {
+                start: self.start,
+                end: self.end,
+            });
+
+        let resolution_base = XY::new(self.width, self.height);
+        let loudness = self.loudness.map(|integrated_lufs| LoudnessTarget {
+            integrated_lufs,
+            true_peak_db: self
+                .true_peak
+                .unwrap_or(LoudnessTarget::PODCAST.true_peak_db),
+        });
+
+        let exporter_output = match self.format {
+            ExportFormat::Mp4 => {
+                let exporter_base = build_video(builder).await?;
+                let settings = Mp4ExportSettings {
+                    fps: self.fps.unwrap_or(60),
+                    resolution_base,
+                    compression: self.compression.into(),
+                    custom_bpp: self.bpp,
+                    force_ffmpeg_decoder: self.force_ffmpeg_decoder,
+                    codec: self.codec.into(),
+                    loudness,
+                };
+                let total_frames = exporter_base.total_frames(settings.fps);
+
+                settings
+                    .export(exporter_base, progress_reporter(total_frames))
+                    .await
+            }
+            ExportFormat::Webm => {
+                let exporter_base = build_video(builder).await?;
+                let settings = WebMExportSettings {
+                    fps: self.fps.unwrap_or(60),
+                    resolution_base,
+                    compression: self.compression.into(),
+                    custom_bpp: self.bpp,
+                    force_ffmpeg_decoder: self.force_ffmpeg_decoder,
+                    loudness,
+                };
+                let total_frames = exporter_base.total_frames(settings.fps);
+
+                settings
+                    .export(exporter_base, progress_reporter(total_frames))
+                    .await
+            }
+            ExportFormat::Gif => {
+                let exporter_base = build_video(builder).await?;
+                let settings = GifExportSettings {
+                    fps: self.fps.unwrap_or(GifExportSettings::default().fps),
+                    resolution_base,
+                    quality: (self.gif_quality.is_some() || self.gif_fast).then_some(GifQuality {
+                        quality: self.gif_quality,
+                        fast: Some(self.gif_fast),
+                    }),
+                };
+                let total_frames = exporter_base.total_frames(settings.fps);
+
+                settings
+                    .export(exporter_base, progress_reporter(total_frames))
+                    .await
+                    .map(|path| ExportOutput {
+                        path,
+                        loudness: None,
+                        sidecars: vec![],
+                    })
+            }
+            ExportFormat::Wav | ExportFormat::M4a | ExportFormat::Opus => {
+                let exporter_base = builder
+                    .build_audio()
+                    .await
+                    .map_err(|v| format!("Exporter build error: {v}"))?;
+                let settings = AudioExportSettings {
+                    format: match self.format {
+                        ExportFormat::Wav => AudioExportFormat::Wav,
+                        ExportFormat::M4a => AudioExportFormat::M4a,
+                        _ => AudioExportFormat::Opus,
+                    },
+                    loudness,
+                };
+                let total_chunks = exporter_base.total_chunks();
+
+                settings
+                    .export(exporter_base, progress_reporter(total_chunks))
+                    .await
+            }
+        }
+        .map_err(|v| format!("Exporter error: {v}"))?;
+
+        let output_path = if let Some(output_path) = self.output_path {
+            std::fs::copy(&exporter_output.path, &output_path).map_err(|e| {
+                format!("Failed to copy export to '{}': {e}", output_path.display())
+            })?;
+            copy_sidecars(
+                &exporter_output.path,
+                &exporter_output.sidecars,
+                &output_path,
+            )?;
+            output_path
+        } else {
+            exporter_output.path
+        };
+
+        println!(
+            "{}",
+            json!({ "output_path": output_path, "loudness": exporter_output.loudness })
+        );
+
+        info!("Exported to '{}'", output_path.display());
+
+        Ok(())
+    }
+}
+
+async fn build_video(builder: ExporterBuilder) -> Result<ExporterBase, String> {
+    builder
+        .build()
+        .await
+        .map_err(|v| format!("Exporter build error: {v}"))
+}
+
+/// Prints a JSON line of `{"frame", "total"}` to stdout for every rendered frame,
+/// or every chunk of audio for audio-only exports.
+fn progress_reporter(total_frames: u32) -> impl FnMut(u32) -> bool + Send + 'static {
+    let mut stdout = stdout();
+
+    move |frame_index| {
+        let line = json!({
+            "frame": (frame_index + 1).min(total_frames),
+            "total": total_frames,
+        });
+
+        writeln!(stdout, "{line}").is_ok() && stdout.flush().is_ok()
+    }
+}
@@ -1,17 +1,21 @@
+mod auto_zoom;
+mod cut_silence;
+mod doctor;
+mod export;
 mod record;
+mod recover;
+mod upload;
 
-use std::{
-    io::{Write, stdout},
-    path::PathBuf,
-};
-
-use cap_export::ExporterBase;
-use cap_project::XY;
+use auto_zoom::AutoZoom;
 use clap::{Args, Parser, Subcommand};
+use cut_silence::CutSilence;
+use doctor::Doctor;
+use export::Export;
 use record::RecordStart;
+use recover::Recover;
 use serde_json::json;
-use tracing::*;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use upload::Upload;
 
 #[derive(Parser)]
 struct Cli {
@@ -21,10 +25,20 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Export a '.cap' project to an mp4 file
+    /// Add zoom segments around the clicks and cursor movement in a '.cap' project
+    AutoZoom(AutoZoom),
+    /// Cut or speed up the pauses in a '.cap' project's microphone audio
+    CutSilence(CutSilence),
+    /// Check a '.cap' project for missing files and broken references
+    Doctor(Doctor),
+    /// Export a '.cap' project to a video, gif or audio file
     Export(Export),
     /// Start a recording or list available capture targets and devices
     Record(RecordArgs),
+    /// List, recover or discard recordings that didn't finish, such as after a crash
+    Recover(Recover),
+    /// Upload an exported '.cap' project or MP4 file and print its share link
+    Upload(Upload),
 }
 
 #[derive(Args)]
@@ -62,18 +76,44 @@ async fn main() -> Result<(), String> {
         .with(
             tracing_subscriber::fmt::layer()
                 .with_ansi(true)
-                .with_target(true),
+                .with_target(true)
+                .with_writer(std::io::stderr),
         )
         .init();
 
     let cli = Cli::parse();
 
     match cli.command {
+        Commands::AutoZoom(z) => {
+            if let Err(e) = z.run().await {
+                eprint!("Auto zoom failed: {e}")
+            }
+        }
+        Commands::CutSilence(c) => {
+            if let Err(e) = c.run().await {
+                eprint!("Cutting silence failed: {e}")
+            }
+        }
+        Commands::Doctor(d) => {
+            if let Err(e) = d.run().await {
+                eprint!("{e}")
+            }
+        }
         Commands::Export(e) => {
             if let Err(e) = e.run().await {
                 eprint!("Export failed: {e}")
             }
         }
+        Commands::Recover(r) => {
+            if let Err(e) = r.run().await {
+                eprint!("Recovery failed: {e}")
+            }
+        }
+        Commands::Upload(u) => {
+            if let Err(e) = u.run().await {
+                eprint!("Upload failed: {e}")
+            }
+        }
         Commands::Record(RecordArgs { command, args }) => match command {
             Some(RecordCommands::Screens) => {
                 let screens = cap_recording::screen_capture::list_displays();
@@ -148,50 +188,6 @@ window {}:
     Ok(())
 }
 
-#[derive(Args)]
-struct Export {
-    project_path: PathBuf,
-    output_path: Option<PathBuf>,
-}
-
-impl Export {
-    async fn run(self) -> Result<(), String> {
-        let exporter_base = ExporterBase::builder(self.project_path)
-            .build()
-            .await
-            .map_err(|v| format!("Exporter build error: {v}"))?;
-
-        let mut stdout = stdout();
-
-        let exporter_output_path = cap_export::mp4::Mp4ExportSettings {
-            fps: 60,
-            resolution_base: XY::new(1920, 1080),
-            compression: cap_export::mp4::ExportCompression::Maximum,
-            custom_bpp: None,
-            force_ffmpeg_decoder: false,
-        }
-        .export(exporter_base, move |_f| {
-            // print!("\rrendered frame {f}");
-
-            stdout.flush().unwrap();
-            true
-        })
-        .await
-        .map_err(|v| format!("Exporter error: {v}"))?;
-
-        let output_path = if let Some(output_path) = self.output_path {
-            std::fs::copy(&exporter_output_path, &output_path).unwrap();
-            output_path
-        } else {
-            exporter_output_path
-        };
-
-        info!("Exported video to '{}'", output_path.display());
-
-        Ok(())
-    }
-}
-
 // fn ffmpeg_callback_experiment() {
 //     unsafe {
 //         unsafe extern "C" fn ffmpeg_log_callback(
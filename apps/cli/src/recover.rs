@@ -0,0 +1,227 @@
+use std::{path::PathBuf, time::Duration};
+
+use cap_recording::recovery::{IncompleteRecording, RecoveryManager};
+use clap::Args;
+use serde_json::json;
+use tracing::*;
+
+#[derive(Args)]
+pub struct Recover {
+    /// Directory containing the '.cap' recordings to scan
+    recordings_dir: PathBuf,
+    /// Only act on these recordings, by project path or directory name (default: all)
+    #[arg(long = "project")]
+    projects: Vec<String>,
+    /// Recover the incomplete recordings into playable projects
+    #[arg(long, conflicts_with = "discard")]
+    recover: bool,
+    /// Delete the incomplete recordings
+    #[arg(long)]
+    discard: bool,
+    /// Print JSON instead of a table
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Action {
+    Recover,
+    Discard,
+}
+
+impl Recover {
+    pub async fn run(self) -> Result<(), String> {
+        if !self.recordings_dir.is_dir() {
+            return Err(format!(
+                "Recordings directory '{}' does not exist",
+                self.recordings_dir.display()
+            ));
+        }
+
+        let recordings_dir = self.recordings_dir.clone();
+        let recordings =
+            tokio::task::spawn_blocking(move || RecoveryManager::find_incomplete(&recordings_dir))
+                .await
+                .map_err(|e| format!("Recovery scan task failed: {e}"))?;
+
+        let recordings = recordings
+            .into_iter()
+            .filter(|recording| self.is_selected(recording))
+            .collect::<Vec<_>>();
+
+        let action = if self.recover {
+            Action::Recover
+        } else if self.discard {
+            Action::Discard
+        } else {
+            self.print_recordings(&recordings);
+            return Ok(());
+        };
+
+        let mut failed = 0;
+        for recording in recordings {
+            let result = tokio::task::spawn_blocking({
+                let recording = recording.clone();
+                move || match action {
+                    Action::Recover => recover(&recording),
+                    Action::Discard => {
+                        RecoveryManager::discard(&recording).map_err(|e| e.to_string())
+                    }
+                }
+            })
+            .await
+            .map_err(|e| format!("Recovery task failed: {e}"))
+            .and_then(|result| result);
+
+            let status = match (&result, action) {
+                (Err(_), _) => "failed",
+                (Ok(()), Action::Recover) => "recovered",
+                (Ok(()), Action::Discard) => "discarded",
+            };
+            let path = recording.project_path.display();
+
+            if self.json {
+                println!(
+                    "{}",
+                    json!({
+                        "project_path": recording.project_path,
+                        "status": status,
+                        "error": result.as_ref().err(),
+                    })
+                );
+            } else {
+                match &result {
+                    Ok(()) => println!("{status} {path}"),
+                    Err(e) => println!("{status} {path}: {e}"),
+                }
+            }
+
+            if let Err(e) = result {
+                error!("Failed to process {path}: {e}");
+                failed += 1;
+            }
+        }
+
+        if failed > 0 {
+            return Err(format!("{failed} recording(s) could not be processed"));
+        }
+
+        Ok(())
+    }
+
+    fn is_selected(&self, recording: &IncompleteRecording) -> bool {
+        self.projects.is_empty()
+            || self.projects.iter().any(|project| {
+                PathBuf::from(project) == recording.project_path
+                    || recording
+                        .project_path
+                        .file_name()
+                        .is_some_and(|name| name.to_string_lossy() == *project)
+            })
+    }
+
+    fn print_recordings(&self, recordings: &[IncompleteRecording]) {
+        if self.json {
+            let recordings = recordings
+                .iter()
+                .map(|recording| {
+                    json!({
+                        "project_path": recording.project_path,
+                        "pretty_name": recording.meta.pretty_name,
+                        "estimated_duration_secs": recording.estimated_duration.as_secs_f64(),
+                        "segments": recording
+                            .recoverable_segments
+                            .iter()
+                            .map(|segment| json!({
+                                "index": segment.index,
+                                "estimated_duration_secs": segment.estimated_duration().as_secs_f64(),
+                                "display_fragments": segment.display_fragments.len(),
+                                "camera": segment.camera_fragments.is_some(),
+                                "mic": segment.mic_fragments.is_some(),
+                                "system_audio": segment.system_audio_fragments.is_some(),
+                                "cursor": segment.cursor_path.is_some(),
+                            }))
+                            .collect::<Vec<_>>(),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            println!("{}", serde_json::to_string_pretty(&recordings).unwrap());
+            return;
+        }
+
+        if recordings.is_empty() {
+            println!("No incomplete recordings found");
+            return;
+        }
+
+        print_row("NAME", "SEGMENTS", "DURATION", "PATH");
+        for recording in recordings {
+            print_row(
+                &recording.meta.pretty_name,
+                &recording.recoverable_segments.len().to_string(),
+                &format_duration(recording.estimated_duration),
+                &recording.project_path.display().to_string(),
+            );
+
+            for segment in &recording.recoverable_segments {
+                let mut sources = vec!["display"];
+                if segment.camera_fragments.is_some() {
+                    sources.push("camera");
+                }
+                if segment.mic_fragments.is_some() {
+                    sources.push("mic");
+                }
+                if segment.system_audio_fragments.is_some() {
+                    sources.push("system audio");
+                }
+                if segment.cursor_path.is_some() {
+                    sources.push("cursor");
+                }
+
+                print_row(
+                    &format!("  segment {}", segment.index),
+                    "",
+                    &format_duration(segment.estimated_duration()),
+                    &sources.join(", "),
+                );
+            }
+        }
+    }
+}
+
+/// Recovers a recording the same way the desktop app does, including its screenshot.
+fn recover(recording: &IncompleteRecording) -> Result<(), String> {
+    if recording.recoverable_segments.is_empty() {
+        return Err("No recoverable segments found".to_string());
+    }
+
+    let recovered = RecoveryManager::recover(recording).map_err(|e| e.to_string())?;
+
+    if let Err(e) = recovered.save_screenshot() {
+        warn!("Failed to create screenshot during recovery: {e}");
+    }
+
+    Ok(())
+}
+
+/// Prints a row of the recordings table, truncating the name so the columns stay aligned.
+fn print_row(name: &str, segments: &str, duration: &str, last: &str) {
+    const NAME_WIDTH: usize = 40;
+
+    let name = if name.chars().count() > NAME_WIDTH {
+        format!("{}…", name.chars().take(NAME_WIDTH - 1).collect::<String>())
+    } else {
+        name.to_string()
+    };
+
+    println!(
+        "{name:<width$} {segments:>8} {duration:>10}  {last}",
+        width = NAME_WIDTH
+    );
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
@@ -0,0 +1,207 @@
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+use cap_api::{
+    Client, CreateVideo, MultipartUpload, PresignedS3PutRequest, PresignedS3PutRequestMethod,
+    S3VideoMeta, build_video_meta, from_file_to_chunks,
+};
+use cap_project::{RecordingMeta, SharingMeta, UploadMeta};
+use clap::Args;
+use futures::{TryStreamExt, stream};
+use serde_json::json;
+use tracing::*;
+
+const DEFAULT_SERVER_URL: &str = "https://cap.so";
+const SCREENSHOT_PATH: &str = "screenshots/display.jpg";
+
+#[derive(Args)]
+pub struct Upload {
+    /// Path to the '.cap' project or MP4 file to upload
+    path: PathBuf,
+    /// URL of the Cap server to upload to, for self-hosted deployments
+    #[arg(long, env = "CAP_SERVER_URL", default_value = DEFAULT_SERVER_URL)]
+    server_url: String,
+    /// API key to authenticate with the server
+    #[arg(long, env = "CAP_API_KEY", hide_env_values = true)]
+    api_key: String,
+    /// Name of the video (default: the project's name, or the file name)
+    #[arg(long)]
+    name: Option<String>,
+    /// ID of the organization to share the video with
+    #[arg(long)]
+    org_id: Option<String>,
+}
+
+impl Upload {
+    pub async fn run(self) -> Result<(), String> {
+        let mut project_meta = if self.path.is_dir() {
+            Some(
+                RecordingMeta::load_for_project(&self.path)
+                    .map_err(|e| format!("Failed to load project meta: {e}"))?,
+            )
+        } else {
+            None
+        };
+
+        let file_path = project_meta
+            .as_ref()
+            .map(|meta| meta.output_path())
+            .unwrap_or_else(|| self.path.clone());
+
+        if !file_path.exists() {
+            return Err(format!(
+                "Rendered video not found at '{}', export the project first",
+                file_path.display()
+            ));
+        }
+
+        let name = self.name.clone().or_else(|| match &project_meta {
+            Some(meta) => Some(meta.pretty_name.clone()),
+            None => file_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned()),
+        });
+
+        let video_meta = build_video_meta(&file_path)?;
+
+        let client = Client::new(&self.server_url)
+            .with_auth_token(self.api_key)
+            // self-hosted servers still use the legacy upload API
+            .with_content_md5(self.server_url.trim_end_matches('/') != DEFAULT_SERVER_URL);
+
+        let s3_config = client
+            .create_or_get_video(CreateVideo {
+                // re-uploading a project replaces the video behind its existing share link
+                video_id: project_meta
+                    .as_ref()
+                    .and_then(|meta| meta.sharing.as_ref())
+                    .map(|sharing| sharing.id.clone()),
+                name,
+                meta: Some(video_meta.clone()),
+                organization_id: self.org_id,
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| format!("Failed to create video: {e}"))?;
+
+        let screenshot_path = project_meta
+            .as_ref()
+            .map(|meta| meta.project_path.join(SCREENSHOT_PATH));
+
+        if let Some(meta) = &mut project_meta {
+            meta.upload = Some(UploadMeta::SinglePartUpload {
+                video_id: s3_config.id.clone(),
+                recording_dir: meta.project_path.clone(),
+                file_path: file_path.clone(),
+                screenshot_path: meta.project_path.join(SCREENSHOT_PATH),
+            });
+            save_meta(meta);
+        }
+
+        let result = upload_video(
+            &client,
+            &s3_config.id,
+            &file_path,
+            screenshot_path.as_deref(),
+            video_meta,
+        )
+        .await;
+
+        let link = client.share_link(&s3_config.id);
+
+        if let Some(meta) = &mut project_meta {
+            match &result {
+                Ok(()) => {
+                    meta.upload = Some(UploadMeta::Complete);
+                    meta.sharing = Some(SharingMeta {
+                        id: s3_config.id.clone(),
+                        link: link.clone(),
+                    });
+                }
+                Err(error) => {
+                    meta.upload = Some(UploadMeta::Failed {
+                        error: error.clone(),
+                    });
+                }
+            }
+            save_meta(meta);
+        }
+
+        result?;
+
+        println!("{}", json!({ "id": s3_config.id, "link": link }));
+
+        info!("Uploaded to '{link}'");
+
+        Ok(())
+    }
+}
+
+/// Uploads the video in parts, printing a JSON line of `{"uploaded", "total"}` bytes to stdout
+/// after each part, followed by the project's screenshot as the video's thumbnail.
+async fn upload_video(
+    client: &Client,
+    video_id: &str,
+    file_path: &Path,
+    screenshot_path: Option<&Path>,
+    meta: S3VideoMeta,
+) -> Result<(), String> {
+    let upload = MultipartUpload::initiate(client.clone(), video_id)
+        .await
+        .map_err(|e| format!("Failed to initiate upload: {e}"))?;
+
+    let mut uploaded = 0;
+    let parts = upload
+        .upload_parts(from_file_to_chunks(file_path.to_path_buf()))
+        .inspect_ok(|part| {
+            uploaded += part.size as u64;
+            println!(
+                "{}",
+                json!({ "uploaded": uploaded, "total": part.total_size })
+            );
+        })
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(|e| format!("Failed to upload video: {e}"))?;
+
+    upload
+        .complete(parts, Some(meta))
+        .await
+        .map_err(|e| format!("Failed to complete upload: {e}"))?;
+
+    let Some(screenshot_path) = screenshot_path.filter(|path| path.exists()) else {
+        return Ok(());
+    };
+
+    // the video is already viewable, so a missing thumbnail isn't worth failing over
+    let thumbnail = match tokio::fs::read(screenshot_path).await {
+        Ok(thumbnail) => thumbnail,
+        Err(e) => {
+            warn!("Failed to read screenshot: {e}");
+            return Ok(());
+        }
+    };
+
+    client
+        .singlepart_upload(
+            PresignedS3PutRequest {
+                video_id: video_id.to_string(),
+                subpath: "screenshot/screen-capture.jpg".to_string(),
+                method: PresignedS3PutRequestMethod::Put,
+                meta: None,
+            },
+            thumbnail.len() as u64,
+            stream::once(async move { Ok(Bytes::from(thumbnail)) }),
+        )
+        .await
+        .map_err(|e| warn!("Failed to upload thumbnail: {e}"))
+        .ok();
+
+    Ok(())
+}
+
+fn save_meta(meta: &RecordingMeta) {
+    meta.save_for_project()
+        .map_err(|e| error!("Failed to save recording meta: {e}"))
+        .ok();
+}
@@ -20,7 +20,11 @@ use tokio::sync::Mutex;
 use tracing::instrument;
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
+use cap_project::{
+    CaptionFormat, CaptionsData, ParseStrictness, RecordingMeta, RecordingMetaInner,
+};
 pub use cap_project::{CaptionSegment, CaptionSettings, CaptionWord};
+use cap_rendering::ProjectRecordingsMeta;
 
 use crate::http_client;
 
@@ -59,6 +63,18 @@ pub async fn save_model_file(path: String, data: Vec<u8>) -> Result<(), String>
     std::fs::write(&path, &data).map_err(|e| format!("Failed to write model file: {e}"))
 }
 
+/// How long each of the project's segments plays for, matching the offsets
+/// `ProjectRecordingsMeta::segment_offsets` gives them.
+fn segment_durations(project_path: &std::path::Path) -> Option<Vec<f64>> {
+    let meta = RecordingMeta::load_for_project(project_path).ok()?;
+    let RecordingMetaInner::Studio(studio_meta) = &meta.inner else {
+        return None;
+    };
+
+    let recordings = ProjectRecordingsMeta::new(&meta.project_path, studio_meta).ok()?;
+    Some(recordings.segments.iter().map(|s| s.duration()).collect())
+}
+
 async fn extract_audio_from_video(video_path: &str, output_path: &PathBuf) -> Result<(), String> {
     log::info!("=== EXTRACT AUDIO START ===");
     log::info!("Attempting to extract audio from: {video_path}");
@@ -98,17 +114,25 @@ async fn extract_audio_from_video(video_path: &str, output_path: &PathBuf) -> Re
                 push_source(segment["mic"]["path"].as_str());
                 push_source(segment["audio"]["path"].as_str());
 
-                if !sources.is_empty() {
-                    segment_audios.push(SegmentAudio { sources });
-                }
+                segment_audios.push(SegmentAudio { sources });
             }
         }
 
-        if segment_audios.is_empty() {
+        if segment_audios
+            .iter()
+            .all(|segment| segment.sources.is_empty())
+        {
             return Err("No audio sources found in the recording metadata".to_string());
         }
 
-        log::info!("Found {} segments with audio sources", segment_audios.len());
+        log::info!("Found {} segments", segment_audios.len());
+
+        // Captions are mapped back to segments with `ProjectRecordingsMeta::segment_offsets`,
+        // so each segment's audio is padded or trimmed to the length it plays for there.
+        let segment_durations = segment_durations(base_path);
+        if segment_durations.is_none() {
+            log::warn!("Failed to load segment durations, caption times may drift across segments");
+        }
 
         let mut final_samples: Vec<f32> = Vec::new();
 
@@ -150,6 +174,14 @@ async fn extract_audio_from_video(video_path: &str, output_path: &PathBuf) -> Re
                 }
             }
 
+            if let Some(duration) = segment_durations
+                .as_ref()
+                .and_then(|durations| durations.get(segment_idx))
+            {
+                let sample_count = (duration * AudioData::SAMPLE_RATE as f64).round() as usize;
+                segment_samples.resize(sample_count, 0.0);
+            }
+
             if !segment_samples.is_empty() {
                 log::info!(
                     "Segment {} produced {} samples, appending to final audio",
@@ -1308,6 +1340,45 @@ pub async fn load_captions(
     }
 }
 
+/// Imports an SRT or WebVTT file as the video's captions, replacing any existing segments.
+#[tauri::command]
+#[specta::specta]
+#[instrument(skip(app))]
+pub async fn import_captions(
+    app: AppHandle,
+    video_id: String,
+    path: PathBuf,
+) -> Result<CaptionData, String> {
+    let format = match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("srt") => CaptionFormat::Srt,
+        Some(ext) if ext.eq_ignore_ascii_case("vtt") => CaptionFormat::WebVtt,
+        _ => return Err("Only .srt and .vtt files can be imported".to_string()),
+    };
+
+    let contents = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read subtitle file: {e}"))?;
+
+    let imported = CaptionsData::parse_subtitles(&contents, format, ParseStrictness::Tolerant)
+        .map_err(|e| format!("Failed to parse subtitle file: {e}"))?;
+    tracing::info!("Imported {} caption segments", imported.segments.len());
+
+    let settings = load_captions(app.clone(), video_id.clone())
+        .await
+        .ok()
+        .flatten()
+        .and_then(|captions| captions.settings);
+
+    let captions = CaptionData {
+        segments: imported.segments,
+        settings: Some(settings.unwrap_or(imported.settings)),
+    };
+
+    save_captions(app, video_id, captions.clone()).await?;
+
+    Ok(captions)
+}
+
 fn app_captions_dir(app: &AppHandle, video_id: &str) -> Result<PathBuf, String> {
     tracing::info!("Getting captions directory for video_id: {}", video_id);
 
@@ -1426,39 +1497,45 @@ pub async fn delete_whisper_model(model_path: String) -> Result<(), String> {
     Ok(())
 }
 
-fn captions_to_srt(captions: &CaptionData) -> String {
-    let mut srt = String::new();
-    for (i, segment) in captions.segments.iter().enumerate() {
-        let start_time = format_srt_time(f64::from(segment.start));
-        let end_time = format_srt_time(f64::from(segment.end));
+/// Maps the captions onto the project's timeline, so exported captions line up with the edited
+/// video. Falls back to the raw recording times if the project can't be loaded.
+fn captions_on_timeline(project_path: PathBuf, captions: CaptionsData) -> Vec<CaptionSegment> {
+    let meta = match RecordingMeta::load_for_project(&project_path) {
+        Ok(meta) => meta,
+        Err(e) => {
+            tracing::warn!("Failed to load project, exporting captions unedited: {e}");
+            return captions.to_timeline(None, &[0.0]);
+        }
+    };
 
-        srt.push_str(&format!(
-            "{}\n{} --> {}\n{}\n\n",
-            i + 1,
-            start_time,
-            end_time,
-            segment.text.trim()
-        ));
-    }
-    srt
-}
+    let RecordingMetaInner::Studio(studio_meta) = &meta.inner else {
+        return captions.to_timeline(None, &[0.0]);
+    };
+
+    let clip_offsets = match ProjectRecordingsMeta::new(&meta.project_path, studio_meta) {
+        Ok(recordings) => recordings.segment_offsets(),
+        Err(e) => {
+            tracing::warn!("Failed to load recordings, exporting captions unedited: {e}");
+            return captions.to_timeline(None, &[0.0]);
+        }
+    };
 
-fn format_srt_time(seconds: f64) -> String {
-    let hours = (seconds / 3600.0) as i32;
-    let minutes = ((seconds % 3600.0) / 60.0) as i32;
-    let secs = (seconds % 60.0) as i32;
-    let millis = ((seconds % 1.0) * 1000.0) as i32;
-    format!("{hours:02}:{minutes:02}:{secs:02},{millis:03}")
+    captions.to_timeline(meta.project_config().timeline.as_ref(), &clip_offsets)
 }
 
 #[tauri::command]
 #[specta::specta]
 #[instrument(skip(app))]
-pub async fn export_captions_srt(
+pub async fn export_captions(
     app: AppHandle,
     video_id: String,
+    format: CaptionFormat,
 ) -> Result<Option<PathBuf>, String> {
-    tracing::info!("Starting SRT export for video_id: {}", video_id);
+    tracing::info!(
+        "Starting {:?} caption export for video_id: {}",
+        format,
+        video_id
+    );
 
     let captions = match load_captions(app.clone(), video_id.clone()).await? {
         Some(c) => {
@@ -1471,32 +1548,44 @@ pub async fn export_captions_srt(
         }
     };
 
-    let captions_with_settings = CaptionData {
+    let captions = CaptionsData {
         segments: captions.segments,
-        settings: captions
-            .settings
-            .or_else(|| Some(CaptionSettings::default())),
+        settings: captions.settings.unwrap_or_default(),
     };
 
-    tracing::info!("Converting captions to SRT format");
-    let srt_content = captions_to_srt(&captions_with_settings);
+    let project_path = PathBuf::from(&video_id);
+    let segments =
+        tokio::task::spawn_blocking(move || captions_on_timeline(project_path, captions))
+            .await
+            .map_err(|e| format!("Failed to map captions onto the timeline: {e}"))?;
+    let content = format.serialize(&segments);
 
     let captions_dir = app_captions_dir(&app, &video_id)?;
-    let srt_path = captions_dir.join("captions.srt");
-    tracing::info!("Will write SRT file to: {:?}", srt_path);
+    let path = captions_dir.join(format!("captions.{}", format.extension()));
+    tracing::info!("Will write captions file to: {:?}", path);
 
-    match std::fs::write(&srt_path, srt_content) {
+    match std::fs::write(&path, content) {
         Ok(_) => {
-            tracing::info!("Successfully wrote SRT file to: {:?}", srt_path);
-            Ok(Some(srt_path))
+            tracing::info!("Successfully wrote captions file to: {:?}", path);
+            Ok(Some(path))
         }
         Err(e) => {
-            tracing::error!("Failed to write SRT file: {}", e);
-            Err(format!("Failed to write SRT file: {e}"))
+            tracing::error!("Failed to write captions file: {}", e);
+            Err(format!("Failed to write captions file: {e}"))
         }
     }
 }
 
+#[tauri::command]
+#[specta::specta]
+#[instrument(skip(app))]
+pub async fn export_captions_srt(
+    app: AppHandle,
+    video_id: String,
+) -> Result<Option<PathBuf>, String> {
+    export_captions(app, video_id, CaptionFormat::Srt).await
+}
+
 fn convert_to_mono(samples: &[f32], channels: usize) -> Vec<f32> {
     if channels == 1 {
         return samples.to_vec();
@@ -1,6 +1,6 @@
 use crate::editor_window::WindowEditorInstance;
 use crate::{FramesRendered, get_video_metadata};
-use cap_export::ExporterBase;
+use cap_export::{ExportOutput, ExporterBase};
 use cap_project::{RecordingMeta, XY};
 use cap_rendering::{
     FrameRenderer, ProjectRecordingsMeta, ProjectUniforms, RenderSegment, RenderVideoConstants,
@@ -36,7 +36,7 @@ async fn do_export(
     settings: &ExportSettings,
     progress: &tauri::ipc::Channel<FramesRendered>,
     force_ffmpeg: bool,
-) -> Result<PathBuf, String> {
+) -> Result<ExportOutput, String> {
     let exporter_base = ExporterBase::builder(project_path.to_path_buf())
         .with_force_ffmpeg_decoder(force_ffmpeg)
         .build()
@@ -76,6 +76,11 @@ async fn do_export(
                         .is_ok()
                 })
                 .await
+                .map(|path| ExportOutput {
+                    path,
+                    loudness: None,
+                    sidecars: vec![],
+                })
         }
     }
 }
@@ -92,7 +97,7 @@ pub async fn export_video(
     project_path: PathBuf,
     progress: tauri::ipc::Channel<FramesRendered>,
     settings: ExportSettings,
-) -> Result<PathBuf, String> {
+) -> Result<ExportOutput, String> {
     let force_ffmpeg = match &settings {
         ExportSettings::Mp4(s) => s.force_ffmpeg_decoder,
         ExportSettings::Gif(_) => false,
@@ -101,9 +106,9 @@ pub async fn export_video(
     let result = do_export(&project_path, &settings, &progress, force_ffmpeg).await;
 
     match result {
-        Ok(path) => {
-            info!("Exported to {} completed", path.display());
-            Ok(path)
+        Ok(output) => {
+            info!("Exported to {} completed", output.path.display());
+            Ok(output)
         }
         Err(e) if !force_ffmpeg && is_frame_decode_error(&e) => {
             info!(
@@ -114,12 +119,12 @@ pub async fn export_video(
             let retry_result = do_export(&project_path, &settings, &progress, true).await;
 
             match retry_result {
-                Ok(path) => {
+                Ok(output) => {
                     info!(
                         "Export succeeded with FFmpeg decoder fallback: {}",
-                        path.display()
+                        output.path.display()
                     );
-                    Ok(path)
+                    Ok(output)
                 }
                 Err(retry_e) => {
                     sentry::capture_message(&retry_e, sentry::Level::Error);
@@ -134,6 +139,20 @@ pub async fn export_video(
     }
 }
 
+/// Copies an export's sidecar files next to `dst`, where the video itself has been saved.
+#[tauri::command]
+#[specta::specta]
+#[instrument]
+pub async fn copy_export_sidecars(
+    video: PathBuf,
+    sidecars: Vec<PathBuf>,
+    dst: PathBuf,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || cap_export::copy_sidecars(&video, &sidecars, &dst))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
 #[derive(Debug, serde::Serialize, specta::Type)]
 pub struct ExportEstimates {
     pub duration_seconds: f64,
@@ -170,7 +189,7 @@ pub async fn get_export_estimates(
 
     let (estimated_size_mb, time_factor) = match &settings {
         ExportSettings::Mp4(mp4_settings) => {
-            let bits_per_pixel = mp4_settings.compression.bits_per_pixel() as f64;
+            let bits_per_pixel = mp4_settings.effective_bpp() as f64;
             let video_bitrate = total_pixels * bits_per_pixel * fps_f64;
             let audio_bitrate = 192_000.0;
             let total_bitrate = video_bitrate + audio_bitrate;
@@ -115,6 +115,8 @@ pub struct GeneralSettingsStore {
     #[serde(default)]
     pub auto_zoom_on_clicks: bool,
     #[serde(default)]
+    pub capture_keystrokes: bool,
+    #[serde(default)]
     pub post_deletion_behaviour: PostDeletionBehaviour,
     #[serde(default = "default_excluded_windows")]
     pub excluded_windows: Vec<WindowExclusion>,
@@ -130,6 +132,8 @@ pub struct GeneralSettingsStore {
     pub max_fps: u32,
     #[serde(default)]
     pub editor_preview_quality: EditorPreviewQuality,
+    #[serde(default = "default_replay_buffer_seconds")]
+    pub replay_buffer_seconds: u32,
 }
 
 fn default_enable_native_camera_preview() -> bool {
@@ -152,6 +156,10 @@ fn default_max_fps() -> u32 {
     60
 }
 
+fn default_replay_buffer_seconds() -> u32 {
+    cap_recording::replay_buffer::DEFAULT_REPLAY_DURATION.as_secs() as u32
+}
+
 fn default_server_url() -> String {
     std::option_env!("VITE_SERVER_URL")
         .unwrap_or("https://cap.so")
@@ -188,6 +196,7 @@ impl Default for GeneralSettingsStore {
             recording_countdown: Some(3),
             enable_native_camera_preview: default_enable_native_camera_preview(),
             auto_zoom_on_clicks: false,
+            capture_keystrokes: false,
             post_deletion_behaviour: PostDeletionBehaviour::DoNothing,
             excluded_windows: default_excluded_windows(),
             delete_instant_recordings_after_upload: false,
@@ -196,6 +205,7 @@ impl Default for GeneralSettingsStore {
             crash_recovery_recording: true,
             max_fps: 60,
             editor_preview_quality: EditorPreviewQuality::Half,
+            replay_buffer_seconds: default_replay_buffer_seconds(),
         }
     }
 }
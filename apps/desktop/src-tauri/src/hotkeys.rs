@@ -165,7 +165,8 @@ async fn handle_hotkey(app: AppHandle, action: HotkeyAction) -> Result<(), Strin
             let next = match current {
                 cap_recording::RecordingMode::Studio => cap_recording::RecordingMode::Instant,
                 cap_recording::RecordingMode::Instant => cap_recording::RecordingMode::Screenshot,
-                cap_recording::RecordingMode::Screenshot => cap_recording::RecordingMode::Studio,
+                cap_recording::RecordingMode::Screenshot => cap_recording::RecordingMode::Replay,
+                cap_recording::RecordingMode::Replay => cap_recording::RecordingMode::Studio,
             };
 
             RecordingSettingsStore::set_mode(&app, next)
@@ -41,8 +41,10 @@ use auth::{AuthStore, Plan};
 use camera::CameraPreviewState;
 use cap_editor::{EditorInstance, EditorState};
 use cap_project::{
-    InstantRecordingMeta, ProjectConfiguration, RecordingMeta, RecordingMetaInner, SharingMeta,
-    StudioRecordingMeta, StudioRecordingStatus, UploadMeta, VideoUploadInfo, XY, ZoomSegment,
+    CaptionWordRef, Chapter, DEFAULT_FILLER_WORDS, InstantRecordingMeta, ProjectConfiguration,
+    RecordingMeta, RecordingMetaInner, SharingMeta, SilenceCutSettings, StudioRecordingMeta,
+    StudioRecordingStatus, TimelineConfiguration, TimelineSegment, TranscriptCut, UploadMeta,
+    VideoUploadInfo, XY, ZoomSegment,
 };
 use cap_recording::{
     RecordingMode,
@@ -100,12 +102,12 @@ use windows::{
     CapWindowId, EditorWindowIds, ScreenshotEditorWindowIds, ShowCapWindow, set_window_transparent,
 };
 
+use crate::{api::build_video_meta, recording::start_recording};
 use crate::{
     camera::CameraPreviewManager,
     recording_settings::{RecordingSettingsStore, RecordingTargetMode},
     upload::InstantMultipartUpload,
 };
-use crate::{recording::start_recording, upload::build_video_meta};
 
 type FinalizingRecordingsMap =
     std::collections::HashMap<PathBuf, (watch::Sender<bool>, watch::Receiver<bool>)>;
@@ -1036,74 +1038,11 @@ pub(crate) async fn create_screenshot(
     output: PathBuf,
     size: Option<(u32, u32)>,
 ) -> Result<(), String> {
-    let result: Result<(), String> = tokio::task::spawn_blocking(move || -> Result<(), String> {
-        let mut ictx = ffmpeg::format::input(&input).map_err(|e| e.to_string())?;
-        let input_stream = ictx
-            .streams()
-            .best(ffmpeg::media::Type::Video)
-            .ok_or("No video stream found")?;
-        let video_stream_index = input_stream.index();
-
-        let mut decoder =
-            ffmpeg::codec::context::Context::from_parameters(input_stream.parameters())
-                .map_err(|e| e.to_string())?
-                .decoder()
-                .video()
-                .map_err(|e| e.to_string())?;
-
-        let mut scaler = ffmpeg::software::scaling::context::Context::get(
-            decoder.format(),
-            decoder.width(),
-            decoder.height(),
-            ffmpeg::format::Pixel::RGB24,
-            size.map_or(decoder.width(), |s| s.0),
-            size.map_or(decoder.height(), |s| s.1),
-            ffmpeg::software::scaling::flag::Flags::BILINEAR,
-        )
-        .map_err(|e| e.to_string())?;
-
-        let mut frame = ffmpeg::frame::Video::empty();
-        for (stream, packet) in ictx.packets() {
-            if stream.index() == video_stream_index {
-                decoder.send_packet(&packet).map_err(|e| e.to_string())?;
-                if decoder.receive_frame(&mut frame).is_ok() {
-                    let mut rgb_frame = ffmpeg::frame::Video::empty();
-                    scaler
-                        .run(&frame, &mut rgb_frame)
-                        .map_err(|e| e.to_string())?;
-
-                    let width = rgb_frame.width() as usize;
-                    let height = rgb_frame.height() as usize;
-                    let bytes_per_pixel = 3;
-                    let src_stride = rgb_frame.stride(0);
-                    let dst_stride = width * bytes_per_pixel;
-
-                    let mut img_buffer = vec![0u8; height * dst_stride];
-
-                    for y in 0..height {
-                        let src_slice =
-                            &rgb_frame.data(0)[y * src_stride..y * src_stride + dst_stride];
-                        let dst_slice = &mut img_buffer[y * dst_stride..(y + 1) * dst_stride];
-                        dst_slice.copy_from_slice(src_slice);
-                    }
-
-                    let img = image::RgbImage::from_raw(width as u32, height as u32, img_buffer)
-                        .ok_or("Failed to create image from frame data")?;
-
-                    img.save_with_format(&output, image::ImageFormat::Jpeg)
-                        .map_err(|e| e.to_string())?;
-
-                    return Ok(());
-                }
-            }
-        }
-
-        Err("Failed to create screenshot".to_string())
+    tokio::task::spawn_blocking(move || {
+        cap_recording::screenshot::save_first_video_frame(&input, &output, size)
     })
     .await
-    .map_err(|e| format!("Task join error: {e}"))?;
-
-    result
+    .map_err(|e| format!("Task join error: {e}"))?
 }
 
 // async fn create_thumbnail(input: PathBuf, output: PathBuf, size: (u32, u32)) -> Result<(), String> {
@@ -1689,6 +1628,107 @@ async fn generate_zoom_segments_from_clicks(
     Ok(zoom_segments)
 }
 
+#[tauri::command]
+#[specta::specta]
+#[instrument(skip(editor_instance))]
+async fn cut_silence(
+    editor_instance: WindowEditorInstance,
+    settings: SilenceCutSettings,
+) -> Result<Vec<TimelineSegment>, String> {
+    settings.validate()?;
+
+    let (segments, mic_offsets) = {
+        let project = editor_instance.project_config.1.borrow();
+        let segments = project
+            .timeline
+            .as_ref()
+            .map(|timeline| timeline.segments.clone())
+            .unwrap_or_else(|| editor_instance.recordings.timeline_segments());
+        let mic_offsets = editor_instance
+            .meta()
+            .studio_meta()
+            .map(|meta| cap_editor::mic_offsets(&project, meta))
+            .unwrap_or_default();
+        (segments, mic_offsets)
+    };
+    let mic_audio = editor_instance
+        .segment_medias
+        .iter()
+        .map(|segment| segment.audio.clone())
+        .collect::<Vec<_>>();
+
+    tokio::task::spawn_blocking(move || {
+        cap_editor::silence_cuts(&mic_audio, &mic_offsets, &segments, &settings)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+#[instrument(skip(editor_instance))]
+async fn find_filler_words(
+    editor_instance: WindowEditorInstance,
+    phrases: Option<Vec<String>>,
+) -> Result<Vec<CaptionWordRef>, String> {
+    let project = editor_instance.project_config.1.borrow();
+    let Some(captions) = &project.captions else {
+        return Ok(vec![]);
+    };
+
+    Ok(match phrases {
+        Some(phrases) => captions.find_phrases(&phrases),
+        None => captions.find_phrases(&DEFAULT_FILLER_WORDS),
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+#[instrument(skip(editor_instance))]
+async fn cut_transcript_words(
+    editor_instance: WindowEditorInstance,
+    words: Vec<CaptionWordRef>,
+) -> Result<TranscriptCut, String> {
+    let project = editor_instance.project_config.1.borrow();
+    let Some(captions) = &project.captions else {
+        return Err("The project has no captions to cut words from".to_string());
+    };
+    let segments = project
+        .timeline
+        .as_ref()
+        .map(|timeline| timeline.segments.clone())
+        .unwrap_or_else(|| editor_instance.recordings.timeline_segments());
+
+    Ok(captions.cut_words(
+        &words,
+        &segments,
+        &editor_instance.recordings.segment_offsets(),
+    ))
+}
+
+/// The chapters a timeline exports with, and where each of its markers lands on it
+#[derive(Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+struct TimelineChapters {
+    chapters: Vec<Chapter>,
+    /// Timeline time of each marker, in the order of `TimelineConfiguration::markers`
+    marker_times: Vec<Option<f64>>,
+}
+
+#[tauri::command]
+#[specta::specta]
+#[instrument(skip(timeline))]
+async fn get_timeline_chapters(timeline: TimelineConfiguration) -> TimelineChapters {
+    TimelineChapters {
+        chapters: timeline.chapters(),
+        marker_times: timeline
+            .markers
+            .iter()
+            .map(|marker| timeline.marker_time(marker))
+            .collect(),
+    }
+}
+
 #[tauri::command]
 #[specta::specta]
 #[instrument]
@@ -2624,6 +2664,7 @@ pub async fn run(recording_logging_handle: LoggingHandle, logs_dir: PathBuf) {
             export::get_export_estimates,
             export::generate_export_preview,
             export::generate_export_preview_fast,
+            export::copy_export_sidecars,
             import::start_video_import,
             import::check_import_ready,
             copy_file_to_path,
@@ -2642,6 +2683,10 @@ pub async fn run(recording_logging_handle: LoggingHandle, logs_dir: PathBuf) {
             set_project_config,
             update_project_config_in_memory,
             generate_zoom_segments_from_clicks,
+            cut_silence,
+            find_filler_words,
+            cut_transcript_words,
+            get_timeline_chapters,
             permissions::open_permission_settings,
             permissions::do_permissions_check,
             permissions::request_permission,
@@ -2688,6 +2733,8 @@ pub async fn run(recording_logging_handle: LoggingHandle, logs_dir: PathBuf) {
             captions::download_whisper_model,
             captions::check_model_exists,
             captions::delete_whisper_model,
+            captions::import_captions,
+            captions::export_captions,
             captions::export_captions_srt,
             target_select_overlay::prewarm_target_select_overlays,
             target_select_overlay::open_target_select_overlays,
@@ -3377,6 +3424,7 @@ async fn resume_uploads(app: AppHandle) -> Result<(), String> {
                             file_path,
                             pre_created_video,
                             recording_dir,
+                            progress: _,
                         } => {
                             InstantMultipartUpload::spawn(
                                 app.clone(),
@@ -1,12 +1,9 @@
 use anyhow::anyhow;
 use cap_fail::fail;
-use cap_project::CursorMoveEvent;
-use cap_project::cursor::SHORT_CURSOR_SHAPE_DEBOUNCE_MS;
 use cap_project::{
-    CameraShape, CursorClickEvent, GlideDirection, InstantRecordingMeta, MultipleSegments,
-    Platform, ProjectConfiguration, RecordingMeta, RecordingMetaInner, SharingMeta,
-    StudioRecordingMeta, StudioRecordingStatus, TimelineConfiguration, TimelineSegment, UploadMeta,
-    ZoomMode, ZoomSegment, cursor::CursorEvents,
+    CameraShape, InstantRecordingMeta, MultipleSegments, Platform, ProjectConfiguration,
+    RecordingMeta, RecordingMetaInner, SharingMeta, StudioRecordingMeta, StudioRecordingStatus,
+    TimelineConfiguration, TimelineSegment, UploadMeta, ZoomSegment,
 };
 use cap_recording::feeds::camera::CameraFeedLock;
 #[cfg(target_os = "macos")]
@@ -16,6 +13,7 @@ use cap_recording::{
     feeds::{camera, microphone},
     instant_recording,
     recovery::RecoveryManager,
+    replay_buffer,
     sources::MicrophoneSourceError,
     sources::{
         screen_capture,
@@ -35,7 +33,6 @@ use std::borrow::Cow;
 use std::error::Error as StdError;
 use std::{
     any::Any,
-    collections::{HashMap, VecDeque},
     panic::AssertUnwindSafe,
     path::{Path, PathBuf},
     str::FromStr,
@@ -55,7 +52,7 @@ use crate::web_api::AuthedApiError;
 use crate::{
     App, CurrentRecordingChanged, FinalizingRecordings, MutableState, NewStudioRecordingAdded,
     RecordingStarted, RecordingState, RecordingStopped, VideoUploadInfo,
-    api::PresignedS3PutRequestMethod,
+    api::{PresignedS3PutRequestMethod, build_video_meta},
     audio::AppSounds,
     auth::AuthStore,
     create_screenshot,
@@ -63,9 +60,7 @@ use crate::{
     open_external_link,
     presets::PresetsStore,
     thumbnails::*,
-    upload::{
-        InstantMultipartUpload, build_video_meta, compress_image, create_or_get_video, upload_video,
-    },
+    upload::{InstantMultipartUpload, compress_image, create_or_get_video, upload_video},
     web_api::ManagerExt,
     windows::{CapWindowId, ShowCapWindow},
 };
@@ -90,6 +85,10 @@ pub enum InProgressRecording {
         common: InProgressRecordingCommon,
         camera_feed: Option<Arc<CameraFeedLock>>,
     },
+    Replay {
+        handle: replay_buffer::ActorHandle,
+        common: InProgressRecordingCommon,
+    },
 }
 
 #[cfg(target_os = "macos")]
@@ -172,6 +171,7 @@ impl InProgressRecording {
         match self {
             Self::Instant { handle, .. } => &handle.capture_target,
             Self::Studio { handle, .. } => &handle.capture_target,
+            Self::Replay { handle, .. } => &handle.capture_target,
         }
     }
 
@@ -179,6 +179,7 @@ impl InProgressRecording {
         match self {
             Self::Instant { common, .. } => &common.inputs,
             Self::Studio { common, .. } => &common.inputs,
+            Self::Replay { common, .. } => &common.inputs,
         }
     }
 
@@ -186,6 +187,7 @@ impl InProgressRecording {
         match self {
             Self::Instant { handle, .. } => handle.pause().await,
             Self::Studio { handle, .. } => handle.pause().await,
+            Self::Replay { .. } => Err(anyhow!("Replay recordings can't be paused")),
         }
     }
 
@@ -193,6 +195,7 @@ impl InProgressRecording {
         match self {
             Self::Instant { handle, .. } => handle.resume().await,
             Self::Studio { handle, .. } => handle.resume().await,
+            Self::Replay { .. } => Err(anyhow!("Replay recordings can't be paused")),
         }
     }
 
@@ -200,6 +203,7 @@ impl InProgressRecording {
         match self {
             Self::Instant { handle, .. } => handle.is_paused().await,
             Self::Studio { handle, .. } => handle.is_paused().await,
+            Self::Replay { .. } => Ok(false),
         }
     }
 
@@ -207,6 +211,7 @@ impl InProgressRecording {
         match self {
             Self::Instant { common, .. } => &common.recording_dir,
             Self::Studio { common, .. } => &common.recording_dir,
+            Self::Replay { common, .. } => &common.recording_dir,
         }
     }
 
@@ -228,6 +233,10 @@ impl InProgressRecording {
                 recording: handle.stop().await?,
                 target_name: common.target_name,
             },
+            Self::Replay { handle, common } => CompletedRecording::Studio {
+                recording: handle.save().await?,
+                target_name: common.target_name,
+            },
         })
     }
 
@@ -235,6 +244,7 @@ impl InProgressRecording {
         match self {
             Self::Instant { handle, .. } => handle.done_fut(),
             Self::Studio { handle, .. } => handle.done_fut(),
+            Self::Replay { handle, .. } => handle.done_fut(),
         }
     }
 
@@ -242,6 +252,7 @@ impl InProgressRecording {
         match self {
             Self::Instant { handle, .. } => handle.cancel().await,
             Self::Studio { handle, .. } => handle.cancel().await,
+            Self::Replay { handle, .. } => handle.cancel().await,
         }
     }
 
@@ -249,6 +260,7 @@ impl InProgressRecording {
         match self {
             Self::Instant { .. } => RecordingMode::Instant,
             Self::Studio { .. } => RecordingMode::Studio,
+            Self::Replay { .. } => RecordingMode::Replay,
         }
     }
 }
@@ -399,6 +411,7 @@ pub fn format_project_name<'a>(
         RecordingMode::Studio => ("Studio", "studio"),
         RecordingMode::Instant => ("Instant", "instant"),
         RecordingMode::Screenshot => ("Screenshot", "screenshot"),
+        RecordingMode::Replay => ("Replay", "replay"),
     };
 
     let result = AC
@@ -539,7 +552,7 @@ pub async fn start_recording(
                 }
             }
         }
-        RecordingMode::Studio => None,
+        RecordingMode::Studio | RecordingMode::Replay => None,
         RecordingMode::Screenshot => return Err("Use take_screenshot for screenshots".to_string()),
     };
 
@@ -548,7 +561,7 @@ pub async fn start_recording(
         project_path: project_file_path.clone(),
         pretty_name: project_name.clone(),
         inner: match inputs.mode {
-            RecordingMode::Studio => {
+            RecordingMode::Studio | RecordingMode::Replay => {
                 RecordingMetaInner::Studio(Box::new(StudioRecordingMeta::MultipleSegments {
                     inner: MultipleSegments {
                         segments: Default::default(),
@@ -737,6 +750,12 @@ pub async fn start_recording(
                                     .map(|s| s.custom_cursor_capture)
                                     .unwrap_or_default(),
                             )
+                            .with_keyboard_capture(
+                                general_settings
+                                    .as_ref()
+                                    .map(|s| s.capture_keystrokes)
+                                    .unwrap_or_default(),
+                            )
                             .with_fragmented(
                                 general_settings
                                     .as_ref()
@@ -830,6 +849,47 @@ pub async fn start_recording(
                                 camera_feed: camera_feed.clone(),
                             })
                         }
+                        RecordingMode::Replay => {
+                            let mut builder = replay_buffer::Actor::builder(
+                                recording_dir.clone(),
+                                inputs.capture_target.clone(),
+                            )
+                            .with_system_audio(inputs.capture_system_audio)
+                            .with_duration(Duration::from_secs(
+                                general_settings
+                                    .as_ref()
+                                    .map(|s| s.replay_buffer_seconds as u64)
+                                    .unwrap_or(replay_buffer::DEFAULT_REPLAY_DURATION.as_secs()),
+                            ))
+                            .with_max_fps(
+                                general_settings.as_ref().map(|s| s.max_fps).unwrap_or(60),
+                            );
+
+                            #[cfg(target_os = "macos")]
+                            {
+                                builder = builder.with_excluded_windows(excluded_windows.clone());
+                            }
+
+                            if let Some(mic_feed) = mic_feed.clone() {
+                                builder = builder.with_mic_feed(mic_feed);
+                            }
+
+                            let handle = builder
+                                .build(
+                                    #[cfg(target_os = "macos")]
+                                    shareable_content.retained(),
+                                )
+                                .await
+                                .map_err(|e| {
+                                    error!("Failed to spawn replay buffer actor: {e:#}");
+                                    e
+                                })?;
+
+                            Ok(InProgressRecording::Replay {
+                                handle,
+                                common: common.clone(),
+                            })
+                        }
                         RecordingMode::Screenshot => Err(anyhow!(
                             "Screenshot mode should be handled via take_screenshot"
                         )),
@@ -1752,270 +1812,6 @@ async fn finalize_studio_recording(
     Ok(())
 }
 
-/// Core logic for generating zoom segments based on mouse click events.
-/// This is an experimental feature that automatically creates zoom effects
-/// around user interactions to highlight important moments.
-fn generate_zoom_segments_from_clicks_impl(
-    mut clicks: Vec<CursorClickEvent>,
-    mut moves: Vec<CursorMoveEvent>,
-    max_duration: f64,
-) -> Vec<ZoomSegment> {
-    const STOP_PADDING_SECONDS: f64 = 0.5;
-    const CLICK_GROUP_TIME_THRESHOLD_SECS: f64 = 2.5;
-    const CLICK_GROUP_SPATIAL_THRESHOLD: f64 = 0.15;
-    const CLICK_PRE_PADDING: f64 = 0.4;
-    const CLICK_POST_PADDING: f64 = 1.8;
-    const MOVEMENT_PRE_PADDING: f64 = 0.3;
-    const MOVEMENT_POST_PADDING: f64 = 1.5;
-    const MERGE_GAP_THRESHOLD: f64 = 0.8;
-    const MIN_SEGMENT_DURATION: f64 = 1.0;
-    const MOVEMENT_WINDOW_SECONDS: f64 = 1.5;
-    const MOVEMENT_EVENT_DISTANCE_THRESHOLD: f64 = 0.02;
-    const MOVEMENT_WINDOW_DISTANCE_THRESHOLD: f64 = 0.08;
-    const AUTO_ZOOM_AMOUNT: f64 = 1.5;
-    const SHAKE_FILTER_THRESHOLD: f64 = 0.33;
-    const SHAKE_FILTER_WINDOW_MS: f64 = 150.0;
-
-    if max_duration <= 0.0 {
-        return Vec::new();
-    }
-
-    let activity_end_limit = if max_duration > STOP_PADDING_SECONDS {
-        max_duration - STOP_PADDING_SECONDS
-    } else {
-        max_duration
-    };
-
-    if activity_end_limit <= f64::EPSILON {
-        return Vec::new();
-    }
-
-    clicks.sort_by(|a, b| {
-        a.time_ms
-            .partial_cmp(&b.time_ms)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-    moves.sort_by(|a, b| {
-        a.time_ms
-            .partial_cmp(&b.time_ms)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-
-    while let Some(index) = clicks.iter().rposition(|c| c.down) {
-        let time_secs = clicks[index].time_ms / 1000.0;
-        if time_secs > activity_end_limit {
-            clicks.remove(index);
-        } else {
-            break;
-        }
-    }
-
-    let click_positions: HashMap<usize, (f64, f64)> = clicks
-        .iter()
-        .enumerate()
-        .filter(|(_, c)| c.down)
-        .filter_map(|(idx, click)| {
-            let click_time = click.time_ms;
-            moves
-                .iter()
-                .rfind(|m| m.time_ms <= click_time)
-                .map(|m| (idx, (m.x, m.y)))
-        })
-        .collect();
-
-    let mut click_groups: Vec<Vec<usize>> = Vec::new();
-    let down_clicks: Vec<(usize, &CursorClickEvent)> = clicks
-        .iter()
-        .enumerate()
-        .filter(|(_, c)| c.down && c.time_ms / 1000.0 < activity_end_limit)
-        .collect();
-
-    for (idx, click) in &down_clicks {
-        let click_time = click.time_ms / 1000.0;
-        let click_pos = click_positions.get(idx);
-
-        let mut found_group = false;
-        for group in click_groups.iter_mut() {
-            let can_join = group.iter().any(|&group_idx| {
-                let group_click = &clicks[group_idx];
-                let group_time = group_click.time_ms / 1000.0;
-                let time_close = (click_time - group_time).abs() < CLICK_GROUP_TIME_THRESHOLD_SECS;
-
-                let spatial_close = match (click_pos, click_positions.get(&group_idx)) {
-                    (Some((x1, y1)), Some((x2, y2))) => {
-                        let dx = x1 - x2;
-                        let dy = y1 - y2;
-                        (dx * dx + dy * dy).sqrt() < CLICK_GROUP_SPATIAL_THRESHOLD
-                    }
-                    _ => true,
-                };
-
-                time_close && spatial_close
-            });
-
-            if can_join {
-                group.push(*idx);
-                found_group = true;
-                break;
-            }
-        }
-
-        if !found_group {
-            click_groups.push(vec![*idx]);
-        }
-    }
-
-    let mut intervals: Vec<(f64, f64)> = Vec::new();
-
-    for group in click_groups {
-        if group.is_empty() {
-            continue;
-        }
-
-        let times: Vec<f64> = group
-            .iter()
-            .map(|&idx| clicks[idx].time_ms / 1000.0)
-            .collect();
-        let group_start = times.iter().cloned().fold(f64::INFINITY, f64::min);
-        let group_end = times.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-
-        let start = (group_start - CLICK_PRE_PADDING).max(0.0);
-        let end = (group_end + CLICK_POST_PADDING).min(activity_end_limit);
-
-        if end > start {
-            intervals.push((start, end));
-        }
-    }
-
-    let mut last_move_by_cursor: HashMap<String, (f64, f64, f64)> = HashMap::new();
-    let mut distance_window: VecDeque<(f64, f64)> = VecDeque::new();
-    let mut window_distance = 0.0_f64;
-    let mut shake_window: VecDeque<(f64, f64, f64)> = VecDeque::new();
-
-    for mv in moves.iter() {
-        let time = mv.time_ms / 1000.0;
-        if time >= activity_end_limit {
-            break;
-        }
-
-        let distance = if let Some((_, last_x, last_y)) = last_move_by_cursor.get(&mv.cursor_id) {
-            let dx = mv.x - last_x;
-            let dy = mv.y - last_y;
-            (dx * dx + dy * dy).sqrt()
-        } else {
-            0.0
-        };
-
-        last_move_by_cursor.insert(mv.cursor_id.clone(), (time, mv.x, mv.y));
-
-        if distance <= f64::EPSILON {
-            continue;
-        }
-
-        shake_window.push_back((mv.time_ms, mv.x, mv.y));
-        while let Some(&(old_time, _, _)) = shake_window.front() {
-            if mv.time_ms - old_time > SHAKE_FILTER_WINDOW_MS {
-                shake_window.pop_front();
-            } else {
-                break;
-            }
-        }
-
-        if shake_window.len() >= 3 {
-            let positions: Vec<(f64, f64)> =
-                shake_window.iter().map(|(_, x, y)| (*x, *y)).collect();
-            let mut direction_changes = 0;
-            for i in 1..positions.len() - 1 {
-                let dx1 = positions[i].0 - positions[i - 1].0;
-                let dy1 = positions[i].1 - positions[i - 1].1;
-                let dx2 = positions[i + 1].0 - positions[i].0;
-                let dy2 = positions[i + 1].1 - positions[i].1;
-
-                if (dx1 * dx2 + dy1 * dy2) < 0.0 {
-                    direction_changes += 1;
-                }
-            }
-
-            let total_dist: f64 = positions
-                .windows(2)
-                .map(|w| ((w[1].0 - w[0].0).powi(2) + (w[1].1 - w[0].1).powi(2)).sqrt())
-                .sum();
-
-            if direction_changes >= 2 && total_dist < SHAKE_FILTER_THRESHOLD * 3.0 {
-                continue;
-            }
-        }
-
-        distance_window.push_back((time, distance));
-        window_distance += distance;
-
-        while let Some(&(old_time, old_distance)) = distance_window.front() {
-            if time - old_time > MOVEMENT_WINDOW_SECONDS {
-                distance_window.pop_front();
-                window_distance -= old_distance;
-            } else {
-                break;
-            }
-        }
-
-        if window_distance < 0.0 {
-            window_distance = 0.0;
-        }
-
-        let significant_movement = distance >= MOVEMENT_EVENT_DISTANCE_THRESHOLD
-            || window_distance >= MOVEMENT_WINDOW_DISTANCE_THRESHOLD;
-
-        if !significant_movement {
-            continue;
-        }
-
-        let start = (time - MOVEMENT_PRE_PADDING).max(0.0);
-        let end = (time + MOVEMENT_POST_PADDING).min(activity_end_limit);
-
-        if end > start {
-            intervals.push((start, end));
-        }
-    }
-
-    if intervals.is_empty() {
-        return Vec::new();
-    }
-
-    intervals.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
-
-    let mut merged: Vec<(f64, f64)> = Vec::new();
-    for interval in intervals {
-        if let Some(last) = merged.last_mut()
-            && interval.0 <= last.1 + MERGE_GAP_THRESHOLD
-        {
-            last.1 = last.1.max(interval.1);
-            continue;
-        }
-        merged.push(interval);
-    }
-
-    merged
-        .into_iter()
-        .filter_map(|(start, end)| {
-            let duration = end - start;
-            if duration < MIN_SEGMENT_DURATION {
-                return None;
-            }
-
-            Some(ZoomSegment {
-                start,
-                end,
-                amount: AUTO_ZOOM_AMOUNT,
-                mode: ZoomMode::Auto,
-                glide_direction: GlideDirection::None,
-                glide_speed: 0.5,
-                instant_animation: false,
-                edge_snap_ratio: 0.25,
-            })
-        })
-        .collect()
-}
-
 /// Generates zoom segments based on mouse click events during recording.
 /// Used during the recording completion process.
 pub fn generate_zoom_segments_from_clicks(
@@ -2041,38 +1837,7 @@ pub fn generate_zoom_segments_for_project(
     recording_meta: &RecordingMeta,
     recordings: &ProjectRecordingsMeta,
 ) -> Vec<ZoomSegment> {
-    let RecordingMetaInner::Studio(studio_meta) = &recording_meta.inner else {
-        return Vec::new();
-    };
-
-    let mut all_clicks = Vec::new();
-    let mut all_moves = Vec::new();
-
-    match &**studio_meta {
-        StudioRecordingMeta::SingleSegment { segment } => {
-            if let Some(cursor_path) = &segment.cursor {
-                let mut events = CursorEvents::load_from_file(&recording_meta.path(cursor_path))
-                    .unwrap_or_default();
-                let pointer_ids = studio_meta.pointer_cursor_ids();
-                let pointer_ids_ref = (!pointer_ids.is_empty()).then_some(&pointer_ids);
-                events.stabilize_short_lived_cursor_shapes(
-                    pointer_ids_ref,
-                    SHORT_CURSOR_SHAPE_DEBOUNCE_MS,
-                );
-                all_clicks = events.clicks;
-                all_moves = events.moves;
-            }
-        }
-        StudioRecordingMeta::MultipleSegments { inner, .. } => {
-            for segment in inner.segments.iter() {
-                let events = segment.cursor_events(recording_meta);
-                all_clicks.extend(events.clicks);
-                all_moves.extend(events.moves);
-            }
-        }
-    }
-
-    generate_zoom_segments_from_clicks_impl(all_clicks, all_moves, recordings.duration())
+    cap_project::generate_zoom_segments_for_project(recording_meta, recordings.duration())
 }
 
 fn project_config_from_recording(
@@ -2133,6 +1898,7 @@ fn project_config_from_recording(
         scene_segments: Vec::new(),
         mask_segments: Vec::new(),
         text_segments: Vec::new(),
+        markers: Vec::new(),
     });
 
     config
@@ -2293,78 +2059,3 @@ fn find_fragments_in_dir(dir: &Path) -> Vec<PathBuf> {
     fragments.sort();
     fragments
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn click_event(time_ms: f64) -> CursorClickEvent {
-        CursorClickEvent {
-            active_modifiers: vec![],
-            cursor_num: 0,
-            cursor_id: "default".to_string(),
-            time_ms,
-            down: true,
-        }
-    }
-
-    fn move_event(time_ms: f64, x: f64, y: f64) -> CursorMoveEvent {
-        CursorMoveEvent {
-            active_modifiers: vec![],
-            cursor_id: "default".to_string(),
-            time_ms,
-            x,
-            y,
-        }
-    }
-
-    #[test]
-    fn skips_trailing_stop_click() {
-        let segments =
-            generate_zoom_segments_from_clicks_impl(vec![click_event(11_900.0)], vec![], 12.0);
-
-        assert!(
-            segments.is_empty(),
-            "expected trailing stop click to be ignored"
-        );
-    }
-
-    #[test]
-    fn generates_segment_for_sustained_activity() {
-        let clicks = vec![click_event(1_200.0), click_event(4_200.0)];
-        let moves = vec![
-            move_event(1_500.0, 0.10, 0.12),
-            move_event(1_720.0, 0.42, 0.45),
-            move_event(1_940.0, 0.74, 0.78),
-        ];
-
-        let segments = generate_zoom_segments_from_clicks_impl(clicks, moves, 20.0);
-
-        assert!(
-            !segments.is_empty(),
-            "expected activity to produce zoom segments"
-        );
-        let first = &segments[0];
-        assert!(first.start < first.end);
-        assert!(first.end - first.start >= 1.3);
-        assert!(first.end <= 19.5);
-    }
-
-    #[test]
-    fn ignores_cursor_jitter() {
-        let jitter_moves = (0..30)
-            .map(|i| {
-                let t = 1_000.0 + (i as f64) * 30.0;
-                let delta = (i as f64) * 0.0004;
-                move_event(t, 0.5 + delta, 0.5)
-            })
-            .collect::<Vec<_>>();
-
-        let segments = generate_zoom_segments_from_clicks_impl(Vec::new(), jitter_moves, 15.0);
-
-        assert!(
-            segments.is_empty(),
-            "small jitter should not generate segments"
-        );
-    }
-}
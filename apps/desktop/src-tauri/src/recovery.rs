@@ -7,8 +7,6 @@ use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
 use tracing::info;
 
-use crate::create_screenshot;
-
 const RECOVERY_CUTOFF_DATE: (i32, u32, u32) = (2025, 12, 31);
 
 fn parse_recording_date(pretty_name: &str) -> Option<NaiveDate> {
@@ -108,23 +106,8 @@ pub async fn recover_recording(app: AppHandle, project_path: String) -> Result<S
         segment_count, project_path
     );
 
-    let display_output_path = match &recovered.meta {
-        StudioRecordingMeta::SingleSegment { segment } => {
-            segment.display.path.to_path(&recovered.project_path)
-        }
-        StudioRecordingMeta::MultipleSegments { inner, .. } => inner.segments[0]
-            .display
-            .path
-            .to_path(&recovered.project_path),
-    };
-
-    let screenshots_dir = recovered.project_path.join("screenshots");
-    std::fs::create_dir_all(&screenshots_dir)
-        .map_err(|e| format!("Failed to create screenshots directory: {e}"))?;
-
-    let display_screenshot = screenshots_dir.join("display.jpg");
-    tokio::spawn(async move {
-        if let Err(e) = create_screenshot(display_output_path, display_screenshot, None).await {
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = recovered.save_screenshot() {
             tracing::error!("Failed to create screenshot during recovery: {}", e);
         }
     });
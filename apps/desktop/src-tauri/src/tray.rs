@@ -46,6 +46,7 @@ pub enum TrayItem {
     ModeStudio,
     ModeInstant,
     ModeScreenshot,
+    ModeReplay,
     RequestPermissions,
 }
 
@@ -69,6 +70,7 @@ impl From<TrayItem> for MenuId {
             TrayItem::ModeStudio => "mode_studio",
             TrayItem::ModeInstant => "mode_instant",
             TrayItem::ModeScreenshot => "mode_screenshot",
+            TrayItem::ModeReplay => "mode_replay",
             TrayItem::RequestPermissions => "request_permissions",
         }
         .into()
@@ -100,6 +102,7 @@ impl TryFrom<MenuId> for TrayItem {
             "mode_studio" => Ok(TrayItem::ModeStudio),
             "mode_instant" => Ok(TrayItem::ModeInstant),
             "mode_screenshot" => Ok(TrayItem::ModeScreenshot),
+            "mode_replay" => Ok(TrayItem::ModeReplay),
             "request_permissions" => Ok(TrayItem::RequestPermissions),
             value => Err(format!("Invalid tray item id {value}")),
         }
@@ -347,6 +350,7 @@ fn create_mode_submenu(app: &AppHandle) -> tauri::Result<Submenu<tauri::Wry>> {
             RecordingMode::Screenshot,
             "Screenshot",
         ),
+        (TrayItem::ModeReplay, RecordingMode::Replay, "Replay"),
     ];
 
     for (tray_item, mode, label) in modes {
@@ -610,7 +614,9 @@ pub fn get_mode_icon(mode: RecordingMode) -> &'static [u8] {
         return get_tray_icon();
     }
     match mode {
-        RecordingMode::Studio => include_bytes!("../icons/tray-default-icon-studio.png"),
+        RecordingMode::Studio | RecordingMode::Replay => {
+            include_bytes!("../icons/tray-default-icon-studio.png")
+        }
         RecordingMode::Instant => include_bytes!("../icons/tray-default-icon-instant.png"),
         RecordingMode::Screenshot => include_bytes!("../icons/tray-default-icon-screenshot.png"),
     }
@@ -801,6 +807,9 @@ pub fn create_tray(app: &AppHandle) -> tauri::Result<()> {
                 Ok(TrayItem::ModeScreenshot) => {
                     handle_mode_selection(app, RecordingMode::Screenshot, &cache);
                 }
+                Ok(TrayItem::ModeReplay) => {
+                    handle_mode_selection(app, RecordingMode::Replay, &cache);
+                }
                 Ok(TrayItem::RequestPermissions) => {
                     let app = app.clone();
                     tokio::spawn(async move {
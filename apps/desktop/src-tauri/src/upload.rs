@@ -2,43 +2,41 @@
 
 use crate::{
     UploadProgress, VideoUploadInfo,
-    api::{self, PresignedS3PutRequest, PresignedS3PutRequestMethod, S3VideoMeta, UploadedPart},
-    http_client::RetryableHttpClient,
+    api::{PresignedS3PutRequest, PresignedS3PutRequestMethod, S3VideoMeta, UploadedPart},
     posthog::{PostHogEvent, async_capture_event},
     web_api::{AuthedApiError, ManagerExt},
 };
-use async_stream::{stream, try_stream};
+use async_stream::stream;
 use bytes::Bytes;
-use cap_project::{RecordingMeta, S3UploadMeta, UploadMeta};
+use cap_api::{
+    Chunk, CreateVideo, MultipartUpload, build_video_meta, from_pending_file_to_chunks,
+    resume_pending_file_to_chunks,
+};
+use cap_project::{MultipartUploadProgress, RecordingMeta, S3UploadMeta, UploadMeta};
 use cap_utils::spawn_actor;
-use ffmpeg::ffi::AV_TIME_BASE;
 use flume::Receiver;
-use futures::future::join;
 use futures::{Stream, StreamExt, TryStreamExt, stream};
 use image::{ImageReader, codecs::jpeg::JpegEncoder};
-use reqwest::StatusCode;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use specta::Type;
 use std::{
-    collections::HashMap,
     io,
     path::{Path, PathBuf},
     pin::pin,
-    sync::{Arc, Mutex, PoisonError},
+    sync::Arc,
     time::Duration,
 };
-use tauri::{AppHandle, Manager, ipc::Channel};
+use tauri::{AppHandle, ipc::Channel};
 use tauri_plugin_clipboard_manager::ClipboardExt;
 use tauri_specta::Event;
 use tokio::{
     fs::File,
-    io::{AsyncReadExt, AsyncSeekExt, BufReader},
+    sync::{Notify, mpsc},
     task::{self, JoinHandle},
-    time::{self, Instant, timeout},
+    time::{self, Instant},
 };
 use tokio_util::io::ReaderStream;
-use tracing::{Span, debug, error, info, info_span, instrument, trace};
-use tracing_futures::Instrument;
+use tracing::{debug, error, info, instrument};
 
 pub struct UploadedItem {
     pub link: String,
@@ -54,10 +52,6 @@ pub struct UploadProgressEvent {
     total: String,
 }
 
-// The size of each S3 multipart upload chunk
-const MIN_CHUNK_SIZE: u64 = 5 * 1024 * 1024; // 5 MB
-const MAX_CHUNK_SIZE: u64 = 20 * 1024 * 1024; // 20 MB
-
 #[instrument(skip(app, channel, file_path, screenshot_path))]
 pub async fn upload_video(
     app: &AppHandle,
@@ -70,18 +64,16 @@ pub async fn upload_video(
     info!("Uploading video {video_id}...");
 
     let start = Instant::now();
-    let upload_id = api::upload_multipart_initiate(app, &video_id).await?;
+    let client = app.api_client().await?;
+    let upload = MultipartUpload::initiate(client.clone(), &video_id).await?;
 
     let video_fut = async {
         let stream = progress(
             app.clone(),
+            client.clone(),
             video_id.clone(),
-            multipart_uploader(
-                app.clone(),
-                video_id.clone(),
-                upload_id.clone(),
-                from_pending_file_to_chunks(file_path.clone(), None),
-            ),
+            0,
+            upload.upload_parts(from_pending_file_to_chunks(file_path.clone(), None)),
         );
 
         let stream = if let Some(channel) = channel {
@@ -90,30 +82,20 @@ pub async fn upload_video(
             stream.boxed()
         };
 
-        let mut parts = stream.try_collect::<Vec<_>>().await?;
-
-        // Deduplicate parts - keep the last occurrence of each part number
-        let mut deduplicated_parts = HashMap::new();
-        for part in parts {
-            deduplicated_parts.insert(part.part_number, part);
-        }
-        parts = deduplicated_parts.into_values().collect::<Vec<_>>();
-        parts.sort_by_key(|part| part.part_number);
+        let parts = stream.try_collect::<Vec<_>>().await?;
 
         let metadata = build_video_meta(&file_path)
             .map_err(|e| error!("Failed to get video metadata: {e}"))
             .ok();
 
-        api::upload_multipart_complete(app, &video_id, &upload_id, &parts, metadata.clone())
-            .await?;
+        upload.complete(parts, metadata.clone()).await?;
 
-        Ok(metadata)
+        Ok::<_, AuthedApiError>(metadata)
     };
 
     // TODO: We don't report progress on image upload
     let bytes = compress_image(screenshot_path).await?;
-    let thumbnail_fut = singlepart_uploader(
-        app.clone(),
+    let thumbnail_fut = client.singlepart_upload(
         PresignedS3PutRequest {
             video_id: video_id.clone(),
             subpath: "screenshot/screen-capture.jpg".to_string(),
@@ -124,7 +106,7 @@ pub async fn upload_video(
         stream::once(async move { Ok::<_, std::io::Error>(bytes::Bytes::from(bytes)) }),
     );
 
-    let (video_result, thumbnail_result): (Result<_, AuthedApiError>, Result<_, AuthedApiError>) =
+    let (video_result, thumbnail_result): (Result<_, AuthedApiError>, Result<_, cap_api::Error>) =
         tokio::join!(video_fut, thumbnail_fut);
 
     async_capture_event(match &video_result {
@@ -147,7 +129,7 @@ pub async fn upload_video(
     let _ = (video_result?, thumbnail_result?);
 
     Ok(UploadedItem {
-        link: app.make_app_url(format!("/s/{video_id}")).await,
+        link: client.share_link(&video_id),
         id: video_id,
     })
 }
@@ -208,93 +190,17 @@ pub async fn create_or_get_video(
     meta: Option<S3VideoMeta>,
     organization_id: Option<String>,
 ) -> Result<S3UploadMeta, AuthedApiError> {
-    let mut s3_config_url = if let Some(id) = video_id {
-        format!("/api/desktop/video/create?recordingMode=desktopMP4&videoId={id}")
-    } else if is_screenshot {
-        "/api/desktop/video/create?recordingMode=desktopMP4&isScreenshot=true".to_string()
-    } else {
-        "/api/desktop/video/create?recordingMode=desktopMP4".to_string()
-    };
-
-    if let Some(name) = name {
-        s3_config_url.push_str(&format!("&name={name}"));
-    }
-
-    if let Some(meta) = meta {
-        s3_config_url.push_str(&format!("&durationInSecs={}", meta.duration_in_secs));
-        s3_config_url.push_str(&format!("&width={}", meta.width));
-        s3_config_url.push_str(&format!("&height={}", meta.height));
-        if let Some(fps) = meta.fps {
-            s3_config_url.push_str(&format!("&fps={fps}"));
-        }
-    }
-
-    if let Some(org_id) = organization_id {
-        s3_config_url.push_str(&format!("&orgId={org_id}"));
-    }
-
-    let response = app
-        .authed_api_request(s3_config_url, |client, url| client.get(url))
-        .await?;
-
-    if response.status() != StatusCode::OK {
-        #[derive(Deserialize, Clone, Debug)]
-        pub struct CreateErrorResponse {
-            error: String,
-        }
-
-        let status = response.status();
-        let body = response.text().await;
-
-        if let Some(error) = body
-            .as_ref()
-            .ok()
-            .and_then(|body| serde_json::from_str::<CreateErrorResponse>(body).ok())
-            && status == StatusCode::FORBIDDEN
-            && error.error == "upgrade_required"
-        {
-            return Err(AuthedApiError::UpgradeRequired);
-        }
-
-        return Err(format!("create_or_get_video/error/{status}: {body:?}").into());
-    }
-
-    let response_text = response
-        .text()
+    app.api_client()
+        .await?
+        .create_or_get_video(CreateVideo {
+            video_id,
+            is_screenshot,
+            name,
+            meta,
+            organization_id,
+        })
         .await
-        .map_err(|e| format!("Failed to read response body: {e}"))?;
-
-    let config = serde_json::from_str::<S3UploadMeta>(&response_text).map_err(|e| {
-        format!("Failed to deserialize response: {e}. Response body: {response_text}")
-    })?;
-
-    Ok(config)
-}
-
-#[instrument]
-pub fn build_video_meta(path: &PathBuf) -> Result<S3VideoMeta, String> {
-    let input =
-        ffmpeg::format::input(path).map_err(|e| format!("Failed to read input file: {e}"))?;
-    let video_stream = input
-        .streams()
-        .best(ffmpeg::media::Type::Video)
-        .ok_or_else(|| "Failed to find appropriate video stream in file".to_string())?;
-
-    let video_codec = ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())
-        .map_err(|e| format!("Unable to read video codec information: {e}"))?;
-    let video = video_codec
-        .decoder()
-        .video()
-        .map_err(|e| format!("Unable to get video decoder: {e}"))?;
-
-    Ok(S3VideoMeta {
-        duration_in_secs: input.duration() as f64 / AV_TIME_BASE as f64,
-        width: video.width(),
-        height: video.height(),
-        fps: video
-            .frame_rate()
-            .map(|v| v.numerator() as f32 / v.denominator() as f32),
-    })
+        .map_err(Into::into)
 }
 
 #[instrument]
@@ -383,60 +289,107 @@ impl InstantMultipartUpload {
         realtime_video_done: Option<Receiver<()>>,
     ) -> Result<Option<S3VideoMeta>, AuthedApiError> {
         let video_id = pre_created_video.id.clone();
-        debug!("Initiating multipart upload for {video_id}...");
 
         let mut project_meta = RecordingMeta::load_for_project(&recording_dir).map_err(|err| {
             format!("Error reading project meta from {recording_dir:?} for upload init: {err}")
         })?;
+
+        // Uploads interrupted by a crash or restart continue from the first part that wasn't uploaded
+        let resumed_progress = match project_meta.upload.take() {
+            Some(UploadMeta::MultipartUpload {
+                video_id: id,
+                progress: Some(progress),
+                ..
+            }) if id == video_id => Some(progress),
+            _ => None,
+        };
+
+        let client = app.api_client().await?;
+        let (upload, upload_progress) = match resumed_progress {
+            Some(upload_progress) => {
+                debug!(
+                    "Resuming multipart upload {} for {video_id}...",
+                    upload_progress.upload_id
+                );
+                MultipartUpload::resume(client.clone(), &video_id, upload_progress).await?
+            }
+            None => {
+                debug!("Initiating multipart upload for {video_id}...");
+                let upload = MultipartUpload::initiate(client.clone(), &video_id).await?;
+                let upload_progress = MultipartUploadProgress::new(upload.upload_id());
+                (upload, upload_progress)
+            }
+        };
+        debug!(
+            "Uploading {video_id} from part {}",
+            upload_progress.resume_point().0
+        );
+
         project_meta.upload = Some(UploadMeta::MultipartUpload {
             video_id: video_id.clone(),
             file_path: file_path.clone(),
             pre_created_video: pre_created_video.clone(),
             recording_dir: recording_dir.clone(),
+            progress: Some(upload_progress.clone()),
         });
         project_meta
             .save_for_project()
             .map_err(|e| error!("Failed to save recording meta: {e}"))
             .ok();
 
-        let upload_id = api::upload_multipart_initiate(&app, &video_id).await?;
-
-        let mut parts = progress(
+        let progress_writer = UploadProgressWriter::spawn(recording_dir.clone(), video_id.clone());
+        let parts = progress(
             app.clone(),
+            client,
             video_id.clone(),
-            multipart_uploader(
-                app.clone(),
-                video_id.clone(),
-                upload_id.clone(),
-                from_pending_file_to_chunks(file_path.clone(), realtime_video_done),
-            ),
+            upload_progress.resume_point().1,
+            upload
+                .upload_parts(resume_pending_file_to_chunks(
+                    file_path.clone(),
+                    realtime_video_done,
+                    &upload_progress,
+                ))
+                .inspect_ok({
+                    let mut upload_progress = upload_progress.clone();
+                    let progress_writer = progress_writer.clone();
+                    move |part| {
+                        upload_progress.record_part(part.into());
+                        progress_writer.save(upload_progress.clone());
+                    }
+                }),
         )
         .try_collect::<Vec<_>>()
-        .await?;
-
-        // Deduplicate parts - keep the last occurrence of each part number
-        let mut deduplicated_parts = HashMap::new();
-        for part in parts {
-            deduplicated_parts.insert(part.part_number, part);
-        }
-        parts = deduplicated_parts.into_values().collect::<Vec<_>>();
-        parts.sort_by_key(|part| part.part_number);
+        .await;
+        progress_writer.finish().await;
+        let parts = parts?;
 
         let metadata = build_video_meta(&file_path)
             .map_err(|e| error!("Failed to get video metadata: {e}"))
             .ok();
 
-        api::upload_multipart_complete(&app, &video_id, &upload_id, &parts, metadata.clone())
+        let previous_parts = upload_progress.parts.into_iter().map(UploadedPart::from);
+        upload
+            .complete(previous_parts.chain(parts), metadata.clone())
             .await?;
         info!("Multipart upload complete for {video_id}.");
 
-        let mut project_meta = RecordingMeta::load_for_project(&recording_dir).map_err(|err| {
-            format!("Error reading project meta from {recording_dir:?} for upload complete: {err}")
-        })?;
-        project_meta.upload = Some(UploadMeta::Complete);
-        project_meta
-            .save_for_project()
-            .map_err(|err| format!("Error reading project meta from {recording_dir:?}: {err}"))?;
+        task::spawn_blocking({
+            let recording_dir = recording_dir.clone();
+            move || {
+                let mut project_meta =
+                    RecordingMeta::load_for_project(&recording_dir).map_err(|err| {
+                        format!(
+                            "Error reading project meta from {recording_dir:?} for upload complete: {err}"
+                        )
+                    })?;
+                project_meta.upload = Some(UploadMeta::Complete);
+                project_meta.save_for_project().map_err(|err| {
+                    format!("Error reading project meta from {recording_dir:?}: {err}")
+                })
+            }
+        })
+        .await
+        .map_err(|err| err.to_string())??;
 
         let _ = app.clipboard().write_text(pre_created_video.link.clone());
 
@@ -444,341 +397,75 @@ impl InstantMultipartUpload {
     }
 }
 
-pub struct Chunk {
-    /// The total size of the file to be uploaded.
-    /// This can change as the recording grows.
-    total_size: u64,
-    /// The part number. `FILE_OFFSET = PART_NUMBER * CHUNK_SIZE`.
-    part_number: u32,
-    /// Actual data bytes of this chunk
-    chunk: Bytes,
-}
-
-/// Creates a stream that reads chunks from a file, yielding [Chunk]'s.
-#[allow(unused)]
-#[instrument]
-pub fn from_file_to_chunks(path: PathBuf) -> impl Stream<Item = io::Result<Chunk>> {
-    try_stream! {
-        let file = File::open(path).await?;
-        let total_size = file.metadata().await?.len();
-        let mut file = BufReader::new(file);
-
-        let mut buf = vec![0u8; MAX_CHUNK_SIZE as usize];
-        let mut part_number = 0;
-        loop {
-            part_number += 1;
-            let n = file.read(&mut buf).await?;
-            if n == 0 { break; }
-            yield Chunk {
-                total_size,
-                part_number,
-                chunk: Bytes::copy_from_slice(&buf[..n]),
-            };
-        }
-    }
-    .instrument(Span::current())
+/// Records the parts uploaded so far in the project's meta, so the upload can be resumed if the app
+/// exits before it completes. Saves happen one at a time on a blocking thread, skipping to the
+/// latest progress when parts finish faster than they can be saved.
+#[derive(Clone)]
+struct UploadProgressWriter {
+    tx: mpsc::UnboundedSender<MultipartUploadProgress>,
+    done: Arc<Notify>,
 }
 
-/// Creates a stream that reads chunks from a potentially growing file, yielding [Chunk]'s.
-/// The first chunk of the file is yielded last to allow for header rewriting after recording completion.
-/// This uploader will continually poll the filesystem and wait for the file to stop uploading before flushing the rest.
-#[instrument(skip(realtime_upload_done))]
-pub fn from_pending_file_to_chunks(
-    path: PathBuf,
-    realtime_upload_done: Option<Receiver<()>>,
-) -> impl Stream<Item = io::Result<Chunk>> {
-    try_stream! {
-        let mut file = timeout(Duration::from_secs(20), async move {
-            loop {
-                if let Ok(file) = tokio::fs::File::open(&path).await.map_err(|err| error!("from_pending_file_to_chunks/open: {err:?}")) {
-                    break file;
-                }
-                tokio::time::sleep(Duration::from_millis(100)).await;
-            }
-        })
-        .await
-        .map_err(|_| io::Error::other("Failed to open file. The recording pipeline may have crashed?"))?;
-
-        let mut part_number = 1;
-        let mut last_read_position: u64 = 0;
-        let mut realtime_is_done = realtime_upload_done.as_ref().map(|_| false);
-        let mut first_chunk_size: Option<u64> = None;
-        let mut chunk_buffer = vec![0u8; MAX_CHUNK_SIZE as usize];
-
-        loop {
-            // Check if realtime recording is done
-            if !realtime_is_done.unwrap_or(true) && let Some(ref realtime_receiver) = realtime_upload_done {
-                    match realtime_receiver.try_recv() {
-                        Ok(_) => realtime_is_done = Some(true),
-                        Err(flume::TryRecvError::Empty) => {},
-                        // This means all senders where dropped.
-                        // This can assume this means realtime is done.
-                        // It possibly means something has gone wrong but that's not the uploader's problem.
-                        Err(_) => realtime_is_done = Some(true),
+impl UploadProgressWriter {
+    fn spawn(recording_dir: PathBuf, video_id: String) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<MultipartUploadProgress>();
+        let done = Arc::new(Notify::new());
+
+        tokio::spawn({
+            let done = done.clone();
+            async move {
+                while let Some(mut progress) = rx.recv().await {
+                    while let Ok(newer) = rx.try_recv() {
+                        progress = newer;
                     }
 
-            }
-
-            let file_size = match file.metadata().await {
-                Ok(metadata) => metadata.len(),
-                Err(_) => {
-                    // File might be temporarily locked, retry with shorter delay
-                    tokio::time::sleep(Duration::from_millis(100)).await;
-                    continue;
+                    let (recording_dir, video_id) = (recording_dir.clone(), video_id.clone());
+                    task::spawn_blocking(move || {
+                        save_upload_progress(&recording_dir, &video_id, progress)
+                    })
+                    .await
+                    .map_err(|e| error!("Upload progress writer panicked: {e}"))
+                    .ok();
                 }
-            };
-
-            let new_data_size = file_size.saturating_sub(last_read_position);
-
-            // Determine if we should read a chunk
-            let should_read_chunk = if let Some(is_done) = realtime_is_done {
-                (new_data_size >= MIN_CHUNK_SIZE) || (is_done && new_data_size > 0)
-            } else {
-                new_data_size > 0
-            };
 
-            if should_read_chunk {
-                let chunk_size = std::cmp::min(new_data_size, MAX_CHUNK_SIZE) as usize;
-
-                file.seek(std::io::SeekFrom::Start(last_read_position)).await?;
-
-                let mut total_read = 0;
-                while total_read < chunk_size {
-                    match file.read(&mut chunk_buffer[total_read..chunk_size]).await {
-                        Ok(0) => break, // EOF
-                        Ok(n) => total_read += n,
-                        Err(e) => yield Err(e)?,
-                    }
-                }
+                done.notify_one();
+            }
+        });
 
-                if total_read > 0 {
-                    // Remember first chunk size for later re-emission with updated header
-                    if last_read_position == 0 {
-                        first_chunk_size = Some(total_read as u64);
-                    }
+        Self { tx, done }
+    }
 
-                    yield Chunk {
-                        total_size: file_size,
-                        part_number,
-                        chunk: Bytes::copy_from_slice(&chunk_buffer[..total_read]),
-                    };
-                    part_number += 1;
-                    last_read_position += total_read as u64;
-                }
-            } else if new_data_size == 0 && realtime_is_done.unwrap_or(true) {
-                // Recording is done and no new data - re-emit first chunk with corrected MP4 header
-                if let Some(first_size) = first_chunk_size && realtime_upload_done.is_some() {
-                    file.seek(std::io::SeekFrom::Start(0)).await?;
-
-                    let chunk_size = first_size as usize;
-                    let mut total_read = 0;
-
-                    while total_read < chunk_size {
-                        match file.read(&mut chunk_buffer[total_read..chunk_size]).await {
-                            Ok(0) => break,
-                            Ok(n) => total_read += n,
-                            Err(e) => yield Err(e)?,
-                        }
-                    }
+    fn save(&self, progress: MultipartUploadProgress) {
+        self.tx.send(progress).ok();
+    }
 
-                    if total_read > 0 {
-                        yield Chunk {
-                            total_size: file_size,
-                            part_number: 1,
-                            chunk: Bytes::copy_from_slice(&chunk_buffer[..total_read]),
-                        };
-                    }
-                }
-                break;
-            } else {
-                tokio::time::sleep(Duration::from_millis(100)).await;
-            }
-        }
+    /// Waits for pending progress to be saved. Every clone must have been dropped.
+    async fn finish(self) {
+        let Self { tx, done } = self;
+        drop(tx);
+        done.notified().await;
     }
-    .instrument(Span::current())
 }
 
-/// Takes an incoming stream of bytes and individually uploads them to S3.
-///
-/// Note: It's on the caller to ensure the chunks are sized correctly within S3 limits.
-#[instrument(skip(app, stream, upload_id))]
-fn multipart_uploader(
-    app: AppHandle,
-    video_id: String,
-    upload_id: String,
-    stream: impl Stream<Item = io::Result<Chunk>> + Send + 'static,
-) -> impl Stream<Item = Result<UploadedPart, AuthedApiError>> + 'static {
-    const MAX_CONCURRENT_UPLOADS: usize = 3;
-
-    debug!("Initializing multipart uploader for video {video_id:?}");
-    let start = Instant::now();
-    let video_id2 = video_id.clone();
-
-    stream::once(async move {
-        let use_md5_hashes = app.is_server_url_custom().await;
-        let first_chunk_presigned_url = Arc::new(Mutex::new(None::<(String, Instant)>));
-
-        stream::unfold(
-            (Box::pin(stream), 1),
-            move |(mut stream, expected_part_number)| {
-                let app = app.clone();
-                let video_id = video_id.clone();
-                let upload_id = upload_id.clone();
-                let first_chunk_presigned_url = first_chunk_presigned_url.clone();
-
-                async move {
-                    let (Some(item), presigned_url) = join(stream.next(), async {
-                        // Self-hosted still uses the legacy web API which requires these so we can't presign the URL.
-                        if use_md5_hashes {
-                            return Ok(None);
-                        }
-
-                        // We generate the presigned URL ahead of time for the part we expect to come next.
-                        // If it's not the chunk that actually comes next we just throw it out.
-                        // This means if the filesystem takes a while for the recording to reach previous total + CHUNK_SIZE, which is the common case, we aren't just doing nothing.
-                        api::upload_multipart_presign_part(
-                            &app,
-                            &video_id,
-                            &upload_id,
-                            expected_part_number,
-                            None,
-                        )
-                        .await
-                        .map(Some)
-                    })
-                    .await
-                    else {
-                        return None;
-                    };
-
-                    let part_number = item
-                        .as_ref()
-                        .map(|c| c.part_number.to_string())
-                        .unwrap_or_else(|_| "--".into());
-
-                    Some((
-                        async move {
-                            let Chunk {
-                                total_size,
-                                part_number,
-                                chunk,
-                            } = item.map_err(|err| {
-                                format!("uploader/part/{expected_part_number:?}/fs: {err:?}")
-                            })?;
-                            trace!(
-                                "Uploading chunk {part_number} ({} bytes) for video {video_id:?}",
-                                chunk.len()
-                            );
-
-                            // We prefetched for the wrong chunk. Let's try again with the correct part number now that we know it.
-                            let md5_sum =
-                                use_md5_hashes.then(|| base64::encode(md5::compute(&chunk).0));
-                            let presigned_url = if let Some(url) = presigned_url?
-                                && part_number == expected_part_number
-                            {
-                                url
-                            } else if part_number == 1
-                                && !use_md5_hashes
-                                // We have a presigned URL left around from the first chunk
-                                && let Some((url, expiry)) = first_chunk_presigned_url
-                                    .lock()
-                                    .unwrap_or_else(PoisonError::into_inner)
-                                    .clone()
-                                // The URL hasn't expired
-                                && expiry.elapsed() < Duration::from_secs(60 * 50)
-                            {
-                                url
-                            } else {
-                                api::upload_multipart_presign_part(
-                                    &app,
-                                    &video_id,
-                                    &upload_id,
-                                    part_number,
-                                    md5_sum.as_deref(),
-                                )
-                                .await?
-                            };
-
-                            // We cache the presigned URL for the first chunk,
-                            // as for instant mode we upload the first chunk at the end again to include the updated video metadata.
-                            if part_number == 1 {
-                                *first_chunk_presigned_url
-                                    .lock()
-                                    .unwrap_or_else(PoisonError::into_inner) =
-                                    Some((presigned_url.clone(), Instant::now()));
-                            }
-
-                            let size = chunk.len();
-                            let mut req = app
-                                .state::<RetryableHttpClient>()
-                                .as_ref()
-                                .map_err(|err| {
-                                    format!("uploader/part/{part_number}/client: {err:?}")
-                                })?
-                                .put(&presigned_url)
-                                .header("Content-Length", chunk.len())
-                                .timeout(Duration::from_secs(5 * 60))
-                                .body(chunk);
-
-                            if let Some(md5_sum) = &md5_sum {
-                                req = req.header("Content-MD5", md5_sum);
-                            }
-
-                            let resp = req
-                                .send()
-                                .instrument(info_span!("s3_put", size = size))
-                                .await
-                                .map_err(|err| {
-                                    format!("uploader/part/{part_number}/error: {err:?}")
-                                })?;
-
-                            let etag = resp
-                                .headers()
-                                .get("ETag")
-                                .as_ref()
-                                .and_then(|etag| etag.to_str().ok())
-                                .map(|v| v.trim_matches('"').to_string());
-
-                            match !resp.status().is_success() {
-                                true => Err(format!(
-                                    "uploader/part/{part_number}/error: {}",
-                                    resp.text().await.unwrap_or_default()
-                                )),
-                                false => Ok(()),
-                            }?;
-
-                            trace!("Completed upload of part {part_number}");
-
-                            Ok::<_, AuthedApiError>(UploadedPart {
-                                etag: etag.ok_or_else(|| {
-                                    format!(
-                                        "uploader/part/{part_number}/error: ETag header not found"
-                                    )
-                                })?,
-                                part_number,
-                                size,
-                                total_size,
-                            })
-                        }
-                        .instrument(info_span!("upload_part", part_number = part_number)),
-                        (stream, expected_part_number + 1),
-                    ))
-                }
-            },
-        )
-        .buffered(MAX_CONCURRENT_UPLOADS)
-        .boxed()
-    })
-    .chain(stream::once(async move {
-        debug!(
-            "Completed multipart upload for {video_id2:?} in {:?}",
-            start.elapsed()
-        );
+fn save_upload_progress(recording_dir: &Path, video_id: &str, progress: MultipartUploadProgress) {
+    let Ok(mut meta) = RecordingMeta::load_for_project(recording_dir)
+        .map_err(|err| error!("Error reading project meta from {recording_dir:?}: {err}"))
+    else {
+        return;
+    };
 
-        stream::empty().boxed()
-    }))
-    .flatten()
-    .instrument(Span::current())
+    if let Some(UploadMeta::MultipartUpload {
+        video_id: id,
+        progress: saved,
+        ..
+    }) = &mut meta.upload
+        && id == video_id
+    {
+        *saved = Some(progress);
+        meta.save_for_project()
+            .map_err(|e| error!("Failed to save upload progress: {e}"))
+            .ok();
+    }
 }
 
 /// Takes an incoming stream of bytes and streams them to an S3 object.
@@ -789,28 +476,11 @@ pub async fn singlepart_uploader(
     total_size: u64,
     stream: impl Stream<Item = io::Result<Bytes>> + Send + 'static,
 ) -> Result<(), AuthedApiError> {
-    let presigned_url = api::upload_signed(&app, request).await?;
-
-    let resp = app
-        .state::<RetryableHttpClient>()
-        .as_ref()
-        .map_err(|err| format!("singlepart_uploader/client: {err:?}"))?
-        .put(&presigned_url)
-        .header("Content-Length", total_size)
-        .body(reqwest::Body::wrap_stream(stream))
-        .send()
+    app.api_client()
+        .await?
+        .singlepart_upload(request, total_size, stream)
         .await
-        .map_err(|err| format!("singlepart_uploader/error: {err:?}"))?;
-
-    match !resp.status().is_success() {
-        true => Err(format!(
-            "singlepart_uploader/error: {}",
-            resp.text().await.unwrap_or_default()
-        )),
-        false => Ok(()),
-    }?;
-
-    Ok(())
+        .map_err(Into::into)
 }
 
 pub trait UploadedChunk {
@@ -852,12 +522,15 @@ impl UploadedChunk for (u64, Bytes) {
 }
 
 /// Monitor the stream to report the upload progress
+///
+/// `uploaded` is the number of bytes uploaded before `stream`, eg. by an upload that's being resumed.
 fn progress<T: UploadedChunk, E>(
     app: AppHandle,
+    client: cap_api::Client,
     video_id: String,
+    mut uploaded: u64,
     stream: impl Stream<Item = Result<T, E>>,
 ) -> impl Stream<Item = Result<T, E>> {
-    let mut uploaded = 0u64;
     let mut pending_task: Option<JoinHandle<()>> = None;
     let mut reemit_task: Option<JoinHandle<()>> = None;
     let (video_id2, app_handle) = (video_id.clone(), app.clone());
@@ -884,18 +557,18 @@ fn progress<T: UploadedChunk, E>(
 
                 if should_send_immediately {
                     // Send immediately if upload is complete
-                    let app_clone = app.clone();
+                    let client = client.clone();
                     let video_id_clone = video_id.clone();
                     tokio::spawn(async move {
-                        api::desktop_video_progress(&app_clone, &video_id_clone, uploaded, total).await.ok();
+                        client.desktop_video_progress(&video_id_clone, uploaded, total).await.ok();
                     });
                 } else {
                     // Schedule delayed update
-                    let app_clone = app.clone();
+                    let client = client.clone();
                     let video_id_clone = video_id.clone();
                     pending_task = Some(tokio::spawn(async move {
                         tokio::time::sleep(Duration::from_secs(2)).await;
-                        api::desktop_video_progress(&app_clone, &video_id_clone, uploaded, total).await.ok();
+                        client.desktop_video_progress(&video_id_clone, uploaded, total).await.ok();
                     }));
 
                     // Start reemit task for continuous progress updates every 700ms
@@ -36,6 +36,17 @@ impl From<reqwest::Error> for AuthedApiError {
     }
 }
 
+impl From<cap_api::Error> for AuthedApiError {
+    fn from(err: cap_api::Error) -> Self {
+        match err {
+            cap_api::Error::InvalidAuthentication => AuthedApiError::InvalidAuthentication,
+            cap_api::Error::UpgradeRequired => AuthedApiError::UpgradeRequired,
+            cap_api::Error::Timeout => AuthedApiError::Timeout,
+            err => AuthedApiError::Other(err.to_string()),
+        }
+    }
+}
+
 impl From<&'static str> for AuthedApiError {
     fn from(value: &'static str) -> Self {
         AuthedApiError::Other(value.into())
@@ -91,6 +102,9 @@ pub trait ManagerExt<R: Runtime>: Manager<R> {
         build: impl FnOnce(&reqwest::Client, String) -> reqwest::RequestBuilder,
     ) -> Result<reqwest::Response, reqwest::Error>;
 
+    /// A [`cap_api::Client`] for the configured server, authenticated as the current user.
+    async fn api_client(&self) -> Result<cap_api::Client, AuthedApiError>;
+
     async fn make_app_url(&self, pathname: impl AsRef<str>) -> String;
 
     async fn is_server_url_custom(&self) -> bool;
@@ -132,6 +146,38 @@ impl<T: Manager<R> + Emitter<R>, R: Runtime> ManagerExt<R> for T {
             .await
     }
 
+    async fn api_client(&self) -> Result<cap_api::Client, AuthedApiError> {
+        let Some(auth) = AuthStore::get(self.app_handle()).map_err(AuthedApiError::AuthStore)?
+        else {
+            warn!("Not logged in");
+            return Err(AuthedApiError::InvalidAuthentication);
+        };
+
+        let upload_client = self
+            .state::<http_client::RetryableHttpClient>()
+            .as_ref()
+            .map_err(|err| format!("api_client/upload_client: {err:?}"))?
+            .clone();
+
+        let mut client = cap_api::Client::new(self.make_app_url("").await)
+            .with_auth_token(match auth.secret {
+                AuthSecret::ApiKey { api_key } => api_key,
+                AuthSecret::Session { token, .. } => token,
+            })
+            .with_header("X-Cap-Desktop-Version", env!("CARGO_PKG_VERSION"))
+            .with_http_client(reqwest::Client::clone(
+                &self.state::<http_client::HttpClient>(),
+            ))
+            .with_upload_http_client(upload_client)
+            .with_content_md5(self.is_server_url_custom().await);
+
+        if let Ok(s) = std::env::var("VITE_VERCEL_AUTOMATION_BYPASS_SECRET") {
+            client = client.with_header("x-vercel-protection-bypass", s);
+        }
+
+        Ok(client)
+    }
+
     async fn make_app_url(&self, pathname: impl AsRef<str>) -> String {
         let app_state = self.state::<ArcLock<crate::App>>();
         let server_url = &app_state.read().await.server_url;
@@ -100,6 +100,29 @@ const Mode = (props: ModeProps) => {
 				</Tooltip>
 			)}
 
+			{!isInfoHovered() && (
+				<Tooltip
+					placement="top"
+					content="Replay mode"
+					openDelay={0}
+					closeDelay={0}
+				>
+					<div
+						onClick={() => {
+							setOptions({ mode: "replay" });
+							commands.setRecordingMode("replay");
+						}}
+						class={`flex justify-center items-center transition-all duration-200 rounded-full size-7 hover:cursor-pointer ${
+							rawOptions.mode === "replay"
+								? "ring-2 ring-offset-1 ring-offset-gray-1 bg-gray-7 hover:bg-gray-7 ring-blue-500"
+								: "bg-gray-3 hover:bg-gray-7"
+						}`}
+					>
+						<IconLucideHistory class="size-3.5" />
+					</div>
+				</Tooltip>
+			)}
+
 			{isInfoHovered() && (
 				<>
 					<div
@@ -143,6 +166,20 @@ const Mode = (props: ModeProps) => {
 					>
 						<IconCapScreenshot class="size-3.5 invert dark:invert-0" />
 					</div>
+
+					<div
+						onClick={() => {
+							setOptions({ mode: "replay" });
+							commands.setRecordingMode("replay");
+						}}
+						class={`flex justify-center items-center transition-all duration-200 rounded-full size-7 hover:cursor-pointer ${
+							rawOptions.mode === "replay"
+								? "ring-2 ring-offset-1 ring-offset-gray-1 bg-gray-5 hover:bg-gray-7 ring-blue-10"
+								: "bg-gray-3 hover:bg-gray-7"
+						}`}
+					>
+						<IconLucideHistory class="size-3.5" />
+					</div>
 				</>
 			)}
 		</div>
@@ -80,6 +80,12 @@ const ModeSelect = (props: { onClose?: () => void; standalone?: boolean }) => {
 			description: "Capture and annotate screenshots instantly.",
 			icon: IconCapScreenshot,
 		},
+		{
+			mode: "replay" as const,
+			title: "Replay",
+			description: "Keep the last few minutes and save them after the fact.",
+			icon: IconLucideHistory,
+		},
 	];
 
 	return (
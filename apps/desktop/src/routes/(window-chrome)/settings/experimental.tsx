@@ -25,6 +25,7 @@ function Inner(props: { initialStore: GeneralSettingsStore | null }) {
 			enableNotifications: true,
 			enableNativeCameraPreview: false,
 			autoZoomOnClicks: false,
+			captureKeystrokes: false,
 			custom_cursor_capture2: true,
 		},
 	);
@@ -84,6 +85,12 @@ function Inner(props: { initialStore: GeneralSettingsStore | null }) {
 								);
 							}}
 						/>
+						<ToggleSettingItem
+							label="Capture keystrokes"
+							description="Record shortcuts and special keys pressed during Studio Mode recordings so they can be shown on the video in the editor. Typed text is never recorded."
+							value={!!settings.captureKeystrokes}
+							onChange={(value) => handleChange("captureKeystrokes", value)}
+						/>
 					</div>
 				</div>
 			</div>
@@ -78,6 +78,7 @@ const createDefaultGeneralSettings = (): ExtendedGeneralSettingsStore => ({
 	instantModeMaxResolution: 1920,
 	crashRecoveryRecording: true,
 	maxFps: 60,
+	replayBufferSeconds: 300,
 });
 
 const deriveInitialSettings = (
@@ -111,6 +112,17 @@ const MAX_FPS_OPTIONS = [
 	label: string;
 }[];
 
+const REPLAY_BUFFER_OPTIONS = [
+	{ value: 30, label: "30 seconds" },
+	{ value: 60, label: "1 minute" },
+	{ value: 120, label: "2 minutes" },
+	{ value: 300, label: "5 minutes" },
+	{ value: 600, label: "10 minutes" },
+] satisfies {
+	value: number;
+	label: string;
+}[];
+
 const DEFAULT_PROJECT_NAME_TEMPLATE =
 	"{target_name} ({target_kind}) {date} {time}";
 
@@ -542,6 +554,16 @@ function Inner(props: { initialStore: GeneralSettingsStore | null }) {
 							</p>
 						)}
 					</div>
+					<SelectSettingItem
+						label="Replay mode length"
+						description="How much of the most recent screen activity Replay mode keeps and saves."
+						value={settings.replayBufferSeconds ?? 300}
+						onChange={(value) => handleChange("replayBufferSeconds", value)}
+						options={REPLAY_BUFFER_OPTIONS.map((option) => ({
+							text: option.label,
+							value: option.value,
+						}))}
+					/>
 				</SettingGroup>
 
 				<SettingGroup
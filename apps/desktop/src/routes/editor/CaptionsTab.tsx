@@ -16,7 +16,7 @@ import {
 import toast from "solid-toast";
 import { Toggle } from "~/components/Toggle";
 import { defaultCaptionSettings } from "~/store/captions";
-import type { CaptionSettings } from "~/utils/tauri";
+import type { CaptionSettings, CaptionWordRef } from "~/utils/tauri";
 import { commands, events } from "~/utils/tauri";
 import IconCapChevronDown from "~icons/cap/chevron-down";
 import IconCapCircleCheck from "~icons/cap/circle-check";
@@ -439,6 +439,55 @@ export function CaptionsTab() {
 		() => (project.captions?.segments?.length ?? 0) > 0,
 	);
 
+	const wordKey = (word: CaptionWordRef) =>
+		`${word.captionId}:${word.wordIndex}`;
+	const [selectedWords, setSelectedWords] = createSignal<
+		Map<string, CaptionWordRef>
+	>(new Map());
+	const [isCuttingWords, setIsCuttingWords] = createSignal(false);
+
+	const toggleWord = (word: CaptionWordRef) => {
+		const selected = new Map(selectedWords());
+		const key = wordKey(word);
+		if (selected.has(key)) selected.delete(key);
+		else selected.set(key, word);
+		setSelectedWords(selected);
+	};
+
+	const cutWords = async (words: CaptionWordRef[]) => {
+		if (words.length === 0 || !project.timeline) return;
+
+		setIsCuttingWords(true);
+		try {
+			const result = await commands.cutTranscriptWords(words);
+			setProject("timeline", "segments", result.segments);
+			setProject("captions", "segments", result.captions.segments);
+			setSelectedWords(new Map());
+			toast.success(
+				`Cut ${words.length} word${words.length === 1 ? "" : "s"} from the video`,
+			);
+		} catch (error) {
+			console.error("Failed to cut words:", error);
+			toast.error("Failed to cut words from the video");
+		} finally {
+			setIsCuttingWords(false);
+		}
+	};
+
+	const removeFillerWords = async () => {
+		try {
+			const words = await commands.findFillerWords(null);
+			if (words.length === 0) {
+				toast.success("No filler words found");
+				return;
+			}
+			await cutWords(words);
+		} catch (error) {
+			console.error("Failed to find filler words:", error);
+			toast.error("Failed to find filler words");
+		}
+	};
+
 	return (
 		<Field name="Captions" icon={<IconCapMessageBubble />}>
 			<div class="flex flex-col gap-4">
@@ -902,6 +951,29 @@ export function CaptionsTab() {
 									</Button>
 								</div>
 
+								<div class="flex gap-2">
+									<Button
+										variant="gray"
+										class="flex-1"
+										disabled={isCuttingWords()}
+										onClick={removeFillerWords}
+									>
+										Remove Filler Words
+									</Button>
+									<Button
+										variant="gray"
+										class="flex-1"
+										disabled={
+											isCuttingWords() || selectedWords().size === 0
+										}
+										onClick={() =>
+											cutWords([...selectedWords().values()])
+										}
+									>
+										Cut Selected Words
+									</Button>
+								</div>
+
 								<div class="max-h-[300px] overflow-y-auto space-y-3 pr-2">
 									<For each={project.captions?.segments}>
 										{(segment) => (
@@ -962,6 +1034,38 @@ export function CaptionsTab() {
 														</div>
 													</div>
 
+													<Show when={segment.words?.length}>
+														<div class="space-y-2">
+															<label class="text-xs text-gray-11">
+																Words (select to cut from the video)
+															</label>
+															<div class="flex flex-wrap gap-1">
+																<For each={segment.words}>
+																	{(word, index) => {
+																		const ref = () => ({
+																			captionId: segment.id,
+																			wordIndex: index(),
+																		});
+																		return (
+																			<button
+																				type="button"
+																				class={cx(
+																					"px-1.5 py-0.5 rounded text-xs transition-colors",
+																					selectedWords().has(wordKey(ref()))
+																						? "bg-red-9 text-white line-through"
+																						: "bg-gray-3 text-gray-12 hover:bg-gray-4",
+																				)}
+																				onClick={() => toggleWord(ref())}
+																			>
+																				{word.text.trim()}
+																			</button>
+																		);
+																	}}
+																</For>
+															</div>
+														</div>
+													</Show>
+
 													<div class="flex justify-end">
 														<Button
 															variant="destructive"
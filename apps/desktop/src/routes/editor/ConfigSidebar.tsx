@@ -14,6 +14,7 @@ import { createEventListenerMap } from "@solid-primitives/event-listener";
 import { createWritableMemo } from "@solid-primitives/memo";
 import { convertFileSrc } from "@tauri-apps/api/core";
 import { appDataDir, resolveResource } from "@tauri-apps/api/path";
+import { open as openDialog } from "@tauri-apps/plugin-dialog";
 import { BaseDirectory, writeFile } from "@tauri-apps/plugin-fs";
 import { type as ostype } from "@tauri-apps/plugin-os";
 import { cx } from "cva";
@@ -44,13 +45,17 @@ import { generalSettingsStore } from "~/store";
 import {
 	type BackgroundSource,
 	type CameraShape,
+	type CaptionPosition,
 	type ClipOffsets,
 	type CursorAnimationStyle,
 	type CursorType,
 	commands,
+	type MusicTrack,
 	type SceneSegment,
 	type StereoMode,
 	type TimelineSegment,
+	type VolumeKeyframe,
+	type VolumeKeyframes,
 	type ZoomSegment,
 } from "~/utils/tauri";
 import IconLucideBoxSelect from "~icons/lucide/box-select";
@@ -65,6 +70,7 @@ import IconLucideTimer from "~icons/lucide/timer";
 import IconLucideType from "~icons/lucide/type";
 import IconLucideWind from "~icons/lucide/wind";
 import { CaptionsTab } from "./CaptionsTab";
+import { createTimelineChapters, markerAtTime } from "./chapters";
 import { type CornerRoundingType, useEditorContext } from "./context";
 import { evaluateMask, type MaskKind, type MaskSegment } from "./masks";
 import {
@@ -76,7 +82,6 @@ import ShadowSettings from "./ShadowSettings";
 import { TextInput } from "./TextInput";
 import type { TextSegment } from "./text";
 import {
-	ComingSoonTooltip,
 	EditorButton,
 	Field,
 	MenuItem,
@@ -86,6 +91,7 @@ import {
 	Subfield,
 	topSlideAnimateClasses,
 } from "./ui";
+import { formatTime } from "./utils";
 
 const BACKGROUND_SOURCES = {
 	wallpaper: "Wallpaper",
@@ -208,6 +214,15 @@ const STEREO_MODES = [
 	{ name: "Mono R", value: "monoR" },
 ] satisfies Array<{ name: string; value: StereoMode }>;
 
+const KEYSTROKE_POSITIONS = [
+	{ name: "Top Left", value: "top-left" },
+	{ name: "Top Center", value: "top-center" },
+	{ name: "Top Right", value: "top-right" },
+	{ name: "Bottom Left", value: "bottom-left" },
+	{ name: "Bottom Center", value: "bottom-center" },
+	{ name: "Bottom Right", value: "bottom-right" },
+] satisfies Array<{ name: string; value: CaptionPosition }>;
+
 const CAMERA_SHAPES = [
 	{
 		name: "Square",
@@ -403,7 +418,7 @@ export function ConfigSidebar() {
 							id: "captions" as const,
 							icon: IconCapMessageBubble,
 						},
-						// { id: "hotkeys" as const, icon: IconCapHotkeys },
+						{ id: "hotkeys" as const, icon: IconCapHotkeys },
 					].filter(Boolean)}
 				>
 					{(item) => (
@@ -473,6 +488,15 @@ export function ConfigSidebar() {
 								onChange={(v) => setProject("audio", "mute", v)}
 							/>
 						</Subfield>
+						{meta().hasMicrophone && (
+							<Subfield name="Improve Mic Quality">
+								<Toggle
+									disabled={project.audio.mute}
+									checked={project.audio.improve}
+									onChange={(v) => setProject("audio", "improve", v)}
+								/>
+							</Subfield>
+						)}
 						{editorInstance.recordings.segments[0].mic?.channels === 2 && (
 							<Subfield name="Microphone Stereo Mode">
 								<KSelect<{ name: string; value: StereoMode }>
@@ -534,12 +558,6 @@ export function ConfigSidebar() {
                   onChange={(v) => setProject("audio", "mute", v)}
                 />
               </Subfield> */}
-
-						{/* <ComingSoonTooltip>
-                <Subfield name="Improve Mic Quality">
-                  <Toggle disabled />
-                </Subfield>
-              </ComingSoonTooltip> */}
 					</Field>
 					{meta().hasMicrophone && (
 						<Field
@@ -559,6 +577,9 @@ export function ConfigSidebar() {
 							/>
 						</Field>
 					)}
+					{meta().hasMicrophone && (
+						<VolumeKeyframesConfig name="Microphone Keyframes" track="mic" />
+					)}
 					{meta().hasSystemAudio && (
 						<Field
 							name="System Audio Volume"
@@ -577,6 +598,13 @@ export function ConfigSidebar() {
 							/>
 						</Field>
 					)}
+					{meta().hasSystemAudio && (
+						<VolumeKeyframesConfig
+							name="System Audio Keyframes"
+							track="system"
+						/>
+					)}
+					<MusicConfig />
 				</KTabs.Content>
 				<KTabs.Content
 					value="cursor"
@@ -805,12 +833,108 @@ export function ConfigSidebar() {
           </Field> */}
 				</KTabs.Content>
 				<KTabs.Content value="hotkeys" class="flex flex-1 p-4 min-h-0">
-					<Field name="Hotkeys" icon={<IconCapHotkeys />}>
-						<ComingSoonTooltip>
-							<Subfield name="Show hotkeys">
-								<Toggle disabled />
+					<Field
+						name="Hotkeys"
+						icon={<IconCapHotkeys />}
+						value={
+							<Toggle
+								checked={project.hotkeys.show}
+								onChange={(v) => setProject("hotkeys", "show", v)}
+							/>
+						}
+					>
+						<Show when={project.hotkeys.show}>
+							<Subfield name="Position">
+								<KSelect<{ name: string; value: CaptionPosition }>
+									options={KEYSTROKE_POSITIONS}
+									optionValue="value"
+									optionTextValue="name"
+									value={KEYSTROKE_POSITIONS.find(
+										(v) => v.value === project.hotkeys.position,
+									)}
+									onChange={(v) => {
+										if (v) setProject("hotkeys", "position", v.value);
+									}}
+									disallowEmptySelection
+									itemComponent={(props) => (
+										<MenuItem<typeof KSelect.Item>
+											as={KSelect.Item}
+											item={props.item}
+										>
+											<KSelect.ItemLabel class="flex-1">
+												{props.item.rawValue.name}
+											</KSelect.ItemLabel>
+										</MenuItem>
+									)}
+								>
+									<KSelect.Trigger class="flex flex-row gap-2 items-center px-2 w-full h-8 rounded-lg transition-colors bg-gray-3 disabled:text-gray-11">
+										<KSelect.Value<{
+											name: string;
+											value: CaptionPosition;
+										}> class="flex-1 text-sm text-left truncate text-[--gray-500] font-normal">
+											{(state) => <span>{state.selectedOption().name}</span>}
+										</KSelect.Value>
+										<KSelect.Icon<ValidComponent>
+											as={(props) => (
+												<IconCapChevronDown
+													{...props}
+													class="size-4 shrink-0 transform transition-transform ui-expanded:rotate-180 text-[--gray-500]"
+												/>
+											)}
+										/>
+									</KSelect.Trigger>
+									<KSelect.Portal>
+										<PopperContent<typeof KSelect.Content>
+											as={KSelect.Content}
+											class={cx(topSlideAnimateClasses, "z-50")}
+										>
+											<MenuItemList<typeof KSelect.Listbox>
+												class="overflow-y-auto max-h-32"
+												as={KSelect.Listbox}
+											/>
+										</PopperContent>
+									</KSelect.Portal>
+								</KSelect>
+							</Subfield>
+							<Subfield name="Size" class="gap-4 items-center">
+								<Slider
+									class="flex-1"
+									value={[project.hotkeys.size]}
+									onChange={(v) => setProject("hotkeys", "size", v[0])}
+									minValue={12}
+									maxValue={96}
+									step={1}
+								/>
 							</Subfield>
-						</ComingSoonTooltip>
+							<Subfield name="Display Duration" class="gap-4 items-center">
+								<Slider
+									class="flex-1"
+									value={[project.hotkeys.displayDuration]}
+									onChange={(v) =>
+										setProject("hotkeys", "displayDuration", v[0])
+									}
+									minValue={0}
+									maxValue={5}
+									step={0.1}
+								/>
+							</Subfield>
+							<Subfield name="Fade Duration" class="gap-4 items-center">
+								<Slider
+									class="flex-1"
+									value={[project.hotkeys.fadeDuration]}
+									onChange={(v) => setProject("hotkeys", "fadeDuration", v[0])}
+									minValue={0}
+									maxValue={1}
+									step={0.05}
+								/>
+							</Subfield>
+							<Subfield name="Combine Shortcuts">
+								<Toggle
+									checked={project.hotkeys.combineChords}
+									onChange={(v) => setProject("hotkeys", "combineChords", v)}
+								/>
+							</Subfield>
+						</Show>
 					</Field>
 				</KTabs.Content>
 				<KTabs.Content
@@ -818,6 +942,7 @@ export function ConfigSidebar() {
 					class="flex flex-col flex-1 gap-6 p-4 min-h-0"
 				>
 					<CaptionsTab />
+					<ChaptersConfig />
 				</KTabs.Content>
 			</div>
 			<div
@@ -2908,6 +3033,318 @@ function MaskSegmentConfig(props: {
 	);
 }
 
+function ChaptersConfig() {
+	const { project, setProject, editorState } = useEditorContext();
+
+	const timelineChapters = createTimelineChapters(() => project.timeline);
+
+	const chapters = () =>
+		(project.timeline?.markers ?? [])
+			.map((marker, index) => ({
+				index,
+				title: marker.title,
+				time: timelineChapters.latest.markerTimes[index] ?? null,
+			}))
+			.sort((a, b) => (a.time ?? Infinity) - (b.time ?? Infinity));
+
+	const addChapter = () => {
+		const segments = project.timeline?.segments ?? [];
+		const time = editorState.previewTime ?? editorState.playbackTime;
+		const marker = markerAtTime(
+			segments,
+			time,
+			`Chapter ${(project.timeline?.markers?.length ?? 0) + 1}`,
+		);
+		if (!marker) return;
+
+		setProject("timeline", "markers", (markers) => [
+			...(markers ?? []),
+			marker,
+		]);
+	};
+
+	return (
+		<Field
+			name="Chapters"
+			icon={<IconLucideFlag class="size-4" />}
+			value={
+				<EditorButton
+					onClick={addChapter}
+					leftIcon={<IconLucidePlus class="size-4" />}
+				>
+					Add at Playhead
+				</EditorButton>
+			}
+		>
+			<For each={chapters()}>
+				{(chapter) => (
+					<div class="flex gap-2 items-center">
+						<span class="w-12 text-xs text-gray-11">
+							{chapter.time === null ? "Cut" : formatTime(chapter.time)}
+						</span>
+						<TextInput
+							class="flex-1 px-3 py-2 text-sm rounded-lg border border-gray-3 bg-gray-2 text-gray-12"
+							value={chapter.title}
+							onChange={(e) =>
+								setProject(
+									"timeline",
+									"markers",
+									chapter.index,
+									"title",
+									e.currentTarget.value,
+								)
+							}
+						/>
+						<EditorButton
+							variant="danger"
+							onClick={() =>
+								setProject("timeline", "markers", (markers) =>
+									(markers ?? []).filter((_, j) => j !== chapter.index),
+								)
+							}
+							leftIcon={<IconCapTrash />}
+						/>
+					</div>
+				)}
+			</For>
+		</Field>
+	);
+}
+
+function VolumeKeyframesConfig(props: {
+	name: string;
+	track: keyof VolumeKeyframes;
+}) {
+	const { project, setProject, editorState } = useEditorContext();
+
+	const keyframes = () => project.audio.volumeKeyframes[props.track];
+
+	const addKeyframe = () => {
+		const time = editorState.previewTime ?? editorState.playbackTime;
+		const keyframe: VolumeKeyframe = { time, volumeDb: 0 };
+
+		setProject("audio", "volumeKeyframes", props.track, (keyframes) =>
+			[
+				...keyframes.filter((k) => Math.abs(k.time - time) > 0.01),
+				keyframe,
+			].sort((a, b) => a.time - b.time),
+		);
+	};
+
+	return (
+		<Field
+			name={props.name}
+			icon={<IconLucideVolume2 class="size-4" />}
+			disabled={project.audio.mute}
+			value={
+				<EditorButton
+					onClick={addKeyframe}
+					leftIcon={<IconLucidePlus class="size-4" />}
+				>
+					Add at Playhead
+				</EditorButton>
+			}
+		>
+			<For each={keyframes()}>
+				{(keyframe, i) => (
+					<div class="flex gap-2 items-center">
+						<div class="flex-1">
+							<LabeledSlider
+								name={`${keyframe.time.toFixed(2)}s`}
+								value={keyframe.volumeDb}
+								minValue={-30}
+								maxValue={10}
+								step={0.1}
+								format={(v) => (v <= -30 ? "Muted" : `${v.toFixed(1)} dB`)}
+								onChange={(v) =>
+									setProject(
+										"audio",
+										"volumeKeyframes",
+										props.track,
+										i(),
+										"volumeDb",
+										v,
+									)
+								}
+							/>
+						</div>
+						<EditorButton
+							variant="danger"
+							onClick={() =>
+								setProject(
+									"audio",
+									"volumeKeyframes",
+									props.track,
+									(keyframes) => keyframes.filter((_, j) => j !== i()),
+								)
+							}
+							leftIcon={<IconCapTrash />}
+						/>
+					</div>
+				)}
+			</For>
+		</Field>
+	);
+}
+
+const MUSIC_EXTENSIONS = ["mp3", "wav", "m4a", "aac", "flac", "ogg", "opus"];
+
+function MusicConfig() {
+	const { project, setProject, totalDuration, meta } = useEditorContext();
+
+	const addTrack = async () => {
+		const path = await openDialog({
+			filters: [{ name: "Audio Files", extensions: MUSIC_EXTENSIONS }],
+			multiple: false,
+		});
+		if (typeof path !== "string") return;
+
+		const track: MusicTrack = {
+			path,
+			start: 0,
+			end: null,
+			loop: true,
+			fadeIn: 1,
+			fadeOut: 2,
+			volumeDb: -12,
+		};
+		setProject("audio", "music", (music) => [...music, track]);
+	};
+
+	return (
+		<Field
+			name="Background Music"
+			icon={<IconLucideMusic class="size-4" />}
+			disabled={project.audio.mute}
+			value={
+				<EditorButton
+					onClick={addTrack}
+					leftIcon={<IconLucidePlus class="size-4" />}
+				>
+					Add
+				</EditorButton>
+			}
+		>
+			<For each={project.audio.music}>
+				{(track, i) => (
+					<div class="flex flex-col gap-2 p-3 rounded-lg border border-gray-3">
+						<div class="flex gap-2 items-center">
+							<span
+								class="flex-1 text-sm truncate text-gray-12"
+								title={track.path}
+							>
+								{track.path.split(/[\\/]/).pop()}
+							</span>
+							<EditorButton
+								variant="danger"
+								onClick={() =>
+									setProject("audio", "music", (music) =>
+										music.filter((_, j) => j !== i()),
+									)
+								}
+								leftIcon={<IconCapTrash />}
+							>
+								Remove
+							</EditorButton>
+						</div>
+						<LabeledSlider
+							name="Volume"
+							value={track.volumeDb ?? 0}
+							minValue={-30}
+							maxValue={10}
+							step={0.1}
+							format={(v) => (v <= -30 ? "Muted" : `${v.toFixed(1)} dB`)}
+							onChange={(v) => setProject("audio", "music", i(), "volumeDb", v)}
+						/>
+						<LabeledSlider
+							name="Start"
+							value={track.start ?? 0}
+							minValue={0}
+							maxValue={Math.max(totalDuration(), 1)}
+							step={0.1}
+							format={(v) => `${v.toFixed(1)}s`}
+							onChange={(v) => setProject("audio", "music", i(), "start", v)}
+						/>
+						<LabeledSlider
+							name="Fade In"
+							value={track.fadeIn ?? 0}
+							minValue={0}
+							maxValue={10}
+							step={0.1}
+							format={(v) => `${v.toFixed(1)}s`}
+							onChange={(v) => setProject("audio", "music", i(), "fadeIn", v)}
+						/>
+						<LabeledSlider
+							name="Fade Out"
+							value={track.fadeOut ?? 0}
+							minValue={0}
+							maxValue={10}
+							step={0.1}
+							format={(v) => `${v.toFixed(1)}s`}
+							onChange={(v) => setProject("audio", "music", i(), "fadeOut", v)}
+						/>
+						<Subfield name="Loop">
+							<Toggle
+								checked={track.loop ?? false}
+								onChange={(v) => setProject("audio", "music", i(), "loop", v)}
+							/>
+						</Subfield>
+					</div>
+				)}
+			</For>
+			<Show when={project.audio.music.length > 0}>
+				<Subfield name="Lower While Speaking">
+					<Toggle
+						disabled={!meta().hasMicrophone}
+						checked={project.audio.musicDucking.enabled}
+						onChange={(v) => setProject("audio", "musicDucking", "enabled", v)}
+					/>
+				</Subfield>
+				<Show when={project.audio.musicDucking.enabled}>
+					<LabeledSlider
+						name="Ducking Amount"
+						value={project.audio.musicDucking.amountDb}
+						minValue={3}
+						maxValue={24}
+						step={0.5}
+						format={(v) => `-${v.toFixed(1)} dB`}
+						onChange={(v) => setProject("audio", "musicDucking", "amountDb", v)}
+					/>
+				</Show>
+			</Show>
+		</Field>
+	);
+}
+
+function LabeledSlider(props: {
+	name: string;
+	value: number;
+	minValue: number;
+	maxValue: number;
+	step: number;
+	format: (value: number) => string;
+	onChange: (value: number) => void;
+}) {
+	return (
+		<Subfield name={props.name} class="gap-4 items-center">
+			<div class="flex flex-1 gap-3 items-center">
+				<Slider
+					class="flex-1"
+					value={[props.value]}
+					onChange={(v) => props.onChange(v[0])}
+					minValue={props.minValue}
+					maxValue={props.maxValue}
+					step={props.step}
+					formatTooltip={props.format}
+				/>
+				<span class="w-14 text-xs text-right text-gray-11">
+					{props.format(props.value)}
+				</span>
+			</div>
+		</Subfield>
+	);
+}
+
 function ZoomSegmentPreview(props: {
 	segmentIndex: number;
 	segment: ZoomSegment;
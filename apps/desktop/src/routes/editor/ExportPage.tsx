@@ -35,6 +35,7 @@ import {
 	type ExportCompression,
 	type ExportSettings,
 	type FramesRendered,
+	type LoudnessReport,
 	type UploadProgress,
 } from "~/utils/tauri";
 import { type RenderState, useEditorContext } from "./context";
@@ -98,6 +99,12 @@ export const EXPORT_TO_OPTIONS = [
 
 type ExportFormat = ExportSettings["format"];
 
+export const LOUDNESS_OPTIONS = [
+	{ label: "Off", value: null },
+	{ label: "-16 LUFS", value: -16 },
+	{ label: "-14 LUFS", value: -14 },
+] satisfies Array<{ label: string; value: number | null }>;
+
 const FORMAT_OPTIONS = [
 	{ label: "MP4", value: "Mp4" },
 	{ label: "GIF", value: "Gif" },
@@ -111,6 +118,7 @@ interface Settings {
 	exportTo: ExportToOption;
 	resolution: { label: string; value: string; width: number; height: number };
 	compression: ExportCompression;
+	loudness?: number | null;
 	organizationId?: string | null;
 }
 
@@ -371,6 +379,10 @@ export function ExportPage() {
 						compression: settings.compression,
 						custom_bpp: customBpp,
 						force_ffmpeg_decoder: forceFfmpegDecoder(),
+						loudness:
+							settings.loudness != null
+								? { integrated_lufs: settings.loudness, true_peak_db: -1 }
+								: null,
 					}
 				: {
 						format: "Gif",
@@ -390,6 +402,7 @@ export function ExportPage() {
 	};
 
 	const [outputPath, setOutputPath] = createSignal<string | null>(null);
+	const [loudness, setLoudness] = createSignal<LoudnessReport | null>(null);
 	const [isCancelled, setIsCancelled] = createSignal(false);
 
 	const handleCancel = async () => {
@@ -420,7 +433,7 @@ export function ExportPage() {
 			if (exportState.type !== "idle") return;
 			setExportState(reconcile({ action: "copy", type: "starting" }));
 
-			const outputPath = await exportWithSettings((progress) => {
+			const output = await exportWithSettings((progress) => {
 				if (isCancelled()) throw new SilentError("Cancelled");
 				setExportState({ type: "rendering", progress });
 			});
@@ -429,7 +442,7 @@ export function ExportPage() {
 
 			setExportState({ type: "copying" });
 
-			await commands.copyVideoToClipboard(outputPath);
+			await commands.copyVideoToClipboard(output.path);
 		},
 		onError: (error) => {
 			if (isCancelled() || isCancellationError(error)) {
@@ -473,6 +486,7 @@ export function ExportPage() {
 			setExportState(reconcile({ action: "save", type: "starting" }));
 
 			setOutputPath(savePath);
+			setLoudness(null);
 
 			trackEvent("export_started", {
 				resolution: settings.resolution,
@@ -480,7 +494,7 @@ export function ExportPage() {
 				path: savePath,
 			});
 
-			const videoPath = await exportWithSettings((progress) => {
+			const output = await exportWithSettings((progress) => {
 				if (isCancelled()) throw new SilentError("Cancelled");
 				setExportState({ type: "rendering", progress });
 			});
@@ -489,7 +503,13 @@ export function ExportPage() {
 
 			setExportState({ type: "copying" });
 
-			await commands.copyFileToPath(videoPath, savePath);
+			await commands.copyFileToPath(output.path, savePath);
+			await commands.copyExportSidecars(
+				output.path,
+				output.sidecars,
+				savePath,
+			);
+			setLoudness(output.loudness);
 
 			setExportState({ type: "done" });
 		},
@@ -604,6 +624,11 @@ export function ExportPage() {
 		},
 	}));
 
+	const formatLoudness = (report: LoudnessReport) => {
+		const integrated = report.integrated_lufs?.toFixed(1) ?? "-∞";
+		return `${integrated} LUFS integrated, ${report.true_peak_db.toFixed(1)} dBTP true peak`;
+	};
+
 	const formatDuration = (seconds: number) => {
 		const hours = Math.floor(seconds / 3600);
 		const minutes = Math.floor((seconds % 3600) / 60);
@@ -1125,6 +1150,33 @@ export function ExportPage() {
 									</div>
 								</Show>
 							</Field>
+
+							<Field
+								name="Loudness"
+								icon={<IconLucideVolume2 class="size-4" />}
+							>
+								<div class="flex gap-1.5">
+									<For each={LOUDNESS_OPTIONS}>
+										{(option) => (
+											<button
+												type="button"
+												class={cx(
+													"flex-1 py-2 text-sm font-medium rounded-lg border transition-colors",
+													(settings.loudness ?? null) === option.value
+														? "bg-gray-3 border-gray-5 text-gray-12"
+														: "bg-transparent border-transparent text-gray-11 hover:bg-gray-3 hover:border-gray-4",
+												)}
+												onClick={() => setSettings("loudness", option.value)}
+											>
+												{option.label}
+											</button>
+										)}
+									</For>
+								</div>
+								<div class="text-[10px] text-gray-10 mt-1.5 px-0.5">
+									Normalizes audio with a -1 dBTP true peak ceiling
+								</div>
+							</Field>
 						</Show>
 					</div>
 
@@ -1351,6 +1403,13 @@ export function ExportPage() {
 																	{settings.format === "Gif" ? "GIF" : "video"}{" "}
 																	is ready
 																</p>
+																<Show when={loudness()}>
+																	{(loudness) => (
+																		<p class="text-xs text-gray-10">
+																			{formatLoudness(loudness())}
+																		</p>
+																	)}
+																</Show>
 															</div>
 														</div>
 													</div>
@@ -2,6 +2,7 @@ import {
 	createEventListener,
 	createEventListenerMap,
 } from "@solid-primitives/event-listener";
+import { Menu } from "@tauri-apps/api/menu";
 import { cx } from "cva";
 import {
 	type ComponentProps,
@@ -18,7 +19,7 @@ import {
 } from "solid-js";
 import { produce } from "solid-js/store";
 
-import type { TimelineSegment } from "~/utils/tauri";
+import { commands, type TimelineSegment } from "~/utils/tauri";
 import { useEditorContext } from "../context";
 import { useSegmentContext, useTimelineContext } from "./context";
 import { getSectionMarker } from "./sectionMarker";
@@ -320,6 +321,21 @@ export function ClipTrack(
 
 	const { secsPerPixel, duration, isSegmentVisible } = useTimelineContext();
 
+	const handleCutSilence = async (speed: number | null) => {
+		try {
+			const segments = await commands.cutSilence({
+				thresholdDb: -40,
+				minDuration: 0.75,
+				padding: 0.2,
+				speed,
+			});
+			if (segments.length > 0 && project.timeline)
+				setProject("timeline", "segments", segments);
+		} catch (error) {
+			console.error("Failed to cut silence:", error);
+		}
+	};
+
 	const segments = (): Array<TimelineSegment> =>
 		project.timeline?.segments ?? [{ start: 0, end: duration(), timescale: 1 }];
 
@@ -367,6 +383,27 @@ export function ClipTrack(
 			ref={props.ref}
 			onMouseEnter={() => setEditorState("timeline", "hoveredTrack", "clip")}
 			onMouseLeave={() => setEditorState("timeline", "hoveredTrack", null)}
+			onContextMenu={async (e) => {
+				if (!editorInstance.recordings.segments.some((s) => s.mic)) return;
+
+				e.preventDefault();
+				const menu = await Menu.new({
+					id: "clip-track-options",
+					items: [
+						{
+							id: "cutSilence",
+							text: "Cut silence",
+							action: () => handleCutSilence(null),
+						},
+						{
+							id: "speedUpSilence",
+							text: "Speed up silence",
+							action: () => handleCutSilence(4),
+						},
+					],
+				});
+				menu.popup();
+			}}
 		>
 			<Index each={visibleSegmentIndices()}>
 				{(segmentIndex) => {
@@ -5,6 +5,7 @@ import { cx } from "cva";
 import {
 	createRoot,
 	createSignal,
+	For,
 	Index,
 	type JSX,
 	onMount,
@@ -16,6 +17,7 @@ import "./styles.css";
 
 import Tooltip from "~/components/Tooltip";
 import { commands } from "~/utils/tauri";
+import { createTimelineChapters } from "../chapters";
 import { FPS, type TimelineTrackType, useEditorContext } from "../context";
 import { formatTime } from "../utils";
 import { ClipTrack } from "./ClipTrack";
@@ -469,6 +471,7 @@ export function Timeline() {
 					<div class="absolute inset-0 flex items-end">
 						<TimelineMarkings />
 					</div>
+					<ChapterFlags />
 					<div class="absolute bottom-0">
 						<Tooltip content="Add track">
 							<TrackManager
@@ -638,3 +641,35 @@ function TimelineMarkings() {
 		</div>
 	);
 }
+
+function ChapterFlags() {
+	const { project, editorState } = useEditorContext();
+	const { secsPerPixel } = useTimelineContext();
+	const transform = () => editorState.timeline.transform;
+
+	const chapters = createTimelineChapters(() => project.timeline);
+
+	return (
+		<div
+			class="absolute inset-y-0 right-0 pointer-events-none"
+			style={{ left: `${TRACK_GUTTER}px` }}
+		>
+			<For each={chapters.latest.chapters}>
+				{(chapter) => (
+					<Tooltip content={chapter.title}>
+						<div
+							class="absolute top-0 bottom-0 w-px pointer-events-auto bg-blue-9"
+							style={{
+								transform: `translateX(${
+									(chapter.start - transform().position) / secsPerPixel()
+								}px)`,
+							}}
+						>
+							<IconLucideFlag class="absolute top-0 size-3 text-blue-9" />
+						</div>
+					</Tooltip>
+				)}
+			</For>
+		</div>
+	);
+}
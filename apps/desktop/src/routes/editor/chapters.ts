@@ -0,0 +1,57 @@
+import { createResource } from "solid-js";
+import {
+	type ChapterMarker,
+	commands,
+	type TimelineSegment,
+} from "~/utils/tauri";
+
+const segmentDuration = (segment: TimelineSegment) =>
+	(segment.end - segment.start) / segment.timescale;
+
+// Markers are placed on the recording, so they follow their content when the
+// timeline is cut
+export function markerAtTime(
+	segments: TimelineSegment[],
+	time: number,
+	title: string,
+): ChapterMarker | null {
+	let start = 0;
+
+	for (const segment of segments) {
+		const duration = segmentDuration(segment);
+		if (time < start + duration) {
+			return {
+				title,
+				recordingSegment: segment.recordingSegment ?? 0,
+				time: segment.start + (time - start) * segment.timescale,
+			};
+		}
+		start += duration;
+	}
+
+	return null;
+}
+
+// Asks `TimelineConfiguration::chapters` for the exported chapters and where
+// each marker lands, refetching whenever the segments or markers change
+export function createTimelineChapters(
+	timeline: () =>
+		| { segments: TimelineSegment[]; markers?: ChapterMarker[] }
+		| null
+		| undefined,
+) {
+	const [chapters] = createResource(
+		() => {
+			const value = timeline();
+			return {
+				segments: (value?.segments ?? []).map((segment) => ({ ...segment })),
+				zoomSegments: [],
+				markers: (value?.markers ?? []).map((marker) => ({ ...marker })),
+			};
+		},
+		(timeline) => commands.getTimelineChapters(timeline),
+		{ initialValue: { chapters: [], markerTimes: [] } },
+	);
+
+	return chapters;
+}
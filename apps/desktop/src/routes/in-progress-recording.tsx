@@ -117,6 +117,7 @@ function InProgressRecordingInner() {
 	const canPauseRecording = createMemo(() => {
 		const mode = recordingMode();
 		const os = ostype();
+		if (mode === "replay") return false;
 		return (
 			mode === "studio" ||
 			os === "macos" ||
@@ -591,7 +591,7 @@ function createRecordingMutations(
 			try {
 				if (isRecording) {
 					// First try to get existing rendered video
-					const outputPath = await exportWithDefaultSettings(
+					const output = await exportWithDefaultSettings(
 						createRenderProgressCallback("copy", setActionState),
 					);
 
@@ -607,7 +607,7 @@ function createRecordingMutations(
 						}),
 					);
 
-					await commands.copyVideoToClipboard(outputPath);
+					await commands.copyVideoToClipboard(output.path);
 				} else {
 					// For screenshots, show quick progress animation
 					setActionState({
@@ -676,11 +676,11 @@ function createRecordingMutations(
 			});
 
 			if (isRecording) {
-				const outputPath = await exportWithDefaultSettings(
+				const output = await exportWithDefaultSettings(
 					createRenderProgressCallback("save", setActionState),
 				);
 
-				await commands.copyFileToPath(outputPath, savePath);
+				await commands.copyFileToPath(output.path, savePath);
 			} else {
 				// For screenshots, show quick progress animation
 				setActionState({ type: "save", state: { type: "saving" } });
@@ -109,6 +109,9 @@ const DEFAULT_AUDIO: AudioConfiguration = {
 	micVolumeDb: 0,
 	micStereoMode: "stereo",
 	systemVolumeDb: 0,
+	volumeKeyframes: { mic: [], system: [] },
+	music: [],
+	musicDucking: { enabled: true, amountDb: 12, thresholdDb: -40 },
 };
 
 const DEFAULT_CURSOR: CursorConfiguration = {
@@ -933,6 +933,14 @@ function RecordingControls(props: {
 					},
 					checked: rawOptions.mode === "screenshot",
 				}),
+				await CheckMenuItem.new({
+					text: "Replay Mode",
+					action: () => {
+						setOptions("mode", "replay");
+						commands.setRecordingMode("replay");
+					},
+					checked: rawOptions.mode === "replay",
+				}),
 			],
 		});
 
@@ -1061,6 +1069,9 @@ function RecordingControls(props: {
 									<Match when={rawOptions.mode === "studio"}>
 										<IconCapFilmCut class="size-4 flex-shrink-0" />
 									</Match>
+									<Match when={rawOptions.mode === "replay"}>
+										<IconLucideHistory class="size-4 flex-shrink-0" />
+									</Match>
 									<Match when={rawOptions.mode === "instant"}>
 										<IconCapInstant class="size-4 flex-shrink-0" />
 									</Match>
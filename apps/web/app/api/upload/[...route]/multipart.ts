@@ -238,6 +238,78 @@ app.post(
 	},
 );
 
+app.post(
+	"/list-parts",
+	zValidator(
+		"json",
+		z.object({ uploadId: z.string() }).and(
+			z.union([
+				z.object({ videoId: z.string() }),
+				// deprecated
+				z.object({ fileKey: z.string() }),
+			]),
+		),
+	),
+	async (c) => {
+		const { uploadId, ...body } = c.req.valid("json");
+		const user = c.get("user");
+
+		const fileKey = parseVideoIdOrFileKey(user.id, {
+			...body,
+			subpath: "result.mp4",
+		});
+
+		return Effect.gen(function* () {
+			const [bucket] = yield* S3Buckets.getBucketAccessForUser(user.id);
+
+			const parts: { partNumber: number; etag: string; size: number }[] = [];
+			let marker: string | undefined;
+
+			do {
+				const page = yield* bucket.multipart.listParts(fileKey, uploadId, {
+					PartNumberMarker: marker,
+				});
+
+				for (const part of page.Parts ?? []) {
+					if (part.PartNumber === undefined || !part.ETag) continue;
+					parts.push({
+						partNumber: part.PartNumber,
+						etag: part.ETag.replaceAll('"', ""),
+						size: part.Size ?? 0,
+					});
+				}
+
+				marker = page.IsTruncated ? page.NextPartNumberMarker : undefined;
+			} while (marker);
+
+			return c.json({ parts });
+		}).pipe(
+			Effect.catchAll((error) => {
+				// S3 forgets uploads once they're completed, aborted or expired
+				const cause = "cause" in error ? error.cause : undefined;
+				if (
+					cause instanceof Error &&
+					(cause.name === "NoSuchUpload" || cause.name === "NotFound")
+				)
+					return Effect.succeed<Response>(c.text("Upload not found", 404));
+
+				console.error("Failed to list multipart upload parts:", error);
+				return Effect.succeed<Response>(
+					c.json(
+						{
+							error: "Failed to list multipart upload parts",
+							details: error instanceof Error ? error.message : String(error),
+						},
+						500,
+					),
+				);
+			}),
+			provideOptionalAuth,
+			runPromiseAnyEnv,
+		);
+	},
+);
+
 app.post(
 	"/complete",
 	zValidator(
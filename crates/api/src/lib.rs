@@ -1,3 +1,207 @@
-//! Types and implements for the Cap web API endpoints.
+//! Types and implementations for the Cap web API endpoints.
+//!
+//! TODO: We should investigate generating this with OpenAPI.
+//! This will come part of the EffectTS rewrite work.
 
-// TODO: Migrate `apps/desktop/src-tauri/upload.rs` here once we figure out how auth will work with that.
+mod upload;
+mod uploader;
+mod video;
+
+pub use upload::*;
+pub use uploader::*;
+pub use video::*;
+
+use reqwest::StatusCode;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("User is not authenticated or credentials have expired!")]
+    InvalidAuthentication,
+    #[error("User needs to upgrade their account to use this feature!")]
+    UpgradeRequired,
+    #[error("The request has timed out")]
+    Timeout,
+    #[error("api/{endpoint}/request: {source}")]
+    Request {
+        endpoint: &'static str,
+        source: reqwest::Error,
+    },
+    #[error("api/{endpoint}/{status}: {body}")]
+    Status {
+        endpoint: &'static str,
+        status: u16,
+        body: String,
+    },
+    #[error("api/{endpoint}/response: {source}")]
+    Response {
+        endpoint: &'static str,
+        source: reqwest::Error,
+    },
+    #[error("{0}")]
+    Upload(String),
+}
+
+impl Error {
+    fn request(endpoint: &'static str, source: reqwest::Error) -> Self {
+        if source.is_timeout() {
+            Self::Timeout
+        } else {
+            Self::Request { endpoint, source }
+        }
+    }
+}
+
+/// Client for the Cap web API.
+///
+/// Cheap to clone, as the underlying HTTP clients are reference counted.
+#[derive(Clone)]
+pub struct Client {
+    base_url: String,
+    auth_token: Option<String>,
+    headers: Vec<(&'static str, String)>,
+    http: reqwest::Client,
+    upload_http: reqwest::Client,
+    content_md5: bool,
+}
+
+impl Client {
+    /// Creates a client for the server at `base_url`, eg. `https://cap.so`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            auth_token: None,
+            headers: vec![],
+            http: reqwest::Client::new(),
+            upload_http: retryable_http_client().unwrap_or_default(),
+            content_md5: false,
+        }
+    }
+
+    /// Token sent as a bearer token with every authenticated request.
+    /// This can be either a session token or an API key.
+    pub fn with_auth_token(mut self, auth_token: impl Into<String>) -> Self {
+        self.auth_token = Some(auth_token.into());
+        self
+    }
+
+    /// Adds a header to every request made to the Cap API.
+    pub fn with_header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    /// Client used for requests to the Cap API.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Client used to upload file contents to presigned S3 URLs.
+    /// Defaults to [`retryable_http_client`].
+    pub fn with_upload_http_client(mut self, upload_http: reqwest::Client) -> Self {
+        self.upload_http = upload_http;
+        self
+    }
+
+    /// Self-hosted servers still use the legacy web API which requires a `Content-MD5` header
+    /// for each part of a multipart upload, so parts can't be presigned ahead of time.
+    pub fn with_content_md5(mut self, content_md5: bool) -> Self {
+        self.content_md5 = content_md5;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn url(&self, path: impl AsRef<str>) -> String {
+        format!("{}{}", self.base_url, path.as_ref())
+    }
+
+    pub fn share_link(&self, video_id: &str) -> String {
+        self.url(format!("/s/{video_id}"))
+    }
+
+    async fn authed_request(
+        &self,
+        endpoint: &'static str,
+        path: &str,
+        build: impl FnOnce(&reqwest::Client, String) -> reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, Error> {
+        let Some(auth_token) = &self.auth_token else {
+            return Err(Error::InvalidAuthentication);
+        };
+
+        let mut req = build(&self.http, self.url(path)).bearer_auth(auth_token);
+        for (name, value) in &self.headers {
+            req = req.header(*name, value);
+        }
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|err| Error::request(endpoint, err))?;
+
+        if resp.status() == StatusCode::UNAUTHORIZED {
+            return Err(Error::InvalidAuthentication);
+        }
+
+        Ok(resp)
+    }
+}
+
+/// Returns `resp` if it was successful, otherwise an [`Error::Status`] containing the response body.
+async fn error_for_status(
+    endpoint: &'static str,
+    resp: reqwest::Response,
+) -> Result<reqwest::Response, Error> {
+    if resp.status().is_success() {
+        return Ok(resp);
+    }
+
+    let status = resp.status().as_u16();
+    let body = resp
+        .text()
+        .await
+        .unwrap_or_else(|_| "<no response body>".to_string());
+
+    Err(Error::Status {
+        endpoint,
+        status,
+        body,
+    })
+}
+
+async fn json_response<T: serde::de::DeserializeOwned>(
+    endpoint: &'static str,
+    resp: reqwest::Response,
+) -> Result<T, Error> {
+    error_for_status(endpoint, resp)
+        .await?
+        .json::<T>()
+        .await
+        .map_err(|source| Error::Response { endpoint, source })
+}
+
+/// HTTP client that retries server and network errors with backoff,
+/// suitable for uploading to S3.
+pub fn retryable_http_client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .retry(
+            reqwest::retry::always()
+                .classify_fn(|req_rep| {
+                    match req_rep.status() {
+                        // Server errors
+                        Some(s) if s.is_server_error() || s == StatusCode::TOO_MANY_REQUESTS => {
+                            req_rep.retryable()
+                        }
+                        // Network errors
+                        None => req_rep.retryable(),
+                        _ => req_rep.success(),
+                    }
+                })
+                .max_retries_per_request(5)
+                .max_extra_load(5.0),
+        )
+        .build()
+}
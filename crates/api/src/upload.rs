@@ -0,0 +1,231 @@
+use cap_project::UploadedPartMeta;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{instrument, trace};
+
+use crate::{Client, Error, S3VideoMeta, json_response};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadedPart {
+    pub part_number: u32,
+    pub etag: String,
+    pub size: usize,
+    #[serde(skip)]
+    pub total_size: u64,
+    /// Position of the part's first byte in the uploaded file
+    #[serde(skip)]
+    pub offset: u64,
+}
+
+impl From<&UploadedPart> for UploadedPartMeta {
+    fn from(part: &UploadedPart) -> Self {
+        Self {
+            part_number: part.part_number,
+            etag: part.etag.clone(),
+            offset: part.offset,
+            size: part.size as u64,
+        }
+    }
+}
+
+/// Parts restored from a previous session don't know the total size of the file.
+impl From<UploadedPartMeta> for UploadedPart {
+    fn from(part: UploadedPartMeta) -> Self {
+        Self {
+            part_number: part.part_number,
+            etag: part.etag,
+            size: part.size as usize,
+            total_size: 0,
+            offset: part.offset,
+        }
+    }
+}
+
+/// A part the server has received for a multipart upload.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListedPart {
+    pub part_number: u32,
+    pub etag: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresignedS3PutRequestMethod {
+    #[allow(unused)]
+    Post,
+    Put,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresignedS3PutRequest {
+    pub video_id: String,
+    pub subpath: String,
+    pub method: PresignedS3PutRequestMethod,
+    #[serde(flatten)]
+    pub meta: Option<S3VideoMeta>,
+}
+
+impl Client {
+    #[instrument(skip(self))]
+    pub async fn upload_multipart_initiate(&self, video_id: &str) -> Result<String, Error> {
+        const ENDPOINT: &str = "upload_multipart_initiate";
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            upload_id: String,
+        }
+
+        let resp = self
+            .authed_request(ENDPOINT, "/api/upload/multipart/initiate", |c, url| {
+                c.post(url).json(&json!({
+                    "videoId": video_id,
+                    "contentType": "video/mp4"
+                }))
+            })
+            .await?;
+
+        json_response::<Response>(ENDPOINT, resp)
+            .await
+            .map(|data| data.upload_id)
+    }
+
+    #[instrument(skip(self, upload_id))]
+    pub async fn upload_multipart_presign_part(
+        &self,
+        video_id: &str,
+        upload_id: &str,
+        part_number: u32,
+        md5_sum: Option<&str>,
+    ) -> Result<String, Error> {
+        const ENDPOINT: &str = "upload_multipart_presign_part";
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            presigned_url: String,
+        }
+
+        let mut body = serde_json::Map::from_iter([
+            ("videoId".to_string(), json!(video_id)),
+            ("uploadId".to_string(), json!(upload_id)),
+            ("partNumber".to_string(), json!(part_number)),
+        ]);
+
+        if let Some(md5_sum) = md5_sum {
+            body.insert("md5Sum".to_string(), json!(md5_sum));
+        }
+
+        let resp = self
+            .authed_request(ENDPOINT, "/api/upload/multipart/presign-part", |c, url| {
+                c.post(url).json(&body)
+            })
+            .await?;
+
+        json_response::<Response>(ENDPOINT, resp)
+            .await
+            .map(|data| data.presigned_url)
+    }
+
+    /// Lists the parts uploaded so far. Fails with a 404 [`Error::Status`] once the upload
+    /// has been completed, aborted or has expired.
+    #[instrument(skip(self, upload_id))]
+    pub async fn upload_multipart_list_parts(
+        &self,
+        video_id: &str,
+        upload_id: &str,
+    ) -> Result<Vec<ListedPart>, Error> {
+        const ENDPOINT: &str = "upload_multipart_list_parts";
+
+        #[derive(Deserialize)]
+        struct Response {
+            parts: Vec<ListedPart>,
+        }
+
+        let resp = self
+            .authed_request(ENDPOINT, "/api/upload/multipart/list-parts", |c, url| {
+                c.post(url).json(&json!({
+                    "videoId": video_id,
+                    "uploadId": upload_id,
+                }))
+            })
+            .await?;
+
+        json_response::<Response>(ENDPOINT, resp)
+            .await
+            .map(|data| data.parts)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn upload_multipart_complete(
+        &self,
+        video_id: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+        meta: Option<S3VideoMeta>,
+    ) -> Result<Option<String>, Error> {
+        const ENDPOINT: &str = "upload_multipart_complete";
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct MultipartCompleteRequest<'a> {
+            video_id: &'a str,
+            upload_id: &'a str,
+            parts: &'a [UploadedPart],
+            #[serde(flatten)]
+            meta: Option<S3VideoMeta>,
+        }
+
+        #[derive(Deserialize)]
+        struct Response {
+            location: Option<String>,
+        }
+
+        trace!("Completing multipart upload");
+
+        let resp = self
+            .authed_request(ENDPOINT, "/api/upload/multipart/complete", |c, url| {
+                c.post(url).json(&MultipartCompleteRequest {
+                    video_id,
+                    upload_id,
+                    parts,
+                    meta,
+                })
+            })
+            .await?;
+
+        json_response::<Response>(ENDPOINT, resp)
+            .await
+            .map(|data| data.location)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn upload_signed(&self, body: PresignedS3PutRequest) -> Result<String, Error> {
+        const ENDPOINT: &str = "upload_signed";
+
+        #[derive(Deserialize)]
+        struct Data {
+            url: String,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            presigned_put_data: Data,
+        }
+
+        let resp = self
+            .authed_request(ENDPOINT, "/api/upload/signed", |client, url| {
+                client.post(url).json(&body)
+            })
+            .await?;
+
+        json_response::<Response>(ENDPOINT, resp)
+            .await
+            .map(|data| data.presigned_put_data.url)
+    }
+}
@@ -0,0 +1,547 @@
+// credit @filleduchaos
+
+use async_stream::try_stream;
+use bytes::Bytes;
+use flume::Receiver;
+use futures::{Stream, StreamExt, future::join, stream};
+use std::{
+    collections::HashMap,
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt, BufReader},
+    time::timeout,
+};
+use tracing::{Span, debug, error, info_span, instrument, trace, warn};
+use tracing_futures::Instrument;
+
+use cap_project::MultipartUploadProgress;
+
+use crate::{Client, Error, PresignedS3PutRequest, S3VideoMeta, UploadedPart};
+
+// The size of each S3 multipart upload chunk
+pub const MIN_CHUNK_SIZE: u64 = 5 * 1024 * 1024; // 5 MB
+pub const MAX_CHUNK_SIZE: u64 = 20 * 1024 * 1024; // 20 MB
+
+pub struct Chunk {
+    /// The total size of the file to be uploaded.
+    /// This can change as the recording grows.
+    pub total_size: u64,
+    /// The part number. `FILE_OFFSET = PART_NUMBER * CHUNK_SIZE`.
+    pub part_number: u32,
+    /// Position of the chunk's first byte in the file
+    pub offset: u64,
+    /// Actual data bytes of this chunk
+    pub chunk: Bytes,
+}
+
+/// Creates a stream that reads chunks from a file, yielding [Chunk]'s.
+#[instrument]
+pub fn from_file_to_chunks(path: PathBuf) -> impl Stream<Item = io::Result<Chunk>> {
+    try_stream! {
+        let file = File::open(path).await?;
+        let total_size = file.metadata().await?.len();
+        let mut file = BufReader::new(file);
+
+        let mut buf = vec![0u8; MAX_CHUNK_SIZE as usize];
+        let mut part_number = 0;
+        let mut offset = 0;
+        loop {
+            part_number += 1;
+
+            // a single read may return less than a full chunk,
+            // but every part except the last must meet S3's minimum part size
+            let mut n = 0;
+            while n < buf.len() {
+                match file.read(&mut buf[n..]).await? {
+                    0 => break,
+                    read => n += read,
+                }
+            }
+
+            if n == 0 { break; }
+            yield Chunk {
+                total_size,
+                part_number,
+                offset,
+                chunk: Bytes::copy_from_slice(&buf[..n]),
+            };
+            offset += n as u64;
+        }
+    }
+    .instrument(Span::current())
+}
+
+/// Creates a stream that reads chunks from a potentially growing file, yielding [Chunk]'s.
+/// The first chunk of the file is yielded last to allow for header rewriting after recording completion.
+/// This uploader will continually poll the filesystem and wait for the file to stop uploading before flushing the rest.
+#[instrument(skip(realtime_upload_done))]
+pub fn from_pending_file_to_chunks(
+    path: PathBuf,
+    realtime_upload_done: Option<Receiver<()>>,
+) -> impl Stream<Item = io::Result<Chunk>> {
+    pending_file_to_chunks(path, realtime_upload_done, (1, 0), None)
+}
+
+/// Like [from_pending_file_to_chunks], but continues after the parts that have already been uploaded.
+/// `progress` should have been truncated with [MultipartUploadProgress::truncate_to_first_gap].
+///
+/// When resuming part way through the file the first chunk is always yielded again at the end,
+/// as its header may have been rewritten after it was uploaded.
+#[instrument(skip(realtime_upload_done, progress))]
+pub fn resume_pending_file_to_chunks(
+    path: PathBuf,
+    realtime_upload_done: Option<Receiver<()>>,
+    progress: &MultipartUploadProgress,
+) -> impl Stream<Item = io::Result<Chunk>> + use<> {
+    pending_file_to_chunks(
+        path,
+        realtime_upload_done,
+        progress.resume_point(),
+        progress.parts.first().map(|part| part.size),
+    )
+}
+
+fn pending_file_to_chunks(
+    path: PathBuf,
+    realtime_upload_done: Option<Receiver<()>>,
+    (resume_part_number, resume_offset): (u32, u64),
+    resumed_first_chunk_size: Option<u64>,
+) -> impl Stream<Item = io::Result<Chunk>> {
+    try_stream! {
+        let mut file = timeout(Duration::from_secs(20), async move {
+            loop {
+                if let Ok(file) = tokio::fs::File::open(&path).await.map_err(|err| error!("from_pending_file_to_chunks/open: {err:?}")) {
+                    break file;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        })
+        .await
+        .map_err(|_| io::Error::other("Failed to open file. The recording pipeline may have crashed?"))?;
+
+        let mut part_number = resume_part_number;
+        let mut last_read_position: u64 = resume_offset;
+        let mut realtime_is_done = realtime_upload_done.as_ref().map(|_| false);
+        let mut first_chunk_size: Option<u64> = resumed_first_chunk_size;
+        let reemit_first_chunk = realtime_upload_done.is_some() || resume_offset > 0;
+        let mut chunk_buffer = vec![0u8; MAX_CHUNK_SIZE as usize];
+
+        loop {
+            // Check if realtime recording is done
+            if !realtime_is_done.unwrap_or(true) && let Some(ref realtime_receiver) = realtime_upload_done {
+                    match realtime_receiver.try_recv() {
+                        Ok(_) => realtime_is_done = Some(true),
+                        Err(flume::TryRecvError::Empty) => {},
+                        // This means all senders where dropped.
+                        // This can assume this means realtime is done.
+                        // It possibly means something has gone wrong but that's not the uploader's problem.
+                        Err(_) => realtime_is_done = Some(true),
+                    }
+
+            }
+
+            let file_size = match file.metadata().await {
+                Ok(metadata) => metadata.len(),
+                Err(_) => {
+                    // File might be temporarily locked, retry with shorter delay
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+
+            let new_data_size = file_size.saturating_sub(last_read_position);
+
+            // Determine if we should read a chunk
+            let should_read_chunk = if let Some(is_done) = realtime_is_done {
+                (new_data_size >= MIN_CHUNK_SIZE) || (is_done && new_data_size > 0)
+            } else {
+                new_data_size > 0
+            };
+
+            if should_read_chunk {
+                let chunk_size = std::cmp::min(new_data_size, MAX_CHUNK_SIZE) as usize;
+
+                file.seek(std::io::SeekFrom::Start(last_read_position)).await?;
+
+                let mut total_read = 0;
+                while total_read < chunk_size {
+                    match file.read(&mut chunk_buffer[total_read..chunk_size]).await {
+                        Ok(0) => break, // EOF
+                        Ok(n) => total_read += n,
+                        Err(e) => yield Err(e)?,
+                    }
+                }
+
+                if total_read > 0 {
+                    // Remember first chunk size for later re-emission with updated header
+                    if last_read_position == 0 {
+                        first_chunk_size = Some(total_read as u64);
+                    }
+
+                    yield Chunk {
+                        total_size: file_size,
+                        part_number,
+                        offset: last_read_position,
+                        chunk: Bytes::copy_from_slice(&chunk_buffer[..total_read]),
+                    };
+                    part_number += 1;
+                    last_read_position += total_read as u64;
+                }
+            } else if new_data_size == 0 && realtime_is_done.unwrap_or(true) {
+                // Recording is done and no new data - re-emit first chunk with corrected MP4 header
+                if let Some(first_size) = first_chunk_size && reemit_first_chunk {
+                    file.seek(std::io::SeekFrom::Start(0)).await?;
+
+                    let chunk_size = first_size as usize;
+                    let mut total_read = 0;
+
+                    while total_read < chunk_size {
+                        match file.read(&mut chunk_buffer[total_read..chunk_size]).await {
+                            Ok(0) => break,
+                            Ok(n) => total_read += n,
+                            Err(e) => yield Err(e)?,
+                        }
+                    }
+
+                    if total_read > 0 {
+                        yield Chunk {
+                            total_size: file_size,
+                            part_number: 1,
+                            offset: 0,
+                            chunk: Bytes::copy_from_slice(&chunk_buffer[..total_read]),
+                        };
+                    }
+                }
+                break;
+            } else {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    }
+    .instrument(Span::current())
+}
+
+/// An S3 multipart upload of a video's contents, created with [`MultipartUpload::initiate`].
+pub struct MultipartUpload {
+    client: Client,
+    video_id: String,
+    upload_id: String,
+}
+
+impl MultipartUpload {
+    /// Continues a multipart upload that was initiated previously, eg. by a process that has
+    /// since exited, keeping only the parts of `progress` the server still has.
+    ///
+    /// The server forgets uploads once they've been aborted or have expired,
+    /// in which case a new upload is initiated and the returned progress starts from scratch.
+    pub async fn resume(
+        client: Client,
+        video_id: impl Into<String>,
+        mut progress: MultipartUploadProgress,
+    ) -> Result<(Self, MultipartUploadProgress), Error> {
+        let video_id = video_id.into();
+
+        let listed = match client
+            .upload_multipart_list_parts(&video_id, &progress.upload_id)
+            .await
+        {
+            Ok(listed) => listed,
+            Err(Error::Status { status: 404, .. }) => {
+                warn!(
+                    "Multipart upload {} for {video_id} no longer exists, starting over",
+                    progress.upload_id
+                );
+                let upload = Self::initiate(client, video_id).await?;
+                let progress = MultipartUploadProgress::new(upload.upload_id());
+                return Ok((upload, progress));
+            }
+            Err(err) => return Err(err),
+        };
+
+        progress.parts.retain(|part| {
+            listed.iter().any(|listed| {
+                listed.part_number == part.part_number
+                    && listed.etag == part.etag
+                    && listed.size == part.size
+            })
+        });
+        progress.truncate_to_first_gap();
+
+        let upload = Self {
+            client,
+            video_id,
+            upload_id: progress.upload_id.clone(),
+        };
+
+        Ok((upload, progress))
+    }
+
+    pub async fn initiate(client: Client, video_id: impl Into<String>) -> Result<Self, Error> {
+        let video_id = video_id.into();
+        let upload_id = client.upload_multipart_initiate(&video_id).await?;
+
+        Ok(Self {
+            client,
+            video_id,
+            upload_id,
+        })
+    }
+
+    pub fn video_id(&self) -> &str {
+        &self.video_id
+    }
+
+    pub fn upload_id(&self) -> &str {
+        &self.upload_id
+    }
+
+    /// Takes an incoming stream of bytes and individually uploads them to S3.
+    ///
+    /// Note: It's on the caller to ensure the chunks are sized correctly within S3 limits.
+    #[instrument(skip(self, stream), fields(video_id = %self.video_id))]
+    pub fn upload_parts(
+        &self,
+        stream: impl Stream<Item = io::Result<Chunk>> + Send + 'static,
+    ) -> impl Stream<Item = Result<UploadedPart, Error>> + 'static {
+        const MAX_CONCURRENT_UPLOADS: usize = 3;
+
+        let client = self.client.clone();
+        let video_id = self.video_id.clone();
+        let upload_id = self.upload_id.clone();
+
+        debug!("Initializing multipart uploader for video {video_id:?}");
+        let start = Instant::now();
+        let video_id2 = video_id.clone();
+
+        stream::once(async move {
+            let use_md5_hashes = client.content_md5;
+            let first_chunk_presigned_url = Arc::new(Mutex::new(None::<(String, Instant)>));
+
+            // Resumed uploads start part way through, so we prefetch from whichever part comes first.
+            let mut stream = Box::pin(stream.peekable());
+            let first_part_number = match stream.as_mut().peek().await {
+                Some(Ok(chunk)) => chunk.part_number,
+                _ => 1,
+            };
+
+            stream::unfold(
+                (stream, first_part_number),
+                move |(mut stream, expected_part_number)| {
+                    let client = client.clone();
+                    let video_id = video_id.clone();
+                    let upload_id = upload_id.clone();
+                    let first_chunk_presigned_url = first_chunk_presigned_url.clone();
+
+                    async move {
+                        let (Some(item), presigned_url) = join(stream.next(), async {
+                            // Self-hosted still uses the legacy web API which requires these so we can't presign the URL.
+                            if use_md5_hashes {
+                                return Ok(None);
+                            }
+
+                            // We generate the presigned URL ahead of time for the part we expect to come next.
+                            // If it's not the chunk that actually comes next we just throw it out.
+                            // This means if the filesystem takes a while for the recording to reach previous total + CHUNK_SIZE, which is the common case, we aren't just doing nothing.
+                            client
+                                .upload_multipart_presign_part(
+                                    &video_id,
+                                    &upload_id,
+                                    expected_part_number,
+                                    None,
+                                )
+                                .await
+                                .map(Some)
+                        })
+                        .await
+                        else {
+                            return None;
+                        };
+
+                        let part_number = item
+                            .as_ref()
+                            .map(|c| c.part_number.to_string())
+                            .unwrap_or_else(|_| "--".into());
+
+                        Some((
+                            async move {
+                                let Chunk {
+                                    total_size,
+                                    part_number,
+                                    offset,
+                                    chunk,
+                                } = item.map_err(|err| {
+                                    Error::Upload(format!(
+                                        "uploader/part/{expected_part_number:?}/fs: {err:?}"
+                                    ))
+                                })?;
+                                trace!(
+                                    "Uploading chunk {part_number} ({} bytes) for video {video_id:?}",
+                                    chunk.len()
+                                );
+
+                                // We prefetched for the wrong chunk. Let's try again with the correct part number now that we know it.
+                                let md5_sum =
+                                    use_md5_hashes.then(|| base64::encode(md5::compute(&chunk).0));
+                                let presigned_url = if let Some(url) = presigned_url?
+                                    && part_number == expected_part_number
+                                {
+                                    url
+                                } else if part_number == 1
+                                    && !use_md5_hashes
+                                    // We have a presigned URL left around from the first chunk
+                                    && let Some((url, expiry)) = first_chunk_presigned_url
+                                        .lock()
+                                        .unwrap_or_else(PoisonError::into_inner)
+                                        .clone()
+                                    // The URL hasn't expired
+                                    && expiry.elapsed() < Duration::from_secs(60 * 50)
+                                {
+                                    url
+                                } else {
+                                    client
+                                        .upload_multipart_presign_part(
+                                            &video_id,
+                                            &upload_id,
+                                            part_number,
+                                            md5_sum.as_deref(),
+                                        )
+                                        .await?
+                                };
+
+                                // We cache the presigned URL for the first chunk,
+                                // as for instant mode we upload the first chunk at the end again to include the updated video metadata.
+                                if part_number == 1 {
+                                    *first_chunk_presigned_url
+                                        .lock()
+                                        .unwrap_or_else(PoisonError::into_inner) =
+                                        Some((presigned_url.clone(), Instant::now()));
+                                }
+
+                                let size = chunk.len();
+                                let mut req = client
+                                    .upload_http
+                                    .put(&presigned_url)
+                                    .header("Content-Length", chunk.len())
+                                    .timeout(Duration::from_secs(5 * 60))
+                                    .body(chunk);
+
+                                if let Some(md5_sum) = &md5_sum {
+                                    req = req.header("Content-MD5", md5_sum);
+                                }
+
+                                let resp = req
+                                    .send()
+                                    .instrument(info_span!("s3_put", size = size))
+                                    .await
+                                    .map_err(|err| {
+                                        Error::Upload(format!(
+                                            "uploader/part/{part_number}/error: {err:?}"
+                                        ))
+                                    })?;
+
+                                let etag = resp
+                                    .headers()
+                                    .get("ETag")
+                                    .as_ref()
+                                    .and_then(|etag| etag.to_str().ok())
+                                    .map(|v| v.trim_matches('"').to_string());
+
+                                if !resp.status().is_success() {
+                                    return Err(Error::Upload(format!(
+                                        "uploader/part/{part_number}/error: {}",
+                                        resp.text().await.unwrap_or_default()
+                                    )));
+                                }
+
+                                trace!("Completed upload of part {part_number}");
+
+                                Ok(UploadedPart {
+                                    etag: etag.ok_or_else(|| {
+                                        Error::Upload(format!(
+                                            "uploader/part/{part_number}/error: ETag header not found"
+                                        ))
+                                    })?,
+                                    part_number,
+                                    size,
+                                    total_size,
+                                    offset,
+                                })
+                            }
+                            .instrument(info_span!("upload_part", part_number = part_number)),
+                            (stream, expected_part_number + 1),
+                        ))
+                    }
+                },
+            )
+            .buffered(MAX_CONCURRENT_UPLOADS)
+            .boxed()
+        })
+        .chain(stream::once(async move {
+            debug!(
+                "Completed multipart upload for {video_id2:?} in {:?}",
+                start.elapsed()
+            );
+
+            stream::empty().boxed()
+        }))
+        .flatten()
+        .instrument(Span::current())
+    }
+
+    /// Completes the upload with the given parts.
+    /// If a part was uploaded more than once only the last upload of it is kept.
+    #[instrument(skip_all, fields(video_id = %self.video_id))]
+    pub async fn complete(
+        &self,
+        parts: impl IntoIterator<Item = UploadedPart>,
+        meta: Option<S3VideoMeta>,
+    ) -> Result<Option<String>, Error> {
+        // Deduplicate parts - keep the last occurrence of each part number
+        let mut deduplicated_parts = HashMap::new();
+        for part in parts {
+            deduplicated_parts.insert(part.part_number, part);
+        }
+        let mut parts = deduplicated_parts.into_values().collect::<Vec<_>>();
+        parts.sort_by_key(|part| part.part_number);
+
+        self.client
+            .upload_multipart_complete(&self.video_id, &self.upload_id, &parts, meta)
+            .await
+    }
+}
+
+impl Client {
+    /// Takes an incoming stream of bytes and streams them to an S3 object.
+    #[instrument(skip(self, stream))]
+    pub async fn singlepart_upload(
+        &self,
+        request: PresignedS3PutRequest,
+        total_size: u64,
+        stream: impl Stream<Item = io::Result<Bytes>> + Send + 'static,
+    ) -> Result<(), Error> {
+        let presigned_url = self.upload_signed(request).await?;
+
+        let resp = self
+            .upload_http
+            .put(&presigned_url)
+            .header("Content-Length", total_size)
+            .body(reqwest::Body::wrap_stream(stream))
+            .send()
+            .await
+            .map_err(|err| Error::Upload(format!("singlepart_uploader/error: {err:?}")))?;
+
+        if !resp.status().is_success() {
+            return Err(Error::Upload(format!(
+                "singlepart_uploader/error: {}",
+                resp.text().await.unwrap_or_default()
+            )));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+use cap_project::S3UploadMeta;
+use ffmpeg::ffi::AV_TIME_BASE;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::Path;
+use tracing::instrument;
+
+use crate::{Client, Error, error_for_status, json_response};
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct S3VideoMeta {
+    #[serde(rename = "durationInSecs")]
+    pub duration_in_secs: f64,
+    pub width: u32,
+    pub height: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fps: Option<f32>,
+}
+
+/// Reads the duration, size and frame rate of a video file to send with its upload.
+#[instrument]
+pub fn build_video_meta(path: &Path) -> Result<S3VideoMeta, String> {
+    let input =
+        ffmpeg::format::input(path).map_err(|e| format!("Failed to read input file: {e}"))?;
+    let video_stream = input
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or_else(|| "Failed to find appropriate video stream in file".to_string())?;
+
+    let video_codec = ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())
+        .map_err(|e| format!("Unable to read video codec information: {e}"))?;
+    let video = video_codec
+        .decoder()
+        .video()
+        .map_err(|e| format!("Unable to get video decoder: {e}"))?;
+
+    Ok(S3VideoMeta {
+        duration_in_secs: input.duration() as f64 / AV_TIME_BASE as f64,
+        width: video.width(),
+        height: video.height(),
+        fps: video
+            .frame_rate()
+            .map(|v| v.numerator() as f32 / v.denominator() as f32),
+    })
+}
+
+/// Options for [`Client::create_or_get_video`].
+#[derive(Debug, Clone, Default)]
+pub struct CreateVideo {
+    /// Fetches the upload config of an existing video instead of creating a new one
+    pub video_id: Option<String>,
+    pub is_screenshot: bool,
+    pub name: Option<String>,
+    pub meta: Option<S3VideoMeta>,
+    pub organization_id: Option<String>,
+}
+
+impl Client {
+    #[instrument(skip(self))]
+    pub async fn create_or_get_video(&self, options: CreateVideo) -> Result<S3UploadMeta, Error> {
+        const ENDPOINT: &str = "create_or_get_video";
+
+        let mut query = vec![("recordingMode", "desktopMP4".to_string())];
+
+        if let Some(id) = options.video_id {
+            query.push(("videoId", id));
+        } else if options.is_screenshot {
+            query.push(("isScreenshot", "true".to_string()));
+        }
+
+        if let Some(name) = options.name {
+            query.push(("name", name));
+        }
+
+        if let Some(meta) = options.meta {
+            query.push(("durationInSecs", meta.duration_in_secs.to_string()));
+            query.push(("width", meta.width.to_string()));
+            query.push(("height", meta.height.to_string()));
+            if let Some(fps) = meta.fps {
+                query.push(("fps", fps.to_string()));
+            }
+        }
+
+        if let Some(org_id) = options.organization_id {
+            query.push(("orgId", org_id));
+        }
+
+        let resp = self
+            .authed_request(ENDPOINT, "/api/desktop/video/create", |client, url| {
+                client.get(url).query(&query)
+            })
+            .await?;
+
+        if resp.status() == StatusCode::FORBIDDEN {
+            #[derive(Deserialize)]
+            struct CreateErrorResponse {
+                error: String,
+            }
+
+            let body = resp.text().await.unwrap_or_default();
+
+            if serde_json::from_str::<CreateErrorResponse>(&body)
+                .is_ok_and(|resp| resp.error == "upgrade_required")
+            {
+                return Err(Error::UpgradeRequired);
+            }
+
+            return Err(Error::Status {
+                endpoint: ENDPOINT,
+                status: StatusCode::FORBIDDEN.as_u16(),
+                body,
+            });
+        }
+
+        json_response(ENDPOINT, resp).await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn desktop_video_progress(
+        &self,
+        video_id: &str,
+        uploaded: u64,
+        total: u64,
+    ) -> Result<(), Error> {
+        const ENDPOINT: &str = "desktop_video_progress";
+
+        let resp = self
+            .authed_request(ENDPOINT, "/api/desktop/video/progress", |client, url| {
+                client.post(url).json(&json!({
+                    "videoId": video_id,
+                    "uploaded": uploaded,
+                    "total": total,
+                    "updatedAt": chrono::Utc::now().to_rfc3339()
+                }))
+            })
+            .await?;
+
+        error_for_status(ENDPOINT, resp).await?;
+
+        Ok(())
+    }
+}
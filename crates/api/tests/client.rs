@@ -0,0 +1,397 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query},
+    http::{HeaderMap, StatusCode},
+    routing::{get, post, put},
+};
+use bytes::Bytes;
+use cap_api::{Chunk, Client, CreateVideo, Error, MultipartUpload, S3VideoMeta};
+use cap_project::{MultipartUploadProgress, UploadedPartMeta};
+use futures::{StreamExt, TryStreamExt, stream};
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+
+/// Binds a mock server to a random local port, returning its base URL.
+async fn listen() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    (listener, base_url)
+}
+
+fn serve(listener: TcpListener, router: Router) {
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+}
+
+#[tokio::test]
+async fn create_or_get_video_sends_auth_and_options() {
+    let requests = Arc::new(Mutex::new(vec![]));
+    let (listener, base_url) = listen().await;
+
+    serve(
+        listener,
+        Router::new().route(
+            "/api/desktop/video/create",
+            get({
+                let requests = requests.clone();
+                move |headers: HeaderMap, Query(query): Query<HashMap<String, String>>| async move {
+                    let auth = headers["Authorization"].to_str().unwrap().to_string();
+                    requests.lock().unwrap().push((auth, query));
+                    Json(json!({ "id": "video-1" }))
+                }
+            }),
+        ),
+    );
+
+    let client = Client::new(&base_url).with_auth_token("token");
+    let meta = client
+        .create_or_get_video(CreateVideo {
+            name: Some("Standup & demo".to_string()),
+            meta: Some(S3VideoMeta {
+                duration_in_secs: 12.5,
+                width: 1920,
+                height: 1080,
+                fps: None,
+            }),
+            organization_id: Some("org-1".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(meta.id, "video-1");
+    assert_eq!(client.share_link(&meta.id), format!("{base_url}/s/video-1"));
+
+    let requests = requests.lock().unwrap();
+    let (auth, query) = &requests[0];
+    assert_eq!(auth, "Bearer token");
+    assert_eq!(query["recordingMode"], "desktopMP4");
+    assert_eq!(query["name"], "Standup & demo");
+    assert_eq!(query["durationInSecs"], "12.5");
+    assert_eq!(query["width"], "1920");
+    assert_eq!(query["orgId"], "org-1");
+    assert!(!query.contains_key("fps"));
+    assert!(!query.contains_key("videoId"));
+}
+
+#[tokio::test]
+async fn auth_errors_are_typed() {
+    let (listener, base_url) = listen().await;
+
+    serve(
+        listener,
+        Router::new()
+            .route(
+                "/api/desktop/video/create",
+                get(|| async {
+                    (
+                        StatusCode::FORBIDDEN,
+                        Json(json!({ "error": "upgrade_required" })),
+                    )
+                }),
+            )
+            .route(
+                "/api/upload/multipart/initiate",
+                post(|| async { StatusCode::UNAUTHORIZED }),
+            )
+            .route(
+                "/api/upload/signed",
+                post(|| async { (StatusCode::INTERNAL_SERVER_ERROR, "boom") }),
+            ),
+    );
+
+    let client = Client::new(&base_url).with_auth_token("token");
+
+    assert!(matches!(
+        client.create_or_get_video(CreateVideo::default()).await,
+        Err(Error::UpgradeRequired)
+    ));
+    assert!(matches!(
+        client.upload_multipart_initiate("video-1").await,
+        Err(Error::InvalidAuthentication)
+    ));
+    assert!(matches!(
+        client
+            .upload_signed(cap_api::PresignedS3PutRequest {
+                video_id: "video-1".to_string(),
+                subpath: "screenshot/screen-capture.jpg".to_string(),
+                method: cap_api::PresignedS3PutRequestMethod::Put,
+                meta: None,
+            })
+            .await,
+        Err(Error::Status { status: 500, ref body, .. }) if body == "boom"
+    ));
+
+    // requests without a token never reach the server
+    assert!(matches!(
+        Client::new(&base_url)
+            .upload_multipart_initiate("video-1")
+            .await,
+        Err(Error::InvalidAuthentication)
+    ));
+}
+
+#[tokio::test]
+async fn multipart_upload_presigns_uploads_and_completes() {
+    let uploads = Arc::new(Mutex::new(vec![]));
+    let completed = Arc::new(Mutex::new(None));
+    let (listener, base_url) = listen().await;
+
+    serve(
+        listener,
+        Router::new()
+            .route(
+                "/api/upload/multipart/initiate",
+                post(|Json(body): Json<Value>| async move {
+                    assert_eq!(body["videoId"], "video-1");
+                    Json(json!({ "uploadId": "upload-1" }))
+                }),
+            )
+            .route(
+                "/api/upload/multipart/presign-part",
+                post({
+                    let base_url = base_url.clone();
+                    move |Json(body): Json<Value>| async move {
+                        assert_eq!(body["uploadId"], "upload-1");
+                        Json(json!({
+                            "presignedUrl": format!("{base_url}/s3/{}", body["partNumber"])
+                        }))
+                    }
+                }),
+            )
+            .route(
+                "/s3/:part",
+                put({
+                    let uploads = uploads.clone();
+                    move |Path(part): Path<u32>, body: Bytes| async move {
+                        uploads.lock().unwrap().push((part, body));
+                        ([("ETag", format!("\"etag-{part}\""))], "")
+                    }
+                }),
+            )
+            .route(
+                "/api/upload/multipart/complete",
+                post({
+                    let completed = completed.clone();
+                    move |Json(body): Json<Value>| async move {
+                        *completed.lock().unwrap() = Some(body);
+                        Json(json!({ "location": "s3://bucket/video-1" }))
+                    }
+                }),
+            ),
+    );
+
+    let client = Client::new(&base_url).with_auth_token("token");
+    let upload = MultipartUpload::initiate(client, "video-1").await.unwrap();
+    assert_eq!(upload.upload_id(), "upload-1");
+
+    // instant mode uploads the first part again once the file's header has been rewritten
+    let chunks =
+        stream::iter([(1, "first"), (2, "second"), (1, "FIRST")]).map(|(part_number, data)| {
+            Ok(Chunk {
+                total_size: 11,
+                part_number,
+                offset: if part_number == 1 { 0 } else { 5 },
+                chunk: Bytes::from_static(data.as_bytes()),
+            })
+        });
+
+    let parts = upload
+        .upload_parts(chunks)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(parts.len(), 3);
+
+    let location = upload
+        .complete(
+            parts,
+            Some(S3VideoMeta {
+                duration_in_secs: 3.0,
+                width: 1280,
+                height: 720,
+                fps: Some(30.0),
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(location.as_deref(), Some("s3://bucket/video-1"));
+
+    let uploads = uploads.lock().unwrap();
+    assert_eq!(uploads.len(), 3);
+    assert!(uploads.contains(&(1, Bytes::from_static(b"FIRST"))));
+
+    let completed = completed.lock().unwrap().take().unwrap();
+    assert_eq!(
+        completed,
+        json!({
+            "videoId": "video-1",
+            "uploadId": "upload-1",
+            "parts": [
+                { "partNumber": 1, "etag": "etag-1", "size": 5 },
+                { "partNumber": 2, "etag": "etag-2", "size": 6 },
+            ],
+            "durationInSecs": 3.0,
+            "width": 1280,
+            "height": 720,
+            "fps": 30.0,
+        })
+    );
+}
+
+fn part(part_number: u32, etag: &str, offset: u64, size: u64) -> UploadedPartMeta {
+    UploadedPartMeta {
+        part_number,
+        etag: etag.to_string(),
+        offset,
+        size,
+    }
+}
+
+#[tokio::test]
+async fn resumed_multipart_upload_keeps_parts_the_server_has() {
+    let (listener, base_url) = listen().await;
+
+    serve(
+        listener,
+        Router::new().route(
+            "/api/upload/multipart/list-parts",
+            post(|Json(body): Json<Value>| async move {
+                assert_eq!(body["videoId"], "video-1");
+                assert_eq!(body["uploadId"], "upload-1");
+                // part 3 was uploaded again by another session
+                Json(json!({
+                    "parts": [
+                        { "partNumber": 1, "etag": "etag-1", "size": 5 },
+                        { "partNumber": 2, "etag": "etag-2", "size": 5 },
+                        { "partNumber": 3, "etag": "etag-3b", "size": 5 },
+                    ]
+                }))
+            }),
+        ),
+    );
+
+    let mut progress = MultipartUploadProgress::new("upload-1");
+    progress.parts = vec![
+        part(1, "etag-1", 0, 5),
+        part(2, "etag-2", 5, 5),
+        part(3, "etag-3", 10, 5),
+        part(4, "etag-4", 15, 5),
+    ];
+
+    let client = Client::new(&base_url).with_auth_token("token");
+    let (upload, progress) = MultipartUpload::resume(client, "video-1", progress)
+        .await
+        .unwrap();
+
+    assert_eq!(upload.upload_id(), "upload-1");
+    assert_eq!(
+        progress.parts,
+        vec![part(1, "etag-1", 0, 5), part(2, "etag-2", 5, 5)]
+    );
+    assert_eq!(progress.resume_point(), (3, 10));
+}
+
+#[tokio::test]
+async fn resumed_multipart_upload_presigns_each_part_once() {
+    let presigned = Arc::new(Mutex::new(vec![]));
+    let (listener, base_url) = listen().await;
+
+    serve(
+        listener,
+        Router::new()
+            .route(
+                "/api/upload/multipart/list-parts",
+                post(|| async {
+                    Json(json!({
+                        "parts": [
+                            { "partNumber": 1, "etag": "etag-1", "size": 5 },
+                            { "partNumber": 2, "etag": "etag-2", "size": 5 },
+                        ]
+                    }))
+                }),
+            )
+            .route(
+                "/api/upload/multipart/presign-part",
+                post({
+                    let base_url = base_url.clone();
+                    let presigned = presigned.clone();
+                    move |Json(body): Json<Value>| async move {
+                        let part_number = body["partNumber"].as_u64().unwrap();
+                        presigned.lock().unwrap().push(part_number);
+                        Json(json!({ "presignedUrl": format!("{base_url}/s3/{part_number}") }))
+                    }
+                }),
+            )
+            .route(
+                "/s3/:part",
+                put(|Path(part): Path<u32>| async move {
+                    ([("ETag", format!("\"etag-{part}\""))], "")
+                }),
+            ),
+    );
+
+    let mut progress = MultipartUploadProgress::new("upload-1");
+    progress.parts = vec![part(1, "etag-1", 0, 5), part(2, "etag-2", 5, 5)];
+
+    let client = Client::new(&base_url).with_auth_token("token");
+    let (upload, progress) = MultipartUpload::resume(client, "video-1", progress)
+        .await
+        .unwrap();
+    assert_eq!(progress.resume_point(), (3, 10));
+
+    let chunks = stream::iter([3, 4]).map(|part_number| {
+        Ok(Chunk {
+            total_size: 20,
+            part_number,
+            offset: u64::from(part_number - 1) * 5,
+            chunk: Bytes::from_static(b"chunk"),
+        })
+    });
+
+    let parts = upload
+        .upload_parts(chunks)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(parts.len(), 2);
+
+    // one presign per part, plus the prefetch for the part after the last
+    let mut presigned = presigned.lock().unwrap().clone();
+    presigned.sort();
+    assert_eq!(presigned, [3, 4, 5]);
+}
+
+#[tokio::test]
+async fn resuming_an_expired_multipart_upload_starts_over() {
+    let (listener, base_url) = listen().await;
+
+    serve(
+        listener,
+        Router::new()
+            .route(
+                "/api/upload/multipart/list-parts",
+                post(|| async { (StatusCode::NOT_FOUND, "Upload not found") }),
+            )
+            .route(
+                "/api/upload/multipart/initiate",
+                post(|| async { Json(json!({ "uploadId": "upload-2" })) }),
+            ),
+    );
+
+    let mut progress = MultipartUploadProgress::new("upload-1");
+    progress.parts = vec![part(1, "etag-1", 0, 5)];
+
+    let client = Client::new(&base_url).with_auth_token("token");
+    let (upload, progress) = MultipartUpload::resume(client, "video-1", progress)
+        .await
+        .unwrap();
+
+    assert_eq!(upload.upload_id(), "upload-2");
+    assert_eq!(progress.upload_id, "upload-2");
+    assert!(progress.parts.is_empty());
+}
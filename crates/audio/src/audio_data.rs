@@ -159,6 +159,11 @@ impl AudioData {
         inner(path.as_ref())
     }
 
+    /// Wraps interleaved samples that are already at [`Self::SAMPLE_RATE`].
+    pub fn new(samples: Vec<f32>, channels: u16) -> Self {
+        Self { samples, channels }
+    }
+
     pub fn channels(&self) -> u16 {
         self.channels
     }
@@ -0,0 +1,108 @@
+/// How quickly the detected sidechain level falls after a peak
+const LEVEL_RELEASE_SECS: f32 = 0.05;
+/// How long the music stays down after the sidechain goes quiet, to ride out gaps between words
+const HOLD_SECS: f32 = 0.3;
+const ATTACK_SECS: f32 = 0.05;
+const RELEASE_SECS: f32 = 0.5;
+
+/// Works out how far to turn music down while a sidechain signal, usually the mic, is active.
+/// The music ducks quickly when speech starts and recovers slowly once it stops.
+pub struct Ducker {
+    threshold: f32,
+    ducked_gain: f32,
+    level: f32,
+    level_release: f32,
+    hold: usize,
+    hold_frames: usize,
+    gain: f32,
+    attack: f32,
+    release: f32,
+}
+
+impl Ducker {
+    pub fn new(sample_rate: u32, threshold_db: f32, amount_db: f32) -> Self {
+        let coefficient = |secs: f32| 1.0 - (-1.0 / (secs * sample_rate as f32)).exp();
+
+        let mut ducker = Self {
+            threshold: 0.0,
+            ducked_gain: 1.0,
+            level: 0.0,
+            level_release: 1.0 - coefficient(LEVEL_RELEASE_SECS),
+            hold: 0,
+            hold_frames: (HOLD_SECS * sample_rate as f32) as usize,
+            gain: 1.0,
+            attack: coefficient(ATTACK_SECS),
+            release: coefficient(RELEASE_SECS),
+        };
+        ducker.set_levels(threshold_db, amount_db);
+        ducker
+    }
+
+    /// Changes the threshold and amount without resetting the current gain
+    pub fn set_levels(&mut self, threshold_db: f32, amount_db: f32) {
+        self.threshold = 10f32.powf(threshold_db / 20.0);
+        self.ducked_gain = 10f32.powf(-amount_db.abs() / 20.0);
+    }
+
+    /// Gain to apply to the music alongside the next frame of the sidechain,
+    /// which holds one sample per channel.
+    pub fn next(&mut self, frame: &[f32]) -> f32 {
+        let peak = frame.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        self.level = peak.max(self.level * self.level_release);
+
+        if self.level > self.threshold {
+            self.hold = self.hold_frames;
+        } else {
+            self.hold = self.hold.saturating_sub(1);
+        }
+
+        let target = if self.hold > 0 { self.ducked_gain } else { 1.0 };
+        let speed = if target < self.gain {
+            self.attack
+        } else {
+            self.release
+        };
+        self.gain += (target - self.gain) * speed;
+
+        self.gain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    fn run(ducker: &mut Ducker, amplitude: f32, secs: f32) -> f32 {
+        let mut gain = 1.0;
+        for i in 0..(secs * SAMPLE_RATE as f32) as usize {
+            let sample = amplitude * (i as f32 * 0.05).sin();
+            gain = ducker.next(&[sample, sample]);
+        }
+        gain
+    }
+
+    #[test]
+    fn quiet_sidechain_leaves_music_alone() {
+        let mut ducker = Ducker::new(SAMPLE_RATE, -40.0, 12.0);
+
+        assert_eq!(run(&mut ducker, 0.001, 1.0), 1.0);
+    }
+
+    #[test]
+    fn speech_ducks_music_then_it_recovers() {
+        let mut ducker = Ducker::new(SAMPLE_RATE, -40.0, 12.0);
+        let ducked = 10f32.powf(-12.0 / 20.0);
+
+        let during = run(&mut ducker, 0.3, 0.5);
+        assert!((during - ducked).abs() < 0.01, "{during}");
+
+        // short gaps between words keep it ducked
+        let gap = run(&mut ducker, 0.0, 0.2);
+        assert!((gap - ducked).abs() < 0.01, "{gap}");
+
+        let after = run(&mut ducker, 0.0, 3.0);
+        assert!(after > 0.99, "{after}");
+    }
+}
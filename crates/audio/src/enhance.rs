@@ -0,0 +1,428 @@
+use std::f32::consts::PI;
+
+use crate::AudioData;
+
+/// Cutoff of the high-pass that removes rumble, plosive thumps and desk bumps
+const HIGH_PASS_HZ: f32 = 80.0;
+
+/// Samples per noise suppression frame, about 21ms at 48kHz
+const FRAME_SIZE: usize = 1024;
+const HOP_SIZE: usize = FRAME_SIZE / 2;
+/// Most a frequency bin is turned down by the noise suppressor, about -20dB
+const MIN_NOISE_GAIN: f32 = 0.1;
+/// How much of the previous frame's clean speech estimate feeds the next gain,
+/// which keeps the suppressor from warbling on noise
+const PRIOR_SNR_SMOOTHING: f32 = 0.98;
+/// Largest number of frames sampled when profiling the noise floor
+const NOISE_PROFILE_FRAMES: usize = 1000;
+/// Fraction of the sampled frames, quietest first, that are assumed to hold only noise
+const NOISE_PROFILE_FRACTION: f32 = 0.1;
+/// Frames within this many dB of the noise floor are treated as noise and update it,
+/// so the estimate follows noise that changes over the recording
+const NOISE_UPDATE_MARGIN_DB: f32 = 3.0;
+const NOISE_UPDATE_RATE: f32 = 0.05;
+
+const DE_ESS_HZ: f32 = 5_000.0;
+const DE_ESS_THRESHOLD_DB: f32 = -30.0;
+const DE_ESS_RATIO: f32 = 4.0;
+const DE_ESS_MAX_REDUCTION_DB: f32 = 12.0;
+
+const COMPRESSOR_THRESHOLD_DB: f32 = -24.0;
+const COMPRESSOR_RATIO: f32 = 3.0;
+const COMPRESSOR_KNEE_DB: f32 = 6.0;
+const COMPRESSOR_ATTACK_SECS: f32 = 0.01;
+const COMPRESSOR_RELEASE_SECS: f32 = 0.15;
+const COMPRESSOR_MAKEUP_DB: f32 = 3.0;
+
+impl AudioData {
+    /// Returns a copy of this audio run through [`enhance_voice`].
+    pub fn enhance_voice(&self) -> AudioData {
+        AudioData::new(
+            enhance_voice(self.samples(), self.channels(), Self::SAMPLE_RATE),
+            self.channels(),
+        )
+    }
+}
+
+/// Cleans up a voice recording: a high-pass filter, spectral noise suppression, a de-esser and
+/// gentle compression, applied to each channel of the interleaved `samples`.
+///
+/// The whole recording is processed at once so the noise floor can be profiled up front and
+/// the output lines up sample for sample with the input.
+pub fn enhance_voice(samples: &[f32], channels: u16, sample_rate: u32) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    let sample_rate = sample_rate as f32;
+    let frame_count = samples.len() / channels;
+    let mut out = vec![0.0; samples.len()];
+
+    for channel in 0..channels {
+        let mut signal = samples[..frame_count * channels]
+            .iter()
+            .skip(channel)
+            .step_by(channels)
+            .copied()
+            .collect::<Vec<_>>();
+
+        Biquad::high_pass(HIGH_PASS_HZ, sample_rate).process(&mut signal);
+        let mut signal = suppress_noise(&signal);
+        de_ess(&mut signal, sample_rate);
+        compress(&mut signal, sample_rate);
+
+        for (i, sample) in signal.into_iter().enumerate() {
+            out[i * channels + channel] = sample;
+        }
+    }
+
+    out
+}
+
+/// Second order IIR filter in direct form I
+pub(crate) struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl Biquad {
+    /// `b` and `a` are the numerator and denominator coefficients, normalized so `a0` is 1
+    pub(crate) fn new([b0, b1, b2]: [f32; 3], [a1, a2]: [f32; 2]) -> Self {
+        Self {
+            b0,
+            b1,
+            b2,
+            a1,
+            a2,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+
+    /// Butterworth high-pass from the RBJ audio EQ cookbook
+    fn high_pass(cutoff: f32, sample_rate: f32) -> Self {
+        let w0 = 2.0 * PI * cutoff / sample_rate;
+        let alpha = w0.sin() / (2.0 * std::f32::consts::FRAC_1_SQRT_2);
+        let cos = w0.cos();
+        let a0 = 1.0 + alpha;
+
+        Self::new(
+            [
+                (1.0 + cos) / 2.0 / a0,
+                -(1.0 + cos) / a0,
+                (1.0 + cos) / 2.0 / a0,
+            ],
+            [-2.0 * cos / a0, (1.0 - alpha) / a0],
+        )
+    }
+
+    pub(crate) fn next(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1
+            - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+
+    fn process(&mut self, signal: &mut [f32]) {
+        for sample in signal {
+            *sample = self.next(*sample);
+        }
+    }
+}
+
+/// Turns down each frequency bin by how far it sits above the noise floor, like a Wiener filter
+/// with a decision-directed SNR estimate. The floor starts from the quietest frames of the whole
+/// recording and then follows any frame that's no louder than it.
+fn suppress_noise(signal: &[f32]) -> Vec<f32> {
+    let bins = FRAME_SIZE / 2 + 1;
+    let window = (0..FRAME_SIZE)
+        .map(|i| (PI * i as f32 / FRAME_SIZE as f32).sin())
+        .collect::<Vec<_>>();
+
+    // Padding both ends by a whole frame means every output sample is covered by two windows
+    let mut padded = vec![0.0; signal.len() + FRAME_SIZE * 2];
+    padded[FRAME_SIZE..FRAME_SIZE + signal.len()].copy_from_slice(signal);
+    let frame_starts = (0..=padded.len() - FRAME_SIZE)
+        .step_by(HOP_SIZE)
+        .collect::<Vec<_>>();
+
+    let spectrum = |start: usize, buffer: &mut Vec<Complex>| {
+        buffer.clear();
+        buffer.extend(
+            padded[start..start + FRAME_SIZE]
+                .iter()
+                .zip(&window)
+                .map(|(s, w)| Complex::new(s * w, 0.0)),
+        );
+        fft(buffer, false);
+    };
+
+    let mut buffer = Vec::with_capacity(FRAME_SIZE);
+    let mut noise = noise_profile(&frame_starts, bins, |start| {
+        spectrum(start, &mut buffer);
+        buffer[..bins].iter().map(Complex::norm_sqr).collect()
+    });
+
+    let mut out = vec![0.0; padded.len()];
+    let mut previous_clean = vec![0.0f32; bins];
+
+    let update_margin = db_to_amplitude(NOISE_UPDATE_MARGIN_DB * 2.0);
+
+    for &start in &frame_starts {
+        spectrum(start, &mut buffer);
+
+        let frame_power = buffer[..bins].iter().map(Complex::norm_sqr).sum::<f32>();
+        if frame_power > 0.0 && frame_power < noise.iter().sum::<f32>() * update_margin {
+            for (bin, noise) in noise.iter_mut().enumerate() {
+                *noise += (buffer[bin].norm_sqr() - *noise) * NOISE_UPDATE_RATE;
+            }
+        }
+
+        for bin in 0..bins {
+            let power = buffer[bin].norm_sqr();
+            let posterior_snr = power / noise[bin];
+            let prior_snr = PRIOR_SNR_SMOOTHING * previous_clean[bin] / noise[bin]
+                + (1.0 - PRIOR_SNR_SMOOTHING) * (posterior_snr - 1.0).max(0.0);
+            let gain = (prior_snr / (1.0 + prior_snr)).max(MIN_NOISE_GAIN);
+
+            previous_clean[bin] = gain * gain * power;
+            buffer[bin] = buffer[bin].scale(gain);
+            if bin > 0 && bin < FRAME_SIZE / 2 {
+                buffer[FRAME_SIZE - bin] = buffer[bin].conj();
+            }
+        }
+
+        fft(&mut buffer, true);
+
+        for (i, value) in buffer.iter().enumerate() {
+            out[start + i] += value.re * window[i];
+        }
+    }
+
+    out.drain(..FRAME_SIZE);
+    out.truncate(signal.len());
+    out
+}
+
+/// Average power of each bin over the quietest [`NOISE_PROFILE_FRACTION`] of the frames.
+/// Frames of digital silence are skipped so padding and muted stretches don't count as noise.
+fn noise_profile(
+    frame_starts: &[usize],
+    bins: usize,
+    mut power_spectrum: impl FnMut(usize) -> Vec<f32>,
+) -> Vec<f32> {
+    let stride = frame_starts.len().div_ceil(NOISE_PROFILE_FRAMES).max(1);
+
+    let mut spectra = frame_starts
+        .iter()
+        .step_by(stride)
+        .map(|&start| power_spectrum(start))
+        .filter(|spectrum| spectrum.iter().any(|p| *p > 0.0))
+        .collect::<Vec<_>>();
+    if spectra.is_empty() {
+        return vec![f32::MIN_POSITIVE; bins];
+    }
+
+    spectra.sort_by(|a, b| a.iter().sum::<f32>().total_cmp(&b.iter().sum::<f32>()));
+    let quietest = &spectra[..((spectra.len() as f32 * NOISE_PROFILE_FRACTION) as usize).max(1)];
+
+    (0..bins)
+        .map(|bin| {
+            (quietest.iter().map(|s| s[bin]).sum::<f32>() / quietest.len() as f32)
+                .max(f32::MIN_POSITIVE)
+        })
+        .collect()
+}
+
+/// Turns down the band above [`DE_ESS_HZ`] while it's louder than the threshold,
+/// which softens harsh 's' and 't' sounds without dulling the rest of the voice.
+fn de_ess(signal: &mut [f32], sample_rate: f32) {
+    let mut high_pass = Biquad::high_pass(DE_ESS_HZ, sample_rate);
+    let mut envelope = Envelope::new(0.001, 0.05, sample_rate);
+
+    for sample in signal {
+        let high = high_pass.next(*sample);
+        let level_db = amplitude_to_db(envelope.next(high.abs()));
+        let reduction_db = ((level_db - DE_ESS_THRESHOLD_DB).max(0.0) * (1.0 - 1.0 / DE_ESS_RATIO))
+            .min(DE_ESS_MAX_REDUCTION_DB);
+
+        *sample -= high * (1.0 - db_to_amplitude(-reduction_db));
+    }
+}
+
+/// Soft-knee feed-forward compressor that evens out loud and quiet words
+fn compress(signal: &mut [f32], sample_rate: f32) {
+    let mut envelope = Envelope::new(COMPRESSOR_ATTACK_SECS, COMPRESSOR_RELEASE_SECS, sample_rate);
+    let makeup = db_to_amplitude(COMPRESSOR_MAKEUP_DB);
+
+    for sample in signal {
+        let level_db = amplitude_to_db(envelope.next(sample.abs()));
+        let over = level_db - COMPRESSOR_THRESHOLD_DB;
+        let slope = 1.0 - 1.0 / COMPRESSOR_RATIO;
+
+        let reduction_db = if over <= -COMPRESSOR_KNEE_DB / 2.0 {
+            0.0
+        } else if over < COMPRESSOR_KNEE_DB / 2.0 {
+            slope * (over + COMPRESSOR_KNEE_DB / 2.0).powi(2) / (2.0 * COMPRESSOR_KNEE_DB)
+        } else {
+            slope * over
+        };
+
+        *sample *= db_to_amplitude(-reduction_db) * makeup;
+    }
+}
+
+/// Peak follower with separate attack and release times
+struct Envelope {
+    attack: f32,
+    release: f32,
+    value: f32,
+}
+
+impl Envelope {
+    fn new(attack_secs: f32, release_secs: f32, sample_rate: f32) -> Self {
+        Self {
+            attack: (-1.0 / (attack_secs * sample_rate)).exp(),
+            release: (-1.0 / (release_secs * sample_rate)).exp(),
+            value: 0.0,
+        }
+    }
+
+    fn next(&mut self, level: f32) -> f32 {
+        let coefficient = if level > self.value {
+            self.attack
+        } else {
+            self.release
+        };
+        self.value = coefficient * self.value + (1.0 - coefficient) * level;
+        self.value
+    }
+}
+
+fn amplitude_to_db(amplitude: f32) -> f32 {
+    20.0 * amplitude.max(1e-9).log10()
+}
+
+fn db_to_amplitude(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Complex {
+    re: f32,
+    im: f32,
+}
+
+impl Complex {
+    fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+
+    fn norm_sqr(&self) -> f32 {
+        self.re * self.re + self.im * self.im
+    }
+
+    fn scale(self, factor: f32) -> Self {
+        Self::new(self.re * factor, self.im * factor)
+    }
+
+    fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+/// In-place radix-2 FFT. `buffer.len()` must be a power of two.
+/// The inverse transform is scaled by `1 / len` so a round trip returns the input.
+fn fft(buffer: &mut [Complex], inverse: bool) {
+    let n = buffer.len();
+    debug_assert!(n.is_power_of_two());
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            buffer.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * 2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let twiddle = Complex::new((angle * k as f32).cos(), (angle * k as f32).sin());
+                let a = buffer[start + k];
+                let b = buffer[start + k + len / 2].mul(twiddle);
+                buffer[start + k] = Complex::new(a.re + b.re, a.im + b.im);
+                buffer[start + k + len / 2] = Complex::new(a.re - b.re, a.im - b.im);
+            }
+        }
+        len <<= 1;
+    }
+
+    if inverse {
+        let scale = 1.0 / n as f32;
+        for value in buffer {
+            *value = value.scale(scale);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fft_round_trip_returns_input() {
+        let input = (0..64)
+            .map(|i| Complex::new((i as f32 * 0.3).sin(), 0.0))
+            .collect::<Vec<_>>();
+        let mut buffer = input.clone();
+
+        fft(&mut buffer, false);
+        fft(&mut buffer, true);
+
+        for (a, b) in input.iter().zip(&buffer) {
+            assert!((a.re - b.re).abs() < 1e-5 && b.im.abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn high_pass_removes_rumble_and_keeps_voice_band() {
+        let level_after_filter = |frequency: f32| {
+            let mut signal = (0..48_000)
+                .map(|i| (2.0 * PI * frequency * i as f32 / 48_000.0).sin())
+                .collect::<Vec<_>>();
+            Biquad::high_pass(HIGH_PASS_HZ, 48_000.0).process(&mut signal);
+            signal[24_000..]
+                .iter()
+                .fold(0.0f32, |max, s| max.max(s.abs()))
+        };
+
+        assert!(level_after_filter(20.0) < 0.1);
+        assert!(level_after_filter(1_000.0) > 0.95);
+    }
+}
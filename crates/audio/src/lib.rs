@@ -1,13 +1,21 @@
 mod audio_data;
 mod calibration_store;
+mod ducking;
+mod enhance;
 mod latency;
+mod loudness;
 mod renderer;
+mod silence;
 mod sync_analysis;
 
 pub use audio_data::*;
 pub use calibration_store::*;
+pub use ducking::*;
+pub use enhance::*;
 pub use latency::*;
+pub use loudness::*;
 pub use renderer::*;
+pub use silence::*;
 pub use sync_analysis::*;
 
 pub trait FromSampleBytes: cpal::SizedSample + std::fmt::Debug + Send + 'static {
@@ -0,0 +1,422 @@
+use std::{collections::VecDeque, f64::consts::PI};
+
+use crate::enhance::Biquad;
+
+/// Loudness is measured over 400ms blocks that overlap by 75%, so one ends every 100ms
+const BLOCK_STEPS: usize = 4;
+const STEP_SECS: f64 = 0.1;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+const OVERSAMPLING: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+
+const LIMITER_LOOKAHEAD_SECS: f32 = 0.005;
+const LIMITER_RELEASE_SECS: f32 = 0.1;
+
+/// Integrated loudness and true peak as defined by ITU-R BS.1770 and used by EBU R128
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoudnessMeasurement {
+    /// `None` when all of the audio is below the -70 LUFS absolute gate, such as silence
+    pub integrated_lufs: Option<f64>,
+    pub true_peak_db: f64,
+}
+
+/// Measures interleaved audio as it's pushed in. Every channel is weighted equally,
+/// which matches BS.1770 for mono and stereo.
+pub struct LoudnessMeter {
+    channels: usize,
+    k_weighting: Vec<[Biquad; 2]>,
+    step_frames: usize,
+    step_energy: f64,
+    step_position: usize,
+    recent_steps: VecDeque<f64>,
+    // mean square of each complete block
+    block_powers: Vec<f64>,
+    true_peak: TruePeak,
+    max_peak: f32,
+}
+
+impl LoudnessMeter {
+    pub fn new(channels: u16, sample_rate: u32) -> Self {
+        let channels = channels.max(1) as usize;
+
+        Self {
+            channels,
+            k_weighting: (0..channels).map(|_| k_weighting(sample_rate)).collect(),
+            step_frames: (sample_rate as f64 * STEP_SECS).round() as usize,
+            step_energy: 0.0,
+            step_position: 0,
+            recent_steps: VecDeque::with_capacity(BLOCK_STEPS),
+            block_powers: vec![],
+            true_peak: TruePeak::new(channels),
+            max_peak: 0.0,
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            let mut energy = 0.0;
+            for (sample, [shelf, high_pass]) in frame.iter().zip(&mut self.k_weighting) {
+                let weighted = high_pass.next(shelf.next(*sample)) as f64;
+                energy += weighted * weighted;
+            }
+
+            self.max_peak = self.max_peak.max(self.true_peak.push(frame));
+
+            self.step_energy += energy;
+            self.step_position += 1;
+            if self.step_position < self.step_frames {
+                continue;
+            }
+
+            if self.recent_steps.len() == BLOCK_STEPS {
+                self.recent_steps.pop_front();
+            }
+            self.recent_steps.push_back(self.step_energy);
+            if self.recent_steps.len() == BLOCK_STEPS {
+                self.block_powers.push(
+                    self.recent_steps.iter().sum::<f64>() / (BLOCK_STEPS * self.step_frames) as f64,
+                );
+            }
+
+            self.step_energy = 0.0;
+            self.step_position = 0;
+        }
+    }
+
+    /// Loudness of the audio pushed so far, gated as in BS.1770-4
+    pub fn integrated_lufs(&self) -> Option<f64> {
+        let above_absolute_gate = self
+            .block_powers
+            .iter()
+            .copied()
+            .filter(|power| block_loudness(*power) > ABSOLUTE_GATE_LUFS)
+            .collect::<Vec<_>>();
+        if above_absolute_gate.is_empty() {
+            return None;
+        }
+
+        let relative_gate = block_loudness(mean(&above_absolute_gate)) + RELATIVE_GATE_LU;
+        let gated = above_absolute_gate
+            .into_iter()
+            .filter(|power| block_loudness(*power) > relative_gate)
+            .collect::<Vec<_>>();
+
+        Some(block_loudness(mean(&gated)))
+    }
+
+    /// Highest sample peak seen so far, including the peaks between samples, in dBTP
+    pub fn true_peak_db(&self) -> f64 {
+        amplitude_to_db(self.max_peak)
+    }
+
+    pub fn measurement(&self) -> LoudnessMeasurement {
+        LoudnessMeasurement {
+            integrated_lufs: self.integrated_lufs(),
+            true_peak_db: self.true_peak_db(),
+        }
+    }
+}
+
+/// Keeps the true peak of interleaved audio under a ceiling, smoothly turning it down ahead of
+/// each peak rather than clipping it. Output is delayed by [`TruePeakLimiter::latency`] frames.
+pub struct TruePeakLimiter {
+    channels: usize,
+    ceiling: f32,
+    detector: TruePeak,
+    window: usize,
+    delay: VecDeque<f32>,
+    // indices and gains of the lowest gain over the last `window` frames, lowest first
+    hold: VecDeque<(usize, f32)>,
+    envelope: f32,
+    release: f32,
+    smoothing: VecDeque<f32>,
+    smoothing_sum: f64,
+    position: usize,
+}
+
+impl TruePeakLimiter {
+    pub fn new(channels: u16, sample_rate: u32, ceiling_db: f64) -> Self {
+        let channels = channels.max(1) as usize;
+        let window = ((sample_rate as f32 * LIMITER_LOOKAHEAD_SECS) as usize).max(1);
+        let latency = TruePeak::DELAY + window - 1;
+
+        Self {
+            channels,
+            ceiling: db_to_amplitude(ceiling_db) as f32,
+            detector: TruePeak::new(channels),
+            window,
+            delay: std::iter::repeat_n(0.0, latency * channels).collect(),
+            hold: VecDeque::new(),
+            envelope: 1.0,
+            release: (-1.0 / (LIMITER_RELEASE_SECS * sample_rate as f32)).exp(),
+            smoothing: std::iter::repeat_n(1.0, window).collect(),
+            smoothing_sum: window as f64,
+            position: 0,
+        }
+    }
+
+    /// Frames between a sample going in and coming back out of [`Self::process`]
+    pub fn latency(&self) -> usize {
+        self.delay.len() / self.channels
+    }
+
+    /// Limits `samples` in place. Each call returns the audio from [`Self::latency`] frames
+    /// earlier, starting with silence.
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for frame in samples.chunks_exact_mut(self.channels) {
+            self.delay.extend(frame.iter().copied());
+
+            // the detector's peaks lag the input, so they line up with a frame in the delay line
+            let detected = self.delay.len() - (TruePeak::DELAY + 1) * self.channels;
+            let sample_peak = self
+                .delay
+                .range(detected..detected + self.channels)
+                .fold(0.0f32, |max, s| max.max(s.abs()));
+            let peak = self.detector.push(frame).max(sample_peak);
+            let gain = if peak > self.ceiling {
+                self.ceiling / peak
+            } else {
+                1.0
+            };
+
+            while self.hold.back().is_some_and(|(_, held)| *held >= gain) {
+                self.hold.pop_back();
+            }
+            self.hold.push_back((self.position, gain));
+            while self
+                .hold
+                .front()
+                .is_some_and(|(i, _)| *i + self.window <= self.position)
+            {
+                self.hold.pop_front();
+            }
+            let held = self.hold.front().map_or(1.0, |(_, gain)| *gain);
+
+            self.envelope = held.min(self.envelope + (1.0 - self.envelope) * (1.0 - self.release));
+
+            self.smoothing.push_back(self.envelope);
+            self.smoothing_sum += self.envelope as f64;
+            if let Some(oldest) = self.smoothing.pop_front() {
+                self.smoothing_sum -= oldest as f64;
+            }
+            let gain = ((self.smoothing_sum / self.window as f64) as f32).min(1.0);
+
+            for sample in frame.iter_mut() {
+                *sample = self.delay.pop_front().unwrap_or_default() * gain;
+            }
+
+            self.position += 1;
+        }
+    }
+}
+
+/// Finds the peaks between samples by upsampling 4x with a windowed sinc filter
+struct TruePeak {
+    channels: usize,
+    phases: [[f32; TAPS_PER_PHASE]; OVERSAMPLING],
+    history: Vec<[f32; TAPS_PER_PHASE]>,
+}
+
+impl TruePeak {
+    /// Frames the interpolated peaks lag behind the input
+    const DELAY: usize = TAPS_PER_PHASE / 2;
+
+    fn new(channels: usize) -> Self {
+        let taps = OVERSAMPLING * TAPS_PER_PHASE;
+        let center = (taps - 1) as f64 / 2.0;
+
+        let mut phases = [[0.0; TAPS_PER_PHASE]; OVERSAMPLING];
+        for (phase, coefficients) in phases.iter_mut().enumerate() {
+            for (k, coefficient) in coefficients.iter_mut().enumerate() {
+                let n = phase + k * OVERSAMPLING;
+                let x = (n as f64 - center) / OVERSAMPLING as f64;
+                let sinc = if x == 0.0 {
+                    1.0
+                } else {
+                    (PI * x).sin() / (PI * x)
+                };
+                let window = 0.5 - 0.5 * (2.0 * PI * n as f64 / (taps - 1) as f64).cos();
+                *coefficient = (sinc * window) as f32;
+            }
+
+            let sum = coefficients.iter().sum::<f32>();
+            for coefficient in coefficients.iter_mut() {
+                *coefficient /= sum;
+            }
+        }
+
+        Self {
+            channels,
+            phases,
+            history: vec![[0.0; TAPS_PER_PHASE]; channels],
+        }
+    }
+
+    /// Adds one interleaved frame and returns the highest interpolated peak across channels
+    fn push(&mut self, frame: &[f32]) -> f32 {
+        let mut peak = 0.0f32;
+
+        for (sample, history) in frame.iter().zip(&mut self.history).take(self.channels) {
+            history.copy_within(0..TAPS_PER_PHASE - 1, 1);
+            history[0] = *sample;
+
+            for coefficients in &self.phases {
+                let value = coefficients
+                    .iter()
+                    .zip(history.iter())
+                    .map(|(c, x)| c * x)
+                    .sum::<f32>();
+                peak = peak.max(value.abs());
+            }
+        }
+
+        peak
+    }
+}
+
+/// Pre-filter that approximates how loud people hear each frequency, from BS.1770.
+/// The coefficients are derived for any sample rate rather than hardcoded for 48kHz.
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let sample_rate = sample_rate as f64;
+
+    let shelf = {
+        let f0 = 1681.974450955533;
+        let gain_db = 3.999843853973347;
+        let q = 0.7071752369554196;
+
+        let k = (PI * f0 / sample_rate).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+
+        Biquad::new(
+            [
+                ((vh + vb * k / q + k * k) / a0) as f32,
+                (2.0 * (k * k - vh) / a0) as f32,
+                ((vh - vb * k / q + k * k) / a0) as f32,
+            ],
+            [
+                (2.0 * (k * k - 1.0) / a0) as f32,
+                ((1.0 - k / q + k * k) / a0) as f32,
+            ],
+        )
+    };
+
+    let high_pass = {
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+
+        let k = (PI * f0 / sample_rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+
+        Biquad::new(
+            [1.0, -2.0, 1.0],
+            [
+                (2.0 * (k * k - 1.0) / a0) as f32,
+                ((1.0 - k / q + k * k) / a0) as f32,
+            ],
+        )
+    };
+
+    [shelf, high_pass]
+}
+
+fn block_loudness(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.max(f64::MIN_POSITIVE).log10()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn amplitude_to_db(amplitude: f32) -> f64 {
+    20.0 * (amplitude as f64).max(1e-10).log10()
+}
+
+fn db_to_amplitude(db: f64) -> f64 {
+    10f64.powf(db / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    fn stereo_sine(frequency: f64, amplitude: f64, seconds: f64, phase: f64) -> Vec<f32> {
+        (0..(SAMPLE_RATE as f64 * seconds) as usize)
+            .flat_map(|i| {
+                let t = i as f64 / SAMPLE_RATE as f64;
+                let sample = ((2.0 * PI * frequency * t + phase).sin() * amplitude) as f32;
+                [sample, sample]
+            })
+            .collect()
+    }
+
+    fn measure(samples: &[f32]) -> LoudnessMeasurement {
+        let mut meter = LoudnessMeter::new(2, SAMPLE_RATE);
+        meter.push(samples);
+        meter.measurement()
+    }
+
+    #[test]
+    fn full_scale_1khz_sine_on_both_channels_is_0_lufs() {
+        let lufs = measure(&stereo_sine(1000.0, 1.0, 5.0, 0.0))
+            .integrated_lufs
+            .unwrap();
+        assert!(lufs.abs() < 0.1, "measured {lufs} LUFS");
+
+        let lufs = measure(&stereo_sine(1000.0, 0.1, 5.0, 0.0))
+            .integrated_lufs
+            .unwrap();
+        assert!((lufs + 20.0).abs() < 0.1, "measured {lufs} LUFS");
+    }
+
+    #[test]
+    fn silence_has_no_integrated_loudness() {
+        assert_eq!(measure(&vec![0.0; 48_000 * 2]).integrated_lufs, None);
+    }
+
+    #[test]
+    fn quiet_passages_are_gated_out() {
+        let mut samples = stereo_sine(1000.0, 0.1, 5.0, 0.0);
+        samples.extend(stereo_sine(1000.0, 0.001, 5.0, 0.0));
+
+        // only the blocks that straddle the change let any of the quiet part through
+        let lufs = measure(&samples).integrated_lufs.unwrap();
+        assert!((lufs + 20.0).abs() < 0.2, "measured {lufs} LUFS");
+    }
+
+    #[test]
+    fn true_peak_finds_peaks_between_samples() {
+        // a quarter of the sample rate offset by 45 degrees never lands a sample on its crest
+        let samples = stereo_sine(12_000.0, 1.0, 1.0, PI / 4.0);
+        let sample_peak = samples.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        assert!(amplitude_to_db(sample_peak) < -2.9);
+
+        let true_peak = measure(&samples).true_peak_db;
+        assert!(true_peak.abs() < 0.5, "measured {true_peak} dBTP");
+    }
+
+    #[test]
+    fn limiter_keeps_true_peak_under_ceiling() {
+        let mut samples = stereo_sine(440.0, 0.2, 1.0, 0.0);
+        samples.extend(stereo_sine(440.0, 2.0, 1.0, 0.0));
+        samples.extend(stereo_sine(440.0, 0.2, 1.0, 0.0));
+
+        let mut limiter = TruePeakLimiter::new(2, SAMPLE_RATE, -1.0);
+        let latency = limiter.latency();
+        let mut output = samples.clone();
+        limiter.process(&mut output);
+
+        assert!(output[..latency * 2].iter().all(|s| *s == 0.0));
+        assert!(measure(&output).true_peak_db < -0.9);
+        // quiet audio well away from the loud part passes through untouched
+        assert_eq!(
+            output[(latency + 1000) * 2..(latency + 2000) * 2],
+            samples[1000 * 2..2000 * 2]
+        );
+    }
+}
@@ -11,6 +11,8 @@ pub struct AudioRendererTrack<'a> {
     pub gain: f32,
     pub stereo_mode: StereoMode,
     pub offset: isize,
+    /// Linear gain for each rendered sample, applied on top of `gain`
+    pub envelope: Option<&'a [f32]>,
 }
 
 pub fn render_audio(
@@ -41,8 +43,6 @@ pub fn render_audio(
         let mut right = 0.0;
 
         for track in tracks {
-            let i = i.wrapping_add_signed(track.offset);
-
             let data = track.data;
             let gain = gain_for_db(track.gain);
 
@@ -50,6 +50,12 @@ pub fn render_audio(
                 continue;
             }
 
+            let gain = match track.envelope {
+                Some(envelope) => gain * envelope.get(i).copied().unwrap_or(1.0),
+                None => gain,
+            };
+            let i = i.wrapping_add_signed(track.offset);
+
             if data.channels() == 1 {
                 if let Some(sample) = data.samples().get(offset + i) {
                     left += sample * 0.707 * gain;
@@ -90,7 +96,8 @@ pub fn render_audio(
     samples
 }
 
-fn gain_for_db(db: f32) -> f32 {
+/// Linear gain for a volume in dB, or negative infinity for a muted volume
+pub fn gain_for_db(db: f32) -> f32 {
     match db {
         // Fully mute when at minimum
         v if v <= -30.0 => f32::NEG_INFINITY,
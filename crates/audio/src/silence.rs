@@ -0,0 +1,127 @@
+use std::ops::Range;
+
+use crate::AudioData;
+
+/// Length of the windows whose loudness is compared against the threshold
+const WINDOW_SECS: f64 = 0.01;
+
+impl AudioData {
+    /// Finds the stretches, in seconds, where the audio stays below `threshold_db` for at least
+    /// `min_duration` seconds.
+    pub fn detect_silence(&self, threshold_db: f32, min_duration: f64) -> Vec<Range<f64>> {
+        detect_silence(
+            self.samples(),
+            self.channels(),
+            Self::SAMPLE_RATE,
+            threshold_db,
+            min_duration,
+        )
+    }
+}
+
+/// Finds the stretches, in seconds, where the interleaved `samples` stay below `threshold_db`
+/// for at least `min_duration` seconds. Loudness is the RMS of all channels over 10ms windows.
+pub fn detect_silence(
+    samples: &[f32],
+    channels: u16,
+    sample_rate: u32,
+    threshold_db: f32,
+    min_duration: f64,
+) -> Vec<Range<f64>> {
+    let channels = channels.max(1) as usize;
+    let window_frames = ((sample_rate as f64 * WINDOW_SECS) as usize).max(1);
+    let threshold = 10f64.powf(threshold_db as f64 / 20.0);
+    let frame_count = samples.len() / channels;
+    let to_secs = |frame: usize| frame as f64 / sample_rate as f64;
+
+    let mut ranges = vec![];
+    let mut silence_start: Option<usize> = None;
+
+    for (i, window) in samples[..frame_count * channels]
+        .chunks(window_frames * channels)
+        .enumerate()
+    {
+        let rms =
+            (window.iter().map(|s| (*s as f64).powi(2)).sum::<f64>() / window.len() as f64).sqrt();
+        let start = i * window_frames;
+
+        if rms < threshold {
+            silence_start.get_or_insert(start);
+        } else if let Some(silence_start) = silence_start.take()
+            && to_secs(start - silence_start) >= min_duration
+        {
+            ranges.push(to_secs(silence_start)..to_secs(start));
+        }
+    }
+
+    if let Some(silence_start) = silence_start
+        && to_secs(frame_count - silence_start) >= min_duration
+    {
+        ranges.push(to_secs(silence_start)..to_secs(frame_count));
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    /// Stereo audio that's a 440Hz tone at half volume where `speech` is true for each second.
+    fn generate(seconds: &[bool]) -> Vec<f32> {
+        seconds
+            .iter()
+            .flat_map(|&speech| {
+                (0..SAMPLE_RATE).flat_map(move |i| {
+                    let t = i as f32 / SAMPLE_RATE as f32;
+                    let sample = if speech {
+                        (t * 440.0 * std::f32::consts::TAU).sin() * 0.5
+                    } else {
+                        0.0005
+                    };
+                    [sample, sample]
+                })
+            })
+            .collect()
+    }
+
+    fn assert_ranges(actual: Vec<Range<f64>>, expected: &[Range<f64>]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!(
+                (actual.start - expected.start).abs() < 0.02
+                    && (actual.end - expected.end).abs() < 0.02,
+                "{actual:?} != {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn finds_pauses_between_speech() {
+        let samples = generate(&[true, false, false, true, false, true, false]);
+
+        assert_ranges(
+            detect_silence(&samples, 2, SAMPLE_RATE, -40.0, 0.5),
+            &[1.0..3.0, 4.0..5.0, 6.0..7.0],
+        );
+    }
+
+    #[test]
+    fn ignores_pauses_shorter_than_min_duration() {
+        let samples = generate(&[true, false, true, false, false, true]);
+
+        assert_ranges(
+            detect_silence(&samples, 2, SAMPLE_RATE, -40.0, 1.5),
+            &[3.0..5.0],
+        );
+    }
+
+    #[test]
+    fn quiet_audio_above_threshold_is_not_silent() {
+        let samples = generate(&[false, false]);
+
+        assert_ranges(detect_silence(&samples, 2, SAMPLE_RATE, -80.0, 0.5), &[]);
+    }
+}
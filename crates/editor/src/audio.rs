@@ -1,9 +1,13 @@
 use cap_audio::{
-    AudioData, AudioRendererTrack, FromSampleBytes, StereoMode, cast_f32_slice_to_bytes,
+    AudioData, AudioRendererTrack, Ducker, FromSampleBytes, StereoMode, cast_f32_slice_to_bytes,
+    gain_for_db,
 };
 use cap_media::MediaError;
 use cap_media_info::AudioInfo;
-use cap_project::{AudioConfiguration, ClipOffsets, ProjectConfiguration, TimelineConfiguration};
+use cap_project::{
+    AudioConfiguration, ClipOffsets, MUTED_VOLUME_DB, ProjectConfiguration, TimelineConfiguration,
+    VolumeKeyframe,
+};
 use ffmpeg::{
     ChannelLayout, Dictionary, format as avformat, frame::Audio as FFAudio, software::resampling,
 };
@@ -12,7 +16,11 @@ use ringbuf::{
     HeapRb,
     traits::{Consumer, Observer, Producer},
 };
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
 use tracing::info;
 
 pub struct AudioRenderer {
@@ -21,6 +29,8 @@ pub struct AudioRenderer {
     // sum of `frame.samples()` that have elapsed
     // this * channel count = cursor
     elapsed_samples: usize,
+    music: MusicLibrary,
+    ducker: Ducker,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -36,6 +46,15 @@ pub struct AudioSegment {
     pub tracks: Vec<AudioSegmentTrack>,
 }
 
+impl AudioSegment {
+    /// Runs [`AudioSegmentTrack::enhance_voice`] on each of the segment's tracks.
+    pub fn enhance_voice(&self) {
+        for track in &self.tracks {
+            track.enhance_voice();
+        }
+    }
+}
+
 // yeah this is cursed oh well
 #[derive(Clone)]
 pub struct AudioSegmentTrack {
@@ -43,6 +62,53 @@ pub struct AudioSegmentTrack {
     get_gain: fn(&AudioConfiguration) -> f32,
     get_stereo_mode: fn(&AudioConfiguration) -> StereoMode,
     get_offset: fn(&ClipOffsets) -> f32,
+    get_volume_keyframes: fn(&AudioConfiguration) -> &[VolumeKeyframe],
+    // only set for tracks that `AudioConfiguration::improve` applies to
+    enhanced: Option<Arc<OnceLock<Arc<AudioData>>>>,
+    ducks_music: bool,
+}
+
+/// Decoded audio of the project's music tracks, keyed by the path they're configured with
+#[derive(Clone, Default)]
+pub struct MusicLibrary {
+    tracks: HashMap<PathBuf, Arc<AudioData>>,
+}
+
+impl MusicLibrary {
+    /// Decodes each of `config`'s music tracks that isn't loaded yet, resolving relative paths
+    /// against `project_path`. Files that can't be decoded are left out and reported in the error,
+    /// after the rest have been loaded.
+    pub fn load(&mut self, project_path: &Path, config: &AudioConfiguration) -> Result<(), String> {
+        let mut errors = vec![];
+
+        for track in &config.music {
+            if self.tracks.contains_key(&track.path) {
+                continue;
+            }
+
+            let path = project_path.join(&track.path);
+            match AudioData::from_file(&path) {
+                Ok(data) => {
+                    self.tracks.insert(track.path.clone(), Arc::new(data));
+                }
+                Err(e) => errors.push(format!("'{}': {e}", path.display())),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join(", "))
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&Arc<AudioData>> {
+        self.tracks.get(path)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
 }
 
 impl AudioSegmentTrack {
@@ -57,13 +123,60 @@ impl AudioSegmentTrack {
             get_gain,
             get_stereo_mode,
             get_offset,
+            get_volume_keyframes: |_| &[],
+            enhanced: None,
+            ducks_music: false,
         }
     }
 
+    /// Follows the volume keyframes `get_volume_keyframes` picks out of the config.
+    pub fn with_volume_keyframes(
+        mut self,
+        get_volume_keyframes: fn(&AudioConfiguration) -> &[VolumeKeyframe],
+    ) -> Self {
+        self.get_volume_keyframes = get_volume_keyframes;
+        self
+    }
+
+    /// Turns background music down while this track has speech.
+    pub fn with_music_ducking(mut self) -> Self {
+        self.ducks_music = true;
+        self
+    }
+
+    /// Lets `AudioConfiguration::improve` run this track through voice enhancement.
+    pub fn with_voice_enhancement(mut self) -> Self {
+        self.enhanced = Some(Arc::new(OnceLock::new()));
+        self
+    }
+
     pub fn data(&self) -> &Arc<AudioData> {
         &self.data
     }
 
+    /// The audio to play for `config`. Until [`Self::enhance_voice`] has finished, tracks play
+    /// their original audio even if `config.improve` is set.
+    pub fn data_for(&self, config: &AudioConfiguration) -> &Arc<AudioData> {
+        match &self.enhanced {
+            Some(enhanced) if config.improve => enhanced.get().unwrap_or(&self.data),
+            _ => &self.data,
+        }
+    }
+
+    /// Works out the enhanced version of the track if it has voice enhancement, which is shared
+    /// between every clone of the track. This is slow, so call it from a blocking context.
+    pub fn enhance_voice(&self) {
+        if let Some(enhanced) = &self.enhanced {
+            enhanced.get_or_init(|| {
+                info!(
+                    samples = self.data.sample_count(),
+                    "Enhancing voice audio track"
+                );
+                Arc::new(self.data.enhance_voice())
+            });
+        }
+    }
+
     pub fn gain(&self, config: &AudioConfiguration) -> f32 {
         (self.get_gain)(config)
     }
@@ -75,6 +188,10 @@ impl AudioSegmentTrack {
     pub fn offset(&self, offsets: &ClipOffsets) -> f32 {
         (self.get_offset)(offsets)
     }
+
+    pub fn volume_keyframes<'a>(&self, config: &'a AudioConfiguration) -> &'a [VolumeKeyframe] {
+        (self.get_volume_keyframes)(config)
+    }
 }
 
 impl AudioRenderer {
@@ -95,11 +212,28 @@ impl AudioRenderer {
                 timescale: 1.0,
             },
             elapsed_samples: 0,
+            music: MusicLibrary::default(),
+            ducker: Self::ducker(&AudioConfiguration::default()),
         }
     }
 
+    /// Mixes in the project's music tracks that have been loaded into `music`.
+    pub fn with_music(mut self, music: MusicLibrary) -> Self {
+        self.music = music;
+        self
+    }
+
+    fn ducker(config: &AudioConfiguration) -> Ducker {
+        Ducker::new(
+            Self::SAMPLE_RATE,
+            config.music_ducking.threshold_db,
+            config.music_ducking.amount_db,
+        )
+    }
+
     pub fn set_playhead(&mut self, playhead: f64, project: &ProjectConfiguration) {
         self.elapsed_samples = self.playhead_to_samples(playhead);
+        self.ducker = Self::ducker(&project.audio);
 
         self.cursor = match project.get_segment_time(playhead) {
             Some((segment_time, segment)) => AudioRendererCursor {
@@ -169,10 +303,109 @@ impl AudioRenderer {
             })
     }
 
+    /// Renders up to `samples` samples from the playhead. Without music, sped up segments and
+    /// gaps past the end of a recording render nothing. With music, exactly `samples` are rendered.
     pub fn render_frame_raw(
         &mut self,
         samples: usize,
         project: &ProjectConfiguration,
+    ) -> Option<(usize, Vec<f32>)> {
+        if self.music.is_empty() || project.audio.music.is_empty() || project.audio.mute {
+            return self.render_recording(samples, project, None);
+        }
+
+        let start = self.elapsed_samples;
+        let mut sidechain = vec![0.0; samples * 2];
+
+        let mut out = self
+            .render_recording(samples, project, Some(sidechain.as_mut_slice()))
+            .map(|(_, data)| data)
+            .unwrap_or_default();
+        out.resize(samples * 2, 0.0);
+        self.elapsed_samples = start + samples;
+
+        self.mix_music(start, &sidechain, &mut out, project);
+
+        Some((samples, out))
+    }
+
+    /// Mixes the music tracks into `out`, which starts `start` samples into the timeline,
+    /// ducking them wherever `sidechain` has speech.
+    fn mix_music(
+        &mut self,
+        start: usize,
+        sidechain: &[f32],
+        out: &mut [f32],
+        project: &ProjectConfiguration,
+    ) {
+        let config = &project.audio;
+        let sample_rate = f64::from(Self::SAMPLE_RATE);
+        let timeline_duration = project.timeline.as_ref().map(|t| t.duration());
+
+        self.ducker.set_levels(
+            config.music_ducking.threshold_db,
+            config.music_ducking.amount_db,
+        );
+        let ducking = sidechain
+            .chunks_exact(2)
+            .map(|frame| {
+                if config.music_ducking.enabled {
+                    self.ducker.next(frame)
+                } else {
+                    1.0
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for track in &config.music {
+            if track.volume_db <= MUTED_VOLUME_DB {
+                continue;
+            }
+            let Some(data) = self.music.get(&track.path) else {
+                continue;
+            };
+
+            let channels = data.channels() as usize;
+            let file_samples = data.sample_count();
+            if file_samples == 0 {
+                continue;
+            }
+            let file_duration = file_samples as f64 / sample_rate;
+            let volume = gain_for_db(track.volume_db);
+
+            for (i, (frame, duck)) in out.chunks_exact_mut(2).zip(&ducking).enumerate() {
+                let time = (start + i) as f64 / sample_rate;
+                let Some((position, fade)) =
+                    track.position_at(time, file_duration, timeline_duration)
+                else {
+                    continue;
+                };
+
+                let index = (position * sample_rate).round() as usize;
+                let index = if track.looped {
+                    index % file_samples
+                } else {
+                    index.min(file_samples - 1)
+                };
+
+                let (left, right) = match &data.samples()[index * channels..][..channels] {
+                    [mono] => (mono * 0.707, mono * 0.707),
+                    [left, right, ..] => (*left, *right),
+                    [] => continue,
+                };
+
+                let gain = volume * fade * duck;
+                frame[0] = (frame[0] + left * gain).clamp(-1.0, 1.0);
+                frame[1] = (frame[1] + right * gain).clamp(-1.0, 1.0);
+            }
+        }
+    }
+
+    fn render_recording(
+        &mut self,
+        samples: usize,
+        project: &ProjectConfiguration,
+        sidechain: Option<&mut [f32]>,
     ) -> Option<(usize, Vec<f32>)> {
         if let Some(timeline) = &project.timeline {
             self.adjust_cursor(timeline);
@@ -219,10 +452,27 @@ impl AudioRenderer {
 
         let mut ret = vec![0.0; samples * 2];
 
+        // keyframes are on the timeline, which this segment plays through at normal speed
+        let envelopes = tracks
+            .iter()
+            .map(|t| {
+                let keyframes = t.volume_keyframes(&project.audio);
+                (!keyframes.is_empty()).then(|| {
+                    (0..samples)
+                        .map(|i| {
+                            let time = (self.elapsed_samples + i) as f64 / Self::SAMPLE_RATE as f64;
+                            VolumeKeyframe::gain_at(keyframes, time)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
         let track_datas = tracks
             .iter()
-            .map(|t| AudioRendererTrack {
-                data: t.data().as_ref(),
+            .zip(&envelopes)
+            .map(|(t, envelope)| AudioRendererTrack {
+                data: t.data_for(&project.audio).as_ref(),
                 gain: if project.audio.mute {
                     f32::NEG_INFINITY
                 } else {
@@ -231,12 +481,30 @@ impl AudioRenderer {
                 },
                 stereo_mode: t.stereo_mode(&project.audio),
                 offset: (t.offset(&offsets) * Self::SAMPLE_RATE as f32) as isize,
+                envelope: envelope.as_deref(),
             })
             .collect::<Vec<_>>();
 
         let actual_sample_count =
             cap_audio::render_audio(&track_datas, start.samples, samples, 0, &mut ret);
 
+        if let Some(sidechain) = sidechain {
+            let voices = tracks
+                .iter()
+                .zip(&track_datas)
+                .filter(|(track, _)| track.ducks_music)
+                .map(|(track, data)| AudioRendererTrack {
+                    data: data.data,
+                    gain: data.gain,
+                    stereo_mode: track.stereo_mode(&project.audio),
+                    offset: data.offset,
+                    envelope: data.envelope,
+                })
+                .collect::<Vec<_>>();
+
+            cap_audio::render_audio(&voices, start.samples, samples, 0, sidechain);
+        }
+
         self.elapsed_samples += actual_sample_count;
         self.cursor.samples += actual_sample_count;
 
@@ -263,7 +531,7 @@ impl<T: FromSampleBytes> AudioPlaybackBuffer<T> {
 
     const PROCESSING_SAMPLES_COUNT: u32 = 1024;
 
-    pub fn new(data: Vec<AudioSegment>, output_info: AudioInfo) -> Self {
+    pub fn new(data: Vec<AudioSegment>, music: MusicLibrary, output_info: AudioInfo) -> Self {
         info!(
             sample_rate = output_info.sample_rate,
             channels = output_info.channels,
@@ -278,7 +546,7 @@ impl<T: FromSampleBytes> AudioPlaybackBuffer<T> {
             * output_info.sample_format.bytes();
         let resampled_buffer = HeapRb::new(capacity);
 
-        let frame_buffer = AudioRenderer::new(data);
+        let frame_buffer = AudioRenderer::new(data).with_music(music);
 
         Self {
             frame_buffer,
@@ -456,6 +724,7 @@ pub struct PrerenderedAudioBuffer<T: FromSampleBytes> {
 impl<T: FromSampleBytes> PrerenderedAudioBuffer<T> {
     pub fn new(
         segments: Vec<AudioSegment>,
+        music: MusicLibrary,
         project: &ProjectConfiguration,
         output_info: AudioInfo,
         duration_secs: f64,
@@ -467,7 +736,7 @@ impl<T: FromSampleBytes> PrerenderedAudioBuffer<T> {
             "Pre-rendering audio for playback"
         );
 
-        let mut renderer = AudioRenderer::new(segments);
+        let mut renderer = AudioRenderer::new(segments).with_music(music);
         let mut resampler = AudioResampler::new(output_info).unwrap();
 
         let total_source_samples = (duration_secs * AudioData::SAMPLE_RATE as f64) as usize;
@@ -558,3 +827,92 @@ impl<T: FromSampleBytes> PrerenderedAudioBuffer<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cap_project::MusicTrack;
+
+    const MIC_SECS: usize = 1;
+    const TOTAL_SECS: usize = 3;
+    const MUSIC_LEVEL: f32 = 0.1;
+
+    fn seconds(secs: usize) -> usize {
+        secs * AudioData::SAMPLE_RATE as usize
+    }
+
+    fn renderer() -> AudioRenderer {
+        // loud for the first second, then silent
+        let mic = (0..seconds(TOTAL_SECS))
+            .flat_map(|i| {
+                let sample = if i < seconds(MIC_SECS) { 0.5 } else { 0.0 };
+                [sample, sample]
+            })
+            .collect();
+        let mic = AudioSegmentTrack::new(
+            Arc::new(AudioData::new(mic, 2)),
+            |c| c.mic_volume_db,
+            |_| StereoMode::Stereo,
+            |o| o.mic,
+        )
+        .with_music_ducking();
+
+        let music = MusicLibrary {
+            tracks: HashMap::from([(
+                PathBuf::from("music.wav"),
+                Arc::new(AudioData::new(
+                    vec![MUSIC_LEVEL; seconds(TOTAL_SECS) * 2],
+                    2,
+                )),
+            )]),
+        };
+
+        AudioRenderer::new(vec![AudioSegment { tracks: vec![mic] }]).with_music(music)
+    }
+
+    fn project(ducking: bool) -> ProjectConfiguration {
+        let mut project = ProjectConfiguration::default();
+        project.audio.music = vec![MusicTrack::new("music.wav")];
+        project.audio.music_ducking.enabled = ducking;
+        project
+    }
+
+    fn music_at(out: &[f32], secs: f64) -> f32 {
+        let index = (secs * AudioData::SAMPLE_RATE as f64) as usize * 2;
+        let mic = if index < seconds(MIC_SECS) * 2 {
+            0.5
+        } else {
+            0.0
+        };
+        out[index] - mic
+    }
+
+    fn render(project: &ProjectConfiguration) -> Vec<f32> {
+        let mut renderer = renderer();
+        renderer.set_playhead(0.0, project);
+
+        let (samples, out) = renderer
+            .render_frame_raw(seconds(TOTAL_SECS), project)
+            .unwrap();
+        assert_eq!(samples, seconds(TOTAL_SECS));
+        out
+    }
+
+    #[test]
+    fn mixes_music_under_the_recording() {
+        let out = render(&project(false));
+
+        assert!((music_at(&out, 0.5) - MUSIC_LEVEL).abs() < 1e-4);
+        assert!((music_at(&out, 2.5) - MUSIC_LEVEL).abs() < 1e-4);
+    }
+
+    #[test]
+    fn ducks_music_while_the_mic_is_active() {
+        let project = project(true);
+        let out = render(&project);
+
+        let ducked_gain = 10f32.powf(-project.audio.music_ducking.amount_db / 20.0);
+        assert!((music_at(&out, 0.9) - MUSIC_LEVEL * ducked_gain).abs() < 1e-3);
+        assert!(music_at(&out, 2.9) > MUSIC_LEVEL * 0.9);
+    }
+}
@@ -1,5 +1,7 @@
+use crate::audio::{AudioSegment, MusicLibrary};
 use crate::editor;
 use crate::playback::{self, PlaybackHandle, PlaybackStartError};
+use crate::segments::get_audio_segments;
 use cap_audio::AudioData;
 use cap_project::StudioRecordingMeta;
 use cap_project::{
@@ -85,6 +87,8 @@ pub struct EditorInstance {
     ),
     // ws_shutdown_token: CancellationToken,
     pub segment_medias: Arc<Vec<SegmentMedia>>,
+    audio_segments: Vec<AudioSegment>,
+    music: Arc<Mutex<MusicLibrary>>,
     meta: RecordingMeta,
     pub export_preview_active: AtomicBool,
 }
@@ -199,6 +203,7 @@ impl EditorInstance {
                     scene_segments: Vec::new(),
                     mask_segments: Vec::new(),
                     text_segments: Vec::new(),
+                    markers: Vec::new(),
                 });
 
                 if let Err(e) = project.write(&recording_meta.project_path) {
@@ -283,7 +288,9 @@ impl EditorInstance {
             on_state_change: Box::new(on_state_change),
             preview_tx,
             project_config: watch::channel(project),
+            audio_segments: get_audio_segments(&segments),
             segment_medias: Arc::new(segments),
+            music: Arc::default(),
             meta: recording_meta,
             playback_active: playback_active_tx,
             playback_active_rx,
@@ -292,10 +299,38 @@ impl EditorInstance {
 
         this.state.lock().await.preview_task =
             Some(this.clone().spawn_preview_renderer(preview_rx));
+        this.spawn_voice_enhancement();
 
         Ok(this)
     }
 
+    /// Enhances the voice tracks in the background once `improve` is turned on, so playback
+    /// doesn't have to. They play unenhanced until it's done.
+    fn spawn_voice_enhancement(&self) {
+        let mut project_rx = self.project_config.0.subscribe();
+        let segments = self.audio_segments.clone();
+
+        tokio::spawn(async move {
+            loop {
+                if project_rx.borrow_and_update().audio.improve {
+                    // the enhanced audio is kept by the tracks, so this only has to happen once
+                    if let Err(e) = tokio::task::spawn_blocking(move || {
+                        segments.iter().for_each(AudioSegment::enhance_voice)
+                    })
+                    .await
+                    {
+                        warn!("Voice enhancement failed: {e}");
+                    }
+                    return;
+                }
+
+                if project_rx.changed().await.is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
     pub fn meta(&self) -> &RecordingMeta {
         &self.meta
     }
@@ -327,7 +362,26 @@ impl EditorInstance {
         (self.on_state_change)(&state);
     }
 
+    /// Decodes any music tracks added to the project since the last call.
+    async fn load_music(&self) -> MusicLibrary {
+        let music = self.music.clone();
+        let project_path = self.project_path.clone();
+        let audio = self.project_config.1.borrow().audio.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut music = music.blocking_lock();
+            if let Err(e) = music.load(&project_path, &audio) {
+                warn!("Failed to load music tracks: {e}");
+            }
+            music.clone()
+        })
+        .await
+        .unwrap_or_default()
+    }
+
     pub async fn start_playback(self: &Arc<Self>, fps: u32, resolution_base: XY<u32>) {
+        let music = self.load_music().await;
+
         let (mut handle, prev) = {
             let mut state = self.state.lock().await;
 
@@ -335,10 +389,12 @@ impl EditorInstance {
 
             let playback_handle = match (playback::Playback {
                 segment_medias: self.segment_medias.clone(),
+                audio_segments: self.audio_segments.clone(),
                 renderer: self.renderer.clone(),
                 render_constants: self.render_constants.clone(),
                 start_frame_number,
                 project: self.project_config.0.subscribe(),
+                music,
             })
             .start(fps, resolution_base)
             .await
@@ -4,6 +4,8 @@ mod editor_instance;
 mod playback;
 mod segments;
 
-pub use audio::AudioRenderer;
+pub use audio::{AudioRenderer, AudioSegment, MusicLibrary};
 pub use editor_instance::{EditorInstance, EditorState, SegmentMedia, create_segments};
-pub use segments::get_audio_segments;
+pub use segments::{
+    get_audio_segments, load_audio_segments, load_mic_audio, mic_offsets, silence_cuts,
+};
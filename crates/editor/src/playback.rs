@@ -31,7 +31,9 @@ use tracing::{error, info, warn};
 #[cfg(not(target_os = "windows"))]
 use crate::audio::AudioPlaybackBuffer;
 use crate::{
-    audio::AudioSegment, editor, editor_instance::SegmentMedia, segments::get_audio_segments,
+    audio::{AudioSegment, MusicLibrary},
+    editor,
+    editor_instance::SegmentMedia,
 };
 
 const PREFETCH_BUFFER_SIZE: usize = 60;
@@ -51,6 +53,8 @@ pub struct Playback {
     pub start_frame_number: u32,
     pub project: watch::Receiver<ProjectConfiguration>,
     pub segment_medias: Arc<Vec<SegmentMedia>>,
+    pub audio_segments: Vec<AudioSegment>,
+    pub music: MusicLibrary,
 }
 
 #[derive(Clone, Copy)]
@@ -356,7 +360,8 @@ impl Playback {
                 watch::channel(self.start_frame_number as f64 / fps as f64);
 
             let has_audio = AudioPlayback {
-                segments: get_audio_segments(&self.segment_medias),
+                segments: self.audio_segments.clone(),
+                music: self.music.clone(),
                 stop_rx: stop_rx.clone(),
                 start_frame_number: self.start_frame_number,
                 project: self.project.clone(),
@@ -739,6 +744,7 @@ impl PlaybackHandle {
 
 struct AudioPlayback {
     segments: Vec<AudioSegment>,
+    music: MusicLibrary,
     stop_rx: watch::Receiver<bool>,
     start_frame_number: u32,
     project: watch::Receiver<ProjectConfiguration>,
@@ -751,7 +757,8 @@ impl AudioPlayback {
     fn spawn(self) -> bool {
         let handle = tokio::runtime::Handle::current();
 
-        if self.segments.is_empty() || self.segments[0].tracks.is_empty() {
+        if self.segments.is_empty() || (self.segments[0].tracks.is_empty() && self.music.is_empty())
+        {
             info!("No audio segments found, skipping audio playback thread.");
             return false;
         }
@@ -847,6 +854,7 @@ impl AudioPlayback {
             start_frame_number,
             project,
             segments,
+            music,
             fps,
             playhead_rx,
             ..
@@ -943,7 +951,8 @@ impl AudioPlayback {
                 .saturating_mul(headroom_multiplier)
                 .max(channels * AudioPlaybackBuffer::<T>::PLAYBACK_SAMPLES_COUNT as usize);
 
-            let mut audio_renderer = AudioPlaybackBuffer::new(segments.clone(), base_output_info);
+            let mut audio_renderer =
+                AudioPlaybackBuffer::new(segments.clone(), music.clone(), base_output_info);
 
             match strategy {
                 BufferSizeStrategy::Fixed(desired) => {
@@ -1141,6 +1150,7 @@ impl AudioPlayback {
             start_frame_number,
             project,
             segments,
+            music,
             fps,
             playhead_rx,
             ..
@@ -1164,6 +1174,7 @@ impl AudioPlayback {
         let project_snapshot = project.borrow().clone();
         let mut audio_buffer = PrerenderedAudioBuffer::<T>::new(
             segments,
+            music,
             &project_snapshot,
             output_info,
             duration_secs,
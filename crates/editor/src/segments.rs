@@ -1,3 +1,11 @@
+use std::sync::Arc;
+
+use cap_audio::AudioData;
+use cap_project::{
+    AudioMeta, ProjectConfiguration, RecordingMeta, SilenceCutSettings, StudioRecordingMeta,
+    TimelineSegment,
+};
+
 use crate::{
     SegmentMedia,
     audio::{AudioSegment, AudioSegmentTrack},
@@ -6,32 +14,164 @@ use crate::{
 pub fn get_audio_segments(segments: &[SegmentMedia]) -> Vec<AudioSegment> {
     segments
         .iter()
-        .map(|s| AudioSegment {
-            tracks: [
-                s.audio.clone().map(|a| {
-                    AudioSegmentTrack::new(
-                        a,
-                        |c| c.mic_volume_db,
-                        |c| match c.mic_stereo_mode {
-                            cap_project::StereoMode::Stereo => cap_audio::StereoMode::Stereo,
-                            cap_project::StereoMode::MonoL => cap_audio::StereoMode::MonoL,
-                            cap_project::StereoMode::MonoR => cap_audio::StereoMode::MonoR,
-                        },
-                        |o| o.mic,
-                    )
-                }),
-                s.system_audio.clone().map(|a| -> AudioSegmentTrack {
-                    AudioSegmentTrack::new(
-                        a,
-                        |c| c.system_volume_db,
-                        |_| cap_audio::StereoMode::Stereo,
-                        |o| o.system_audio,
+        .map(|s| audio_segment(s.audio.clone(), s.system_audio.clone()))
+        .collect::<Vec<_>>()
+}
+
+/// Loads only the audio of each recording segment, without creating any video decoders.
+pub fn load_audio_segments(
+    recording_meta: &RecordingMeta,
+    meta: &StudioRecordingMeta,
+) -> Result<Vec<AudioSegment>, String> {
+    let load = |audio: &AudioMeta, name: &str| {
+        AudioData::from_file(recording_meta.path(&audio.path))
+            .map(Arc::new)
+            .map_err(|e| format!("{name} / {e}"))
+    };
+
+    match meta {
+        StudioRecordingMeta::SingleSegment { segment: s } => {
+            let audio = s
+                .audio
+                .as_ref()
+                .map(|audio| load(audio, "SingleSegment Audio"))
+                .transpose()?;
+
+            Ok(vec![audio_segment(audio, None)])
+        }
+        StudioRecordingMeta::MultipleSegments { inner, .. } => inner
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let audio = s
+                    .mic
+                    .as_ref()
+                    .map(|audio| load(audio, &format!("MultipleSegments {i} Audio")))
+                    .transpose()?;
+                let system_audio = s
+                    .system_audio
+                    .as_ref()
+                    .map(|audio| load(audio, &format!("MultipleSegments {i} System Audio")))
+                    .transpose()?;
+
+                Ok(audio_segment(audio, system_audio))
+            })
+            .collect(),
+    }
+}
+
+/// Loads the mic audio of each recording segment, if it has any.
+pub fn load_mic_audio(
+    recording_meta: &RecordingMeta,
+    meta: &StudioRecordingMeta,
+) -> Result<Vec<Option<Arc<AudioData>>>, String> {
+    let mic_metas = match meta {
+        StudioRecordingMeta::SingleSegment { segment } => vec![segment.audio.as_ref()],
+        StudioRecordingMeta::MultipleSegments { inner, .. } => {
+            inner.segments.iter().map(|s| s.mic.as_ref()).collect()
+        }
+    };
+
+    mic_metas
+        .into_iter()
+        .enumerate()
+        .map(|(i, audio)| {
+            audio
+                .map(|audio| {
+                    AudioData::from_file(recording_meta.path(&audio.path))
+                        .map(Arc::new)
+                        .map_err(|e| format!("Segment {i} Mic / {e}"))
+                })
+                .transpose()
+        })
+        .collect()
+}
+
+/// How many seconds ahead of each recording segment its mic audio plays, from the project's clip
+/// offsets. Segments without clip offsets get the ones the editor would generate from the mic's
+/// start time.
+pub fn mic_offsets(project: &ProjectConfiguration, meta: &StudioRecordingMeta) -> Vec<f64> {
+    let clip_offset = |index: usize| {
+        project
+            .clips
+            .iter()
+            .find(|c| c.index as usize == index)
+            .map(|c| c.offsets.mic as f64)
+    };
+
+    match meta {
+        StudioRecordingMeta::SingleSegment { .. } => vec![clip_offset(0).unwrap_or_default()],
+        StudioRecordingMeta::MultipleSegments { inner, .. } => inner
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(i, s)| clip_offset(i).unwrap_or_else(|| s.calculate_audio_offsets().mic as f64))
+            .collect(),
+    }
+}
+
+/// Cuts or speeds up the pauses in the mic audio of `segments`.
+/// `mic_audio` and `mic_offsets` are indexed by recording segment, as returned by
+/// [`load_mic_audio`] and [`mic_offsets`].
+pub fn silence_cuts(
+    mic_audio: &[Option<Arc<AudioData>>],
+    mic_offsets: &[f64],
+    segments: &[TimelineSegment],
+    settings: &SilenceCutSettings,
+) -> Vec<TimelineSegment> {
+    let silences = mic_audio
+        .iter()
+        .enumerate()
+        .map(|(i, audio)| {
+            audio
+                .as_ref()
+                .map(|audio| {
+                    cap_project::silences_in_clip_time(
+                        &audio.detect_silence(settings.threshold_db, settings.min_duration),
+                        mic_offsets.get(i).copied().unwrap_or_default(),
                     )
-                }),
-            ]
-            .into_iter()
-            .flatten()
-            .collect::<Vec<_>>(),
+                })
+                .unwrap_or_default()
         })
-        .collect::<Vec<_>>()
+        .collect::<Vec<_>>();
+
+    cap_project::cut_silence(segments, &silences, settings)
+}
+
+fn audio_segment(
+    audio: Option<Arc<AudioData>>,
+    system_audio: Option<Arc<AudioData>>,
+) -> AudioSegment {
+    AudioSegment {
+        tracks: [
+            audio.map(|a| {
+                AudioSegmentTrack::new(
+                    a,
+                    |c| c.mic_volume_db,
+                    |c| match c.mic_stereo_mode {
+                        cap_project::StereoMode::Stereo => cap_audio::StereoMode::Stereo,
+                        cap_project::StereoMode::MonoL => cap_audio::StereoMode::MonoL,
+                        cap_project::StereoMode::MonoR => cap_audio::StereoMode::MonoR,
+                    },
+                    |o| o.mic,
+                )
+                .with_voice_enhancement()
+                .with_music_ducking()
+                .with_volume_keyframes(|c| &c.volume_keyframes.mic)
+            }),
+            system_audio.map(|a| -> AudioSegmentTrack {
+                AudioSegmentTrack::new(
+                    a,
+                    |c| c.system_volume_db,
+                    |_| cap_audio::StereoMode::Stereo,
+                    |o| o.system_audio,
+                )
+                .with_volume_keyframes(|c| &c.volume_keyframes.system)
+            }),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>(),
+    }
 }
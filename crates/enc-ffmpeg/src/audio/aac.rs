@@ -9,7 +9,7 @@ use ffmpeg::{
 };
 
 use crate::{
-    AudioEncoder,
+    AudioEncoder, PacketTap,
     audio::{base::AudioEncoderBase, buffered_resampler::BufferedResampler},
 };
 
@@ -104,6 +104,10 @@ impl AACEncoder {
         self.base.send_frame(frame, timestamp, output)
     }
 
+    pub fn set_packet_tap(&mut self, tap: PacketTap) {
+        self.base.set_packet_tap(tap);
+    }
+
     pub fn flush(&mut self, output: &mut format::context::Output) -> Result<(), ffmpeg::Error> {
         self.base.flush(output)
     }
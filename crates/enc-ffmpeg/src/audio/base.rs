@@ -1,5 +1,5 @@
 use super::buffered_resampler::BufferedResampler;
-use crate::base::EncoderBase;
+use crate::base::{EncoderBase, PacketTap};
 use ffmpeg::{codec::encoder, format, frame};
 use std::time::Duration;
 
@@ -29,15 +29,27 @@ impl AudioEncoderBase {
 
         self.resampler.add_frame(frame);
 
-        while let Some(frame) = self.resampler.get_frame(self.encoder.frame_size() as usize) {
+        while let Some(frame) = self.resampler.get_frame(self.frame_size()) {
             self.inner.send_frame(&frame, output, &mut self.encoder)?;
         }
 
         Ok(())
     }
 
+    pub fn set_packet_tap(&mut self, tap: PacketTap) {
+        self.inner.set_packet_tap(tap);
+    }
+
+    // codecs like PCM accept frames of any size and report a frame size of 0
+    fn frame_size(&self) -> usize {
+        match self.encoder.frame_size() {
+            0 => 1024,
+            size => size as usize,
+        }
+    }
+
     pub fn flush(&mut self, output: &mut format::context::Output) -> Result<(), ffmpeg::Error> {
-        while let Some(frame) = self.resampler.flush(self.encoder.frame_size() as usize) {
+        while let Some(frame) = self.resampler.flush(self.frame_size()) {
             self.inner.send_frame(&frame, output, &mut self.encoder)?;
         }
 
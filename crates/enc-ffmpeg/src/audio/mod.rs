@@ -7,3 +7,4 @@ pub mod buffered_resampler;
 
 pub mod aac;
 pub mod opus;
+pub mod pcm;
@@ -7,10 +7,15 @@ use ffmpeg::{
     frame,
 };
 
+/// Called with every encoded packet just before it's written, already rescaled to its output
+/// stream's time base, so the same packets can be written somewhere else too
+pub type PacketTap = Box<dyn FnMut(&Packet) + Send>;
+
 pub struct EncoderBase {
     packet: ffmpeg::Packet,
     stream_index: usize,
     first_pts: Option<i64>,
+    tap: Option<PacketTap>,
 }
 
 impl EncoderBase {
@@ -19,9 +24,14 @@ impl EncoderBase {
             packet: Packet::empty(),
             first_pts: None,
             stream_index,
+            tap: None,
         }
     }
 
+    pub fn set_packet_tap(&mut self, tap: PacketTap) {
+        self.tap = Some(tap);
+    }
+
     pub fn update_pts(
         &mut self,
         frame: &mut frame::Frame,
@@ -70,6 +80,9 @@ impl EncoderBase {
                 encoder.time_base(),
                 output.stream(self.stream_index).unwrap().time_base(),
             );
+            if let Some(tap) = &mut self.tap {
+                tap(&self.packet);
+            }
             self.packet.write_interleaved(output)?;
         }
 
@@ -1,4 +1,5 @@
 mod base;
+pub use base::PacketTap;
 
 mod audio;
 pub use audio::*;
@@ -0,0 +1,75 @@
+use ffmpeg::{format, frame};
+use std::path::PathBuf;
+
+use crate::audio::AudioEncoder;
+
+/// An audio-only file whose container is picked from the output path's extension.
+pub struct AudioFile {
+    encoder: Box<dyn AudioEncoder + Send>,
+    output: format::context::Output,
+    finished: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum FinishError {
+    #[error("Already finished")]
+    AlreadyFinished,
+    #[error("{0}")]
+    WriteTrailerFailed(ffmpeg::Error),
+}
+
+impl AudioFile {
+    pub fn init(
+        output: PathBuf,
+        encoder: impl FnOnce(
+            &mut format::context::Output,
+        )
+            -> Result<Box<dyn AudioEncoder + Send>, Box<dyn std::error::Error>>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if let Some(parent) = output.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+
+        let mut output = format::output(&output)?;
+
+        let encoder = encoder(&mut output)?;
+
+        // make sure this happens after adding all encoders!
+        output.write_header()?;
+
+        Ok(Self {
+            encoder,
+            output,
+            finished: false,
+        })
+    }
+
+    pub fn queue_frame(&mut self, frame: frame::Audio) {
+        if self.finished {
+            return;
+        }
+
+        self.encoder.send_frame(frame, &mut self.output);
+    }
+
+    pub fn finish(&mut self) -> Result<Result<(), ffmpeg::Error>, FinishError> {
+        if self.finished {
+            return Err(FinishError::AlreadyFinished);
+        }
+
+        self.finished = true;
+
+        let flush_result = self.encoder.flush(&mut self.output);
+        self.output
+            .write_trailer()
+            .map_err(FinishError::WriteTrailerFailed)?;
+
+        Ok(flush_result)
+    }
+}
+
+impl Drop for AudioFile {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
@@ -0,0 +1,35 @@
+use ffmpeg::{Rational, format};
+use tracing::*;
+
+/// A chapter from `start` to `end`, in seconds from the start of the output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChapterMark {
+    pub start: f64,
+    pub end: f64,
+    pub title: String,
+}
+
+const TIME_BASE: Rational = Rational(1, 1000);
+
+/// Adds chapters to `output`, skipping any that are empty.
+/// Must be called before the output's header is written.
+pub(crate) fn add_chapters(
+    output: &mut format::context::Output,
+    chapters: &[ChapterMark],
+) -> Result<(), ffmpeg::Error> {
+    let chapters = chapters
+        .iter()
+        .filter(|chapter| chapter.end > chapter.start)
+        .enumerate();
+
+    for (id, chapter) in chapters {
+        let start = (chapter.start * 1000.0).round() as i64;
+        let end = (chapter.end * 1000.0).round() as i64;
+
+        trace!("Adding chapter '{}' at {start}ms", chapter.title);
+
+        output.add_chapter(id as i64, TIME_BASE, start, end, chapter.title.trim())?;
+    }
+
+    Ok(())
+}
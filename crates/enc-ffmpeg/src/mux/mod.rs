@@ -1,5 +1,10 @@
+pub mod audio_file;
+pub mod chapters;
 pub mod fragmented_audio;
 pub mod mp4;
 pub mod ogg;
+mod retention;
 pub mod segmented_audio;
 pub mod segmented_stream;
+pub mod subtitles;
+pub mod webm;
@@ -5,25 +5,86 @@ use tracing::*;
 
 use crate::{
     audio::AudioEncoder,
-    h264,
-    video::h264::{H264Encoder, H264EncoderError},
+    chapters::{ChapterMark, add_chapters},
+    h264, hevc,
+    subtitles::{SubtitleCue, SubtitleFormat, SubtitleTrack},
+    video::{
+        h264::{H264Encoder, H264EncoderError},
+        hevc::{HevcEncoder, HevcEncoderError},
+    },
 };
 
 pub struct MP4File {
     #[allow(unused)]
     tag: &'static str,
     output: format::context::Output,
-    video: H264Encoder,
+    video: MP4VideoEncoder,
     audio: Option<Box<dyn AudioEncoder + Send>>,
+    subtitles: Option<SubtitleTrack>,
     is_finished: bool,
 }
 
+/// Video encoders that can be muxed into an [`MP4File`]
+pub enum MP4VideoEncoder {
+    H264(H264Encoder),
+    Hevc(HevcEncoder),
+}
+
+impl MP4VideoEncoder {
+    fn queue_frame(
+        &mut self,
+        frame: frame::Video,
+        timestamp: Duration,
+        output: &mut format::context::Output,
+    ) -> Result<(), QueueVideoFrameError> {
+        match self {
+            Self::H264(encoder) => Ok(encoder.queue_frame(frame, timestamp, output)?),
+            Self::Hevc(encoder) => Ok(encoder.queue_frame(frame, timestamp, output)?),
+        }
+    }
+
+    fn flush(&mut self, output: &mut format::context::Output) -> Result<(), ffmpeg::Error> {
+        match self {
+            Self::H264(encoder) => encoder.flush(output),
+            Self::Hevc(encoder) => encoder.flush(output),
+        }
+    }
+}
+
+impl From<H264Encoder> for MP4VideoEncoder {
+    fn from(value: H264Encoder) -> Self {
+        Self::H264(value)
+    }
+}
+
+impl From<HevcEncoder> for MP4VideoEncoder {
+    fn from(value: HevcEncoder) -> Self {
+        Self::Hevc(value)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum VideoInitError {
+    #[error("H264/{0}")]
+    H264(#[from] H264EncoderError),
+    #[error("HEVC/{0}")]
+    Hevc(#[from] HevcEncoderError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum QueueVideoFrameError {
+    #[error("H264/{0}")]
+    H264(#[from] h264::QueueFrameError),
+    #[error("HEVC/{0}")]
+    Hevc(#[from] hevc::QueueFrameError),
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum InitError {
     #[error("{0:?}")]
     Ffmpeg(ffmpeg::Error),
     #[error("Video/{0}")]
-    VideoInit(H264EncoderError),
+    VideoInit(VideoInitError),
     #[error("Audio/{0}")]
     AudioInit(Box<dyn std::error::Error>),
 }
@@ -45,11 +106,13 @@ impl MP4File {
     pub fn init(
         tag: &'static str,
         mut output: PathBuf,
-        video: impl FnOnce(&mut format::context::Output) -> Result<H264Encoder, H264EncoderError>,
+        video: impl FnOnce(&mut format::context::Output) -> Result<MP4VideoEncoder, VideoInitError>,
         audio: impl FnOnce(
             &mut format::context::Output,
         )
             -> Option<Result<Box<dyn AudioEncoder + Send>, Box<dyn std::error::Error>>>,
+        subtitles: Vec<SubtitleCue>,
+        chapters: &[ChapterMark],
     ) -> Result<Self, InitError> {
         output.set_extension("mp4");
 
@@ -65,6 +128,9 @@ impl MP4File {
         let audio = audio(&mut output)
             .transpose()
             .map_err(InitError::AudioInit)?;
+        let subtitles = SubtitleTrack::init(&mut output, SubtitleFormat::MovText, subtitles)
+            .map_err(InitError::Ffmpeg)?;
+        add_chapters(&mut output, chapters).map_err(InitError::Ffmpeg)?;
 
         info!("Prepared encoders for mp4 file");
 
@@ -76,6 +142,7 @@ impl MP4File {
             output,
             video,
             audio,
+            subtitles,
             is_finished: false,
         })
     }
@@ -88,7 +155,7 @@ impl MP4File {
         &mut self,
         frame: frame::Video,
         timestamp: Duration,
-    ) -> Result<(), h264::QueueFrameError> {
+    ) -> Result<(), QueueVideoFrameError> {
         if self.is_finished {
             return Ok(());
         }
@@ -108,6 +175,22 @@ impl MP4File {
         audio.send_frame(frame, &mut self.output);
     }
 
+    /// Writes the subtitle cues that start at or before `time`,
+    /// which should be called before queueing the video frame at `time`.
+    pub fn queue_subtitles_until(&mut self, time: Duration) {
+        if self.is_finished {
+            return;
+        }
+
+        let Some(subtitles) = &mut self.subtitles else {
+            return;
+        };
+
+        if let Err(e) = subtitles.write_until(time, &mut self.output) {
+            error!("Failed to write subtitles: {e:#}");
+        }
+    }
+
     pub fn finish(&mut self) -> Result<FinishResult, FinishError> {
         if self.is_finished {
             return Err(FinishError::AlreadyFinished);
@@ -132,6 +215,12 @@ impl MP4File {
             })
             .unwrap_or(Ok(()));
 
+        if let Some(subtitles) = &mut self.subtitles
+            && let Err(e) = subtitles.finish(&mut self.output)
+        {
+            error!("Failed to write subtitles: {e:#}");
+        }
+
         tracing::info!("MP4Encoder: Writing trailer");
         self.output
             .write_trailer()
@@ -143,11 +232,11 @@ impl MP4File {
         })
     }
 
-    pub fn video(&self) -> &H264Encoder {
+    pub fn video(&self) -> &MP4VideoEncoder {
         &self.video
     }
 
-    pub fn video_mut(&mut self) -> &mut H264Encoder {
+    pub fn video_mut(&mut self) -> &mut MP4VideoEncoder {
         &mut self.video
     }
 }
@@ -164,3 +253,4 @@ pub struct MP4Input {
 }
 
 unsafe impl Send for H264Encoder {}
+unsafe impl Send for HevcEncoder {}
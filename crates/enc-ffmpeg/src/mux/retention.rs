@@ -0,0 +1,75 @@
+use std::{path::Path, time::Duration};
+
+/// A finished segment of a muxer that only keeps its most recent output on disk
+pub(crate) trait RetainedSegment {
+    fn path(&self) -> &Path;
+
+    fn duration(&self) -> Duration;
+}
+
+/// Deletes the oldest of `segments` that aren't needed to keep `retention` worth of them on disk,
+/// and removes them from the list.
+pub(crate) fn remove_expired_segments<S: RetainedSegment>(
+    segments: &mut Vec<S>,
+    retention: Duration,
+) {
+    let durations = segments.iter().map(|s| s.duration()).collect::<Vec<_>>();
+    let expired = expired_segment_count(&durations, retention);
+
+    for segment in segments.drain(..expired) {
+        let path = segment.path();
+        match std::fs::remove_file(path) {
+            Ok(()) => tracing::trace!("Removed expired segment {}", path.display()),
+            Err(e) => tracing::warn!("Failed to remove expired segment {}: {e}", path.display()),
+        }
+    }
+}
+
+/// How many of the oldest segments can be deleted while the rest still last at least `retention`.
+/// The newest segment is always kept, as the muxer may not have finished writing it.
+fn expired_segment_count(durations: &[Duration], retention: Duration) -> usize {
+    let mut remaining: Duration = durations.iter().sum();
+    let mut expired = 0;
+
+    for duration in durations.iter().take(durations.len().saturating_sub(1)) {
+        remaining = remaining.saturating_sub(*duration);
+        if remaining < retention {
+            break;
+        }
+        expired += 1;
+    }
+
+    expired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(durations: &[u64]) -> Vec<Duration> {
+        durations.iter().copied().map(Duration::from_secs).collect()
+    }
+
+    #[test]
+    fn expires_segments_older_than_retention() {
+        let durations = secs(&[3, 3, 3, 3, 3]);
+
+        assert_eq!(expired_segment_count(&durations, Duration::from_secs(6)), 3);
+        assert_eq!(expired_segment_count(&durations, Duration::from_secs(7)), 2);
+        assert_eq!(
+            expired_segment_count(&durations, Duration::from_secs(15)),
+            0
+        );
+        assert_eq!(
+            expired_segment_count(&durations, Duration::from_secs(60)),
+            0
+        );
+    }
+
+    #[test]
+    fn always_keeps_newest_segment() {
+        assert_eq!(expired_segment_count(&secs(&[3, 3]), Duration::ZERO), 1);
+        assert_eq!(expired_segment_count(&secs(&[3]), Duration::ZERO), 0);
+        assert_eq!(expired_segment_count(&[], Duration::from_secs(1)), 0);
+    }
+}
@@ -1,3 +1,4 @@
+use super::retention::{RetainedSegment, remove_expired_segments};
 use crate::audio::aac::{AACEncoder, AACEncoderError};
 use cap_media_info::AudioInfo;
 use ffmpeg::{format, frame};
@@ -51,6 +52,7 @@ pub struct SegmentedAudioEncoder {
     last_frame_timestamp: Option<Duration>,
 
     completed_segments: Vec<SegmentInfo>,
+    retention: Option<Duration>,
 }
 
 struct AudioSegmentEncoder {
@@ -63,14 +65,26 @@ struct AudioSegmentEncoder {
 pub struct SegmentInfo {
     pub path: PathBuf,
     pub index: u32,
+    pub start: Duration,
     pub duration: Duration,
     pub file_size: Option<u64>,
 }
 
+impl RetainedSegment for SegmentInfo {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
 #[derive(Serialize)]
 struct FragmentEntry {
     path: String,
     index: u32,
+    start: f64,
     duration: f64,
     is_complete: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -132,6 +146,7 @@ impl SegmentedAudioEncoder {
             segment_start_time: None,
             last_frame_timestamp: None,
             completed_segments: Vec::new(),
+            retention: None,
         };
 
         instance.write_in_progress_manifest();
@@ -139,6 +154,11 @@ impl SegmentedAudioEncoder {
         Ok(instance)
     }
 
+    /// Deletes completed fragments once the newer ones cover at least `retention`.
+    pub fn set_retention(&mut self, retention: Option<Duration>) {
+        self.retention = retention;
+    }
+
     fn create_segment_encoder(
         path: PathBuf,
         audio_config: AudioInfo,
@@ -214,10 +234,14 @@ impl SegmentedAudioEncoder {
             self.completed_segments.push(SegmentInfo {
                 path: completed_segment_path,
                 index: self.current_index,
+                start: segment_start,
                 duration: segment_duration,
                 file_size,
             });
 
+            if let Some(retention) = self.retention {
+                remove_expired_segments(&mut self.completed_segments, retention);
+            }
             self.write_manifest();
         }
 
@@ -251,6 +275,7 @@ impl SegmentedAudioEncoder {
                         .to_string_lossy()
                         .into_owned(),
                     index: s.index,
+                    start: s.start.as_secs_f64(),
                     duration: s.duration.as_secs_f64(),
                     is_complete: true,
                     file_size: s.file_size,
@@ -281,6 +306,7 @@ impl SegmentedAudioEncoder {
                     .to_string_lossy()
                     .into_owned(),
                 index: s.index,
+                start: s.start.as_secs_f64(),
                 duration: s.duration.as_secs_f64(),
                 is_complete: true,
                 file_size: s.file_size,
@@ -295,6 +321,7 @@ impl SegmentedAudioEncoder {
                 .to_string_lossy()
                 .into_owned(),
             index: self.current_index,
+            start: self.segment_start_time.unwrap_or_default().as_secs_f64(),
             duration: 0.0,
             is_complete: false,
             file_size: None,
@@ -342,6 +369,7 @@ impl SegmentedAudioEncoder {
                     self.completed_segments.push(SegmentInfo {
                         path: segment_path,
                         index: self.current_index,
+                        start,
                         duration: final_duration,
                         file_size,
                     });
@@ -393,6 +421,7 @@ impl SegmentedAudioEncoder {
                     self.completed_segments.push(SegmentInfo {
                         path: segment_path,
                         index: self.current_index,
+                        start,
                         duration: final_duration,
                         file_size,
                     });
@@ -431,6 +460,7 @@ impl SegmentedAudioEncoder {
                         .to_string_lossy()
                         .into_owned(),
                     index: s.index,
+                    start: s.start.as_secs_f64(),
                     duration: s.duration.as_secs_f64(),
                     is_complete: true,
                     file_size: s.file_size,
@@ -9,6 +9,7 @@ use std::{
     time::Duration,
 };
 
+use super::retention::{RetainedSegment, remove_expired_segments};
 use crate::video::h264::{
     DEFAULT_KEYFRAME_INTERVAL_SECS, H264Encoder, H264EncoderBuilder, H264EncoderError, H264Preset,
 };
@@ -74,20 +75,33 @@ pub struct SegmentedVideoEncoder {
     codec_info: CodecInfo,
 
     disk_space_callback: Option<DiskSpaceCallback>,
+    retention: Option<Duration>,
 }
 
 #[derive(Debug, Clone)]
 pub struct VideoSegmentInfo {
     pub path: PathBuf,
     pub index: u32,
+    pub start: Duration,
     pub duration: Duration,
     pub file_size: Option<u64>,
 }
 
+impl RetainedSegment for VideoSegmentInfo {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
 #[derive(Serialize)]
 struct SegmentEntry {
     path: String,
     index: u32,
+    start: f64,
     duration: f64,
     is_complete: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -155,6 +169,9 @@ pub struct SegmentedVideoEncoderConfig {
     pub preset: H264Preset,
     pub bpp: f32,
     pub output_size: Option<(u32, u32)>,
+    /// When set, completed segments are deleted once the newer ones cover at least this long,
+    /// keeping disk usage bounded for recordings that only need their most recent part.
+    pub retention: Option<Duration>,
 }
 
 impl Default for SegmentedVideoEncoderConfig {
@@ -164,6 +181,7 @@ impl Default for SegmentedVideoEncoderConfig {
             preset: H264Preset::Ultrafast,
             bpp: H264EncoderBuilder::QUALITY_BPP,
             output_size: None,
+            retention: None,
         }
     }
 }
@@ -262,6 +280,7 @@ impl SegmentedVideoEncoder {
             completed_segments: Vec::new(),
             codec_info,
             disk_space_callback: None,
+            retention: config.retention,
         };
 
         instance.write_in_progress_manifest();
@@ -318,6 +337,7 @@ impl SegmentedVideoEncoder {
         self.completed_segments.push(VideoSegmentInfo {
             path: segment_path,
             index: completed_index,
+            start: segment_start,
             duration: segment_duration,
             file_size: None,
         });
@@ -325,6 +345,10 @@ impl SegmentedVideoEncoder {
         self.current_index = completed_index + 1;
         self.segment_start_time = Some(timestamp);
         self.frames_in_segment = 0;
+
+        if let Some(retention) = self.retention {
+            remove_expired_segments(&mut self.completed_segments, retention);
+        }
     }
 
     fn current_segment_path(&self) -> PathBuf {
@@ -344,6 +368,7 @@ impl SegmentedVideoEncoder {
                     .to_string_lossy()
                     .into_owned(),
                 index: s.index,
+                start: s.start.as_secs_f64(),
                 duration: s.duration.as_secs_f64(),
                 is_complete: true,
                 file_size: s.file_size,
@@ -358,6 +383,7 @@ impl SegmentedVideoEncoder {
                 .to_string_lossy()
                 .into_owned(),
             index: self.current_index,
+            start: self.segment_start_time.unwrap_or_default().as_secs_f64(),
             duration: 0.0,
             is_complete: false,
             file_size: None,
@@ -558,6 +584,19 @@ impl SegmentedVideoEncoder {
 
                 sync_file(&segment_path);
 
+                let previous_end = self
+                    .completed_segments
+                    .iter()
+                    .filter(|s| s.index < index)
+                    .map(|s| s.start + s.duration)
+                    .max()
+                    .unwrap_or_default();
+                let start = if index == self.current_index {
+                    segment_start.unwrap_or(previous_end)
+                } else {
+                    previous_end
+                };
+
                 let duration = if index == self.current_index && frames_before_flush > 0 {
                     if let Some(start) = segment_start {
                         end_timestamp.saturating_sub(start)
@@ -578,6 +617,7 @@ impl SegmentedVideoEncoder {
                 self.completed_segments.push(VideoSegmentInfo {
                     path: segment_path,
                     index,
+                    start,
                     duration,
                     file_size: Some(file_size),
                 });
@@ -606,6 +646,7 @@ impl SegmentedVideoEncoder {
                         .to_string_lossy()
                         .into_owned(),
                     index: s.index,
+                    start: s.start.as_secs_f64(),
                     duration: s.duration.as_secs_f64(),
                     is_complete: true,
                     file_size: s.file_size,
@@ -0,0 +1,173 @@
+use std::{collections::VecDeque, time::Duration};
+
+use ffmpeg::{Packet, Rational, ffi, format};
+use tracing::*;
+
+/// A subtitle shown from `start` to `end`, in seconds from the start of the output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleCue {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum SubtitleFormat {
+    /// The 3GPP timed text format used by MP4 files
+    MovText,
+    /// The WebVTT format used by WebM files
+    WebVtt,
+}
+
+// The sample description FFmpeg's mov_text encoder writes when it has no styles to
+// convert: bottom centered, 18pt white text with no background.
+const MOV_TEXT_SAMPLE_DESCRIPTION: [u8; 48] = [
+    0x00, 0x00, 0x00, 0x00, // display flags
+    0x01, 0xFF, // horizontal and vertical justification
+    0x00, 0x00, 0x00, 0x00, // background color
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // text box
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x12, 0xFF, 0xFF, 0xFF, 0xFF, // default style
+    0x00, 0x00, 0x00, 0x12, b'f', b't', b'a', b'b', 0x00, 0x01, 0x00, 0x01, 0x05, b'S', b'e', b'r',
+    b'i', b'f', // font table
+];
+
+const TIME_BASE: Rational = Rational(1, 1000);
+
+/// A subtitle stream whose cues are written as the rest of the output catches up to them,
+/// so they're interleaved with the audio and video packets.
+pub(crate) struct SubtitleTrack {
+    stream_index: usize,
+    format: SubtitleFormat,
+    cues: VecDeque<SubtitleCue>,
+}
+
+impl SubtitleTrack {
+    /// Adds a subtitle stream to `output`, returning `None` if there are no cues to write.
+    /// Must be called before the output's header is written.
+    pub(crate) fn init(
+        output: &mut format::context::Output,
+        format: SubtitleFormat,
+        mut cues: Vec<SubtitleCue>,
+    ) -> Result<Option<Self>, ffmpeg::Error> {
+        cues.retain(|cue| cue.end > cue.start && !cue.text.trim().is_empty());
+        cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+        // samples in a timed text track can't overlap, so each cue ends when the next starts
+        for i in 1..cues.len() {
+            let next_start = cues[i].start;
+            let cue = &mut cues[i - 1];
+            cue.end = cue.end.min(next_start);
+        }
+        cues.retain(|cue| cue.end > cue.start);
+
+        if cues.is_empty() {
+            return Ok(None);
+        }
+
+        let mut stream = output.add_stream(None)?;
+        let stream_index = stream.index();
+
+        unsafe {
+            let par = (*stream.as_mut_ptr()).codecpar;
+            (*par).codec_type = ffi::AVMediaType::AVMEDIA_TYPE_SUBTITLE;
+            (*par).codec_id = match format {
+                SubtitleFormat::MovText => ffi::AVCodecID::AV_CODEC_ID_MOV_TEXT,
+                SubtitleFormat::WebVtt => ffi::AVCodecID::AV_CODEC_ID_WEBVTT,
+            };
+
+            if let SubtitleFormat::MovText = format {
+                let size = MOV_TEXT_SAMPLE_DESCRIPTION.len();
+                let extradata =
+                    ffi::av_mallocz(size + ffi::AV_INPUT_BUFFER_PADDING_SIZE as usize) as *mut u8;
+                if extradata.is_null() {
+                    return Err(ffmpeg::Error::Other {
+                        errno: ffmpeg::error::ENOMEM,
+                    });
+                }
+                std::ptr::copy_nonoverlapping(
+                    MOV_TEXT_SAMPLE_DESCRIPTION.as_ptr(),
+                    extradata,
+                    size,
+                );
+                (*par).extradata = extradata;
+                (*par).extradata_size = size as i32;
+            }
+
+            (*stream.as_mut_ptr()).time_base = TIME_BASE.into();
+        }
+
+        Ok(Some(Self {
+            stream_index,
+            format,
+            cues: cues.into(),
+        }))
+    }
+
+    /// Writes the cues that start at or before `time`.
+    pub(crate) fn write_until(
+        &mut self,
+        time: Duration,
+        output: &mut format::context::Output,
+    ) -> Result<(), ffmpeg::Error> {
+        while let Some(cue) = self.cues.front()
+            && cue.start <= time.as_secs_f64()
+        {
+            let cue = self.cues.pop_front().unwrap();
+            self.write_cue(&cue, output)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes every remaining cue.
+    pub(crate) fn finish(
+        &mut self,
+        output: &mut format::context::Output,
+    ) -> Result<(), ffmpeg::Error> {
+        while let Some(cue) = self.cues.pop_front() {
+            self.write_cue(&cue, output)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_cue(
+        &self,
+        cue: &SubtitleCue,
+        output: &mut format::context::Output,
+    ) -> Result<(), ffmpeg::Error> {
+        let text = cue.text.trim();
+        let data = match self.format {
+            // timed text samples are the text's length as a 16 bit integer, followed by the text
+            SubtitleFormat::MovText => {
+                let mut len = text.len().min(u16::MAX as usize);
+                while !text.is_char_boundary(len) {
+                    len -= 1;
+                }
+                let text = &text.as_bytes()[..len];
+                let mut data = Vec::with_capacity(2 + text.len());
+                data.extend_from_slice(&(text.len() as u16).to_be_bytes());
+                data.extend_from_slice(text);
+                data
+            }
+            SubtitleFormat::WebVtt => text.as_bytes().to_vec(),
+        };
+
+        let start = (cue.start * 1000.0).round() as i64;
+        let end = (cue.end * 1000.0).round() as i64;
+
+        trace!("Writing subtitle cue at {start}ms");
+
+        let mut packet = Packet::copy(&data);
+        packet.set_pts(Some(start));
+        packet.set_dts(Some(start));
+        packet.set_duration(end - start);
+        packet.set_stream(self.stream_index);
+        packet.rescale_ts(
+            TIME_BASE,
+            output.stream(self.stream_index).unwrap().time_base(),
+        );
+
+        packet.write_interleaved(output)
+    }
+}
@@ -127,6 +127,16 @@ fn remux_streams(
     ictx: &mut avformat::context::Input,
     octx: &mut avformat::context::Output,
 ) -> Result<(), RemuxError> {
+    remux_streams_shifted(ictx, octx, false).map(|_| ())
+}
+
+/// Copies every audio and video packet, optionally shifting all streams back so the output
+/// starts at zero. Returns the shift, which is zero when `start_at_zero` is false.
+fn remux_streams_shifted(
+    ictx: &mut avformat::context::Input,
+    octx: &mut avformat::context::Output,
+    start_at_zero: bool,
+) -> Result<Duration, RemuxError> {
     let mut stream_mapping: Vec<Option<usize>> = Vec::new();
     let mut output_stream_index = 0usize;
 
@@ -152,6 +162,7 @@ fn remux_streams(
 
     let mut last_dts: Vec<i64> = vec![i64::MIN; output_stream_index];
     let mut dts_offset: Vec<i64> = vec![0; output_stream_index];
+    let mut shift_secs: Option<f64> = None;
 
     for (input_stream, packet) in ictx.packets() {
         let input_stream_index = input_stream.index();
@@ -164,6 +175,24 @@ fn remux_streams(
 
             packet.rescale_ts(input_time_base, output_time_base);
 
+            if start_at_zero {
+                let time_base = f64::from(output_time_base);
+                let shift_secs = *shift_secs.get_or_insert_with(|| {
+                    packet.dts().or(packet.pts()).unwrap_or(0) as f64 * time_base
+                });
+                let shift = (shift_secs / time_base).round() as i64;
+
+                unsafe {
+                    let raw = packet.as_mut_ptr();
+                    if let Some(dts) = packet.dts() {
+                        (*raw).dts = dts - shift;
+                    }
+                    if let Some(pts) = packet.pts() {
+                        (*raw).pts = pts - shift;
+                    }
+                }
+            }
+
             let current_dts = packet.dts().unwrap_or(0);
 
             if last_dts[output_index] != i64::MIN && current_dts <= last_dts[output_index] {
@@ -191,7 +220,7 @@ fn remux_streams(
 
     octx.write_trailer()?;
 
-    Ok(())
+    Ok(Duration::from_secs_f64(shift_secs.unwrap_or(0.0).max(0.0)))
 }
 
 fn concatenate_with_concat_demuxer(
@@ -455,6 +484,26 @@ pub fn concatenate_m4s_segments_with_init(
     segments: &[PathBuf],
     output: &Path,
 ) -> Result<(), RemuxError> {
+    concatenate_m4s_segments(init_path, segments, output, false).map(|_| ())
+}
+
+/// Like [`concatenate_m4s_segments_with_init`], for segments taken from the end of a longer
+/// recording whose earlier segments were deleted. The output is shifted to start at zero,
+/// and the returned duration is how far into the original recording it starts.
+pub fn concatenate_trailing_m4s_segments_with_init(
+    init_path: &Path,
+    segments: &[PathBuf],
+    output: &Path,
+) -> Result<Duration, RemuxError> {
+    concatenate_m4s_segments(init_path, segments, output, true)
+}
+
+fn concatenate_m4s_segments(
+    init_path: &Path,
+    segments: &[PathBuf],
+    output: &Path,
+    start_at_zero: bool,
+) -> Result<Duration, RemuxError> {
     if segments.is_empty() {
         return Err(RemuxError::NoFragments);
     }
@@ -483,7 +532,7 @@ pub fn concatenate_m4s_segments_with_init(
         combined_file.sync_all()?;
     }
 
-    let result = remux_to_regular_mp4(&combined_path, output);
+    let result = remux_to_regular_mp4(&combined_path, output, start_at_zero);
 
     if let Err(e) = std::fs::remove_file(&combined_path) {
         tracing::warn!(
@@ -496,9 +545,13 @@ pub fn concatenate_m4s_segments_with_init(
     result
 }
 
-fn remux_to_regular_mp4(input_path: &Path, output_path: &Path) -> Result<(), RemuxError> {
+fn remux_to_regular_mp4(
+    input_path: &Path,
+    output_path: &Path,
+    start_at_zero: bool,
+) -> Result<Duration, RemuxError> {
     let mut ictx = avformat::input(input_path)?;
     let mut octx = avformat::output(output_path)?;
 
-    remux_streams(&mut ictx, &mut octx)
+    remux_streams_shifted(&mut ictx, &mut octx, start_at_zero)
 }
@@ -11,7 +11,7 @@ use ffmpeg::{
 };
 use tracing::{debug, error, trace, warn};
 
-use crate::base::EncoderBase;
+use crate::base::{EncoderBase, PacketTap};
 
 fn is_420(format: ffmpeg::format::Pixel) -> bool {
     format
@@ -26,6 +26,7 @@ pub struct H264EncoderBuilder {
     preset: H264Preset,
     output_size: Option<(u32, u32)>,
     external_conversion: bool,
+    global_header: bool,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -58,6 +59,7 @@ impl H264EncoderBuilder {
             preset: H264Preset::Ultrafast,
             output_size: None,
             external_conversion: false,
+            global_header: false,
         }
     }
 
@@ -85,6 +87,13 @@ impl H264EncoderBuilder {
         self
     }
 
+    /// Puts the SPS/PPS in the stream's extradata instead of only in-band,
+    /// which containers like FLV need to describe the stream up front.
+    pub fn with_global_header(mut self) -> Self {
+        self.global_header = true;
+        self
+    }
+
     pub fn build(
         self,
         output: &mut format::context::Output,
@@ -126,6 +135,7 @@ impl H264EncoderBuilder {
                 output_height,
                 self.bpp,
                 self.external_conversion,
+                self.global_header,
             ) {
                 Ok(encoder) => {
                     let is_hardware = matches!(
@@ -186,6 +196,7 @@ impl H264EncoderBuilder {
         output_height: u32,
         bpp: f32,
         external_conversion: bool,
+        global_header: bool,
     ) -> Result<H264Encoder, H264EncoderError> {
         let encoder_supports_input_format = codec
             .video()
@@ -318,6 +329,9 @@ impl H264EncoderBuilder {
         );
 
         encoder.set_bit_rate(bitrate);
+        if global_header {
+            encoder.set_flags(ffmpeg::codec::Flags::GLOBAL_HEADER);
+        }
 
         let encoder = encoder.open_with(encoder_options)?;
 
@@ -489,6 +503,10 @@ impl H264Encoder {
         Ok(())
     }
 
+    pub fn set_packet_tap(&mut self, tap: PacketTap) {
+        self.base.set_packet_tap(tap);
+    }
+
     pub fn flush(&mut self, output: &mut format::context::Output) -> Result<(), ffmpeg::Error> {
         self.base.process_eof(output, &mut self.encoder)
     }
@@ -272,6 +272,10 @@ impl HevcEncoderBuilder {
         output_stream.set_time_base((1, HevcEncoder::TIME_BASE));
         output_stream.set_rate(input_config.frame_rate);
         output_stream.set_parameters(&encoder);
+        // ffmpeg tags hevc as 'hev1' by default, which QuickTime and Apple devices refuse to play
+        unsafe {
+            (*(*output_stream.as_mut_ptr()).codecpar).codec_tag = u32::from_le_bytes(*b"hvc1");
+        }
 
         Ok(HevcEncoder {
             base: EncoderBase::new(stream_index),
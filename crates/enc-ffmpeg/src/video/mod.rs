@@ -1,2 +1,3 @@
 pub mod h264;
 pub mod hevc;
+pub mod vp9;
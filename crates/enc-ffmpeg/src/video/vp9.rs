@@ -0,0 +1,259 @@
+use std::{thread, time::Duration};
+
+use cap_media_info::{Pixel, VideoInfo, ensure_even};
+use ffmpeg::{
+    Dictionary,
+    codec::{context, encoder},
+    color,
+    format::{self},
+    frame,
+    threading::Config,
+};
+use tracing::{debug, error, warn};
+
+use crate::base::EncoderBase;
+
+pub struct VP9EncoderBuilder {
+    bpp: f32,
+    input_config: VideoInfo,
+    output_size: Option<(u32, u32)>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum VP9EncoderError {
+    #[error("{0:?}")]
+    FFmpeg(#[from] ffmpeg::Error),
+    #[error("Codec not found")]
+    CodecNotFound,
+    #[error("Pixel format {0:?} not supported")]
+    PixFmtNotSupported(Pixel),
+    #[error("Invalid output dimensions {width}x{height}; expected non-zero even width and height")]
+    InvalidOutputDimensions { width: u32, height: u32 },
+}
+
+impl VP9EncoderBuilder {
+    pub const QUALITY_BPP: f32 = 0.2;
+
+    pub fn new(input_config: VideoInfo) -> Self {
+        Self {
+            input_config,
+            bpp: Self::QUALITY_BPP,
+            output_size: None,
+        }
+    }
+
+    pub fn with_bpp(mut self, bpp: f32) -> Self {
+        self.bpp = bpp;
+        self
+    }
+
+    pub fn with_output_size(mut self, width: u32, height: u32) -> Result<Self, VP9EncoderError> {
+        if width == 0 || height == 0 {
+            return Err(VP9EncoderError::InvalidOutputDimensions { width, height });
+        }
+
+        self.output_size = Some((width, height));
+        Ok(self)
+    }
+
+    pub fn build(
+        self,
+        output: &mut format::context::Output,
+    ) -> Result<VP9Encoder, VP9EncoderError> {
+        let input_config = self.input_config;
+        let (raw_width, raw_height) = self
+            .output_size
+            .unwrap_or((input_config.width, input_config.height));
+
+        let output_width = ensure_even(raw_width);
+        let output_height = ensure_even(raw_height);
+
+        if raw_width != output_width || raw_height != output_height {
+            warn!(
+                raw_width,
+                raw_height,
+                output_width,
+                output_height,
+                "Auto-adjusted odd dimensions to even for VP9 encoding"
+            );
+        }
+
+        let codec = encoder::find_by_name("libvpx-vp9").ok_or(VP9EncoderError::CodecNotFound)?;
+
+        let encoder_supports_input_format = codec
+            .video()
+            .ok()
+            .and_then(|codec_video| codec_video.formats())
+            .is_some_and(|mut formats| formats.any(|f| f == input_config.pixel_format));
+
+        let output_format = if encoder_supports_input_format {
+            input_config.pixel_format
+        } else {
+            ffmpeg::format::Pixel::YUV420P
+        };
+
+        let needs_scaling =
+            output_width != input_config.width || output_height != input_config.height;
+
+        let converter = if output_format != input_config.pixel_format || needs_scaling {
+            debug!(
+                "Converting from {:?} {}x{} to {:?} {}x{} for VP9 encoding",
+                input_config.pixel_format,
+                input_config.width,
+                input_config.height,
+                output_format,
+                output_width,
+                output_height
+            );
+
+            let flags = if needs_scaling {
+                ffmpeg::software::scaling::flag::Flags::BICUBIC
+            } else {
+                ffmpeg::software::scaling::flag::Flags::FAST_BILINEAR
+            };
+
+            Some(
+                ffmpeg::software::scaling::Context::get(
+                    input_config.pixel_format,
+                    input_config.width,
+                    input_config.height,
+                    output_format,
+                    output_width,
+                    output_height,
+                    flags,
+                )
+                .map_err(|e| {
+                    error!(
+                        "Failed to create converter from {:?} to {:?}: {:?}",
+                        input_config.pixel_format, output_format, e
+                    );
+                    VP9EncoderError::PixFmtNotSupported(input_config.pixel_format)
+                })?,
+            )
+        } else {
+            None
+        };
+
+        let mut encoder_ctx = context::Context::new_with_codec(codec);
+
+        let thread_count = thread::available_parallelism()
+            .map(|v| v.get())
+            .unwrap_or(1);
+        encoder_ctx.set_threading(Config::count(thread_count));
+        let mut encoder = encoder_ctx.encoder().video()?;
+
+        encoder.set_width(output_width);
+        encoder.set_height(output_height);
+        encoder.set_format(output_format);
+        encoder.set_time_base(input_config.time_base);
+        encoder.set_frame_rate(Some(input_config.frame_rate));
+        encoder.set_colorspace(color::Space::BT709);
+        encoder.set_color_range(color::Range::MPEG);
+        unsafe {
+            (*encoder.as_mut_ptr()).color_primaries =
+                ffmpeg::ffi::AVColorPrimaries::AVCOL_PRI_BT709;
+            (*encoder.as_mut_ptr()).color_trc =
+                ffmpeg::ffi::AVColorTransferCharacteristic::AVCOL_TRC_BT709;
+        }
+
+        let frame_rate = input_config.frame_rate.0 as f32 / input_config.frame_rate.1.max(1) as f32;
+        encoder.set_bit_rate(get_bitrate(
+            output_width,
+            output_height,
+            frame_rate,
+            self.bpp,
+        ));
+
+        let mut options = Dictionary::new();
+        options.set("deadline", "good");
+        options.set("cpu-used", "4");
+        options.set("row-mt", "1");
+        options.set("tile-columns", "2");
+        options.set(
+            "g",
+            &((frame_rate * 2.0).round().max(1.0) as i32).to_string(),
+        );
+
+        let encoder = encoder.open_with(options)?;
+
+        let mut output_stream = output.add_stream(codec)?;
+        let stream_index = output_stream.index();
+        output_stream.set_time_base((1, VP9Encoder::TIME_BASE));
+        output_stream.set_rate(input_config.frame_rate);
+        output_stream.set_parameters(&encoder);
+
+        Ok(VP9Encoder {
+            base: EncoderBase::new(stream_index),
+            encoder,
+            converter,
+            output_format,
+            output_width,
+            output_height,
+        })
+    }
+}
+
+pub struct VP9Encoder {
+    base: EncoderBase,
+    encoder: encoder::Video,
+    converter: Option<ffmpeg::software::scaling::Context>,
+    output_format: format::Pixel,
+    output_width: u32,
+    output_height: u32,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum QueueFrameError {
+    #[error("Converter: {0}")]
+    Converter(ffmpeg::Error),
+    #[error("Encode: {0}")]
+    Encode(ffmpeg::Error),
+}
+
+impl VP9Encoder {
+    // matroska always uses millisecond timestamps
+    const TIME_BASE: i32 = 1000;
+
+    pub fn builder(input_config: VideoInfo) -> VP9EncoderBuilder {
+        VP9EncoderBuilder::new(input_config)
+    }
+
+    pub fn queue_frame(
+        &mut self,
+        mut frame: frame::Video,
+        timestamp: Duration,
+        output: &mut format::context::Output,
+    ) -> Result<(), QueueFrameError> {
+        self.base
+            .update_pts(&mut frame, timestamp, &mut self.encoder);
+
+        if let Some(converter) = &mut self.converter {
+            let pts = frame.pts();
+            let mut converted =
+                frame::Video::new(self.output_format, self.output_width, self.output_height);
+            converter
+                .run(&frame, &mut converted)
+                .map_err(QueueFrameError::Converter)?;
+            converted.set_pts(pts);
+            frame = converted;
+        }
+
+        self.base
+            .send_frame(&frame, output, &mut self.encoder)
+            .map_err(QueueFrameError::Encode)?;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self, output: &mut format::context::Output) -> Result<(), ffmpeg::Error> {
+        self.base.process_eof(output, &mut self.encoder)
+    }
+}
+
+fn get_bitrate(width: u32, height: u32, frame_rate: f32, bpp: f32) -> usize {
+    let frame_rate_multiplier = ((frame_rate as f64 - 30.0).max(0.0) * 0.6) + 30.0;
+    let area = (width as f64) * (height as f64);
+    let pixels_per_second = area * frame_rate_multiplier;
+
+    (pixels_per_second * bpp as f64) as usize
+}
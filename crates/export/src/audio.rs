@@ -0,0 +1,161 @@
+use crate::{
+    AudioExporterBase, ExportOutput,
+    loudness::{ExportAudio, LoudnessTarget},
+};
+use cap_editor::AudioRenderer;
+use cap_enc_ffmpeg::{
+    AudioEncoder, aac::AACEncoder, audio_file::AudioFile, opus::OpusEncoder, pcm::PcmEncoder,
+};
+use ffmpeg::frame::Audio as FFAudio;
+use serde::Deserialize;
+use specta::Type;
+use tracing::info;
+
+#[derive(Deserialize, Type, Clone, Copy, Debug, PartialEq)]
+pub enum AudioExportFormat {
+    /// Uncompressed 16-bit PCM
+    Wav,
+    /// AAC in an MPEG-4 audio container
+    M4a,
+    /// Opus in an Ogg container
+    Opus,
+}
+
+impl AudioExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Wav => "wav",
+            Self::M4a => "m4a",
+            Self::Opus => "opus",
+        }
+    }
+}
+
+#[derive(Deserialize, Type, Clone, Copy, Debug)]
+pub struct AudioExportSettings {
+    pub format: AudioExportFormat,
+    /// Normalizes the audio to this loudness when set
+    #[serde(default)]
+    pub loudness: Option<LoudnessTarget>,
+}
+
+impl AudioExportSettings {
+    /// Renders the project's mixed timeline audio to a standalone audio file.
+    /// `on_progress` is called with the index of each chunk of
+    /// [`AudioExporterBase::CHUNK_SAMPLES`] samples before it is rendered.
+    pub async fn export(
+        self,
+        base: AudioExporterBase,
+        on_progress: impl FnMut(u32) -> bool + Send + 'static,
+    ) -> Result<ExportOutput, String> {
+        tokio::task::spawn_blocking(move || self.export_blocking(base, on_progress))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+
+    fn export_blocking(
+        self,
+        base: AudioExporterBase,
+        mut on_progress: impl FnMut(u32) -> bool,
+    ) -> Result<ExportOutput, String> {
+        info!("Exporting audio with settings: {:?}", &self);
+
+        if base.project_config.audio.mute {
+            return Err("Project audio is muted".to_string());
+        }
+
+        if base.audio_segments.iter().all(|s| s.tracks.is_empty()) && base.music.is_empty() {
+            return Err("Project has no audio tracks".to_string());
+        }
+
+        let mut output_path = base.output_path.clone();
+        output_path.set_extension(self.format.extension());
+
+        let info = AudioRenderer::info();
+        let export_format = self.format;
+
+        let mut file = AudioFile::init(output_path.clone(), |o| match export_format {
+            AudioExportFormat::Wav => PcmEncoder::init(info, o)
+                .map(|v| v.boxed())
+                .map_err(Into::into),
+            AudioExportFormat::M4a => AACEncoder::init(info, o)
+                .map(|v| v.boxed())
+                .map_err(Into::into),
+            AudioExportFormat::Opus => OpusEncoder::init(info, o)
+                .map(|v| v.boxed())
+                .map_err(Into::into),
+        })
+        .map_err(|e| format!("Failed to create audio file: {e}"))?;
+
+        let total_samples = base.total_samples();
+        let project = &base.project_config;
+
+        let mut renderer = ExportAudio::new(
+            base.audio_segments,
+            base.music,
+            project,
+            base.range.0,
+            total_samples,
+            self.loudness,
+        );
+        renderer.set_playhead(base.range.0, project);
+
+        let mut sample_cursor = 0;
+        let mut chunk = 0;
+
+        while sample_cursor < total_samples {
+            if !(on_progress)(chunk) {
+                return Err("Export cancelled".to_string());
+            }
+
+            let samples = AudioExporterBase::CHUNK_SAMPLES.min(total_samples - sample_cursor);
+
+            // sped up segments and gaps past the end of a recording render short or
+            // nothing, so pad them with silence to keep the output aligned with the timeline
+            let mut frame = pad_with_silence(renderer.render_frame(samples, project), samples);
+            frame.set_pts(Some(sample_cursor as i64));
+
+            file.queue_frame(frame);
+
+            sample_cursor += samples;
+            chunk += 1;
+        }
+
+        file.finish()
+            .map_err(|e| format!("Failed to finish encoding: {e}"))?
+            .map_err(|e| format!("Audio encoding failed: {e}"))?;
+
+        let loudness = renderer.loudness_report();
+        if let Some(loudness) = &loudness {
+            info!("Export loudness: {loudness:?}");
+        }
+
+        Ok(ExportOutput {
+            path: output_path,
+            loudness,
+            sidecars: vec![],
+        })
+    }
+}
+
+/// Copies `rendered` into a frame of exactly `samples` samples, silent past its end.
+fn pad_with_silence(rendered: Option<FFAudio>, samples: usize) -> FFAudio {
+    let info = AudioRenderer::info();
+    let mut frame = info.empty_frame(samples);
+
+    let plane_bytes = |samples: usize| {
+        let channels = if frame.is_planar() { 1 } else { info.channels };
+        samples.min(frame.samples()) * channels * info.sample_size()
+    };
+    let rendered_bytes = rendered.as_ref().map_or(0, |r| plane_bytes(r.samples()));
+
+    for plane in 0..frame.planes() {
+        let data = frame.data_mut(plane);
+        if let Some(rendered) = &rendered {
+            data[..rendered_bytes].copy_from_slice(&rendered.data(plane)[..rendered_bytes]);
+        }
+        data[rendered_bytes..].fill(0);
+    }
+
+    frame
+}
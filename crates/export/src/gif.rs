@@ -112,7 +112,7 @@ impl GifExportSettings {
                 .and_then(|v| v.map_err(|v| v.to_string()))
         });
 
-        let render_video_task = cap_rendering::render_video_to_channel(
+        let render_video_task = cap_rendering::render_video_range_to_channel(
             &base.render_constants,
             &base.project_config,
             tx_image_data,
@@ -128,6 +128,7 @@ impl GifExportSettings {
             fps,
             self.resolution_base,
             &base.recordings,
+            base.frame_range(fps),
         )
         .then(|f| async { f.map_err(|v| v.to_string()) });
 
@@ -1,10 +1,24 @@
+pub mod audio;
 pub mod gif;
+pub mod loudness;
 pub mod mp4;
+mod pipeline;
+pub mod webm;
 
-use cap_editor::SegmentMedia;
-use cap_project::{ProjectConfiguration, RecordingMeta, StudioRecordingMeta};
+use cap_editor::{AudioRenderer, AudioSegment, MusicLibrary, SegmentMedia, get_audio_segments};
+use cap_enc_ffmpeg::{chapters::ChapterMark, subtitles::SubtitleCue};
+use cap_project::{
+    Chapter, ChapterFormat, ProjectConfiguration, RecordingMeta, StudioRecordingMeta,
+};
 use cap_rendering::{ProjectRecordingsMeta, RenderVideoConstants};
-use std::{path::PathBuf, sync::Arc};
+use loudness::{ExportAudio, LoudnessReport, LoudnessTarget};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum ExportError {
@@ -46,6 +60,49 @@ pub enum ExporterBuildError {
     MediaLoad(String),
     #[error("IO error at path '{0}': {1}")]
     IO(PathBuf, std::io::Error),
+    #[error("Invalid export range: {0}")]
+    InvalidRange(String),
+    #[error("Failed to load music: {0}")]
+    MusicLoad(String),
+}
+
+/// A finished export
+#[derive(Serialize, Type, Clone, Debug)]
+pub struct ExportOutput {
+    pub path: PathBuf,
+    /// Set when the export's audio was normalized to a loudness target
+    pub loudness: Option<LoudnessReport>,
+    /// Files written next to `path` that belong with the video, such as chapter lists
+    pub sidecars: Vec<PathBuf>,
+}
+
+/// A section of the timeline to export, in timeline seconds.
+#[derive(Deserialize, Type, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ExportRange {
+    /// Defaults to the start of the timeline
+    pub start: Option<f64>,
+    /// Defaults to the end of the timeline
+    pub end: Option<f64>,
+}
+
+impl ExportRange {
+    /// Resolves the range against a timeline of `duration` seconds, clamping it to the timeline.
+    pub fn resolve(&self, duration: f64) -> Result<(f64, f64), String> {
+        let start = self.start.unwrap_or(0.0);
+        let end = self.end.unwrap_or(duration).min(duration);
+
+        if !start.is_finite() || start < 0.0 {
+            return Err(format!("start must be a non-negative number, got {start}"));
+        }
+
+        if !end.is_finite() || end <= start {
+            return Err(format!(
+                "end ({end}) must be after start ({start}) and within the timeline ({duration}s)"
+            ));
+        }
+
+        Ok((start, end))
+    }
 }
 
 pub struct ExporterBuilder {
@@ -53,6 +110,7 @@ pub struct ExporterBuilder {
     config: Option<ProjectConfiguration>,
     output_path: Option<PathBuf>,
     force_ffmpeg_decoder: bool,
+    range: ExportRange,
 }
 
 impl ExporterBuilder {
@@ -66,7 +124,12 @@ impl ExporterBuilder {
         self
     }
 
-    pub async fn build(self) -> Result<ExporterBase, ExporterBuildError> {
+    pub fn with_range(mut self, range: ExportRange) -> Self {
+        self.range = range;
+        self
+    }
+
+    fn load_project(&self) -> Result<(ProjectConfiguration, RecordingMeta), ExporterBuildError> {
         type Error = ExporterBuildError;
 
         let project_config = serde_json::from_reader(
@@ -77,6 +140,47 @@ impl ExporterBuilder {
 
         let recording_meta =
             RecordingMeta::load_for_project(&self.project_path).map_err(Error::MetaLoad)?;
+
+        Ok((project_config, recording_meta))
+    }
+
+    async fn load_music(
+        &self,
+        project_config: &ProjectConfiguration,
+    ) -> Result<MusicLibrary, ExporterBuildError> {
+        let project_path = self.project_path.clone();
+        let audio = project_config.audio.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut music = MusicLibrary::default();
+            music.load(&project_path, &audio).map(|()| music)
+        })
+        .await
+        .map_err(|e| ExporterBuildError::MusicLoad(e.to_string()))?
+        .map_err(ExporterBuildError::MusicLoad)
+    }
+
+    fn resolve_output_path(
+        &self,
+        recording_meta: &RecordingMeta,
+    ) -> Result<PathBuf, ExporterBuildError> {
+        let output_path = self
+            .output_path
+            .clone()
+            .unwrap_or_else(|| recording_meta.output_path());
+
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ExporterBuildError::IO(parent.to_path_buf(), e))?;
+        }
+
+        Ok(output_path)
+    }
+
+    pub async fn build(self) -> Result<ExporterBase, ExporterBuildError> {
+        type Error = ExporterBuildError;
+
+        let (project_config, recording_meta) = self.load_project()?;
         let studio_meta = recording_meta
             .studio_meta()
             .ok_or(Error::NotStudioRecording)?;
@@ -96,22 +200,27 @@ impl ExporterBuilder {
             .map_err(Error::RendererSetup)?,
         );
 
+        let range = self
+            .range
+            .resolve(cap_rendering::get_duration(
+                &recordings,
+                &recording_meta,
+                studio_meta,
+                &project_config,
+            ))
+            .map_err(Error::InvalidRange)?;
+
         let segments =
             cap_editor::create_segments(&recording_meta, studio_meta, self.force_ffmpeg_decoder)
                 .await
                 .map_err(Error::MediaLoad)?;
 
-        let output_path = self
-            .output_path
-            .unwrap_or_else(|| recording_meta.output_path());
-
-        if let Some(parent) = output_path.parent() {
-            std::fs::create_dir_all(parent)
-                .map_err(|e| ExporterBuildError::IO(parent.to_path_buf(), e))?;
-        }
+        let output_path = self.resolve_output_path(&recording_meta)?;
+        let music = self.load_music(&project_config).await?;
 
         Ok(ExporterBase {
             output_path,
+            music,
             studio_meta: studio_meta.clone(),
             recordings,
             render_constants,
@@ -119,6 +228,45 @@ impl ExporterBuilder {
             recording_meta,
             project_config,
             project_path: self.project_path,
+            range,
+        })
+    }
+
+    /// Loads only what's needed to export the project's mixed audio,
+    /// skipping the GPU renderer and video decoders entirely.
+    pub async fn build_audio(self) -> Result<AudioExporterBase, ExporterBuildError> {
+        type Error = ExporterBuildError;
+
+        let (project_config, recording_meta) = self.load_project()?;
+        let studio_meta = recording_meta
+            .studio_meta()
+            .ok_or(Error::NotStudioRecording)?;
+
+        let recordings = ProjectRecordingsMeta::new(&recording_meta.project_path, studio_meta)
+            .map_err(Error::RecordingsMeta)?;
+
+        let range = self
+            .range
+            .resolve(cap_rendering::get_duration(
+                &recordings,
+                &recording_meta,
+                studio_meta,
+                &project_config,
+            ))
+            .map_err(Error::InvalidRange)?;
+
+        let audio_segments = cap_editor::load_audio_segments(&recording_meta, studio_meta)
+            .map_err(Error::MediaLoad)?;
+
+        let output_path = self.resolve_output_path(&recording_meta)?;
+        let music = self.load_music(&project_config).await?;
+
+        Ok(AudioExporterBase {
+            project_config,
+            audio_segments,
+            music,
+            output_path,
+            range,
         })
     }
 }
@@ -131,19 +279,120 @@ pub struct ExporterBase {
     recordings: Arc<ProjectRecordingsMeta>,
     render_constants: Arc<RenderVideoConstants>,
     segments: Vec<SegmentMedia>,
+    music: MusicLibrary,
     output_path: PathBuf,
+    // start and end of the exported section, in timeline seconds
+    range: (f64, f64),
 }
 
 impl ExporterBase {
     pub fn total_frames(&self, fps: u32) -> u32 {
-        let duration = cap_rendering::get_duration(
-            &self.recordings,
-            &self.recording_meta,
-            &self.studio_meta,
-            &self.project_config,
-        );
+        self.frame_range(fps).len() as u32
+    }
 
-        (fps as f64 * duration).ceil() as u32
+    /// Timeline frame numbers that will be rendered at `fps`.
+    pub fn frame_range(&self, fps: u32) -> Range<u32> {
+        let (start, end) = self.range;
+
+        (fps as f64 * start).floor() as u32..(fps as f64 * end).ceil() as u32
+    }
+
+    /// Timeline time in seconds of the first exported frame.
+    pub fn start_time(&self, fps: u32) -> f64 {
+        self.frame_range(fps).start as f64 / fps as f64
+    }
+
+    /// Timeline audio to export alongside the frames rendered at `fps`, or `None` if the
+    /// project has no audio or is muted. Normalizing measures the whole range up front.
+    pub(crate) async fn export_audio(
+        &self,
+        fps: u32,
+        target: Option<LoudnessTarget>,
+    ) -> Result<Option<ExportAudio>, String> {
+        let audio_segments = get_audio_segments(&self.segments);
+
+        if audio_segments.is_empty() || self.project_config.audio.mute {
+            return Ok(None);
+        }
+
+        let music = self.music.clone();
+        let project = self.project_config.clone();
+        let start_time = self.start_time(fps);
+        let total_samples = (u64::from(self.total_frames(fps))
+            * u64::from(AudioRenderer::SAMPLE_RATE)
+            / u64::from(fps)) as usize;
+
+        tokio::task::spawn_blocking(move || {
+            ExportAudio::new(
+                audio_segments,
+                music,
+                &project,
+                start_time,
+                total_samples,
+                target,
+            )
+        })
+        .await
+        .map(Some)
+        .map_err(|e| e.to_string())
+    }
+
+    /// The project's captions as subtitle cues relative to the first exported frame,
+    /// or none if the project doesn't export them as subtitles.
+    pub fn subtitle_cues(&self, fps: u32) -> Vec<SubtitleCue> {
+        let Some(captions) = self
+            .project_config
+            .captions
+            .clone()
+            .or_else(|| self.recording_meta.project_config().captions)
+        else {
+            return vec![];
+        };
+
+        if !captions.settings.export_with_subtitles {
+            return vec![];
+        }
+
+        // captions are transcribed from the recording's segments played back to back
+        let clip_offsets = self.recordings.segment_offsets();
+
+        let start_time = self.start_time(fps);
+        let (_, end_time) = self.range;
+
+        captions
+            .to_timeline(self.project_config.timeline.as_ref(), &clip_offsets)
+            .into_iter()
+            .filter(|caption| {
+                f64::from(caption.end) > start_time && f64::from(caption.start) < end_time
+            })
+            .map(|caption| SubtitleCue {
+                start: f64::from(caption.start).max(start_time) - start_time,
+                end: f64::from(caption.end).min(end_time) - start_time,
+                text: caption.text,
+            })
+            .collect()
+    }
+
+    /// The timeline's chapters relative to the first exported frame,
+    /// trimmed to the exported range.
+    pub fn chapters(&self, fps: u32) -> Vec<Chapter> {
+        let Some(timeline) = &self.project_config.timeline else {
+            return vec![];
+        };
+
+        let start_time = self.start_time(fps);
+        let (_, end_time) = self.range;
+
+        timeline
+            .chapters()
+            .into_iter()
+            .filter(|chapter| chapter.end > start_time && chapter.start < end_time)
+            .map(|chapter| Chapter {
+                start: chapter.start.max(start_time) - start_time,
+                end: chapter.end.min(end_time) - start_time,
+                title: chapter.title,
+            })
+            .collect()
     }
 
     pub fn builder(project_path: PathBuf) -> ExporterBuilder {
@@ -152,6 +401,159 @@ impl ExporterBase {
             config: None,
             output_path: None,
             force_ffmpeg_decoder: false,
+            range: ExportRange::default(),
+        }
+    }
+}
+
+/// Chapters in the form the muxers write into the video
+pub(crate) fn chapter_marks(chapters: &[Chapter]) -> Vec<ChapterMark> {
+    chapters
+        .iter()
+        .map(|chapter| ChapterMark {
+            start: chapter.start,
+            end: chapter.end,
+            title: chapter.title.clone(),
+        })
+        .collect()
+}
+
+/// Writes `chapters` next to the video at `output_path`, as a list for YouTube descriptions
+/// (`video.chapters.txt`) and a WebVTT chapters track (`video.chapters.vtt`),
+/// returning the paths written.
+pub(crate) fn write_chapter_files(
+    output_path: &Path,
+    chapters: &[Chapter],
+) -> Result<Vec<PathBuf>, String> {
+    if chapters.is_empty() {
+        return Ok(vec![]);
+    }
+
+    [ChapterFormat::YouTube, ChapterFormat::WebVtt]
+        .into_iter()
+        .map(|format| {
+            let path = output_path.with_extension(format!("chapters.{}", format.extension()));
+            std::fs::write(&path, format.serialize(chapters))
+                .map_err(|e| format!("Failed to write chapters to '{}': {e}", path.display()))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Copies the `sidecars` of the video at `video` next to `destination`, where the video has been
+/// copied to, renaming them to match it (`video.chapters.txt` becomes `destination.chapters.txt`).
+pub fn copy_sidecars(video: &Path, sidecars: &[PathBuf], destination: &Path) -> Result<(), String> {
+    let video_stem = video.file_stem().unwrap_or_default().to_string_lossy();
+    let destination_stem = destination
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+
+    for sidecar in sidecars {
+        let name = sidecar.file_name().unwrap_or_default().to_string_lossy();
+        let suffix = name.strip_prefix(&*video_stem).unwrap_or(&*name);
+        let target = destination.with_file_name(format!("{destination_stem}{suffix}"));
+
+        std::fs::copy(sidecar, &target)
+            .map_err(|e| format!("Failed to copy '{}': {e}", target.display()))?;
+    }
+
+    Ok(())
+}
+
+pub struct AudioExporterBase {
+    project_config: ProjectConfiguration,
+    audio_segments: Vec<AudioSegment>,
+    music: MusicLibrary,
+    output_path: PathBuf,
+    // start and end of the exported section, in timeline seconds
+    range: (f64, f64),
+}
+
+impl AudioExporterBase {
+    /// Number of samples rendered between each progress update
+    pub const CHUNK_SAMPLES: usize = 1024;
+
+    pub fn total_samples(&self) -> usize {
+        let (start, end) = self.range;
+
+        ((end - start) * cap_editor::AudioRenderer::SAMPLE_RATE as f64).ceil() as usize
+    }
+
+    pub fn total_chunks(&self) -> u32 {
+        self.total_samples().div_ceil(Self::CHUNK_SAMPLES) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_defaults_to_whole_timeline() {
+        assert_eq!(ExportRange::default().resolve(42.0), Ok((0.0, 42.0)));
+    }
+
+    #[test]
+    fn range_end_is_clamped_to_timeline() {
+        let range = ExportRange {
+            start: Some(10.0),
+            end: Some(100.0),
+        };
+        assert_eq!(range.resolve(30.0), Ok((10.0, 30.0)));
+    }
+
+    #[test]
+    fn invalid_ranges_are_rejected() {
+        let inverted = ExportRange {
+            start: Some(20.0),
+            end: Some(10.0),
+        };
+        assert!(inverted.resolve(30.0).is_err());
+
+        let past_end = ExportRange {
+            start: Some(40.0),
+            end: None,
+        };
+        assert!(past_end.resolve(30.0).is_err());
+
+        let negative = ExportRange {
+            start: Some(-1.0),
+            end: None,
+        };
+        assert!(negative.resolve(30.0).is_err());
+    }
+
+    #[test]
+    fn chapter_files_are_copied_alongside_the_video() {
+        let dir = tempfile::tempdir().unwrap();
+        let video = dir.path().join("output.mp4");
+        let destination = dir.path().join("saved").join("My Video.mp4");
+        std::fs::create_dir(destination.parent().unwrap()).unwrap();
+
+        let chapters = [Chapter {
+            start: 0.0,
+            end: 5.0,
+            title: "Intro".to_string(),
+        }];
+        let sidecars = write_chapter_files(&video, &chapters).unwrap();
+        assert_eq!(
+            sidecars,
+            [
+                dir.path().join("output.chapters.txt"),
+                dir.path().join("output.chapters.vtt"),
+            ]
+        );
+
+        copy_sidecars(&video, &sidecars, &destination).unwrap();
+        for (sidecar, copied) in sidecars
+            .iter()
+            .zip(["My Video.chapters.txt", "My Video.chapters.vtt"])
+        {
+            assert_eq!(
+                std::fs::read_to_string(destination.with_file_name(copied)).unwrap(),
+                std::fs::read_to_string(sidecar).unwrap()
+            );
         }
     }
 }
@@ -0,0 +1,254 @@
+use cap_audio::{LoudnessMeasurement, LoudnessMeter, TruePeakLimiter, cast_f32_slice_to_bytes};
+use cap_editor::{AudioRenderer, AudioSegment, MusicLibrary};
+use cap_project::ProjectConfiguration;
+use ffmpeg::{ChannelLayout, frame::Audio as FFAudio};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tracing::info;
+
+/// Quiet recordings are only turned up this far, so background noise isn't blown up to speech level
+const MAX_GAIN_DB: f64 = 20.0;
+const MEASURE_CHUNK_SAMPLES: usize = 4096;
+
+/// Loudness to normalize exported audio to, following EBU R128
+#[derive(Deserialize, Type, Clone, Copy, Debug, PartialEq)]
+pub struct LoudnessTarget {
+    pub integrated_lufs: f64,
+    /// Ceiling for the true (inter-sample) peak, in dBTP
+    pub true_peak_db: f64,
+}
+
+impl LoudnessTarget {
+    /// Common target for podcasts and Apple platforms
+    pub const PODCAST: Self = Self {
+        integrated_lufs: -16.0,
+        true_peak_db: -1.0,
+    };
+
+    /// Common target for YouTube and Spotify
+    pub const STREAMING: Self = Self {
+        integrated_lufs: -14.0,
+        true_peak_db: -1.0,
+    };
+}
+
+/// Loudness of an export's audio before and after normalization.
+/// Loudness is `None` when the audio is silent.
+#[derive(Serialize, Type, Clone, Copy, Debug, PartialEq)]
+pub struct LoudnessReport {
+    pub source_integrated_lufs: Option<f64>,
+    pub source_true_peak_db: f64,
+    pub gain_db: f64,
+    pub integrated_lufs: Option<f64>,
+    pub true_peak_db: f64,
+}
+
+/// The timeline audio of an export, optionally normalized to a [`LoudnessTarget`].
+/// Normalized audio is rendered twice: once up front to measure it, then again while exporting.
+pub(crate) enum ExportAudio {
+    Direct(AudioRenderer),
+    Normalized(Box<NormalizedAudio>),
+}
+
+impl ExportAudio {
+    /// `total_samples` is the length of the export starting at `start_time`, which is measured
+    /// when normalizing. This enhances voice tracks and renders the whole range, so call it from
+    /// a blocking context.
+    pub fn new(
+        segments: Vec<AudioSegment>,
+        music: MusicLibrary,
+        project: &ProjectConfiguration,
+        start_time: f64,
+        total_samples: usize,
+        target: Option<LoudnessTarget>,
+    ) -> Self {
+        if project.audio.improve {
+            segments.iter().for_each(AudioSegment::enhance_voice);
+        }
+
+        match target {
+            None => Self::Direct(AudioRenderer::new(segments).with_music(music)),
+            Some(target) => Self::Normalized(Box::new(NormalizedAudio::new(
+                segments,
+                music,
+                project,
+                start_time,
+                total_samples,
+                target,
+            ))),
+        }
+    }
+
+    pub fn set_playhead(&mut self, playhead: f64, project: &ProjectConfiguration) {
+        match self {
+            Self::Direct(renderer) => renderer.set_playhead(playhead, project),
+            Self::Normalized(audio) => audio.set_playhead(playhead, project),
+        }
+    }
+
+    /// Like [`AudioRenderer::render_frame`], except normalized audio always renders
+    /// exactly `samples` samples, with silence in any gaps.
+    pub fn render_frame(
+        &mut self,
+        samples: usize,
+        project: &ProjectConfiguration,
+    ) -> Option<FFAudio> {
+        match self {
+            Self::Direct(renderer) => renderer.render_frame(samples, project),
+            Self::Normalized(audio) => Some(audio.render_frame(samples, project)),
+        }
+    }
+
+    pub fn loudness_report(&self) -> Option<LoudnessReport> {
+        match self {
+            Self::Direct(_) => None,
+            Self::Normalized(audio) => Some(audio.report()),
+        }
+    }
+}
+
+pub(crate) struct NormalizedAudio {
+    renderer: AudioRenderer,
+    target: LoudnessTarget,
+    source: LoudnessMeasurement,
+    gain_db: f64,
+    gain: f32,
+    limiter: TruePeakLimiter,
+    primed: bool,
+    output: LoudnessMeter,
+}
+
+impl NormalizedAudio {
+    fn new(
+        segments: Vec<AudioSegment>,
+        music: MusicLibrary,
+        project: &ProjectConfiguration,
+        start_time: f64,
+        total_samples: usize,
+        target: LoudnessTarget,
+    ) -> Self {
+        let source = measure(
+            AudioRenderer::new(segments.clone()).with_music(music.clone()),
+            project,
+            start_time,
+            total_samples,
+        );
+        let gain_db = source
+            .integrated_lufs
+            .map(|lufs| (target.integrated_lufs - lufs).min(MAX_GAIN_DB))
+            .unwrap_or(0.0);
+
+        info!(
+            source_lufs = ?source.integrated_lufs,
+            source_true_peak_db = source.true_peak_db,
+            gain_db,
+            "Measured export loudness"
+        );
+
+        Self {
+            renderer: AudioRenderer::new(segments).with_music(music),
+            target,
+            source,
+            gain_db,
+            gain: 10f64.powf(gain_db / 20.0) as f32,
+            limiter: new_limiter(target),
+            primed: false,
+            output: LoudnessMeter::new(AudioRenderer::CHANNELS, AudioRenderer::SAMPLE_RATE),
+        }
+    }
+
+    fn set_playhead(&mut self, playhead: f64, project: &ProjectConfiguration) {
+        self.renderer.set_playhead(playhead, project);
+        self.limiter = new_limiter(self.target);
+        self.primed = false;
+    }
+
+    fn render_frame(&mut self, samples: usize, project: &ProjectConfiguration) -> FFAudio {
+        // the limiter's output lags its input, so feed it a little ahead of the playhead
+        // once and drop what it returns to keep the output aligned with the timeline
+        if !self.primed {
+            let mut lookahead = render_samples(&mut self.renderer, self.limiter.latency(), project);
+            self.apply(&mut lookahead);
+            self.primed = true;
+        }
+
+        let mut data = render_samples(&mut self.renderer, samples, project);
+        self.apply(&mut data);
+        self.output.push(&data);
+
+        let mut frame = FFAudio::new(AudioRenderer::SAMPLE_FORMAT, samples, ChannelLayout::STEREO);
+        frame.set_rate(AudioRenderer::SAMPLE_RATE);
+        frame.data_mut(0)[0..data.len() * size_of::<f32>()]
+            .copy_from_slice(unsafe { cast_f32_slice_to_bytes(&data) });
+        frame
+    }
+
+    fn apply(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            *sample *= self.gain;
+        }
+        self.limiter.process(samples);
+    }
+
+    fn report(&self) -> LoudnessReport {
+        let output = self.output.measurement();
+
+        LoudnessReport {
+            source_integrated_lufs: self.source.integrated_lufs,
+            source_true_peak_db: self.source.true_peak_db,
+            gain_db: self.gain_db,
+            integrated_lufs: output.integrated_lufs,
+            true_peak_db: output.true_peak_db,
+        }
+    }
+}
+
+fn new_limiter(target: LoudnessTarget) -> TruePeakLimiter {
+    TruePeakLimiter::new(
+        AudioRenderer::CHANNELS,
+        AudioRenderer::SAMPLE_RATE,
+        target.true_peak_db,
+    )
+}
+
+fn measure(
+    mut renderer: AudioRenderer,
+    project: &ProjectConfiguration,
+    start_time: f64,
+    total_samples: usize,
+) -> LoudnessMeasurement {
+    renderer.set_playhead(start_time, project);
+
+    let mut meter = LoudnessMeter::new(AudioRenderer::CHANNELS, AudioRenderer::SAMPLE_RATE);
+    let mut rendered = 0;
+
+    while rendered < total_samples {
+        let samples = MEASURE_CHUNK_SAMPLES.min(total_samples - rendered);
+        meter.push(&render_samples(&mut renderer, samples, project));
+        rendered += samples;
+    }
+
+    meter.measurement()
+}
+
+/// Renders exactly `samples` interleaved stereo samples, filling anything the renderer
+/// skips (sped up segments, gaps past the end of a recording) with silence
+fn render_samples(
+    renderer: &mut AudioRenderer,
+    samples: usize,
+    project: &ProjectConfiguration,
+) -> Vec<f32> {
+    let channels = AudioRenderer::CHANNELS as usize;
+    let mut out = Vec::with_capacity(samples * channels);
+
+    while out.len() < samples * channels {
+        let remaining = samples - out.len() / channels;
+
+        match renderer.render_frame_raw(remaining, project) {
+            Some((_, data)) if !data.is_empty() => out.extend_from_slice(&data),
+            _ => out.resize(samples * channels, 0.0),
+        }
+    }
+
+    out
+}
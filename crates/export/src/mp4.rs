@@ -1,14 +1,15 @@
-use crate::ExporterBase;
-use cap_editor::{AudioRenderer, get_audio_segments};
-use cap_enc_ffmpeg::{AudioEncoder, aac::AACEncoder, h264::H264Encoder, mp4::*};
-use cap_media_info::{RawVideoFormat, VideoInfo};
+use crate::{
+    ExportOutput, ExporterBase, chapter_marks, loudness::LoudnessTarget, pipeline::ExportEncoder,
+    write_chapter_files,
+};
+use cap_editor::AudioRenderer;
+use cap_enc_ffmpeg::{AudioEncoder, aac::AACEncoder, h264::H264Encoder, hevc::HevcEncoder, mp4::*};
 use cap_project::XY;
-use cap_rendering::{ProjectUniforms, RenderSegment, RenderedFrame};
-use futures::FutureExt;
+use ffmpeg::frame;
 use image::ImageBuffer;
 use serde::Deserialize;
 use specta::Type;
-use std::{path::PathBuf, time::Duration};
+use std::time::Duration;
 use tracing::{info, trace, warn};
 
 #[derive(Deserialize, Type, Clone, Copy, Debug)]
@@ -30,6 +31,24 @@ impl ExportCompression {
     }
 }
 
+#[derive(Deserialize, Type, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ExportCodec {
+    #[default]
+    H264,
+    Hevc,
+}
+
+impl ExportCodec {
+    /// HEVC reaches the same quality as H.264 at roughly half the bitrate,
+    /// so the compression presets are scaled accordingly.
+    pub fn bpp_multiplier(&self) -> f32 {
+        match self {
+            Self::H264 => 1.0,
+            Self::Hevc => 0.5,
+        }
+    }
+}
+
 #[derive(Deserialize, Type, Clone, Copy, Debug)]
 pub struct Mp4ExportSettings {
     pub fps: u32,
@@ -38,12 +57,17 @@ pub struct Mp4ExportSettings {
     pub custom_bpp: Option<f32>,
     #[serde(default)]
     pub force_ffmpeg_decoder: bool,
+    #[serde(default)]
+    pub codec: ExportCodec,
+    /// Normalizes the audio to this loudness when set
+    #[serde(default)]
+    pub loudness: Option<LoudnessTarget>,
 }
 
 impl Mp4ExportSettings {
     pub fn effective_bpp(&self) -> f32 {
         self.custom_bpp
-            .unwrap_or_else(|| self.compression.bits_per_pixel())
+            .unwrap_or_else(|| self.compression.bits_per_pixel() * self.codec.bpp_multiplier())
     }
 }
 
@@ -51,255 +75,134 @@ impl Mp4ExportSettings {
     pub async fn export(
         self,
         base: ExporterBase,
-        mut on_progress: impl FnMut(u32) -> bool + Send + 'static,
-    ) -> Result<PathBuf, String> {
+        on_progress: impl FnMut(u32) -> bool + Send + 'static,
+    ) -> Result<ExportOutput, String> {
         let output_path = base.output_path.clone();
-        let meta = &base.studio_meta;
 
         info!("Exporting mp4 with settings: {:?}", &self);
         info!("Expected to render {} frames", base.total_frames(self.fps));
 
-        let (tx_image_data, mut video_rx) = tokio::sync::mpsc::channel::<(RenderedFrame, u32)>(8);
-        let (frame_tx, frame_rx) = std::sync::mpsc::sync_channel::<MP4Input>(8);
-
-        let fps = self.fps;
-
-        let output_size = ProjectUniforms::get_output_size(
-            &base.render_constants.options,
-            &base.project_config,
-            self.resolution_base,
-        );
-
-        let mut video_info =
-            VideoInfo::from_raw(RawVideoFormat::Rgba, output_size.0, output_size.1, fps);
-        video_info.time_base = ffmpeg::Rational::new(1, fps as i32);
-
-        let audio_segments = get_audio_segments(&base.segments);
-
-        let mut audio_renderer = audio_segments
-            .first()
-            .filter(|_| !base.project_config.audio.mute)
-            .map(|_| AudioRenderer::new(audio_segments.clone()));
-        let has_audio = audio_renderer.is_some();
-
-        let encoder_thread = tokio::task::spawn_blocking(move || {
-            trace!("Creating MP4File encoder");
-
-            let mut encoder = MP4File::init(
-                "output",
-                base.output_path.clone(),
-                |o| {
-                    H264Encoder::builder(video_info)
-                        .with_bpp(self.effective_bpp())
-                        .build(o)
-                },
-                |o| {
-                    has_audio.then(|| {
-                        AACEncoder::init(AudioRenderer::info(), o)
-                            .map(|v| v.boxed())
-                            .map_err(Into::into)
-                    })
+        let audio = base.export_audio(self.fps, self.loudness).await?;
+        let subtitles = base.subtitle_cues(self.fps);
+        let chapters = base.chapters(self.fps);
+        let encoder_chapters = chapter_marks(&chapters);
+
+        let rendered = base
+            .render_to_encoder(
+                self.fps,
+                self.resolution_base,
+                audio,
+                on_progress,
+                move |video_info, has_audio| {
+                    trace!("Creating MP4File encoder");
+
+                    MP4File::init(
+                        "output",
+                        output_path.clone(),
+                        |o| match self.codec {
+                            ExportCodec::H264 => H264Encoder::builder(video_info)
+                                .with_bpp(self.effective_bpp())
+                                .build(o)
+                                .map(Into::into)
+                                .map_err(Into::into),
+                            ExportCodec::Hevc => HevcEncoder::builder(video_info)
+                                .with_bpp(self.effective_bpp())
+                                .build(o)
+                                .map(Into::into)
+                                .map_err(Into::into),
+                        },
+                        |o| {
+                            has_audio.then(|| {
+                                AACEncoder::init(AudioRenderer::info(), o)
+                                    .map(|v| v.boxed())
+                                    .map_err(Into::into)
+                            })
+                        },
+                        subtitles,
+                        &encoder_chapters,
+                    )
+                    .map_err(|v| v.to_string())
                 },
             )
-            .map_err(|v| v.to_string())?;
-
-            info!("Created MP4File encoder");
-
-            while let Ok(frame) = frame_rx.recv() {
-                encoder
-                    .queue_video_frame(frame.video, Duration::MAX)
-                    .map_err(|err| err.to_string())?;
-                if let Some(audio) = frame.audio {
-                    encoder.queue_audio_frame(audio);
-                }
-            }
-
-            let res = encoder
-                .finish()
-                .map_err(|e| format!("Failed to finish encoding: {e}"))?;
-
-            if let Err(e) = res.video_finish {
-                return Err(format!("Video encoding failed: {e}"));
-            }
-            if let Err(e) = res.audio_finish {
-                return Err(format!("Audio encoding failed: {e}"));
-            }
-
-            Ok::<_, String>(base.output_path)
-        })
-        .then(|r| async { r.map_err(|e| e.to_string()).and_then(|v| v) });
+            .await?;
 
-        let render_task = tokio::spawn({
-            let project = base.project_config.clone();
+        if let Some(frame) = rendered.first_frame {
             let project_path = base.project_path.clone();
-            async move {
-                let mut frame_count = 0;
-                let mut first_frame = None;
-                let sample_rate = u64::from(AudioRenderer::SAMPLE_RATE);
-                let fps_u64 = u64::from(fps);
-                let mut audio_sample_cursor = 0u64;
-
-                let mut consecutive_timeouts = 0u32;
-                const MAX_CONSECUTIVE_TIMEOUTS: u32 = 3;
-
-                loop {
-                    let timeout_secs = if frame_count == 0 { 120 } else { 90 };
-                    let (frame, frame_number) = match tokio::time::timeout(
-                        Duration::from_secs(timeout_secs),
-                        video_rx.recv(),
-                    )
-                    .await
-                    {
-                        Err(_) => {
-                            consecutive_timeouts += 1;
-
-                            if consecutive_timeouts >= MAX_CONSECUTIVE_TIMEOUTS {
-                                tracing::error!(
-                                    frame_count = frame_count,
-                                    timeout_secs = timeout_secs,
-                                    consecutive_timeouts = consecutive_timeouts,
-                                    "Export render_task timed out {} consecutive times - aborting",
-                                    MAX_CONSECUTIVE_TIMEOUTS
-                                );
-                                return Err(format!(
-                                    "Export timed out {MAX_CONSECUTIVE_TIMEOUTS} times consecutively after {timeout_secs}s each waiting for frame {frame_count} - GPU/decoder may be unresponsive"
-                                ));
-                            }
-
-                            tracing::warn!(
-                                frame_count = frame_count,
-                                timeout_secs = timeout_secs,
-                                consecutive_timeouts = consecutive_timeouts,
-                                "Frame receive timed out, waiting for next frame..."
-                            );
-                            continue;
-                        }
-                        Ok(Some(v)) => {
-                            consecutive_timeouts = 0;
-                            v
-                        }
-                        Ok(None) => {
-                            tracing::debug!(
-                                frame_count = frame_count,
-                                "Render channel closed - rendering complete"
-                            );
-                            break;
-                        }
-                    };
-
-                    if !(on_progress)(frame_count) {
-                        return Err("Export cancelled".to_string());
-                    }
-
-                    if frame_count == 0 {
-                        first_frame = Some(frame.clone());
-                        if let Some(audio) = &mut audio_renderer {
-                            audio.set_playhead(0.0, &project);
-                        }
-                    }
-
-                    let audio_frame = audio_renderer.as_mut().and_then(|audio| {
-                        let n = u64::from(frame_number);
-                        let end = ((n + 1) * sample_rate) / fps_u64;
-                        if end <= audio_sample_cursor {
-                            return None;
-                        }
-                        let pts = audio_sample_cursor as i64;
-                        let samples = (end - audio_sample_cursor) as usize;
-                        audio_sample_cursor = end;
-                        audio.render_frame(samples, &project).map(|mut frame| {
-                            frame.set_pts(Some(pts));
-                            frame
+            let screenshot_task = tokio::task::spawn_blocking(move || {
+                let rgb_img = ImageBuffer::<image::Rgb<u8>, Vec<u8>>::from_raw(
+                    frame.width,
+                    frame.height,
+                    frame
+                        .data
+                        .chunks(frame.padded_bytes_per_row as usize)
+                        .flat_map(|row| {
+                            row[0..(frame.width * 4) as usize]
+                                .chunks(4)
+                                .flat_map(|chunk| [chunk[0], chunk[1], chunk[2]])
                         })
-                    });
+                        .collect::<Vec<_>>(),
+                );
 
-                    if frame_tx
-                        .send(MP4Input {
-                            audio: audio_frame,
-                            video: video_info.wrap_frame(
-                                &frame.data,
-                                frame_number as i64,
-                                frame.padded_bytes_per_row as usize,
-                            ),
-                        })
-                        .is_err()
-                    {
-                        warn!("Renderer task sender dropped. Exiting");
-                        return Ok(());
-                    }
+                let Some(rgb_img) = rgb_img else {
+                    return;
+                };
 
-                    frame_count += 1;
+                let screenshots_dir = project_path.join("screenshots");
+                if std::fs::create_dir_all(&screenshots_dir).is_err() {
+                    return;
                 }
 
-                drop(frame_tx);
-
-                if let Some(frame) = first_frame {
-                    let project_path = project_path.clone();
-                    let screenshot_task = tokio::task::spawn_blocking(move || {
-                        let rgb_img = ImageBuffer::<image::Rgb<u8>, Vec<u8>>::from_raw(
-                            frame.width,
-                            frame.height,
-                            frame
-                                .data
-                                .chunks(frame.padded_bytes_per_row as usize)
-                                .flat_map(|row| {
-                                    row[0..(frame.width * 4) as usize]
-                                        .chunks(4)
-                                        .flat_map(|chunk| [chunk[0], chunk[1], chunk[2]])
-                                })
-                                .collect::<Vec<_>>(),
-                        );
+                let screenshot_path = screenshots_dir.join("display.jpg");
+                let _ = rgb_img.save(&screenshot_path);
+            });
 
-                        let Some(rgb_img) = rgb_img else {
-                            return;
-                        };
+            if let Err(e) = screenshot_task.await {
+                warn!("Screenshot task failed: {e}");
+            }
+        } else if base.frame_range(self.fps).start == 0 {
+            warn!("No frames were processed, cannot save screenshot or thumbnail");
+        }
 
-                        let screenshots_dir = project_path.join("screenshots");
-                        if std::fs::create_dir_all(&screenshots_dir).is_err() {
-                            return;
-                        }
+        let sidecars = write_chapter_files(&base.output_path, &chapters)?;
 
-                        let screenshot_path = screenshots_dir.join("display.jpg");
-                        let _ = rgb_img.save(&screenshot_path);
-                    });
+        Ok(ExportOutput {
+            path: base.output_path,
+            loudness: rendered.loudness,
+            sidecars,
+        })
+    }
+}
 
-                    if let Err(e) = screenshot_task.await {
-                        warn!("Screenshot task failed: {e}");
-                    }
-                } else {
-                    warn!("No frames were processed, cannot save screenshot or thumbnail");
-                }
+impl ExportEncoder for MP4File {
+    fn encode_frame(
+        &mut self,
+        video: frame::Video,
+        audio: Option<frame::Audio>,
+        timestamp: Duration,
+    ) -> Result<(), String> {
+        self.queue_subtitles_until(timestamp);
+        self.queue_video_frame(video, Duration::MAX)
+            .map_err(|err| err.to_string())?;
+        if let Some(audio) = audio {
+            self.queue_audio_frame(audio);
+        }
 
-                Ok::<_, String>(())
-            }
-        })
-        .then(|r| async {
-            r.map_err(|e| e.to_string())
-                .and_then(|v| v.map_err(|e| e.to_string()))
-        });
+        Ok(())
+    }
 
-        let render_video_task = cap_rendering::render_video_to_channel(
-            &base.render_constants,
-            &base.project_config,
-            tx_image_data,
-            &base.recording_meta,
-            meta,
-            base.segments
-                .iter()
-                .map(|s| RenderSegment {
-                    cursor: s.cursor.clone(),
-                    decoders: s.decoders.clone(),
-                })
-                .collect(),
-            fps,
-            self.resolution_base,
-            &base.recordings,
-        )
-        .then(|v| async { v.map_err(|e| e.to_string()) });
+    fn finish_encoding(mut self) -> Result<(), String> {
+        let res = self
+            .finish()
+            .map_err(|e| format!("Failed to finish encoding: {e}"))?;
 
-        tokio::try_join!(encoder_thread, render_video_task, render_task)?;
+        if let Err(e) = res.video_finish {
+            return Err(format!("Video encoding failed: {e}"));
+        }
+        if let Err(e) = res.audio_finish {
+            return Err(format!("Audio encoding failed: {e}"));
+        }
 
-        Ok(output_path)
+        Ok(())
     }
 }
 
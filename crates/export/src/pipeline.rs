@@ -0,0 +1,233 @@
+use cap_editor::AudioRenderer;
+use cap_media_info::{RawVideoFormat, VideoInfo};
+use cap_project::XY;
+use cap_rendering::{ProjectUniforms, RenderSegment, RenderedFrame};
+use ffmpeg::frame::{Audio as FFAudio, Video as FFVideo};
+use futures::FutureExt;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+use crate::{ExporterBase, loudness::ExportAudio, loudness::LoudnessReport};
+
+const MAX_CONSECUTIVE_TIMEOUTS: u32 = 3;
+
+/// A container and its encoders, fed one frame at a time by [`ExporterBase::render_to_encoder`].
+pub(crate) trait ExportEncoder {
+    /// Encodes a video frame, the audio that plays alongside it, and anything else that's due by
+    /// `timestamp`, the frame's time from the start of the export.
+    fn encode_frame(
+        &mut self,
+        video: FFVideo,
+        audio: Option<FFAudio>,
+        timestamp: Duration,
+    ) -> Result<(), String>;
+
+    /// Flushes the encoders and finalizes the file.
+    fn finish_encoding(self) -> Result<(), String>;
+}
+
+/// What's left over from rendering once an export's frames are encoded.
+pub(crate) struct RenderedExport {
+    pub loudness: Option<LoudnessReport>,
+    /// The first frame of the timeline, if the export starts there
+    pub first_frame: Option<RenderedFrame>,
+}
+
+struct EncoderInput {
+    video: FFVideo,
+    audio: Option<FFAudio>,
+    timestamp: Duration,
+}
+
+impl ExporterBase {
+    /// Renders the exported range at `fps` and feeds each frame, along with its slice of `audio`,
+    /// to the encoder `init_encoder` creates on a blocking thread. `init_encoder` is told whether
+    /// there's audio to encode.
+    pub(crate) async fn render_to_encoder<E: ExportEncoder>(
+        &self,
+        fps: u32,
+        resolution_base: XY<u32>,
+        mut audio: Option<ExportAudio>,
+        mut on_progress: impl FnMut(u32) -> bool + Send + 'static,
+        init_encoder: impl FnOnce(VideoInfo, bool) -> Result<E, String> + Send + 'static,
+    ) -> Result<RenderedExport, String> {
+        let (tx_image_data, mut video_rx) = tokio::sync::mpsc::channel::<(RenderedFrame, u32)>(8);
+        let (frame_tx, frame_rx) = std::sync::mpsc::sync_channel::<EncoderInput>(8);
+
+        let frame_range = self.frame_range(fps);
+        let start_frame = frame_range.start;
+        let start_time = self.start_time(fps);
+
+        let output_size = ProjectUniforms::get_output_size(
+            &self.render_constants.options,
+            &self.project_config,
+            resolution_base,
+        );
+
+        let mut video_info =
+            VideoInfo::from_raw(RawVideoFormat::Rgba, output_size.0, output_size.1, fps);
+        video_info.time_base = ffmpeg::Rational::new(1, fps as i32);
+
+        let has_audio = audio.is_some();
+
+        let encoder_thread = tokio::task::spawn_blocking(move || {
+            let mut encoder = init_encoder(video_info, has_audio)?;
+            info!("Created export encoder");
+
+            while let Ok(input) = frame_rx.recv() {
+                encoder.encode_frame(input.video, input.audio, input.timestamp)?;
+            }
+
+            encoder.finish_encoding()
+        })
+        .then(|r| async { r.map_err(|e| e.to_string()).and_then(|v| v) });
+
+        let render_task = tokio::spawn({
+            let project = self.project_config.clone();
+            async move {
+                let mut frame_count = 0;
+                let mut first_frame = None;
+                let sample_rate = u64::from(AudioRenderer::SAMPLE_RATE);
+                let fps_u64 = u64::from(fps);
+                let mut audio_sample_cursor = 0u64;
+                let mut consecutive_timeouts = 0u32;
+
+                loop {
+                    let timeout_secs = if frame_count == 0 { 120 } else { 90 };
+                    let (frame, frame_number) = match tokio::time::timeout(
+                        Duration::from_secs(timeout_secs),
+                        video_rx.recv(),
+                    )
+                    .await
+                    {
+                        Err(_) => {
+                            consecutive_timeouts += 1;
+
+                            if consecutive_timeouts >= MAX_CONSECUTIVE_TIMEOUTS {
+                                error!(
+                                    frame_count = frame_count,
+                                    timeout_secs = timeout_secs,
+                                    consecutive_timeouts = consecutive_timeouts,
+                                    "Export render_task timed out {} consecutive times - aborting",
+                                    MAX_CONSECUTIVE_TIMEOUTS
+                                );
+                                return Err(format!(
+                                    "Export timed out {MAX_CONSECUTIVE_TIMEOUTS} times consecutively after {timeout_secs}s each waiting for frame {frame_count} - GPU/decoder may be unresponsive"
+                                ));
+                            }
+
+                            warn!(
+                                frame_count = frame_count,
+                                timeout_secs = timeout_secs,
+                                consecutive_timeouts = consecutive_timeouts,
+                                "Frame receive timed out, waiting for next frame..."
+                            );
+                            continue;
+                        }
+                        Ok(Some(v)) => {
+                            consecutive_timeouts = 0;
+                            v
+                        }
+                        Ok(None) => {
+                            debug!(
+                                frame_count = frame_count,
+                                "Render channel closed - rendering complete"
+                            );
+                            break;
+                        }
+                    };
+
+                    if !(on_progress)(frame_count) {
+                        return Err("Export cancelled".to_string());
+                    }
+
+                    // frames are numbered by their position in the timeline,
+                    // so shift them to start at zero when exporting a range
+                    let output_frame_number = frame_number - start_frame;
+
+                    if frame_count == 0 {
+                        // only refresh the project's screenshot from the start of the timeline
+                        if start_frame == 0 {
+                            first_frame = Some(frame.clone());
+                        }
+                        if let Some(audio) = &mut audio {
+                            audio.set_playhead(start_time, &project);
+                        }
+                    }
+
+                    let audio_frame = audio.as_mut().and_then(|audio| {
+                        let n = u64::from(output_frame_number);
+                        let end = ((n + 1) * sample_rate) / fps_u64;
+                        if end <= audio_sample_cursor {
+                            return None;
+                        }
+                        let pts = audio_sample_cursor as i64;
+                        let samples = (end - audio_sample_cursor) as usize;
+                        audio_sample_cursor = end;
+                        audio.render_frame(samples, &project).map(|mut frame| {
+                            frame.set_pts(Some(pts));
+                            frame
+                        })
+                    });
+
+                    let input = EncoderInput {
+                        video: video_info.wrap_frame(
+                            &frame.data,
+                            output_frame_number as i64,
+                            frame.padded_bytes_per_row as usize,
+                        ),
+                        audio: audio_frame,
+                        timestamp: Duration::from_secs_f64(output_frame_number as f64 / fps as f64),
+                    };
+
+                    if frame_tx.send(input).is_err() {
+                        warn!("Encoder stopped receiving frames. Exiting");
+                        break;
+                    }
+
+                    frame_count += 1;
+                }
+
+                drop(frame_tx);
+
+                let loudness = audio.and_then(|audio| audio.loudness_report());
+                if let Some(loudness) = &loudness {
+                    info!("Export loudness: {loudness:?}");
+                }
+
+                Ok::<_, String>(RenderedExport {
+                    loudness,
+                    first_frame,
+                })
+            }
+        })
+        .then(|r| async {
+            r.map_err(|e| e.to_string())
+                .and_then(|v| v.map_err(|e| e.to_string()))
+        });
+
+        let render_video_task = cap_rendering::render_video_range_to_channel(
+            &self.render_constants,
+            &self.project_config,
+            tx_image_data,
+            &self.recording_meta,
+            &self.studio_meta,
+            self.segments
+                .iter()
+                .map(|s| RenderSegment {
+                    cursor: s.cursor.clone(),
+                    decoders: s.decoders.clone(),
+                })
+                .collect(),
+            fps,
+            resolution_base,
+            &self.recordings,
+            frame_range,
+        )
+        .then(|v| async { v.map_err(|e| e.to_string()) });
+
+        let (_, _, rendered) = tokio::try_join!(encoder_thread, render_video_task, render_task)?;
+
+        Ok(rendered)
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{
+    ExportOutput, ExporterBase, chapter_marks, loudness::LoudnessTarget, mp4::ExportCompression,
+    pipeline::ExportEncoder, write_chapter_files,
+};
+use cap_editor::AudioRenderer;
+use cap_enc_ffmpeg::{AudioEncoder, opus::OpusEncoder, vp9::VP9Encoder, webm::WebMFile};
+use cap_project::XY;
+use ffmpeg::frame;
+use serde::Deserialize;
+use specta::Type;
+use std::time::Duration;
+use tracing::{info, trace};
+
+/// VP9 reaches the same quality as H.264 at roughly half the bitrate,
+/// so the compression presets are scaled accordingly.
+const VP9_BPP_MULTIPLIER: f32 = 0.5;
+
+#[derive(Deserialize, Type, Clone, Copy, Debug)]
+pub struct WebMExportSettings {
+    pub fps: u32,
+    pub resolution_base: XY<u32>,
+    pub compression: ExportCompression,
+    pub custom_bpp: Option<f32>,
+    #[serde(default)]
+    pub force_ffmpeg_decoder: bool,
+    /// Normalizes the audio to this loudness when set
+    #[serde(default)]
+    pub loudness: Option<LoudnessTarget>,
+}
+
+impl WebMExportSettings {
+    pub fn effective_bpp(&self) -> f32 {
+        self.custom_bpp
+            .unwrap_or_else(|| self.compression.bits_per_pixel() * VP9_BPP_MULTIPLIER)
+    }
+
+    pub async fn export(
+        self,
+        base: ExporterBase,
+        on_progress: impl FnMut(u32) -> bool + Send + 'static,
+    ) -> Result<ExportOutput, String> {
+        info!("Exporting webm with settings: {:?}", &self);
+        info!("Expected to render {} frames", base.total_frames(self.fps));
+
+        let audio = base.export_audio(self.fps, self.loudness).await?;
+        let subtitles = base.subtitle_cues(self.fps);
+        let chapters = base.chapters(self.fps);
+        let encoder_chapters = chapter_marks(&chapters);
+
+        let mut output_path = base.output_path.clone();
+        output_path.set_extension("webm");
+
+        let rendered = base
+            .render_to_encoder(self.fps, self.resolution_base, audio, on_progress, {
+                let output_path = output_path.clone();
+                move |video_info, has_audio| {
+                    trace!("Creating WebMFile encoder");
+
+                    WebMFile::init(
+                        output_path,
+                        |o| {
+                            VP9Encoder::builder(video_info)
+                                .with_bpp(self.effective_bpp())
+                                .build(o)
+                        },
+                        |o| {
+                            has_audio.then(|| {
+                                OpusEncoder::init(AudioRenderer::info(), o)
+                                    .map(|v| v.boxed())
+                                    .map_err(Into::into)
+                            })
+                        },
+                        subtitles,
+                        &encoder_chapters,
+                    )
+                    .map_err(|v| v.to_string())
+                }
+            })
+            .await?;
+
+        let sidecars = write_chapter_files(&output_path, &chapters)?;
+
+        Ok(ExportOutput {
+            path: output_path,
+            loudness: rendered.loudness,
+            sidecars,
+        })
+    }
+}
+
+impl ExportEncoder for WebMFile {
+    fn encode_frame(
+        &mut self,
+        video: frame::Video,
+        audio: Option<frame::Audio>,
+        timestamp: Duration,
+    ) -> Result<(), String> {
+        self.queue_subtitles_until(timestamp);
+        self.queue_video_frame(video, Duration::MAX)
+            .map_err(|err| err.to_string())?;
+        if let Some(audio) = audio {
+            self.queue_audio_frame(audio);
+        }
+
+        Ok(())
+    }
+
+    fn finish_encoding(mut self) -> Result<(), String> {
+        let res = self
+            .finish()
+            .map_err(|e| format!("Failed to finish encoding: {e}"))?;
+
+        if let Err(e) = res.video_finish {
+            return Err(format!("Video encoding failed: {e}"));
+        }
+        if let Err(e) = res.audio_finish {
+            return Err(format!("Audio encoding failed: {e}"));
+        }
+
+        Ok(())
+    }
+}
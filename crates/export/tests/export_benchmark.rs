@@ -11,7 +11,7 @@ use std::{
 
 use cap_export::{
     ExporterBase,
-    mp4::{ExportCompression, Mp4ExportSettings},
+    mp4::{ExportCodec, ExportCompression, Mp4ExportSettings},
 };
 use cap_project::XY;
 
@@ -27,6 +27,8 @@ async fn run_export(project_path: PathBuf) -> Result<(PathBuf, Duration, u32), S
         compression: ExportCompression::Maximum,
         custom_bpp: None,
         force_ffmpeg_decoder: false,
+        codec: ExportCodec::H264,
+        loudness: None,
     };
 
     let start = Instant::now();
@@ -39,7 +41,8 @@ async fn run_export(project_path: PathBuf) -> Result<(PathBuf, Duration, u32), S
             true
         })
         .await
-        .map_err(|err| format!("Exporter error: {err}"))?;
+        .map_err(|err| format!("Exporter error: {err}"))?
+        .path;
 
     let elapsed = start.elapsed();
     let frames = last_frame.load(Ordering::Relaxed);
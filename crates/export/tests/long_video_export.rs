@@ -1,6 +1,6 @@
 use cap_export::{
     ExporterBase,
-    mp4::{ExportCompression, Mp4ExportSettings},
+    mp4::{ExportCodec, ExportCompression, Mp4ExportSettings},
 };
 use cap_project::XY;
 use std::{
@@ -134,6 +134,8 @@ async fn run_export(project_path: PathBuf, fps: u32) -> Result<(PathBuf, Duratio
         compression: ExportCompression::Potato,
         custom_bpp: None,
         force_ffmpeg_decoder: false,
+        codec: ExportCodec::H264,
+        loudness: None,
     };
 
     let total_frames = exporter_base.total_frames(fps);
@@ -161,7 +163,8 @@ async fn run_export(project_path: PathBuf, fps: u32) -> Result<(PathBuf, Duratio
             true
         })
         .await
-        .map_err(|err| format!("Export error: {err}"))?;
+        .map_err(|err| format!("Export error: {err}"))?
+        .path;
 
     let elapsed = start.elapsed();
     let frames = last_frame.load(Ordering::Relaxed);
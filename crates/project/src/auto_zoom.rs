@@ -0,0 +1,636 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    CursorClickEvent, CursorEvents, CursorMoveEvent, GlideDirection, RecordingMeta,
+    RecordingMetaInner, StudioRecordingMeta, ZoomMode, ZoomSegment,
+    cursor::SHORT_CURSOR_SHAPE_DEBOUNCE_MS,
+};
+
+const STOP_PADDING_SECONDS: f64 = 0.5;
+const CLICK_GROUP_TIME_THRESHOLD_SECS: f64 = 2.5;
+const CLICK_GROUP_SPATIAL_THRESHOLD: f64 = 0.15;
+const CLICK_PRE_PADDING: f64 = 0.4;
+const CLICK_POST_PADDING: f64 = 1.8;
+const MOVEMENT_PRE_PADDING: f64 = 0.3;
+const MOVEMENT_POST_PADDING: f64 = 1.5;
+const MERGE_GAP_THRESHOLD: f64 = 0.8;
+const MIN_SEGMENT_DURATION: f64 = 1.0;
+const MOVEMENT_WINDOW_SECONDS: f64 = 1.5;
+const MOVEMENT_EVENT_DISTANCE_THRESHOLD: f64 = 0.02;
+const MOVEMENT_WINDOW_DISTANCE_THRESHOLD: f64 = 0.08;
+const SHAKE_FILTER_THRESHOLD: f64 = 0.33;
+const SHAKE_FILTER_WINDOW_MS: f64 = 150.0;
+
+/// How far the cursor can drift while still dwelling on the same spot
+const DWELL_RADIUS: f64 = 0.03;
+const MIN_DWELL_SECONDS: f64 = 0.4;
+/// How much a click counts towards the focus point, relative to a second of dwelling
+const CLICK_FOCUS_WEIGHT: f64 = 1.0;
+/// Activity spread further than this from its focus point is followed with `ZoomMode::Auto`
+const MAX_FOCUS_SPREAD: f64 = 0.3;
+/// Space kept between the outermost focus point and the edge of the zoomed in view
+const FOCUS_MARGIN: f64 = 0.1;
+/// Average cursor speed, in display widths per second, above which the zoom is eased off
+const FAST_MOVEMENT_SPEED: f64 = 0.6;
+const AUTO_ZOOM_AMOUNT: f64 = 1.5;
+const MIN_ZOOM_AMOUNT: f64 = 1.25;
+const MAX_ZOOM_AMOUNT: f64 = 2.5;
+
+/// Generates zoom segments around the clicks and cursor movement in a recording that's
+/// `duration` seconds long.
+///
+/// Bursts of activity become segments. When the clicks and dwell points in a burst are close
+/// together the segment zooms in on them with `ZoomMode::Manual`, zooming further the tighter
+/// they are, otherwise it follows the cursor. Fast cursor movement eases off the zoom.
+pub fn generate_zoom_segments(events: &CursorEvents, duration: f64) -> Vec<ZoomSegment> {
+    if duration <= 0.0 {
+        return Vec::new();
+    }
+
+    let activity_end_limit = if duration > STOP_PADDING_SECONDS {
+        duration - STOP_PADDING_SECONDS
+    } else {
+        duration
+    };
+
+    if activity_end_limit <= f64::EPSILON {
+        return Vec::new();
+    }
+
+    let mut clicks = events.clicks.clone();
+    let mut moves = events.moves.clone();
+    clicks.sort_by(|a, b| a.time_ms.total_cmp(&b.time_ms));
+    moves.sort_by(|a, b| a.time_ms.total_cmp(&b.time_ms));
+
+    // the last click is usually the one that stopped the recording
+    while let Some(index) = clicks.iter().rposition(|c| c.down) {
+        if clicks[index].time_ms / 1000.0 > activity_end_limit {
+            clicks.remove(index);
+        } else {
+            break;
+        }
+    }
+
+    let clicks = clicks
+        .iter()
+        .filter(|c| c.down && c.time_ms / 1000.0 < activity_end_limit)
+        .map(|click| Click {
+            time: click.time_ms / 1000.0,
+            position: position_at(&moves, click),
+        })
+        .collect::<Vec<_>>();
+
+    let mut intervals = click_intervals(&clicks, activity_end_limit);
+    intervals.extend(movement_intervals(&moves, activity_end_limit));
+
+    if intervals.is_empty() {
+        return Vec::new();
+    }
+
+    let dwells = dwells(&moves, activity_end_limit);
+
+    merge_intervals(intervals)
+        .into_iter()
+        .filter(|(start, end)| end - start >= MIN_SEGMENT_DURATION)
+        .map(|(start, end)| {
+            let (mode, amount) = focus(start, end, &clicks, &dwells, &moves);
+
+            ZoomSegment {
+                start,
+                end,
+                amount,
+                mode,
+                glide_direction: GlideDirection::None,
+                glide_speed: 0.5,
+                instant_animation: false,
+                edge_snap_ratio: 0.25,
+            }
+        })
+        .collect()
+}
+
+/// Loads the cursor events for every segment of a studio recording and generates zoom segments
+/// from them with [`generate_zoom_segments`].
+pub fn generate_zoom_segments_for_project(
+    recording_meta: &RecordingMeta,
+    duration: f64,
+) -> Vec<ZoomSegment> {
+    let RecordingMetaInner::Studio(studio_meta) = &recording_meta.inner else {
+        return Vec::new();
+    };
+
+    let mut events = CursorEvents::default();
+
+    match &**studio_meta {
+        StudioRecordingMeta::SingleSegment { segment } => {
+            if let Some(cursor_path) = &segment.cursor {
+                events = CursorEvents::load_from_file(&recording_meta.path(cursor_path))
+                    .unwrap_or_default();
+                let pointer_ids = studio_meta.pointer_cursor_ids();
+                let pointer_ids_ref = (!pointer_ids.is_empty()).then_some(&pointer_ids);
+                events.stabilize_short_lived_cursor_shapes(
+                    pointer_ids_ref,
+                    SHORT_CURSOR_SHAPE_DEBOUNCE_MS,
+                );
+            }
+        }
+        StudioRecordingMeta::MultipleSegments { inner, .. } => {
+            for segment in inner.segments.iter() {
+                let segment_events = segment.cursor_events(recording_meta);
+                events.clicks.extend(segment_events.clicks);
+                events.moves.extend(segment_events.moves);
+            }
+        }
+    }
+
+    generate_zoom_segments(&events, duration)
+}
+
+struct Click {
+    time: f64,
+    position: Option<(f64, f64)>,
+}
+
+/// A spot the cursor stayed on for a while
+struct Dwell {
+    start: f64,
+    end: f64,
+    position: (f64, f64),
+}
+
+fn position_at(moves: &[CursorMoveEvent], click: &CursorClickEvent) -> Option<(f64, f64)> {
+    let index = moves.partition_point(|m| m.time_ms <= click.time_ms);
+    index.checked_sub(1).map(|i| (moves[i].x, moves[i].y))
+}
+
+fn distance((x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> f64 {
+    ((x1 - x2).powi(2) + (y1 - y2).powi(2)).sqrt()
+}
+
+/// Groups clicks that are close in time and space, returning the time around each group.
+fn click_intervals(clicks: &[Click], activity_end_limit: f64) -> Vec<(f64, f64)> {
+    let mut click_groups: Vec<Vec<&Click>> = Vec::new();
+
+    for click in clicks {
+        let group = click_groups.iter_mut().find(|group| {
+            group.iter().any(|other| {
+                let time_close = (click.time - other.time).abs() < CLICK_GROUP_TIME_THRESHOLD_SECS;
+                let spatial_close = match (click.position, other.position) {
+                    (Some(a), Some(b)) => distance(a, b) < CLICK_GROUP_SPATIAL_THRESHOLD,
+                    _ => true,
+                };
+
+                time_close && spatial_close
+            })
+        });
+
+        match group {
+            Some(group) => group.push(click),
+            None => click_groups.push(vec![click]),
+        }
+    }
+
+    click_groups
+        .into_iter()
+        .filter_map(|group| {
+            let group_start = group.iter().map(|c| c.time).fold(f64::INFINITY, f64::min);
+            let group_end = group
+                .iter()
+                .map(|c| c.time)
+                .fold(f64::NEG_INFINITY, f64::max);
+
+            let start = (group_start - CLICK_PRE_PADDING).max(0.0);
+            let end = (group_end + CLICK_POST_PADDING).min(activity_end_limit);
+
+            (end > start).then_some((start, end))
+        })
+        .collect()
+}
+
+/// Returns the time around significant cursor movement, ignoring jitter and shaking.
+fn movement_intervals(moves: &[CursorMoveEvent], activity_end_limit: f64) -> Vec<(f64, f64)> {
+    let mut intervals = Vec::new();
+    let mut last_move_by_cursor: HashMap<&str, (f64, f64)> = HashMap::new();
+    let mut distance_window: VecDeque<(f64, f64)> = VecDeque::new();
+    let mut window_distance = 0.0_f64;
+    let mut shake_window: VecDeque<(f64, f64, f64)> = VecDeque::new();
+
+    for mv in moves {
+        let time = mv.time_ms / 1000.0;
+        if time >= activity_end_limit {
+            break;
+        }
+
+        let moved = last_move_by_cursor
+            .insert(&mv.cursor_id, (mv.x, mv.y))
+            .map(|last| distance(last, (mv.x, mv.y)))
+            .unwrap_or(0.0);
+
+        if moved <= f64::EPSILON {
+            continue;
+        }
+
+        shake_window.push_back((mv.time_ms, mv.x, mv.y));
+        while let Some(&(old_time, _, _)) = shake_window.front() {
+            if mv.time_ms - old_time > SHAKE_FILTER_WINDOW_MS {
+                shake_window.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        if shake_window.len() >= 3 {
+            let positions: Vec<(f64, f64)> =
+                shake_window.iter().map(|(_, x, y)| (*x, *y)).collect();
+            let mut direction_changes = 0;
+            for i in 1..positions.len() - 1 {
+                let dx1 = positions[i].0 - positions[i - 1].0;
+                let dy1 = positions[i].1 - positions[i - 1].1;
+                let dx2 = positions[i + 1].0 - positions[i].0;
+                let dy2 = positions[i + 1].1 - positions[i].1;
+
+                if (dx1 * dx2 + dy1 * dy2) < 0.0 {
+                    direction_changes += 1;
+                }
+            }
+
+            let total_dist: f64 = positions.windows(2).map(|w| distance(w[0], w[1])).sum();
+
+            if direction_changes >= 2 && total_dist < SHAKE_FILTER_THRESHOLD * 3.0 {
+                continue;
+            }
+        }
+
+        distance_window.push_back((time, moved));
+        window_distance += moved;
+
+        while let Some(&(old_time, old_distance)) = distance_window.front() {
+            if time - old_time > MOVEMENT_WINDOW_SECONDS {
+                distance_window.pop_front();
+                window_distance -= old_distance;
+            } else {
+                break;
+            }
+        }
+
+        if window_distance < 0.0 {
+            window_distance = 0.0;
+        }
+
+        let significant_movement = moved >= MOVEMENT_EVENT_DISTANCE_THRESHOLD
+            || window_distance >= MOVEMENT_WINDOW_DISTANCE_THRESHOLD;
+
+        if !significant_movement {
+            continue;
+        }
+
+        let start = (time - MOVEMENT_PRE_PADDING).max(0.0);
+        let end = (time + MOVEMENT_POST_PADDING).min(activity_end_limit);
+
+        if end > start {
+            intervals.push((start, end));
+        }
+    }
+
+    intervals
+}
+
+fn merge_intervals(mut intervals: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    intervals.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut merged: Vec<(f64, f64)> = Vec::new();
+    for interval in intervals {
+        if let Some(last) = merged.last_mut()
+            && interval.0 <= last.1 + MERGE_GAP_THRESHOLD
+        {
+            last.1 = last.1.max(interval.1);
+            continue;
+        }
+        merged.push(interval);
+    }
+
+    merged
+}
+
+/// Finds the spots the cursor stayed within [`DWELL_RADIUS`] of for at least
+/// [`MIN_DWELL_SECONDS`].
+fn dwells(moves: &[CursorMoveEvent], activity_end_limit: f64) -> Vec<Dwell> {
+    let mut dwells = Vec::new();
+    let mut anchor: Option<(f64, (f64, f64))> = None;
+
+    let mut push_dwell = |start: f64, end: f64, position| {
+        if end - start >= MIN_DWELL_SECONDS {
+            dwells.push(Dwell {
+                start,
+                end,
+                position,
+            });
+        }
+    };
+
+    for mv in moves {
+        let time = mv.time_ms / 1000.0;
+        if time >= activity_end_limit {
+            break;
+        }
+
+        let position = (mv.x, mv.y);
+        match anchor {
+            Some((_, anchor_position)) if distance(anchor_position, position) <= DWELL_RADIUS => {}
+            Some((start, anchor_position)) => {
+                // the cursor stayed put until this move took it away
+                push_dwell(start, time, anchor_position);
+                anchor = Some((time, position));
+            }
+            None => anchor = Some((time, position)),
+        }
+    }
+
+    if let Some((start, position)) = anchor {
+        push_dwell(start, activity_end_limit, position);
+    }
+
+    dwells
+}
+
+/// Picks the zoom mode and amount for the segment from `start` to `end`.
+fn focus(
+    start: f64,
+    end: f64,
+    clicks: &[Click],
+    dwells: &[Dwell],
+    moves: &[CursorMoveEvent],
+) -> (ZoomMode, f64) {
+    // (position, weight)
+    let points = clicks
+        .iter()
+        .filter(|click| click.time >= start && click.time <= end)
+        .filter_map(|click| Some((click.position?, CLICK_FOCUS_WEIGHT)))
+        .chain(dwells.iter().filter_map(|dwell| {
+            let overlap = dwell.end.min(end) - dwell.start.max(start);
+            (overlap > 0.0).then_some((dwell.position, overlap))
+        }))
+        .collect::<Vec<_>>();
+
+    let speed_factor = {
+        let path_length = moves
+            .iter()
+            .filter(|m| {
+                let time = m.time_ms / 1000.0;
+                time >= start && time <= end
+            })
+            .map(|m| (m.x, m.y))
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|w| distance(w[0], w[1]))
+            .sum::<f64>();
+        let speed = path_length / (end - start);
+
+        if speed > FAST_MOVEMENT_SPEED {
+            (FAST_MOVEMENT_SPEED / speed).max(0.5)
+        } else {
+            1.0
+        }
+    };
+    let ease_off = |amount: f64| (1.0 + (amount - 1.0) * speed_factor).max(MIN_ZOOM_AMOUNT);
+
+    let total_weight = points.iter().map(|(_, weight)| weight).sum::<f64>();
+    if total_weight <= f64::EPSILON {
+        return (ZoomMode::Auto, ease_off(AUTO_ZOOM_AMOUNT));
+    }
+
+    let focus = points
+        .iter()
+        .fold((0.0, 0.0), |(x, y), ((px, py), weight)| {
+            (
+                x + px * weight / total_weight,
+                y + py * weight / total_weight,
+            )
+        });
+    let spread = points
+        .iter()
+        .map(|(position, _)| distance(*position, focus))
+        .fold(0.0, f64::max);
+
+    if spread > MAX_FOCUS_SPREAD {
+        return (ZoomMode::Auto, ease_off(AUTO_ZOOM_AMOUNT));
+    }
+
+    // zoom in as far as possible while keeping every focus point in view
+    let amount = (0.5 / (spread + FOCUS_MARGIN)).clamp(MIN_ZOOM_AMOUNT, MAX_ZOOM_AMOUNT);
+
+    (
+        ZoomMode::Manual {
+            x: focus.0.clamp(0.0, 1.0) as f32,
+            y: focus.1.clamp(0.0, 1.0) as f32,
+        },
+        ease_off(amount),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn click_event(time_ms: f64) -> CursorClickEvent {
+        CursorClickEvent {
+            active_modifiers: vec![],
+            cursor_num: 0,
+            cursor_id: "default".to_string(),
+            time_ms,
+            down: true,
+        }
+    }
+
+    fn move_event(time_ms: f64, x: f64, y: f64) -> CursorMoveEvent {
+        CursorMoveEvent {
+            active_modifiers: vec![],
+            cursor_id: "default".to_string(),
+            time_ms,
+            x,
+            y,
+        }
+    }
+
+    fn events(clicks: Vec<CursorClickEvent>, moves: Vec<CursorMoveEvent>) -> CursorEvents {
+        CursorEvents {
+            clicks,
+            moves,
+            keys: vec![],
+        }
+    }
+
+    /// Moves the cursor in a straight line from `from` to `to`, with a move event every 20ms.
+    fn glide(start_ms: f64, end_ms: f64, from: (f64, f64), to: (f64, f64)) -> Vec<CursorMoveEvent> {
+        let steps = ((end_ms - start_ms) / 20.0) as usize;
+        (0..=steps)
+            .map(|i| {
+                let t = i as f64 / steps as f64;
+                move_event(
+                    start_ms + (end_ms - start_ms) * t,
+                    from.0 + (to.0 - from.0) * t,
+                    from.1 + (to.1 - from.1) * t,
+                )
+            })
+            .collect()
+    }
+
+    fn manual_focus(segment: &ZoomSegment) -> (f32, f32) {
+        match segment.mode {
+            ZoomMode::Manual { x, y } => (x, y),
+            ZoomMode::Auto => panic!("expected a manual focus point"),
+        }
+    }
+
+    #[test]
+    fn skips_trailing_stop_click() {
+        let segments = generate_zoom_segments(&events(vec![click_event(11_900.0)], vec![]), 12.0);
+
+        assert!(
+            segments.is_empty(),
+            "expected trailing stop click to be ignored"
+        );
+    }
+
+    #[test]
+    fn generates_segment_for_sustained_activity() {
+        let clicks = vec![click_event(1_200.0), click_event(4_200.0)];
+        let moves = vec![
+            move_event(1_500.0, 0.10, 0.12),
+            move_event(1_720.0, 0.42, 0.45),
+            move_event(1_940.0, 0.74, 0.78),
+        ];
+
+        let segments = generate_zoom_segments(&events(clicks, moves), 20.0);
+
+        assert!(
+            !segments.is_empty(),
+            "expected activity to produce zoom segments"
+        );
+        let first = &segments[0];
+        assert!(first.start < first.end);
+        assert!(first.end - first.start >= 1.3);
+        assert!(first.end <= 19.5);
+    }
+
+    #[test]
+    fn ignores_cursor_jitter() {
+        let jitter_moves = (0..30)
+            .map(|i| {
+                let t = 1_000.0 + (i as f64) * 30.0;
+                let delta = (i as f64) * 0.0004;
+                move_event(t, 0.5 + delta, 0.5)
+            })
+            .collect::<Vec<_>>();
+
+        let segments = generate_zoom_segments(&events(Vec::new(), jitter_moves), 15.0);
+
+        assert!(
+            segments.is_empty(),
+            "small jitter should not generate segments"
+        );
+    }
+
+    #[test]
+    fn clustered_clicks_zoom_in_on_their_position() {
+        let moves = vec![move_event(900.0, 0.7, 0.3)];
+        let clicks = vec![
+            click_event(1_000.0),
+            click_event(1_400.0),
+            click_event(1_800.0),
+        ];
+
+        let segments = generate_zoom_segments(&events(clicks, moves), 10.0);
+
+        assert_eq!(segments.len(), 1);
+        assert_eq!(manual_focus(&segments[0]), (0.7, 0.3));
+        assert_eq!(segments[0].amount, MAX_ZOOM_AMOUNT);
+    }
+
+    #[test]
+    fn spread_out_clicks_zoom_in_less() {
+        let tight = generate_zoom_segments(
+            &events(
+                vec![click_event(1_000.0), click_event(2_000.0)],
+                vec![move_event(900.0, 0.5, 0.5), move_event(1_900.0, 0.52, 0.5)],
+            ),
+            10.0,
+        );
+        let loose = generate_zoom_segments(
+            &events(
+                vec![click_event(1_000.0), click_event(2_000.0)],
+                vec![move_event(900.0, 0.5, 0.5), move_event(1_900.0, 0.75, 0.5)],
+            ),
+            10.0,
+        );
+
+        assert_eq!(tight.len(), 1);
+        assert_eq!(loose.len(), 1);
+        assert!(loose[0].amount < tight[0].amount);
+        assert!(loose[0].amount >= MIN_ZOOM_AMOUNT);
+
+        let (x, y) = manual_focus(&loose[0]);
+        assert!(x > 0.5 && x < 0.75 && y == 0.5);
+    }
+
+    #[test]
+    fn dwelling_pulls_the_focus_towards_the_cursor() {
+        // one click, then the cursor rests further down and to the right for 3 seconds
+        let mut moves = vec![move_event(900.0, 0.3, 0.3)];
+        moves.extend(glide(1_100.0, 1_400.0, (0.3, 0.3), (0.45, 0.45)));
+        moves.push(move_event(4_400.0, 0.45, 0.45));
+        moves.extend(glide(4_420.0, 4_800.0, (0.45, 0.45), (0.9, 0.9)));
+
+        let segments = generate_zoom_segments(&events(vec![click_event(1_000.0)], moves), 10.0);
+
+        assert!(!segments.is_empty());
+        let (x, y) = manual_focus(&segments[0]);
+        assert!(
+            x > 0.375 && y > 0.375,
+            "focus {x},{y} should favour the dwell"
+        );
+    }
+
+    #[test]
+    fn scattered_activity_follows_the_cursor() {
+        let moves = vec![
+            move_event(900.0, 0.1, 0.1),
+            move_event(1_900.0, 0.9, 0.9),
+            move_event(2_900.0, 0.1, 0.9),
+        ];
+        let clicks = vec![
+            click_event(1_000.0),
+            click_event(2_000.0),
+            click_event(3_000.0),
+        ];
+
+        let segments = generate_zoom_segments(&events(clicks, moves), 10.0);
+
+        assert!(!segments.is_empty());
+        assert!(matches!(segments[0].mode, ZoomMode::Auto));
+    }
+
+    #[test]
+    fn fast_movement_eases_off_the_zoom() {
+        let still = generate_zoom_segments(
+            &events(
+                vec![click_event(1_000.0)],
+                vec![move_event(900.0, 0.5, 0.5)],
+            ),
+            10.0,
+        );
+
+        // whip the cursor back and forth around the click
+        let mut moves = vec![move_event(900.0, 0.5, 0.5)];
+        for i in 0..4 {
+            let start = 1_100.0 + i as f64 * 400.0;
+            moves.extend(glide(start, start + 190.0, (0.5, 0.5), (0.95, 0.5)));
+            moves.extend(glide(start + 200.0, start + 390.0, (0.95, 0.5), (0.5, 0.5)));
+        }
+        let fast = generate_zoom_segments(&events(vec![click_event(1_000.0)], moves), 10.0);
+
+        assert_eq!(still.len(), 1);
+        assert_eq!(fast.len(), 1);
+        assert!(fast[0].amount < still[0].amount);
+    }
+}
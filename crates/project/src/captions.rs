@@ -0,0 +1,680 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{CaptionSegment, CaptionWord, CaptionsData, TimelineConfiguration};
+
+/// File formats captions can be exported to.
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum CaptionFormat {
+    Srt,
+    /// WebVTT, with each word's timing so players can highlight the current word
+    WebVtt,
+    /// A plain text transcript, one caption per line
+    Text,
+}
+
+impl CaptionFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::WebVtt => "vtt",
+            Self::Text => "txt",
+        }
+    }
+
+    /// Serializes captions, which should already be mapped onto the timeline with
+    /// [`CaptionsData::to_timeline`].
+    pub fn serialize(&self, captions: &[CaptionSegment]) -> String {
+        match self {
+            Self::Srt => captions_to_srt(captions),
+            Self::WebVtt => captions_to_vtt(captions),
+            Self::Text => captions_to_text(captions),
+        }
+    }
+}
+
+fn captions_to_srt(captions: &[CaptionSegment]) -> String {
+    let mut srt = String::new();
+
+    for (i, caption) in captions.iter().enumerate() {
+        srt.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            format_timestamp(caption.start, ','),
+            format_timestamp(caption.end, ','),
+            caption.text.trim()
+        ));
+    }
+
+    srt
+}
+
+fn captions_to_vtt(captions: &[CaptionSegment]) -> String {
+    let mut vtt = String::from("WEBVTT\n\n");
+
+    for (i, caption) in captions.iter().enumerate() {
+        let text = if caption.words.is_empty() {
+            escape_vtt(caption.text.trim())
+        } else {
+            caption
+                .words
+                .iter()
+                .enumerate()
+                .map(|(i, word)| {
+                    let text = escape_vtt(word.text.trim());
+                    // the first word is highlighted as soon as the cue starts
+                    if i == 0 {
+                        format!("<c>{text}</c>")
+                    } else {
+                        format!("<{}><c>{text}</c>", format_timestamp(word.start, '.'))
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        vtt.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            format_timestamp(caption.start, '.'),
+            format_timestamp(caption.end, '.'),
+            text
+        ));
+    }
+
+    vtt
+}
+
+fn captions_to_text(captions: &[CaptionSegment]) -> String {
+    let mut text = String::new();
+
+    for caption in captions {
+        text.push_str(caption.text.trim());
+        text.push('\n');
+    }
+
+    text
+}
+
+pub(crate) fn escape_vtt(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Formats seconds as `HH:MM:SS` followed by milliseconds after `separator`.
+pub(crate) fn format_timestamp(seconds: impl Into<f64>, separator: char) -> String {
+    let millis = (seconds.into().max(0.0) * 1000.0).round() as u64;
+
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// How [`CaptionsData::parse_subtitles`] handles malformed cues.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParseStrictness {
+    /// Fail on the first malformed cue
+    Strict,
+    /// Accept timestamps with missing hours or milliseconds, either decimal separator and any
+    /// number of digits, and skip cues that still can't be parsed
+    Tolerant,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CaptionParseError {
+    UnsupportedFormat(CaptionFormat),
+    MissingHeader,
+    MissingTiming { line: usize },
+    InvalidTimestamp { line: usize, timestamp: String },
+    InvalidRange { line: usize },
+}
+
+impl fmt::Display for CaptionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedFormat(format) => write!(f, "{format:?} files can't be imported"),
+            Self::MissingHeader => write!(f, "file doesn't start with a WEBVTT header"),
+            Self::MissingTiming { line } => write!(f, "line {line}: cue has no timing line"),
+            Self::InvalidTimestamp { line, timestamp } => {
+                write!(f, "line {line}: invalid timestamp '{timestamp}'")
+            }
+            Self::InvalidRange { line } => write!(f, "line {line}: cue ends before it starts"),
+        }
+    }
+}
+
+impl std::error::Error for CaptionParseError {}
+
+impl CaptionsData {
+    /// Parses an SRT or WebVTT file into captions with default settings.
+    ///
+    /// Formatting tags are stripped from the cue text. WebVTT inline timestamps
+    /// (`<00:00:01.500>`) become word timings, with each word lasting until the next one starts.
+    pub fn parse_subtitles(
+        contents: &str,
+        format: CaptionFormat,
+        strictness: ParseStrictness,
+    ) -> Result<Self, CaptionParseError> {
+        let (is_vtt, separator) = match format {
+            CaptionFormat::Srt => (false, ','),
+            CaptionFormat::WebVtt => (true, '.'),
+            CaptionFormat::Text => return Err(CaptionParseError::UnsupportedFormat(format)),
+        };
+        let tolerant = strictness == ParseStrictness::Tolerant;
+
+        let contents = contents.trim_start_matches('\u{feff}');
+        let lines = contents.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+        // cues are separated by blank lines
+        let mut blocks = vec![];
+        let mut block = vec![];
+        for (number, line) in lines {
+            if line.trim().is_empty() {
+                if !block.is_empty() {
+                    blocks.push(std::mem::take(&mut block));
+                }
+            } else {
+                block.push((number, line));
+            }
+        }
+        if !block.is_empty() {
+            blocks.push(block);
+        }
+
+        let mut blocks = blocks.into_iter().peekable();
+        if is_vtt {
+            let has_header = blocks
+                .peek()
+                .is_some_and(|block| block[0].1.starts_with("WEBVTT"));
+            if has_header {
+                blocks.next();
+            } else if !tolerant {
+                return Err(CaptionParseError::MissingHeader);
+            }
+        }
+
+        let mut segments = vec![];
+        for block in blocks {
+            let first = block[0].1.trim_start();
+            if is_vtt
+                && ["NOTE", "STYLE", "REGION"]
+                    .iter()
+                    .any(|k| first.starts_with(k))
+            {
+                continue;
+            }
+
+            match parse_cue(&block, separator, tolerant) {
+                Ok(Some((start, end, text, words))) => segments.push(CaptionSegment {
+                    id: format!("imported-{}", segments.len()),
+                    start,
+                    end,
+                    text,
+                    words,
+                }),
+                Ok(None) => {}
+                Err(_) if tolerant => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(Self {
+            segments,
+            settings: Default::default(),
+        })
+    }
+}
+
+type ParsedCue = (f32, f32, String, Vec<CaptionWord>);
+
+fn parse_cue(
+    block: &[(usize, &str)],
+    separator: char,
+    tolerant: bool,
+) -> Result<Option<ParsedCue>, CaptionParseError> {
+    let Some(timing_index) = block.iter().position(|(_, line)| line.contains("-->")) else {
+        return Err(CaptionParseError::MissingTiming { line: block[0].0 });
+    };
+    let (line, timing) = block[timing_index];
+
+    let (start, rest) = timing.split_once("-->").unwrap();
+    // webvtt cue settings follow the end time
+    let end = rest.split_whitespace().next().unwrap_or_default();
+
+    let parse = |timestamp: &str| {
+        parse_timestamp(timestamp.trim(), separator, tolerant).ok_or_else(|| {
+            CaptionParseError::InvalidTimestamp {
+                line,
+                timestamp: timestamp.trim().to_string(),
+            }
+        })
+    };
+    let start = parse(start)?;
+    let end = parse(end)?;
+
+    if end < start {
+        return Err(CaptionParseError::InvalidRange { line });
+    }
+
+    let text = block[timing_index + 1..]
+        .iter()
+        .map(|(_, line)| line.trim())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let (text, words) = parse_cue_text(&text, start, end, tolerant);
+    if text.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some((start as f32, end as f32, text, words)))
+}
+
+/// Strips the tags from a cue's text, turning inline timestamps into word timings.
+fn parse_cue_text(text: &str, start: f64, end: f64, tolerant: bool) -> (String, Vec<CaptionWord>) {
+    let mut plain = String::new();
+    // words with their start time, if an inline timestamp preceded them
+    let mut words: Vec<(String, Option<f64>)> = vec![];
+    let mut time = None;
+    let mut in_word = false;
+    let mut has_timestamps = false;
+
+    let mut rest = text;
+    while !rest.is_empty() {
+        if let Some(tag) = rest.strip_prefix('<')
+            && let Some(close) = tag.find('>')
+        {
+            if let Some(timestamp) = parse_timestamp(&tag[..close], '.', tolerant) {
+                time = Some(timestamp.clamp(start, end));
+                has_timestamps = true;
+                in_word = false;
+            }
+            rest = &tag[close + 1..];
+            continue;
+        }
+
+        let (c, len) = decode_entity(rest);
+        rest = &rest[len..];
+
+        if c.is_whitespace() {
+            in_word = false;
+            if !plain.is_empty() && !plain.ends_with(' ') {
+                plain.push(' ');
+            }
+            continue;
+        }
+
+        plain.push(c);
+        match words.last_mut() {
+            Some((word, _)) if in_word => word.push(c),
+            _ => {
+                words.push((c.to_string(), time.take()));
+                in_word = true;
+            }
+        }
+    }
+
+    let plain = plain.trim_end().to_string();
+    if !has_timestamps {
+        return (plain, vec![]);
+    }
+
+    let mut starts = Vec::with_capacity(words.len());
+    let mut current = start;
+    for (_, word_start) in &words {
+        current = word_start.unwrap_or(current);
+        starts.push(current);
+    }
+
+    let words = words
+        .into_iter()
+        .enumerate()
+        .map(|(i, (text, _))| CaptionWord {
+            text,
+            start: starts[i] as f32,
+            end: starts.get(i + 1).copied().unwrap_or(end) as f32,
+        })
+        .collect();
+
+    (plain, words)
+}
+
+/// Decodes the character or HTML entity at the start of `text`, returning it and its length.
+fn decode_entity(text: &str) -> (char, usize) {
+    const ENTITIES: [(&str, char); 6] = [
+        ("&amp;", '&'),
+        ("&lt;", '<'),
+        ("&gt;", '>'),
+        ("&nbsp;", ' '),
+        ("&lrm;", '\u{200e}'),
+        ("&rlm;", '\u{200f}'),
+    ];
+
+    for (entity, c) in ENTITIES {
+        if text.starts_with(entity) {
+            return (c, entity.len());
+        }
+    }
+
+    let c = text.chars().next().unwrap();
+    (c, c.len_utf8())
+}
+
+/// Parses `HH:MM:SS,mmm` (SRT) or `[HH:]MM:SS.mmm` (WebVTT) into seconds, where `separator`
+/// comes before the milliseconds.
+fn parse_timestamp(timestamp: &str, separator: char, tolerant: bool) -> Option<f64> {
+    let (clock, fraction) = match timestamp.rsplit_once(separator) {
+        Some(split) => split,
+        None if tolerant => timestamp
+            .rsplit_once([',', '.'])
+            .unwrap_or((timestamp, "0")),
+        None => return None,
+    };
+
+    let number = |part: &str, digits: usize| {
+        let valid = !part.is_empty()
+            && part.bytes().all(|b| b.is_ascii_digit())
+            && (tolerant || part.len() == digits);
+        valid.then(|| part.parse::<f64>().ok()).flatten()
+    };
+
+    let (hours, minutes, seconds) = match clock.split(':').collect::<Vec<_>>()[..] {
+        // hours can have more than two digits
+        [hours, minutes, seconds] => (number(hours, hours.len().max(2))?, minutes, seconds),
+        // webvtt allows leaving out the hours
+        [minutes, seconds] if tolerant || separator == '.' => (0.0, minutes, seconds),
+        [seconds] if tolerant => (0.0, "0", seconds),
+        _ => return None,
+    };
+    let minutes = number(minutes, 2)?;
+    let seconds = number(seconds, 2)?;
+    let millis = number(fraction, 3)? / 10f64.powi(fraction.len() as i32 - 3);
+
+    if !tolerant && (minutes >= 60.0 || seconds >= 60.0) {
+        return None;
+    }
+
+    Some(hours * 3600.0 + minutes * 60.0 + seconds + millis / 1000.0)
+}
+
+impl CaptionsData {
+    /// Maps the captions onto the edited timeline, sorted by their start time.
+    ///
+    /// Caption times are relative to the start of the recording, with its segments played back
+    /// to back, so `clip_offsets[i]` is the time recording segment `i` starts at.
+    /// Captions inside sections cut from the timeline are dropped, and captions spanning a cut are
+    /// split at it, keeping only the words that are still on the timeline.
+    pub fn to_timeline(
+        &self,
+        timeline: Option<&TimelineConfiguration>,
+        clip_offsets: &[f64],
+    ) -> Vec<CaptionSegment> {
+        let Some(timeline) = timeline else {
+            let mut segments = self.segments.clone();
+            segments.sort_by(|a, b| a.start.total_cmp(&b.start));
+            return segments;
+        };
+
+        let mut captions = vec![];
+        let mut timeline_start = 0.0;
+
+        for segment in &timeline.segments {
+            let Some(offset) = clip_offsets.get(segment.recording_clip as usize) else {
+                timeline_start += segment.duration();
+                continue;
+            };
+
+            let source_start = offset + segment.start;
+            let source_end = offset + segment.end;
+            let to_timeline = |time: f64| {
+                let time = time.clamp(source_start, source_end);
+                (timeline_start + (time - source_start) / segment.timescale) as f32
+            };
+            let overlaps = |start: f32, end: f32| {
+                f64::from(end) > source_start && f64::from(start) < source_end
+            };
+
+            for caption in &self.segments {
+                if !overlaps(caption.start, caption.end) {
+                    continue;
+                }
+
+                let words = caption
+                    .words
+                    .iter()
+                    .filter(|word| overlaps(word.start, word.end))
+                    .map(|word| CaptionWord {
+                        text: word.text.clone(),
+                        start: to_timeline(word.start.into()),
+                        end: to_timeline(word.end.into()),
+                    })
+                    .collect::<Vec<_>>();
+
+                // the caption's words all fall in the cut sections around this part of it
+                if words.is_empty() && !caption.words.is_empty() {
+                    continue;
+                }
+
+                let text = if words.len() == caption.words.len() {
+                    caption.text.clone()
+                } else {
+                    words
+                        .iter()
+                        .map(|word| word.text.as_str())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                };
+
+                captions.push(CaptionSegment {
+                    id: caption.id.clone(),
+                    start: to_timeline(caption.start.into()),
+                    end: to_timeline(caption.end.into()),
+                    text,
+                    words,
+                });
+            }
+
+            timeline_start += segment.duration();
+        }
+
+        captions.sort_by(|a, b| a.start.total_cmp(&b.start));
+        captions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        TimelineSegment,
+        test_utils::{caption, captions, segment, timeline},
+    };
+
+    fn times(captions: &[CaptionSegment]) -> Vec<(&str, f32, f32)> {
+        captions
+            .iter()
+            .map(|c| (c.text.as_str(), c.start, c.end))
+            .collect()
+    }
+
+    #[test]
+    fn without_timeline_captions_are_unchanged() {
+        let data = captions(vec![
+            caption("b", &[("world", 2.0, 3.0)]),
+            caption("a", &[("hello", 0.0, 1.0)]),
+        ]);
+
+        assert_eq!(
+            times(&data.to_timeline(None, &[0.0])),
+            [("hello", 0.0, 1.0), ("world", 2.0, 3.0)]
+        );
+    }
+
+    #[test]
+    fn cuts_drop_and_split_captions() {
+        let data = captions(vec![
+            caption("a", &[("one", 0.0, 1.0), ("two", 1.0, 2.0)]),
+            caption("b", &[("gone", 4.0, 5.0)]),
+            caption("c", &[("three", 5.0, 6.0), ("four", 8.0, 9.0)]),
+        ]);
+
+        // keeps 0-1.5 and 8-10 of the recording
+        let timeline = timeline(vec![segment(0, 0.0, 1.5), segment(0, 8.0, 10.0)], vec![]);
+
+        assert_eq!(
+            times(&data.to_timeline(Some(&timeline), &[0.0])),
+            [("one two", 0.0, 1.5), ("four", 1.5, 2.5)]
+        );
+    }
+
+    #[test]
+    fn srt_and_text_use_timeline_times() {
+        let data = captions(vec![
+            caption("a", &[("one", 0.0, 1.0), ("two", 1.0, 2.0)]),
+            caption("b", &[("three", 3661.25, 3662.5)]),
+        ]);
+        let timeline = timeline(vec![segment(0, 1.0, 3700.0)], vec![]);
+        let captions = data.to_timeline(Some(&timeline), &[0.0]);
+
+        assert_eq!(
+            CaptionFormat::Srt.serialize(&captions),
+            "1\n00:00:00,000 --> 00:00:01,000\ntwo\n\n2\n01:01:00,250 --> 01:01:01,500\nthree\n\n"
+        );
+        assert_eq!(CaptionFormat::Text.serialize(&captions), "two\nthree\n");
+    }
+
+    #[test]
+    fn vtt_includes_word_timings() {
+        let data = captions(vec![
+            caption("a", &[("a", 0.5, 1.0), ("<b>", 1.25, 2.0)]),
+            CaptionSegment {
+                id: "b".to_string(),
+                start: 3.0,
+                end: 4.0,
+                text: "no words".to_string(),
+                words: vec![],
+            },
+        ]);
+
+        assert_eq!(
+            CaptionFormat::WebVtt.serialize(&data.to_timeline(None, &[0.0])),
+            "WEBVTT\n\n\
+             1\n00:00:00.500 --> 00:00:02.000\n<c>a</c> <00:00:01.250><c>&lt;b&gt;</c>\n\n\
+             2\n00:00:03.000 --> 00:00:04.000\nno words\n\n"
+        );
+    }
+
+    #[test]
+    fn parses_srt() {
+        let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\n<i>Hello</i> &amp;\r\nwelcome\r\n\r\n\
+                   2\r\n01:00:00,000 --> 01:00:01,250\r\nBye\r\n";
+        let data = CaptionsData::parse_subtitles(srt, CaptionFormat::Srt, ParseStrictness::Strict)
+            .unwrap();
+
+        assert_eq!(
+            times(&data.segments),
+            [("Hello & welcome", 1.0, 2.5), ("Bye", 3600.0, 3601.25)]
+        );
+        assert!(data.segments.iter().all(|segment| segment.words.is_empty()));
+        assert_ne!(data.segments[0].id, data.segments[1].id);
+    }
+
+    #[test]
+    fn parses_vtt_word_timings() {
+        let vtt = "WEBVTT - title\n\nNOTE a comment\n\n\
+                   intro\n00:01.000 --> 00:03.000 align:start\n\
+                   <v Speaker><c>one</c> <00:01.500><c>two</c> <00:02.250>three</v>\n";
+        let data =
+            CaptionsData::parse_subtitles(vtt, CaptionFormat::WebVtt, ParseStrictness::Strict)
+                .unwrap();
+
+        assert_eq!(times(&data.segments), [("one two three", 1.0, 3.0)]);
+        assert_eq!(
+            data.segments[0]
+                .words
+                .iter()
+                .map(|w| (w.text.as_str(), w.start, w.end))
+                .collect::<Vec<_>>(),
+            [("one", 1.0, 1.5), ("two", 1.5, 2.25), ("three", 2.25, 3.0)]
+        );
+    }
+
+    #[test]
+    fn strict_mode_rejects_malformed_files() {
+        let parse = |contents, format| {
+            CaptionsData::parse_subtitles(contents, format, ParseStrictness::Strict).unwrap_err()
+        };
+
+        assert_eq!(
+            parse("00:01.000 --> 00:02.000\nhi", CaptionFormat::WebVtt),
+            CaptionParseError::MissingHeader
+        );
+        assert_eq!(
+            parse("1\n00:00:01.000 --> 00:00:02,000\nhi", CaptionFormat::Srt),
+            CaptionParseError::InvalidTimestamp {
+                line: 2,
+                timestamp: "00:00:01.000".to_string()
+            }
+        );
+        assert_eq!(
+            parse("1\n00:00:02,000 --> 00:00:01,000\nhi", CaptionFormat::Srt),
+            CaptionParseError::InvalidRange { line: 2 }
+        );
+        assert_eq!(
+            parse("1\nhi", CaptionFormat::Srt),
+            CaptionParseError::MissingTiming { line: 1 }
+        );
+        assert_eq!(
+            parse("hi", CaptionFormat::Text),
+            CaptionParseError::UnsupportedFormat(CaptionFormat::Text)
+        );
+    }
+
+    #[test]
+    fn tolerant_mode_fixes_timestamps_and_skips_bad_cues() {
+        let srt = "1\n0:0:1.5 --> 00:00:02\nfirst\n\n\
+                   2\nnot a timing line\n\n\
+                   3\n00:00:04,000 --> nonsense\nskipped\n\n\
+                   4\n00:05,25 --> 00:06,000\nlast";
+        let data =
+            CaptionsData::parse_subtitles(srt, CaptionFormat::Srt, ParseStrictness::Tolerant)
+                .unwrap();
+
+        assert_eq!(
+            times(&data.segments),
+            [("first", 1.5, 2.0), ("last", 5.25, 6.0)]
+        );
+    }
+
+    #[test]
+    fn timescale_and_clip_offsets_are_applied() {
+        let data = captions(vec![
+            caption("a", &[("first", 1.0, 2.0)]),
+            caption("b", &[("second", 11.0, 13.0)]),
+        ]);
+
+        // the second recording segment starts 10s into the captions, and is played at 2x
+        let timeline = timeline(
+            vec![
+                segment(0, 0.0, 4.0),
+                TimelineSegment {
+                    timescale: 2.0,
+                    ..segment(1, 0.0, 4.0)
+                },
+            ],
+            vec![],
+        );
+
+        assert_eq!(
+            times(&data.to_timeline(Some(&timeline), &[0.0, 10.0])),
+            [("first", 1.0, 2.0), ("second", 4.5, 5.5)]
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    TimelineConfiguration,
+    captions::{escape_vtt, format_timestamp},
+};
+
+/// Starts a chapter at a point in a recording segment. Markers are placed on the recording
+/// rather than the timeline, so they stay with their content as the timeline is cut.
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChapterMarker {
+    pub title: String,
+    #[serde(default, rename = "recordingSegment")]
+    pub recording_clip: u32,
+    /// Seconds into the recording segment
+    pub time: f64,
+}
+
+/// A section of the edited timeline, in timeline seconds.
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Chapter {
+    pub start: f64,
+    pub end: f64,
+    pub title: String,
+}
+
+/// File formats chapters can be exported to next to a video.
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ChapterFormat {
+    /// `00:00 Title` lines, as pasted into a YouTube description
+    YouTube,
+    /// A WebVTT chapters track
+    WebVtt,
+}
+
+impl ChapterFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::YouTube => "txt",
+            Self::WebVtt => "vtt",
+        }
+    }
+
+    /// Serializes chapters, which should already be mapped onto the timeline with
+    /// [`TimelineConfiguration::chapters`].
+    pub fn serialize(&self, chapters: &[Chapter]) -> String {
+        match self {
+            Self::YouTube => chapters_to_youtube(chapters),
+            Self::WebVtt => chapters_to_vtt(chapters),
+        }
+    }
+}
+
+/// YouTube only picks up chapters that begin at `00:00`, so an "Intro" chapter is added
+/// when the first one starts later.
+fn chapters_to_youtube(chapters: &[Chapter]) -> String {
+    let Some(last) = chapters.last() else {
+        return String::new();
+    };
+    let with_hours = last.start >= 3600.0;
+
+    let mut text = String::new();
+    if chapters[0].start >= 1.0 {
+        text.push_str(&format!("{} Intro\n", format_clock(0.0, with_hours)));
+    }
+
+    for chapter in chapters {
+        text.push_str(&format!(
+            "{} {}\n",
+            format_clock(chapter.start, with_hours),
+            chapter.title.trim()
+        ));
+    }
+
+    text
+}
+
+fn chapters_to_vtt(chapters: &[Chapter]) -> String {
+    let mut vtt = String::from("WEBVTT\n\n");
+
+    for (i, chapter) in chapters.iter().enumerate() {
+        vtt.push_str(&format!(
+            "chapter-{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            format_timestamp(chapter.start, '.'),
+            format_timestamp(chapter.end, '.'),
+            escape_vtt(chapter.title.trim())
+        ));
+    }
+
+    vtt
+}
+
+/// Formats seconds as `MM:SS`, or `H:MM:SS` when `with_hours` is set.
+fn format_clock(seconds: f64, with_hours: bool) -> String {
+    let seconds = seconds.max(0.0) as u64;
+
+    if with_hours {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+impl TimelineConfiguration {
+    /// Where `marker` lands on the timeline. A marker inside a section cut from the timeline
+    /// moves to where its recording picks up again after the cut, and is `None` when the
+    /// recording never does.
+    pub fn marker_time(&self, marker: &ChapterMarker) -> Option<f64> {
+        let mut timeline_start = 0.0;
+
+        self.segments
+            .iter()
+            .filter_map(|segment| {
+                let start = timeline_start;
+                timeline_start += segment.duration();
+
+                if segment.recording_clip != marker.recording_clip || segment.end <= marker.time {
+                    return None;
+                }
+
+                let skipped = segment.start > marker.time;
+                let offset = (marker.time - segment.start).max(0.0) / segment.timescale;
+                Some((skipped, segment.start, start + offset))
+            })
+            // prefer a segment playing the marker, then the one resuming soonest after it
+            .min_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)))
+            .map(|(.., time)| time)
+    }
+
+    /// Maps the chapter markers onto the timeline with [`Self::marker_time`], sorted by their
+    /// start time. Each chapter runs until the next one starts, and the last until the end of
+    /// the timeline. Markers with nothing left after them are dropped.
+    pub fn chapters(&self) -> Vec<Chapter> {
+        let mut starts = self
+            .markers
+            .iter()
+            .filter_map(|marker| {
+                self.marker_time(marker)
+                    .map(|time| (time, marker.title.clone()))
+            })
+            .collect::<Vec<_>>();
+
+        starts.sort_by(|a, b| a.0.total_cmp(&b.0));
+        starts.dedup_by(|b, a| (b.0 - a.0).abs() < 0.001);
+
+        let duration = self.duration();
+        let ends = starts
+            .iter()
+            .skip(1)
+            .map(|(start, _)| *start)
+            .chain([duration])
+            .collect::<Vec<_>>();
+
+        starts
+            .into_iter()
+            .zip(ends)
+            .filter(|((start, _), _)| *start < duration)
+            .map(|((start, title), end)| Chapter { start, end, title })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        TimelineSegment,
+        test_utils::{segment, timeline},
+    };
+
+    fn marker(recording_clip: u32, time: f64, title: &str) -> ChapterMarker {
+        ChapterMarker {
+            title: title.to_string(),
+            recording_clip,
+            time,
+        }
+    }
+
+    fn chapter(start: f64, end: f64, title: &str) -> Chapter {
+        Chapter {
+            start,
+            end,
+            title: title.to_string(),
+        }
+    }
+
+    #[test]
+    fn markers_follow_their_content_through_cuts() {
+        // 0-10s of clip 0 at normal speed, a cut to 20s, then 20-40s at double speed
+        let timeline = timeline(
+            vec![
+                segment(0, 0.0, 10.0),
+                TimelineSegment {
+                    timescale: 2.0,
+                    ..segment(0, 20.0, 40.0)
+                },
+            ],
+            vec![
+                marker(0, 30.0, "Demo"),
+                marker(0, 0.0, "Intro"),
+                marker(0, 15.0, "Setup"),
+            ],
+        );
+
+        assert_eq!(
+            timeline.chapters(),
+            vec![
+                chapter(0.0, 10.0, "Intro"),
+                chapter(10.0, 15.0, "Setup"),
+                chapter(15.0, 20.0, "Demo"),
+            ]
+        );
+    }
+
+    #[test]
+    fn markers_after_the_timeline_are_dropped() {
+        let timeline = timeline(
+            vec![segment(0, 0.0, 10.0), segment(1, 0.0, 5.0)],
+            vec![marker(0, 12.0, "Trimmed"), marker(1, 2.0, "Second clip")],
+        );
+
+        assert_eq!(
+            timeline.chapters(),
+            vec![chapter(12.0, 15.0, "Second clip")]
+        );
+    }
+
+    #[test]
+    fn serializes_youtube_and_webvtt_chapters() {
+        let chapters = [chapter(5.0, 65.5, "Setup"), chapter(65.5, 130.0, "Demo")];
+
+        assert_eq!(
+            ChapterFormat::YouTube.serialize(&chapters),
+            "00:00 Intro\n00:05 Setup\n01:05 Demo\n"
+        );
+        assert_eq!(
+            ChapterFormat::WebVtt.serialize(&chapters),
+            "WEBVTT\n\nchapter-1\n00:00:05.000 --> 00:01:05.500\nSetup\n\n\
+             chapter-2\n00:01:05.500 --> 00:02:10.000\nDemo\n\n"
+        );
+    }
+}
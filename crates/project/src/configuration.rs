@@ -9,6 +9,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use specta::Type;
 
+use crate::{ChapterMarker, MusicDucking, MusicTrack, VolumeKeyframes};
+
 #[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub enum AspectRatio {
@@ -379,6 +381,9 @@ pub struct AudioConfiguration {
     pub mic_volume_db: f32,
     pub mic_stereo_mode: StereoMode,
     pub system_volume_db: f32,
+    pub volume_keyframes: VolumeKeyframes,
+    pub music: Vec<MusicTrack>,
+    pub music_ducking: MusicDucking,
 }
 
 impl Default for AudioConfiguration {
@@ -389,6 +394,9 @@ impl Default for AudioConfiguration {
             mic_volume_db: 0.0,
             mic_stereo_mode: StereoMode::default(),
             system_volume_db: 0.0,
+            volume_keyframes: VolumeKeyframes::default(),
+            music: vec![],
+            music_ducking: MusicDucking::default(),
         }
     }
 }
@@ -547,10 +555,32 @@ impl CursorConfiguration {
     }
 }
 
-#[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
+/// The overlay showing the keys pressed during the recording
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase", default)]
 pub struct HotkeysConfiguration {
-    show: bool,
+    pub show: bool,
+    pub position: CaptionPosition,
+    /// Font size at 1080p
+    pub size: u32,
+    pub fade_duration: f32,
+    /// Seconds a keystroke stays on screen after its keys are released
+    pub display_duration: f32,
+    /// Show keys pressed together as one shortcut, such as `Ctrl + C`, instead of one at a time
+    pub combine_chords: bool,
+}
+
+impl Default for HotkeysConfiguration {
+    fn default() -> Self {
+        Self {
+            show: false,
+            position: CaptionPosition::BottomCenter,
+            size: 32,
+            fade_duration: 0.15,
+            display_duration: 1.0,
+            combine_chords: true,
+        }
+    }
 }
 
 #[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
@@ -788,6 +818,8 @@ pub struct TimelineConfiguration {
     pub mask_segments: Vec<MaskSegment>,
     #[serde(default)]
     pub text_segments: Vec<TextSegment>,
+    #[serde(default)]
+    pub markers: Vec<ChapterMarker>,
 }
 
 impl TimelineConfiguration {
@@ -39,6 +39,16 @@ impl PartialOrd for CursorClickEvent {
     }
 }
 
+/// A key being pressed or released. Keys are named after what they type, such as `A` or `/`,
+/// with modifiers named `Control`, `Alt`, `Shift` and `Meta`.
+#[derive(Serialize, Deserialize, Clone, Type, Debug, PartialEq)]
+pub struct KeyboardEvent {
+    pub active_modifiers: Vec<String>,
+    pub key: String,
+    pub time_ms: f64,
+    pub down: bool,
+}
+
 #[derive(Default, Serialize, Deserialize, Debug, Clone)]
 #[serde(transparent)]
 pub struct CursorImages(pub HashMap<String, CursorImage>);
@@ -67,6 +77,8 @@ impl CursorData {
 pub struct CursorEvents {
     pub clicks: Vec<CursorClickEvent>,
     pub moves: Vec<CursorMoveEvent>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<KeyboardEvent>,
 }
 
 impl CursorEvents {
@@ -262,6 +274,7 @@ impl From<CursorData> for CursorEvents {
         Self {
             clicks: value.clicks,
             moves: value.moves,
+            keys: vec![],
         }
     }
 }
@@ -312,6 +325,7 @@ mod tests {
                 move_event(900.0, "pointer"),
             ],
             clicks: vec![click_event(250.0, "ibeam")],
+            keys: vec![],
         };
 
         events.stabilize_short_lived_cursor_shapes(
@@ -345,6 +359,7 @@ mod tests {
                 move_event(1500.0, "pointer"),
             ],
             clicks: vec![click_event(400.0, "ibeam")],
+            keys: vec![],
         };
 
         events.stabilize_short_lived_cursor_shapes(
@@ -366,6 +381,7 @@ mod tests {
                 move_event(1200.0, "pointer"),
             ],
             clicks: vec![click_event(250.0, "ibeam")],
+            keys: vec![],
         };
 
         events.stabilize_short_lived_cursor_shapes(None, SHORT_CURSOR_SHAPE_DEBOUNCE_MS);
@@ -0,0 +1,271 @@
+use crate::{CursorEvents, KeyboardEvent, Platform};
+
+/// Modifier keys, in the order they're shown in a shortcut
+pub const MODIFIER_KEYS: [&str; 4] = ["Control", "Alt", "Shift", "Meta"];
+
+impl KeyboardEvent {
+    pub fn is_modifier(&self) -> bool {
+        is_modifier(&self.key)
+    }
+
+    /// Whether the key types text, such as a letter, digit, punctuation or space.
+    pub fn is_text(&self) -> bool {
+        self.key.chars().count() == 1 || self.key == "Space"
+    }
+
+    /// Whether recording the key would reveal typed text rather than a shortcut, because it
+    /// types text without `Control` or `Meta` held. `Alt` still types characters, such as
+    /// Option+letter on macOS or AltGr on many European layouts.
+    pub fn is_private(&self) -> bool {
+        self.is_text()
+            && !self
+                .active_modifiers
+                .iter()
+                .any(|modifier| modifier == "Control" || modifier == "Meta")
+    }
+}
+
+fn is_modifier(key: &str) -> bool {
+    MODIFIER_KEYS.contains(&key)
+}
+
+/// A key or shortcut shown in the keystroke overlay, pressed from `start_ms` until `end_ms`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keystroke {
+    pub keys: Vec<String>,
+    pub start_ms: f64,
+    pub end_ms: f64,
+}
+
+impl Keystroke {
+    /// The keys' labels joined into a shortcut, such as `⇧ + ⌘ + P` on macOS.
+    pub fn label(&self, platform: Option<&Platform>) -> String {
+        self.keys
+            .iter()
+            .map(|key| key_label(key, platform))
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+}
+
+/// The label shown for a key in the keystroke overlay.
+pub fn key_label<'a>(key: &'a str, platform: Option<&Platform>) -> &'a str {
+    let mac = matches!(platform, Some(Platform::MacOS));
+
+    match key {
+        "Control" if mac => "⌃",
+        "Control" => "Ctrl",
+        "Alt" if mac => "⌥",
+        "Shift" if mac => "⇧",
+        "Meta" if mac => "⌘",
+        "Meta" => "Win",
+        "Enter" => "↵",
+        "Backspace" => "⌫",
+        "Delete" => "Del",
+        "Escape" => "Esc",
+        "CapsLock" => "Caps",
+        "PageUp" => "PgUp",
+        "PageDown" => "PgDn",
+        "Up" => "↑",
+        "Down" => "↓",
+        "Left" => "←",
+        "Right" => "→",
+        key => key,
+    }
+}
+
+impl CursorEvents {
+    /// Groups the key presses into the keystrokes shown by the overlay.
+    ///
+    /// With `combine_chords`, each key is shown together with the modifiers held while it was
+    /// pressed, and modifiers pressed on their own are shown once they're all released.
+    /// Otherwise every key press, including modifiers, is its own keystroke.
+    pub fn keystrokes(&self, combine_chords: bool) -> Vec<Keystroke> {
+        let mut events = self.keys.iter().collect::<Vec<_>>();
+        events.sort_by(|a, b| a.time_ms.total_cmp(&b.time_ms));
+
+        let mut keystrokes: Vec<Keystroke> = vec![];
+        // keystrokes waiting for their last key to be released
+        let mut open: Vec<usize> = vec![];
+        let mut held: Vec<&str> = vec![];
+        // the modifiers pressed since none were held, and when the first was pressed
+        let mut modifier_chord: Vec<&str> = vec![];
+        let mut modifier_chord_start = 0.0;
+        let mut modifier_chord_used = false;
+
+        for event in events {
+            let key = event.key.as_str();
+
+            if event.down {
+                if held.contains(&key) {
+                    continue;
+                }
+                held.push(key);
+
+                if combine_chords && is_modifier(key) {
+                    if modifier_chord.is_empty() {
+                        modifier_chord_start = event.time_ms;
+                        modifier_chord_used = false;
+                    }
+                    if !modifier_chord.contains(&key) {
+                        modifier_chord.push(key);
+                    }
+                    continue;
+                }
+
+                let keys = if combine_chords {
+                    modifier_chord_used = true;
+                    sorted_modifiers(held.iter().copied().filter(|k| is_modifier(k)))
+                        .chain([key.to_string()])
+                        .collect()
+                } else {
+                    vec![key.to_string()]
+                };
+
+                open.push(keystrokes.len());
+                keystrokes.push(Keystroke {
+                    keys,
+                    start_ms: event.time_ms,
+                    end_ms: event.time_ms,
+                });
+            } else {
+                held.retain(|k| *k != key);
+
+                open.retain(|&i| {
+                    let keystroke = &mut keystrokes[i];
+                    if keystroke.keys.last().is_some_and(|k| k == key) {
+                        keystroke.end_ms = event.time_ms;
+                        false
+                    } else {
+                        true
+                    }
+                });
+
+                if combine_chords
+                    && is_modifier(key)
+                    && !held.iter().any(|k| is_modifier(k))
+                    && !modifier_chord.is_empty()
+                {
+                    if !modifier_chord_used {
+                        keystrokes.push(Keystroke {
+                            keys: sorted_modifiers(modifier_chord.iter().copied()).collect(),
+                            start_ms: modifier_chord_start,
+                            end_ms: event.time_ms,
+                        });
+                    }
+                    modifier_chord.clear();
+                }
+            }
+        }
+
+        keystrokes.sort_by(|a, b| a.start_ms.total_cmp(&b.start_ms));
+        keystrokes
+    }
+}
+
+fn sorted_modifiers<'a>(modifiers: impl Iterator<Item = &'a str>) -> impl Iterator<Item = String> {
+    let modifiers = modifiers.collect::<Vec<_>>();
+    MODIFIER_KEYS
+        .into_iter()
+        .filter(move |modifier| modifiers.contains(modifier))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(time_ms: f64, key: &str, down: bool) -> KeyboardEvent {
+        KeyboardEvent {
+            active_modifiers: vec![],
+            key: key.to_string(),
+            time_ms,
+            down,
+        }
+    }
+
+    fn keystrokes(keys: Vec<KeyboardEvent>, combine_chords: bool) -> Vec<(String, f64, f64)> {
+        CursorEvents {
+            clicks: vec![],
+            moves: vec![],
+            keys,
+        }
+        .keystrokes(combine_chords)
+        .iter()
+        .map(|k| (k.label(Some(&Platform::MacOS)), k.start_ms, k.end_ms))
+        .collect()
+    }
+
+    #[test]
+    fn text_is_private_unless_part_of_a_shortcut() {
+        let event = |name: &str, modifiers: &[&str]| KeyboardEvent {
+            active_modifiers: modifiers.iter().map(|m| m.to_string()).collect(),
+            ..key(0.0, name, true)
+        };
+
+        assert!(event("A", &[]).is_private());
+        assert!(event("A", &["Shift"]).is_private());
+        assert!(event("Space", &[]).is_private());
+        assert!(event("Q", &["Alt"]).is_private());
+        assert!(event("2", &["Alt", "Shift"]).is_private());
+        assert!(!event("A", &["Meta"]).is_private());
+        assert!(!event("/", &["Shift", "Control"]).is_private());
+        assert!(!event("Enter", &[]).is_private());
+        assert!(!event("Shift", &[]).is_private());
+    }
+
+    #[test]
+    fn chords_are_combined() {
+        let events = vec![
+            key(0.0, "Meta", true),
+            key(100.0, "Shift", true),
+            key(200.0, "P", true),
+            key(300.0, "P", false),
+            key(350.0, "Shift", false),
+            key(400.0, "Meta", false),
+            key(1000.0, "Escape", true),
+            key(1100.0, "Escape", false),
+        ];
+
+        assert_eq!(
+            keystrokes(events, true),
+            [
+                ("⇧ + ⌘ + P".to_string(), 200.0, 300.0),
+                ("Esc".to_string(), 1000.0, 1100.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn modifiers_pressed_alone_are_shown_when_released() {
+        let events = vec![
+            key(0.0, "Shift", true),
+            key(100.0, "Control", true),
+            key(200.0, "Shift", false),
+            key(300.0, "Control", false),
+        ];
+
+        assert_eq!(
+            keystrokes(events, true),
+            [("⌃ + ⇧".to_string(), 0.0, 300.0)]
+        );
+    }
+
+    #[test]
+    fn separate_keys_without_combining() {
+        let events = vec![
+            key(0.0, "Meta", true),
+            key(200.0, "C", true),
+            key(300.0, "C", false),
+            key(400.0, "Meta", false),
+        ];
+
+        assert_eq!(
+            keystrokes(events, false),
+            [
+                ("⌘".to_string(), 0.0, 400.0),
+                ("C".to_string(), 200.0, 300.0)
+            ]
+        );
+    }
+}
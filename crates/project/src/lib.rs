@@ -1,10 +1,30 @@
+mod auto_zoom;
+mod captions;
+mod chapters;
 mod configuration;
 pub mod cursor;
+mod keystrokes;
 mod meta;
+mod music;
+mod silence;
+#[cfg(test)]
+mod test_utils;
+mod transcript;
+mod validate;
+mod volume;
 
+pub use auto_zoom::*;
+pub use captions::*;
+pub use chapters::*;
 pub use configuration::*;
 pub use cursor::*;
+pub use keystrokes::*;
 pub use meta::*;
+pub use music::*;
+pub use silence::*;
+pub use transcript::*;
+pub use validate::*;
+pub use volume::*;
 
 use serde::{Deserialize, Serialize};
 use specta::Type;
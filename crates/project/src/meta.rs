@@ -97,6 +97,8 @@ pub enum UploadMeta {
         file_path: PathBuf,
         pre_created_video: VideoUploadInfo,
         recording_dir: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        progress: Option<MultipartUploadProgress>,
     },
     SinglePartUpload {
         video_id: String,
@@ -110,6 +112,70 @@ pub enum UploadMeta {
     Complete,
 }
 
+/// The parts of an S3 multipart upload that have been uploaded so far,
+/// persisted so the upload can continue where it left off after a restart.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct MultipartUploadProgress {
+    pub upload_id: String,
+    #[serde(default)]
+    pub parts: Vec<UploadedPartMeta>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct UploadedPartMeta {
+    pub part_number: u32,
+    pub etag: String,
+    /// Position of the part's first byte in the uploaded file
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl MultipartUploadProgress {
+    pub fn new(upload_id: impl Into<String>) -> Self {
+        Self {
+            upload_id: upload_id.into(),
+            parts: vec![],
+        }
+    }
+
+    /// Records an uploaded part, replacing any previous upload of the same part.
+    pub fn record_part(&mut self, part: UploadedPartMeta) {
+        self.parts.retain(|p| p.part_number != part.part_number);
+        self.parts.push(part);
+        self.parts.sort_by_key(|p| p.part_number);
+    }
+
+    /// Drops every part after the first missing one.
+    ///
+    /// Parts are read from the file in order and an upload continues after the last
+    /// contiguous part, so parts past a gap won't line up with the parts that replace them.
+    pub fn truncate_to_first_gap(&mut self) {
+        let mut expected_offset = 0;
+
+        let contiguous = self
+            .parts
+            .iter()
+            .zip(1..)
+            .take_while(|(part, part_number)| {
+                let is_next = part.part_number == *part_number && part.offset == expected_offset;
+                expected_offset += part.size;
+                is_next
+            })
+            .count();
+
+        self.parts.truncate(contiguous);
+    }
+
+    /// The number of the next part to upload and the position in the file it starts at.
+    /// Only meaningful once [`Self::truncate_to_first_gap`] has been called.
+    pub fn resume_point(&self) -> (u32, u64) {
+        match self.parts.last() {
+            Some(last) => (last.part_number + 1, last.offset + last.size),
+            None => (1, 0),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
 #[serde(untagged, rename_all = "camelCase")]
 pub enum RecordingMetaInner {
@@ -467,7 +533,7 @@ impl MultipleSegment {
 
 #[cfg(test)]
 mod test {
-    use super::RecordingMeta;
+    use super::{MultipartUploadProgress, RecordingMeta, UploadMeta, UploadedPartMeta};
 
     fn test_meta_deserialize(s: &str) {
         let _: RecordingMeta = serde_json::from_str(s).unwrap();
@@ -572,4 +638,70 @@ mod test {
 	        }"#,
         );
     }
+
+    fn part(part_number: u32, offset: u64, size: u64) -> UploadedPartMeta {
+        UploadedPartMeta {
+            part_number,
+            etag: format!("etag-{part_number}"),
+            offset,
+            size,
+        }
+    }
+
+    #[test]
+    fn multipart_progress_resumes_after_last_contiguous_part() {
+        let mut progress = MultipartUploadProgress::new("upload");
+        assert_eq!(progress.resume_point(), (1, 0));
+
+        progress.record_part(part(2, 10, 20));
+        progress.record_part(part(1, 0, 10));
+        progress.record_part(part(4, 50, 10));
+        progress.truncate_to_first_gap();
+
+        assert_eq!(progress.parts, vec![part(1, 0, 10), part(2, 10, 20)]);
+        assert_eq!(progress.resume_point(), (3, 30));
+    }
+
+    #[test]
+    fn multipart_progress_replaces_reuploaded_parts() {
+        let mut progress = MultipartUploadProgress::new("upload");
+        progress.record_part(part(1, 0, 10));
+        progress.record_part(part(2, 10, 10));
+        progress.record_part(UploadedPartMeta {
+            etag: "reuploaded".to_string(),
+            ..part(1, 0, 10)
+        });
+
+        assert_eq!(progress.parts.len(), 2);
+        assert_eq!(progress.parts[0].etag, "reuploaded");
+    }
+
+    #[test]
+    fn multipart_progress_drops_parts_that_dont_line_up() {
+        let mut progress = MultipartUploadProgress::new("upload");
+        progress.record_part(part(1, 0, 10));
+        progress.record_part(part(2, 15, 10));
+        progress.truncate_to_first_gap();
+
+        assert_eq!(progress.resume_point(), (2, 10));
+    }
+
+    #[test]
+    fn multipart_upload_without_progress() {
+        let meta: UploadMeta = serde_json::from_str(
+            r#"{
+                "state": "MultipartUpload",
+                "video_id": "video",
+                "file_path": "content/output.mp4",
+                "pre_created_video": { "id": "video", "link": "https://cap.so/s/video", "config": { "id": "video" } },
+                "recording_dir": "recording.cap"
+            }"#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            meta,
+            UploadMeta::MultipartUpload { progress: None, .. }
+        ));
+    }
 }
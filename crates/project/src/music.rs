@@ -0,0 +1,169 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// An external audio file mixed under the recording, such as background music.
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MusicTrack {
+    /// Audio file to play, either absolute or relative to the project directory
+    pub path: PathBuf,
+    /// Timeline time in seconds the track starts playing at
+    #[serde(default)]
+    pub start: f64,
+    /// Timeline time in seconds the track stops at. Defaults to the end of the file,
+    /// or the end of the timeline when looping.
+    #[serde(default)]
+    pub end: Option<f64>,
+    /// Start the file over whenever it finishes
+    #[serde(default, rename = "loop")]
+    pub looped: bool,
+    /// Seconds to fade in over from `start`
+    #[serde(default)]
+    pub fade_in: f64,
+    /// Seconds to fade out over before the track stops
+    #[serde(default)]
+    pub fade_out: f64,
+    #[serde(default)]
+    pub volume_db: f32,
+}
+
+impl MusicTrack {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            start: 0.0,
+            end: None,
+            looped: false,
+            fade_in: 0.0,
+            fade_out: 0.0,
+            volume_db: 0.0,
+        }
+    }
+
+    /// Timeline time the track stops playing at, or `None` if it plays forever
+    /// (looping without an end on a project without a timeline).
+    pub fn end_time(&self, file_duration: f64, timeline_duration: Option<f64>) -> Option<f64> {
+        let natural_end = if self.looped {
+            timeline_duration
+        } else {
+            Some(self.start + file_duration)
+        };
+
+        match (self.end, natural_end) {
+            (Some(end), Some(natural_end)) => Some(end.min(natural_end)),
+            (end, natural_end) => end.or(natural_end),
+        }
+    }
+
+    /// Seconds into the file to play at timeline `time`, and the fade gain from 0 to 1 at
+    /// that point, or `None` if the track is silent then.
+    pub fn position_at(
+        &self,
+        time: f64,
+        file_duration: f64,
+        timeline_duration: Option<f64>,
+    ) -> Option<(f64, f32)> {
+        if file_duration <= 0.0 || time < self.start {
+            return None;
+        }
+
+        let end = self.end_time(file_duration, timeline_duration);
+        if end.is_some_and(|end| time >= end) {
+            return None;
+        }
+
+        let elapsed = time - self.start;
+        let position = if self.looped {
+            elapsed % file_duration
+        } else {
+            elapsed
+        };
+
+        let mut gain = 1.0f64;
+        if self.fade_in > 0.0 {
+            gain = gain.min(elapsed / self.fade_in);
+        }
+        if let Some(end) = end
+            && self.fade_out > 0.0
+        {
+            gain = gain.min((end - time) / self.fade_out);
+        }
+
+        Some((position, gain as f32))
+    }
+}
+
+/// Turns the music down while the mic picks up speech.
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MusicDucking {
+    pub enabled: bool,
+    /// How far the music is turned down during speech, in dB
+    pub amount_db: f32,
+    /// Mic loudness in dBFS above which it counts as speech
+    pub threshold_db: f32,
+}
+
+impl Default for MusicDucking {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            amount_db: 12.0,
+            threshold_db: -40.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_from_start_until_the_file_ends() {
+        let track = MusicTrack {
+            start: 5.0,
+            ..MusicTrack::new("music.mp3")
+        };
+
+        assert_eq!(track.position_at(4.9, 10.0, Some(60.0)), None);
+        assert_eq!(track.position_at(7.5, 10.0, Some(60.0)), Some((2.5, 1.0)));
+        assert_eq!(track.position_at(15.0, 10.0, Some(60.0)), None);
+    }
+
+    #[test]
+    fn looping_repeats_until_the_timeline_ends() {
+        let track = MusicTrack {
+            looped: true,
+            ..MusicTrack::new("music.mp3")
+        };
+
+        assert_eq!(track.position_at(25.0, 10.0, Some(60.0)), Some((5.0, 1.0)));
+        assert_eq!(track.position_at(60.0, 10.0, Some(60.0)), None);
+
+        let ends_early = MusicTrack {
+            end: Some(30.0),
+            ..track
+        };
+        assert_eq!(ends_early.position_at(30.0, 10.0, Some(60.0)), None);
+    }
+
+    #[test]
+    fn fades_in_and_out() {
+        let track = MusicTrack {
+            start: 2.0,
+            end: Some(20.0),
+            looped: true,
+            fade_in: 4.0,
+            fade_out: 2.0,
+            ..MusicTrack::new("music.mp3")
+        };
+
+        let gain_at = |time| track.position_at(time, 10.0, None).unwrap().1;
+        assert_eq!(gain_at(2.0), 0.0);
+        assert_eq!(gain_at(4.0), 0.5);
+        assert_eq!(gain_at(10.0), 1.0);
+        assert_eq!(gain_at(19.0), 0.5);
+    }
+}
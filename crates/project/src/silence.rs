@@ -0,0 +1,218 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::TimelineSegment;
+
+/// Pieces of a timeline segment shorter than this are dropped rather than kept as slivers
+const MIN_PIECE_DURATION: f64 = 0.01;
+
+/// How pauses in the mic audio are cut from the timeline
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SilenceCutSettings {
+    /// Loudness in dBFS below which the mic counts as silent
+    pub threshold_db: f32,
+    /// Shortest pause, in seconds, that gets cut
+    pub min_duration: f64,
+    /// Seconds of each pause kept next to speech, so words aren't clipped
+    pub padding: f64,
+    /// Speed pauses up by this factor instead of removing them
+    pub speed: Option<f64>,
+}
+
+impl Default for SilenceCutSettings {
+    fn default() -> Self {
+        Self {
+            threshold_db: -40.0,
+            min_duration: 0.75,
+            padding: 0.2,
+            speed: None,
+        }
+    }
+}
+
+impl SilenceCutSettings {
+    /// Rejects a `speed` that wouldn't speed pauses up, rather than letting it fall back to
+    /// removing them.
+    pub fn validate(&self) -> Result<(), String> {
+        match self.speed {
+            Some(speed) if speed.is_nan() || speed <= 1.0 => Err(format!(
+                "Speed must be greater than 1 to speed pauses up, got {speed}"
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Cuts the silent ranges out of `segments`, or speeds them up if `settings.speed` is set.
+///
+/// `silences` holds the silent ranges of each recording clip, in seconds from the clip's start,
+/// indexed by [`TimelineSegment::recording_clip`]. Clips without an entry are left untouched.
+pub fn cut_silence(
+    segments: &[TimelineSegment],
+    silences: &[Vec<Range<f64>>],
+    settings: &SilenceCutSettings,
+) -> Vec<TimelineSegment> {
+    let cuts = |clip: usize, segment: &TimelineSegment| {
+        silences
+            .get(clip)
+            .into_iter()
+            .flatten()
+            // padding is only kept on the sides of a pause that have speech in this segment
+            .map(|silence| {
+                let start = if silence.start <= segment.start {
+                    segment.start
+                } else {
+                    silence.start + settings.padding
+                };
+                let end = if silence.end >= segment.end {
+                    segment.end
+                } else {
+                    silence.end - settings.padding
+                };
+                start..end
+            })
+            .collect()
+    };
+
+    cut_ranges(segments, cuts, settings.speed.filter(|speed| *speed > 1.0))
+}
+
+/// Moves silent ranges found in a clip's mic file, in seconds from the file's start, onto the
+/// clip's time. The mic is played `mic_offset` seconds ahead of the clip, so what's at `t` in the
+/// file is heard at `t - mic_offset`.
+pub fn silences_in_clip_time(silences: &[Range<f64>], mic_offset: f64) -> Vec<Range<f64>> {
+    silences
+        .iter()
+        .map(|silence| (silence.start - mic_offset).max(0.0)..silence.end - mic_offset)
+        .filter(|silence| silence.end > silence.start)
+        .collect()
+}
+
+/// Splits each segment around the ranges returned by `cuts` for its recording clip, which are in
+/// seconds from the clip's start. The ranges are removed, or played `speed` times faster if set.
+pub(crate) fn cut_ranges(
+    segments: &[TimelineSegment],
+    cuts: impl Fn(usize, &TimelineSegment) -> Vec<Range<f64>>,
+    speed: Option<f64>,
+) -> Vec<TimelineSegment> {
+    let mut result = vec![];
+
+    for segment in segments {
+        let mut cuts = cuts(segment.recording_clip as usize, segment)
+            .into_iter()
+            .map(|cut| cut.start.max(segment.start)..cut.end.min(segment.end))
+            .filter(|cut| cut.end - cut.start >= MIN_PIECE_DURATION)
+            .collect::<Vec<_>>();
+        cuts.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+        let mut push = |start: f64, end: f64, timescale: f64| {
+            if end - start >= MIN_PIECE_DURATION {
+                result.push(TimelineSegment {
+                    recording_clip: segment.recording_clip,
+                    timescale,
+                    start,
+                    end,
+                });
+            }
+        };
+
+        let mut position = segment.start;
+        for cut in cuts {
+            let start = cut.start.max(position);
+            if start >= cut.end {
+                continue;
+            }
+
+            push(position, start, segment.timescale);
+            if let Some(speed) = speed {
+                push(start, cut.end, segment.timescale * speed);
+            }
+            position = cut.end;
+        }
+        push(position, segment.end, segment.timescale);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{ranges, segment};
+
+    fn settings(padding: f64, speed: Option<f64>) -> SilenceCutSettings {
+        SilenceCutSettings {
+            padding,
+            speed,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn removes_silence_keeping_padding() {
+        let cut = cut_silence(
+            &[segment(0, 0.0, 10.0)],
+            &[vec![2.0..5.0, 8.0..10.0]],
+            &settings(0.25, None),
+        );
+
+        assert_eq!(ranges(&cut), [(0, 0.0, 2.25, 1.0), (0, 4.75, 8.25, 1.0)]);
+    }
+
+    #[test]
+    fn speeds_up_silence() {
+        let cut = cut_silence(
+            &[segment(0, 0.0, 6.0)],
+            &[vec![2.0..4.0]],
+            &settings(0.0, Some(4.0)),
+        );
+
+        assert_eq!(
+            ranges(&cut),
+            [(0, 0.0, 2.0, 1.0), (0, 2.0, 4.0, 4.0), (0, 4.0, 6.0, 1.0)]
+        );
+    }
+
+    #[test]
+    fn rejects_speeds_that_dont_speed_up() {
+        assert!(settings(0.0, Some(1.5)).validate().is_ok());
+        assert!(settings(0.0, None).validate().is_ok());
+        assert!(settings(0.0, Some(1.0)).validate().is_err());
+        assert!(settings(0.0, Some(0.5)).validate().is_err());
+    }
+
+    #[test]
+    fn only_cuts_within_each_segment() {
+        let cut = cut_silence(
+            &[segment(0, 3.0, 6.0), segment(1, 0.0, 4.0)],
+            &[vec![1.0..4.0, 5.0..9.0]],
+            &settings(0.0, None),
+        );
+
+        assert_eq!(ranges(&cut), [(0, 4.0, 5.0, 1.0), (1, 0.0, 4.0, 1.0)]);
+    }
+
+    #[test]
+    fn shifts_silence_by_mic_offset() {
+        let silences = silences_in_clip_time(&[0.0..0.5, 3.0..6.0, 9.0..12.0], 1.0);
+        assert_eq!(silences, [2.0..5.0, 8.0..11.0]);
+
+        let cut = cut_silence(&[segment(0, 0.0, 10.0)], &[silences], &settings(0.0, None));
+
+        assert_eq!(ranges(&cut), [(0, 0.0, 2.0, 1.0), (0, 5.0, 8.0, 1.0)]);
+    }
+
+    #[test]
+    fn padding_can_swallow_short_pauses() {
+        let cut = cut_silence(
+            &[segment(0, 0.0, 4.0)],
+            &[vec![1.0..1.5]],
+            &settings(0.25, None),
+        );
+
+        assert_eq!(ranges(&cut), [(0, 0.0, 4.0, 1.0)]);
+    }
+}
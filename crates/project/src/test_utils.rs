@@ -0,0 +1,68 @@
+//! Fixtures shared by the unit tests of this crate.
+
+use crate::{
+    CaptionSegment, CaptionWord, CaptionsData, ChapterMarker, TimelineConfiguration,
+    TimelineSegment,
+};
+
+/// A caption made of `words`, each given as `(text, start, end)`.
+pub fn caption(id: &str, words: &[(&str, f32, f32)]) -> CaptionSegment {
+    CaptionSegment {
+        id: id.to_string(),
+        start: words.first().unwrap().1,
+        end: words.last().unwrap().2,
+        text: words
+            .iter()
+            .map(|(text, ..)| *text)
+            .collect::<Vec<_>>()
+            .join(" "),
+        words: words
+            .iter()
+            .map(|(text, start, end)| CaptionWord {
+                text: text.to_string(),
+                start: *start,
+                end: *end,
+            })
+            .collect(),
+    }
+}
+
+pub fn captions(segments: Vec<CaptionSegment>) -> CaptionsData {
+    CaptionsData {
+        segments,
+        settings: Default::default(),
+    }
+}
+
+/// A timeline segment playing `start..end` of `recording_clip` at normal speed.
+pub fn segment(recording_clip: u32, start: f64, end: f64) -> TimelineSegment {
+    TimelineSegment {
+        recording_clip,
+        timescale: 1.0,
+        start,
+        end,
+    }
+}
+
+/// A timeline of just `segments` and chapter `markers`.
+pub fn timeline(
+    segments: Vec<TimelineSegment>,
+    markers: Vec<ChapterMarker>,
+) -> TimelineConfiguration {
+    TimelineConfiguration {
+        segments,
+        zoom_segments: vec![],
+        scene_segments: vec![],
+        mask_segments: vec![],
+        text_segments: vec![],
+        markers,
+    }
+}
+
+/// `segments` as `(recording_clip, start, end, timescale)`, for comparing in assertions.
+pub fn ranges(segments: &[TimelineSegment]) -> Vec<(u32, f64, f64, f64)> {
+    segments
+        .iter()
+        .map(|s| (s.recording_clip, s.start, s.end, s.timescale))
+        .collect()
+}
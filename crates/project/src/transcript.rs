@@ -0,0 +1,252 @@
+use std::{collections::HashSet, ops::Range};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{CaptionsData, TimelineSegment, silence::cut_ranges};
+
+/// Filler words and phrases removed by default, in lowercase
+pub const DEFAULT_FILLER_WORDS: [&str; 12] = [
+    "um", "umm", "uh", "uhh", "uhm", "er", "erm", "ah", "hmm", "mm", "you know", "i mean",
+];
+
+/// A word in the captions, by its caption's id and its position in the caption's words
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptionWordRef {
+    pub caption_id: String,
+    pub word_index: u32,
+}
+
+/// The timeline segments and captions left after cutting words from the transcript
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptCut {
+    pub segments: Vec<TimelineSegment>,
+    pub captions: CaptionsData,
+}
+
+struct TranscriptWord {
+    word_ref: CaptionWordRef,
+    text: String,
+    time: Range<f64>,
+}
+
+impl CaptionsData {
+    /// Finds the words matching any of `phrases`, ignoring case and surrounding punctuation.
+    /// Phrases of several words match consecutive words, even across captions.
+    pub fn find_phrases(&self, phrases: &[impl AsRef<str>]) -> Vec<CaptionWordRef> {
+        let words = self.transcript_words();
+        let mut matched = vec![false; words.len()];
+
+        for phrase in phrases {
+            let phrase = phrase
+                .as_ref()
+                .split_whitespace()
+                .map(normalize_word)
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>();
+            if phrase.is_empty() || phrase.len() > words.len() {
+                continue;
+            }
+
+            for start in 0..=words.len() - phrase.len() {
+                let window = &words[start..start + phrase.len()];
+                if window.iter().zip(&phrase).all(|(word, p)| &word.text == p) {
+                    matched[start..start + phrase.len()].fill(true);
+                }
+            }
+        }
+
+        words
+            .into_iter()
+            .zip(matched)
+            .filter(|(_, matched)| *matched)
+            .map(|(word, _)| word.word_ref)
+            .collect()
+    }
+
+    /// Cuts `words` out of the timeline `segments` and removes them from the captions.
+    ///
+    /// Caption times are relative to the start of the recording, with its segments played back
+    /// to back, so `clip_offsets[i]` is the time recording segment `i` starts at.
+    /// The pause between two removed words is cut too, so removing a phrase leaves no gaps.
+    pub fn cut_words(
+        &self,
+        words: &[CaptionWordRef],
+        segments: &[TimelineSegment],
+        clip_offsets: &[f64],
+    ) -> TranscriptCut {
+        let removed = words.iter().collect::<HashSet<_>>();
+
+        let mut cuts: Vec<Range<f64>> = vec![];
+        let mut previous_removed = false;
+        for word in self.transcript_words() {
+            let is_removed = removed.contains(&word.word_ref);
+            if is_removed {
+                match cuts.last_mut() {
+                    Some(cut) if previous_removed => cut.end = cut.end.max(word.time.end),
+                    _ => cuts.push(word.time),
+                }
+            }
+            previous_removed = is_removed;
+        }
+
+        let segments = cut_ranges(
+            segments,
+            |clip, _| {
+                let Some(&clip_start) = clip_offsets.get(clip) else {
+                    return vec![];
+                };
+                let clip_end = clip_offsets.get(clip + 1).copied().unwrap_or(f64::MAX);
+
+                cuts.iter()
+                    .filter(|cut| cut.end > clip_start && cut.start < clip_end)
+                    .map(|cut| {
+                        cut.start.max(clip_start) - clip_start..cut.end.min(clip_end) - clip_start
+                    })
+                    .collect()
+            },
+            None,
+        );
+
+        let mut captions = self.clone();
+        captions.segments.retain_mut(|caption| {
+            let word_count = caption.words.len();
+            let mut index = 0;
+            caption.words.retain(|_| {
+                let word_ref = CaptionWordRef {
+                    caption_id: caption.id.clone(),
+                    word_index: index,
+                };
+                index += 1;
+                !removed.contains(&word_ref)
+            });
+
+            if caption.words.len() == word_count {
+                return true;
+            }
+
+            let (Some(first), Some(last)) = (caption.words.first(), caption.words.last()) else {
+                return false;
+            };
+            caption.start = first.start;
+            caption.end = last.end;
+            caption.text = caption
+                .words
+                .iter()
+                .map(|word| word.text.trim())
+                .collect::<Vec<_>>()
+                .join(" ");
+            true
+        });
+
+        TranscriptCut { segments, captions }
+    }
+
+    /// Every word in the captions, in the order they're spoken.
+    fn transcript_words(&self) -> Vec<TranscriptWord> {
+        let mut words = self
+            .segments
+            .iter()
+            .flat_map(|caption| {
+                caption
+                    .words
+                    .iter()
+                    .enumerate()
+                    .map(|(index, word)| TranscriptWord {
+                        word_ref: CaptionWordRef {
+                            caption_id: caption.id.clone(),
+                            word_index: index as u32,
+                        },
+                        text: normalize_word(&word.text),
+                        time: f64::from(word.start)..f64::from(word.end),
+                    })
+            })
+            .collect::<Vec<_>>();
+        words.sort_by(|a, b| a.time.start.total_cmp(&b.time.start));
+        words
+    }
+}
+
+fn normalize_word(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{caption, captions, ranges, segment};
+
+    fn word(caption_id: &str, word_index: u32) -> CaptionWordRef {
+        CaptionWordRef {
+            caption_id: caption_id.to_string(),
+            word_index,
+        }
+    }
+
+    #[test]
+    fn finds_fillers_ignoring_case_and_punctuation() {
+        let data = captions(vec![
+            caption(
+                "a",
+                &[("Um,", 0.0, 0.5), ("so", 0.5, 1.0), ("you", 1.0, 1.5)],
+            ),
+            caption("b", &[("know...", 1.5, 2.0), ("hummus", 2.0, 3.0)]),
+        ]);
+
+        assert_eq!(
+            data.find_phrases(&DEFAULT_FILLER_WORDS),
+            [word("a", 0), word("a", 2), word("b", 0)]
+        );
+    }
+
+    #[test]
+    fn cutting_words_splits_segments_and_updates_captions() {
+        let data = captions(vec![
+            caption(
+                "a",
+                &[("Hello", 0.0, 1.0), ("um", 1.0, 1.5), ("world", 1.5, 2.0)],
+            ),
+            caption("b", &[("uh", 3.0, 3.5)]),
+            caption(
+                "c",
+                &[("you", 4.0, 4.2), ("know", 4.25, 4.5), ("bye", 4.5, 5.0)],
+            ),
+        ]);
+        let fillers = data.find_phrases(&DEFAULT_FILLER_WORDS);
+
+        let cut = data.cut_words(&fillers, &[segment(0, 0.0, 6.0)], &[0.0]);
+
+        assert_eq!(
+            ranges(&cut.segments),
+            [(0, 0.0, 1.0, 1.0), (0, 1.5, 3.0, 1.0), (0, 4.5, 6.0, 1.0)]
+        );
+        assert_eq!(
+            cut.captions
+                .segments
+                .iter()
+                .map(|c| (c.id.as_str(), c.text.as_str(), c.start, c.end))
+                .collect::<Vec<_>>(),
+            [("a", "Hello world", 0.0, 2.0), ("c", "bye", 4.5, 5.0)]
+        );
+    }
+
+    #[test]
+    fn cuts_are_made_in_the_clip_they_fall_in() {
+        let data = captions(vec![caption("a", &[("one", 1.0, 2.0), ("two", 5.0, 6.0)])]);
+
+        // the second clip starts 4s into the recording
+        let cut = data.cut_words(
+            &[word("a", 1)],
+            &[segment(0, 0.0, 4.0), segment(1, 0.0, 4.0)],
+            &[0.0, 4.0],
+        );
+
+        assert_eq!(
+            ranges(&cut.segments),
+            [(0, 0.0, 4.0, 1.0), (1, 0.0, 1.0, 1.0), (1, 2.0, 4.0, 1.0)]
+        );
+    }
+}
@@ -0,0 +1,688 @@
+use std::{collections::BTreeSet, fmt, path::Path};
+
+use relative_path::RelativePathBuf;
+use serde::Serialize;
+
+use crate::{
+    AnnotationValidationError, CaptionsData, CursorEvents, Cursors, InstantRecordingMeta,
+    ProjectConfiguration, RecordingMeta, RecordingMetaInner, StudioRecordingMeta,
+    StudioRecordingStatus,
+};
+
+const META_FILE: &str = "recording-meta.json";
+const CONFIG_FILE: &str = "project-config.json";
+const CAPTIONS_FILE: &str = "captions.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The project opens, but may not look or sound as intended
+    Warning,
+    /// The project can't be opened or exported
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A structural problem found in a `.cap` project.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectIssue {
+    pub severity: Severity,
+    /// The project file containing the problem, followed by a JSON pointer to the offending value
+    pub path: String,
+    pub message: String,
+    /// Whether [`repair_project`] can fix the problem
+    pub repairable: bool,
+    pub repaired: bool,
+}
+
+impl fmt::Display for ProjectIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.severity, self.path, self.message)?;
+
+        if self.repaired {
+            write!(f, " (repaired)")
+        } else if self.repairable {
+            write!(f, " (repairable)")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProjectReport {
+    pub issues: Vec<ProjectIssue>,
+}
+
+impl ProjectReport {
+    /// Whether any errors remain that prevent the project from being opened or exported.
+    pub fn has_errors(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| issue.severity == Severity::Error && !issue.repaired)
+    }
+}
+
+/// Checks a `.cap` project for missing files and references that don't line up,
+/// without modifying it.
+pub fn validate_project(project_path: &Path) -> ProjectReport {
+    Validator::new(project_path, false)
+        .run()
+        .expect("validation doesn't write to the project")
+}
+
+/// Checks a `.cap` project like [`validate_project`], fixing the problems that can be fixed
+/// by dropping a broken reference or out of range segment and saving the result.
+pub fn repair_project(project_path: &Path) -> Result<ProjectReport, std::io::Error> {
+    Validator::new(project_path, true).run()
+}
+
+struct Validator<'a> {
+    project_path: &'a Path,
+    repair: bool,
+    report: ProjectReport,
+}
+
+impl<'a> Validator<'a> {
+    fn new(project_path: &'a Path, repair: bool) -> Self {
+        Self {
+            project_path,
+            repair,
+            report: ProjectReport::default(),
+        }
+    }
+
+    fn issue(&mut self, severity: Severity, path: String, message: String) {
+        self.report.issues.push(ProjectIssue {
+            severity,
+            path,
+            message,
+            repairable: false,
+            repaired: false,
+        });
+    }
+
+    /// Records an issue that can be repaired, returning whether the caller should repair it.
+    fn repairable_issue(&mut self, severity: Severity, path: String, message: String) -> bool {
+        self.report.issues.push(ProjectIssue {
+            severity,
+            path,
+            message,
+            repairable: true,
+            repaired: self.repair,
+        });
+
+        self.repair
+    }
+
+    fn run(mut self) -> Result<ProjectReport, std::io::Error> {
+        let mut meta = match RecordingMeta::load_for_project(self.project_path) {
+            Ok(meta) => meta,
+            Err(e) => {
+                self.issue(
+                    Severity::Error,
+                    META_FILE.to_string(),
+                    format!("failed to load: {e}"),
+                );
+                return Ok(self.report);
+            }
+        };
+
+        let meta_changed = self.check_meta(&mut meta);
+
+        let config_path = self.project_path.join(CONFIG_FILE);
+        let mut config = None;
+        let mut config_changed = false;
+
+        if config_path.exists() {
+            match std::fs::read_to_string(&config_path)
+                .map_err(|e| e.to_string())
+                .and_then(|s| {
+                    serde_json::from_str::<ProjectConfiguration>(&s).map_err(|e| e.to_string())
+                }) {
+                Ok(value) => config = Some(value),
+                Err(e) => self.issue(
+                    Severity::Error,
+                    CONFIG_FILE.to_string(),
+                    format!("failed to load: {e}"),
+                ),
+            }
+        } else if meta.studio_meta().is_some()
+            && self.repairable_issue(
+                Severity::Warning,
+                CONFIG_FILE.to_string(),
+                "does not exist, the default configuration will be used".to_string(),
+            )
+        {
+            config = Some(ProjectConfiguration::default());
+            config_changed = true;
+        }
+
+        if let Some(config) = &mut config {
+            let segment_count = meta.studio_meta().map(|studio| match studio {
+                StudioRecordingMeta::SingleSegment { .. } => 1,
+                StudioRecordingMeta::MultipleSegments { inner } => inner.segments.len(),
+            });
+
+            config_changed |= self.check_config(config, segment_count);
+        }
+
+        let captions_path = self.project_path.join(CAPTIONS_FILE);
+        if captions_path.exists()
+            && let Err(e) = std::fs::read_to_string(&captions_path)
+                .map_err(|e| e.to_string())
+                .and_then(|s| serde_json::from_str::<CaptionsData>(&s).map_err(|e| e.to_string()))
+        {
+            self.issue(
+                Severity::Warning,
+                CAPTIONS_FILE.to_string(),
+                format!("failed to load, captions will be ignored: {e}"),
+            );
+        }
+
+        if meta_changed {
+            meta.save_for_project()
+                .map_err(|e| e.either_into::<std::io::Error>())?;
+        }
+
+        if config_changed && let Some(config) = config {
+            config.write(self.project_path)?;
+        }
+
+        Ok(self.report)
+    }
+
+    fn missing(&self, path: &RelativePathBuf) -> bool {
+        !path.to_path(self.project_path).is_file()
+    }
+
+    fn check_required_file(&mut self, path: &RelativePathBuf, pointer: String) {
+        if self.missing(path) {
+            self.issue(
+                Severity::Error,
+                format!("{META_FILE}#{pointer}"),
+                format!("'{path}' does not exist"),
+            );
+        }
+    }
+
+    /// Reports a reference to a missing file that the project can do without,
+    /// returning whether the reference was removed.
+    fn check_optional_file<T>(
+        &mut self,
+        value: &mut Option<T>,
+        path: impl FnOnce(&T) -> &RelativePathBuf,
+        pointer: String,
+    ) -> bool {
+        let Some(relative_path) = value.as_ref().map(path) else {
+            return false;
+        };
+
+        if !self.missing(relative_path) {
+            return false;
+        }
+
+        let message = format!("'{relative_path}' does not exist");
+        if self.repairable_issue(Severity::Error, format!("{META_FILE}#{pointer}"), message) {
+            *value = None;
+            return true;
+        }
+
+        false
+    }
+
+    fn check_meta(&mut self, meta: &mut RecordingMeta) -> bool {
+        let mut changed = false;
+
+        match &mut meta.inner {
+            RecordingMetaInner::Instant(InstantRecordingMeta::Complete { .. }) => {
+                if self.missing(&RelativePathBuf::from("content/output.mp4")) {
+                    self.issue(
+                        Severity::Error,
+                        META_FILE.to_string(),
+                        "'content/output.mp4' does not exist".to_string(),
+                    );
+                }
+            }
+            RecordingMetaInner::Instant(InstantRecordingMeta::InProgress { .. }) => self.issue(
+                Severity::Warning,
+                META_FILE.to_string(),
+                "recording is incomplete".to_string(),
+            ),
+            RecordingMetaInner::Instant(InstantRecordingMeta::Failed { error }) => self.issue(
+                Severity::Warning,
+                META_FILE.to_string(),
+                format!("recording failed: {error}"),
+            ),
+            RecordingMetaInner::Studio(studio) => match studio.as_mut() {
+                StudioRecordingMeta::SingleSegment { segment } => {
+                    self.check_required_file(&segment.display.path, "/display/path".to_string());
+                    changed |= self.check_optional_file(
+                        &mut segment.camera,
+                        |camera| &camera.path,
+                        "/camera/path".to_string(),
+                    );
+                    changed |= self.check_optional_file(
+                        &mut segment.audio,
+                        |audio| &audio.path,
+                        "/audio/path".to_string(),
+                    );
+                    changed |= self.check_optional_file(
+                        &mut segment.cursor,
+                        |cursor| cursor,
+                        "/cursor".to_string(),
+                    );
+                }
+                StudioRecordingMeta::MultipleSegments { inner } => {
+                    match &inner.status {
+                        Some(StudioRecordingStatus::InProgress)
+                        | Some(StudioRecordingStatus::NeedsRemux) => self.issue(
+                            Severity::Warning,
+                            format!("{META_FILE}#/status"),
+                            "recording is incomplete and may need to be recovered".to_string(),
+                        ),
+                        Some(StudioRecordingStatus::Failed { error }) => self.issue(
+                            Severity::Warning,
+                            format!("{META_FILE}#/status"),
+                            format!("recording failed: {error}"),
+                        ),
+                        Some(StudioRecordingStatus::Complete) | None => {}
+                    }
+
+                    if inner.segments.is_empty() {
+                        self.issue(
+                            Severity::Error,
+                            format!("{META_FILE}#/segments"),
+                            "recording has no segments".to_string(),
+                        );
+                    }
+
+                    for (i, segment) in inner.segments.iter_mut().enumerate() {
+                        self.check_required_file(
+                            &segment.display.path,
+                            format!("/segments/{i}/display/path"),
+                        );
+                        changed |= self.check_optional_file(
+                            &mut segment.camera,
+                            |camera| &camera.path,
+                            format!("/segments/{i}/camera/path"),
+                        );
+                        changed |= self.check_optional_file(
+                            &mut segment.mic,
+                            |mic| &mic.path,
+                            format!("/segments/{i}/mic/path"),
+                        );
+                        changed |= self.check_optional_file(
+                            &mut segment.system_audio,
+                            |system_audio| &system_audio.path,
+                            format!("/segments/{i}/system_audio/path"),
+                        );
+                        changed |= self.check_optional_file(
+                            &mut segment.cursor,
+                            |cursor| cursor,
+                            format!("/segments/{i}/cursor"),
+                        );
+                    }
+
+                    if let Cursors::Correct(cursors) = &inner.cursors {
+                        let mut ids = cursors.iter().collect::<Vec<_>>();
+                        ids.sort_by_key(|(id, _)| *id);
+
+                        for (id, cursor) in ids {
+                            if self.missing(&cursor.image_path) {
+                                self.issue(
+                                    Severity::Warning,
+                                    format!("{META_FILE}#/cursors/{id}/imagePath"),
+                                    format!("'{}' does not exist", cursor.image_path),
+                                );
+                            }
+                        }
+
+                        for (i, segment) in inner.segments.iter().enumerate() {
+                            let Some(cursor_path) = &segment.cursor else {
+                                continue;
+                            };
+
+                            let events = match CursorEvents::load_from_file(
+                                &cursor_path.to_path(self.project_path),
+                            ) {
+                                Ok(events) => events,
+                                Err(e) => {
+                                    self.issue(
+                                        Severity::Warning,
+                                        format!("{META_FILE}#/segments/{i}/cursor"),
+                                        e,
+                                    );
+                                    continue;
+                                }
+                            };
+
+                            let unknown_ids = events
+                                .moves
+                                .iter()
+                                .map(|event| &event.cursor_id)
+                                .chain(events.clicks.iter().map(|event| &event.cursor_id))
+                                .filter(|id| !cursors.contains_key(*id))
+                                .collect::<BTreeSet<_>>();
+
+                            if !unknown_ids.is_empty() {
+                                self.issue(
+                                    Severity::Warning,
+                                    format!("{META_FILE}#/segments/{i}/cursor"),
+                                    format!(
+                                        "cursor events reference unknown cursor ids: {}",
+                                        unknown_ids
+                                            .into_iter()
+                                            .map(String::as_str)
+                                            .collect::<Vec<_>>()
+                                            .join(", ")
+                                    ),
+                                );
+                            }
+                        }
+                    }
+                }
+            },
+        }
+
+        changed
+    }
+
+    fn check_config(
+        &mut self,
+        config: &mut ProjectConfiguration,
+        segment_count: Option<usize>,
+    ) -> bool {
+        let mut changed = false;
+
+        for (i, annotation) in config.annotations.iter_mut().enumerate() {
+            let Err(error) = annotation.validate() else {
+                continue;
+            };
+
+            let path = format!("{CONFIG_FILE}#/annotations/{i}");
+            if matches!(error, AnnotationValidationError::MaskDataNotAllowed { .. }) {
+                if self.repairable_issue(Severity::Error, path, error.to_string()) {
+                    annotation.mask_type = None;
+                    annotation.mask_level = None;
+                    changed = true;
+                }
+            } else {
+                self.issue(Severity::Error, path, error.to_string());
+            }
+        }
+
+        if let Some(timeline) = &mut config.timeline {
+            let mut invalid = vec![];
+
+            for (i, segment) in timeline.segments.iter_mut().enumerate() {
+                let path = format!("{CONFIG_FILE}#/timeline/segments/{i}");
+
+                if let Some(count) = segment_count
+                    && segment.recording_clip as usize >= count
+                    && self.repairable_issue(
+                        Severity::Error,
+                        format!("{path}/recordingSegment"),
+                        format!(
+                            "refers to recording segment {} but the recording has {count}",
+                            segment.recording_clip
+                        ),
+                    )
+                {
+                    invalid.push(i);
+                    continue;
+                }
+
+                if (!segment.timescale.is_finite() || segment.timescale <= 0.0)
+                    && self.repairable_issue(
+                        Severity::Error,
+                        format!("{path}/timescale"),
+                        format!("timescale {} is not positive", segment.timescale),
+                    )
+                {
+                    segment.timescale = 1.0;
+                    changed = true;
+                }
+
+                if segment.end <= segment.start
+                    && self.repairable_issue(
+                        Severity::Error,
+                        path,
+                        format!(
+                            "ends at {} before it starts at {}",
+                            segment.end, segment.start
+                        ),
+                    )
+                {
+                    invalid.push(i);
+                }
+            }
+
+            changed |= remove_indices(&mut timeline.segments, &invalid);
+
+            let duration = timeline.duration();
+            changed |=
+                self.check_ranges(&mut timeline.zoom_segments, "zoomSegments", duration, |s| {
+                    (s.start, s.end)
+                });
+            changed |= self.check_ranges(
+                &mut timeline.scene_segments,
+                "sceneSegments",
+                duration,
+                |s| (s.start, s.end),
+            );
+            changed |=
+                self.check_ranges(&mut timeline.mask_segments, "maskSegments", duration, |s| {
+                    (s.start, s.end)
+                });
+            changed |=
+                self.check_ranges(&mut timeline.text_segments, "textSegments", duration, |s| {
+                    (s.start, s.end)
+                });
+        }
+
+        if let Some(count) = segment_count {
+            let mut invalid = vec![];
+
+            for (i, clip) in config.clips.iter().enumerate() {
+                if clip.index as usize >= count
+                    && self.repairable_issue(
+                        Severity::Warning,
+                        format!("{CONFIG_FILE}#/clips/{i}/index"),
+                        format!(
+                            "refers to recording segment {} but the recording has {count}",
+                            clip.index
+                        ),
+                    )
+                {
+                    invalid.push(i);
+                }
+            }
+
+            changed |= remove_indices(&mut config.clips, &invalid);
+        }
+
+        changed
+    }
+
+    /// Reports timeline items that are empty or start after the timeline ends,
+    /// returning whether any were removed.
+    fn check_ranges<T>(
+        &mut self,
+        items: &mut Vec<T>,
+        name: &str,
+        duration: f64,
+        range: impl Fn(&T) -> (f64, f64),
+    ) -> bool {
+        let mut invalid = vec![];
+
+        for (i, item) in items.iter().enumerate() {
+            let (start, end) = range(item);
+            let path = format!("{CONFIG_FILE}#/timeline/{name}/{i}");
+
+            let message = if end <= start {
+                format!("ends at {end} before it starts at {start}")
+            } else if start >= duration {
+                format!("starts at {start} after the timeline ends at {duration}")
+            } else {
+                continue;
+            };
+
+            if self.repairable_issue(Severity::Warning, path, message) {
+                invalid.push(i);
+            }
+        }
+
+        remove_indices(items, &invalid)
+    }
+}
+
+fn remove_indices<T>(items: &mut Vec<T>, indices: &[usize]) -> bool {
+    let mut i = 0;
+    items.retain(|_| {
+        let keep = !indices.contains(&i);
+        i += 1;
+        keep
+    });
+
+    !indices.is_empty()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write_project(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+
+        for (path, contents) in files {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        dir
+    }
+
+    const META: &str = r#"{
+        "pretty_name": "Cap",
+        "segments": [
+            {
+                "display": { "path": "content/segments/segment-0/display.mp4", "fps": 60 },
+                "camera": { "path": "content/segments/segment-0/camera.mp4", "fps": 30 },
+                "cursor": "content/segments/segment-0/cursor.json"
+            }
+        ],
+        "cursors": {
+            "0": { "imagePath": "content/cursors/cursor_0.png", "hotspot": { "x": 0, "y": 0 } }
+        },
+        "status": { "status": "Complete" }
+    }"#;
+
+    const CURSOR: &str = r#"{
+        "moves": [
+            { "active_modifiers": [], "cursor_id": "0", "time_ms": 0, "x": 0, "y": 0 },
+            { "active_modifiers": [], "cursor_id": "7", "time_ms": 10, "x": 0, "y": 0 }
+        ],
+        "clicks": []
+    }"#;
+
+    const CONFIG: &str = r#"{
+        "timeline": {
+            "segments": [
+                { "recordingSegment": 0, "timescale": 1.0, "start": 0.0, "end": 5.0 },
+                { "recordingSegment": 2, "timescale": 1.0, "start": 0.0, "end": 5.0 }
+            ],
+            "zoomSegments": [
+                { "start": 1.0, "end": 2.0, "amount": 1.5, "mode": "auto" },
+                { "start": 12.0, "end": 13.0, "amount": 1.5, "mode": "auto" }
+            ]
+        }
+    }"#;
+
+    fn paths(report: &ProjectReport) -> Vec<&str> {
+        report
+            .issues
+            .iter()
+            .map(|issue| issue.path.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn reports_missing_files_and_dangling_references() {
+        let dir = write_project(&[
+            (META_FILE, META),
+            (CONFIG_FILE, CONFIG),
+            ("content/segments/segment-0/display.mp4", ""),
+            ("content/segments/segment-0/cursor.json", CURSOR),
+        ]);
+
+        let report = validate_project(dir.path());
+
+        assert_eq!(
+            paths(&report),
+            [
+                "recording-meta.json#/segments/0/camera/path",
+                "recording-meta.json#/cursors/0/imagePath",
+                "recording-meta.json#/segments/0/cursor",
+                "project-config.json#/timeline/segments/1/recordingSegment",
+                "project-config.json#/timeline/zoomSegments/1",
+            ]
+        );
+        assert!(report.has_errors());
+        assert!(report.issues.iter().all(|issue| !issue.repaired));
+    }
+
+    #[test]
+    fn repairs_trivially_fixable_issues() {
+        let dir = write_project(&[
+            (META_FILE, META),
+            (CONFIG_FILE, CONFIG),
+            ("content/segments/segment-0/display.mp4", ""),
+            ("content/segments/segment-0/cursor.json", CURSOR),
+        ]);
+
+        let report = repair_project(dir.path()).unwrap();
+        assert!(!report.has_errors());
+
+        let meta = RecordingMeta::load_for_project(dir.path()).unwrap();
+        let Some(StudioRecordingMeta::MultipleSegments { inner }) = meta.studio_meta() else {
+            panic!("expected a multiple segment recording");
+        };
+        assert!(inner.segments[0].camera.is_none());
+
+        let config = ProjectConfiguration::load(dir.path()).unwrap();
+        let timeline = config.timeline.unwrap();
+        assert_eq!(timeline.segments.len(), 1);
+        assert_eq!(timeline.zoom_segments.len(), 1);
+
+        // only the issues that need a person to look at them remain
+        let report = validate_project(dir.path());
+        assert!(report.issues.iter().all(|issue| !issue.repairable));
+        assert_eq!(report.issues.len(), 2);
+    }
+
+    #[test]
+    fn missing_display_is_an_unrepairable_error() {
+        let dir = write_project(&[(META_FILE, META)]);
+
+        let report = repair_project(dir.path()).unwrap();
+        let display = report
+            .issues
+            .iter()
+            .find(|issue| issue.path == "recording-meta.json#/segments/0/display/path")
+            .unwrap();
+
+        assert_eq!(display.severity, Severity::Error);
+        assert!(!display.repaired);
+        assert!(report.has_errors());
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use specta::Type;
+
+/// Volumes at or below this are silent, matching the volume sliders
+pub const MUTED_VOLUME_DB: f32 = -30.0;
+
+/// A track's volume at a point on the timeline, relative to its overall volume
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeKeyframe {
+    /// Timeline time in seconds
+    pub time: f64,
+    pub volume_db: f32,
+}
+
+/// Volume automation for each recorded audio track, with keyframes sorted by time.
+/// Tracks without keyframes play at their overall volume throughout.
+#[derive(Type, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VolumeKeyframes {
+    #[serde(deserialize_with = "sorted_keyframes")]
+    pub mic: Vec<VolumeKeyframe>,
+    #[serde(deserialize_with = "sorted_keyframes")]
+    pub system: Vec<VolumeKeyframe>,
+}
+
+// hand-edited configs can list keyframes in any order, but `gain_at` needs them sorted
+fn sorted_keyframes<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<VolumeKeyframe>, D::Error> {
+    let mut keyframes = Vec::<VolumeKeyframe>::deserialize(deserializer)?;
+    keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+    Ok(keyframes)
+}
+
+impl VolumeKeyframe {
+    /// Linear gain of `keyframes` at timeline `time`. The volume holds before the first and after
+    /// the last keyframe, and eases between keyframes so changes never step or click.
+    pub fn gain_at(keyframes: &[Self], time: f64) -> f32 {
+        let next = keyframes.partition_point(|k| k.time <= time);
+        let prev = next.checked_sub(1).map(|i| &keyframes[i]);
+
+        match (prev, keyframes.get(next)) {
+            (None, None) => 1.0,
+            (Some(k), None) | (None, Some(k)) => k.gain(),
+            (Some(prev), Some(next)) => {
+                let t = ((time - prev.time) / (next.time - prev.time)) as f32;
+                let eased = t * t * (3.0 - 2.0 * t);
+                prev.gain() + (next.gain() - prev.gain()) * eased
+            }
+        }
+    }
+
+    fn gain(&self) -> f32 {
+        if self.volume_db <= MUTED_VOLUME_DB {
+            0.0
+        } else {
+            10f32.powf(self.volume_db / 20.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframe(time: f64, volume_db: f32) -> VolumeKeyframe {
+        VolumeKeyframe { time, volume_db }
+    }
+
+    #[test]
+    fn holds_outside_the_keyframes() {
+        let keyframes = [keyframe(2.0, -6.0), keyframe(4.0, 0.0)];
+
+        assert_eq!(VolumeKeyframe::gain_at(&[], 1.0), 1.0);
+        assert_eq!(
+            VolumeKeyframe::gain_at(&keyframes, 0.0),
+            10f32.powf(-6.0 / 20.0)
+        );
+        assert_eq!(VolumeKeyframe::gain_at(&keyframes, 10.0), 1.0);
+    }
+
+    #[test]
+    fn eases_between_keyframes_down_to_silence() {
+        let keyframes = [
+            keyframe(1.0, 0.0),
+            keyframe(2.0, MUTED_VOLUME_DB),
+            keyframe(3.0, MUTED_VOLUME_DB),
+            keyframe(4.0, 0.0),
+        ];
+        let gain_at = |time| VolumeKeyframe::gain_at(&keyframes, time);
+
+        assert_eq!(gain_at(1.5), 0.5);
+        assert!(gain_at(1.1) > 0.95 && gain_at(1.9) < 0.05);
+        assert_eq!(gain_at(2.5), 0.0);
+        assert_eq!(gain_at(4.0), 1.0);
+    }
+
+    #[test]
+    fn sorts_keyframes_when_deserializing() {
+        let keyframes: VolumeKeyframes = serde_json::from_str(
+            r#"{ "mic": [{ "time": 4.0, "volumeDb": 0.0 }, { "time": 2.0, "volumeDb": -6.0 }] }"#,
+        )
+        .unwrap();
+
+        assert_eq!(keyframes.mic, [keyframe(2.0, -6.0), keyframe(4.0, 0.0)]);
+        assert!(keyframes.system.is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+//! Streams a moving test pattern to a media server.
+//!
+//! Start a listener first, for example
+//! `ffplay -listen 1 rtmp://127.0.0.1:1935/live/cap` or `ffplay "srt://127.0.0.1:9000?mode=listener"`,
+//! then run `cargo run --example stream -- rtmp://127.0.0.1:1935/live/cap`.
+
+use cap_media_info::{Pixel, VideoInfo};
+use cap_recording::{
+    ChannelVideoSource, ChannelVideoSourceConfig, FFmpegVideoFrame, OutputPipeline, StreamMuxer,
+    StreamMuxerConfig,
+};
+use cap_timestamp::Timestamp;
+use std::time::{Duration, Instant};
+use tracing::*;
+
+const WIDTH: u32 = 1280;
+const HEIGHT: u32 = 720;
+const FPS: u32 = 30;
+
+#[tokio::main]
+pub async fn main() {
+    tracing_subscriber::fmt::init();
+    ffmpeg::init().unwrap();
+
+    let url = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "rtmp://127.0.0.1:1935/live/cap".to_string());
+    let seconds = std::env::args()
+        .nth(2)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(20u64);
+
+    let video_info = VideoInfo::from_raw_ffmpeg(Pixel::YUV420P, WIDTH, HEIGHT, FPS);
+    let (frame_tx, frame_rx) = flume::bounded(8);
+
+    let config = StreamMuxerConfig::new(&url);
+    let stats = config.stats.clone();
+
+    let pipeline = OutputPipeline::builder(url.clone().into())
+        .with_video::<ChannelVideoSource<FFmpegVideoFrame>>(ChannelVideoSourceConfig::new(
+            video_info, frame_rx,
+        ))
+        .build::<StreamMuxer>(config)
+        .await
+        .unwrap();
+
+    info!("Streaming to {url} for {seconds}s");
+
+    let start = Instant::now();
+    let mut index = 0u32;
+    while start.elapsed() < Duration::from_secs(seconds) {
+        let mut frame = ffmpeg::frame::Video::new(Pixel::YUV420P, WIDTH, HEIGHT);
+        draw_test_pattern(&mut frame, index);
+
+        let _ = frame_tx
+            .send_async(FFmpegVideoFrame {
+                inner: frame,
+                timestamp: Timestamp::Instant(Instant::now()),
+            })
+            .await;
+
+        index += 1;
+        tokio::time::sleep_until(
+            (start + Duration::from_secs_f64(index as f64 / FPS as f64)).into(),
+        )
+        .await;
+
+        if index.is_multiple_of(FPS * 5) {
+            info!(
+                connected = stats.connected(),
+                sent = stats.sent_video_frames(),
+                dropped = stats.dropped_video_frames(),
+                reconnects = stats.reconnects(),
+                "Stream stats"
+            );
+        }
+    }
+
+    drop(frame_tx);
+    pipeline.stop().await.unwrap();
+}
+
+/// Vertical bars scrolling sideways, so dropped or frozen frames are easy to spot
+fn draw_test_pattern(frame: &mut ffmpeg::frame::Video, index: u32) {
+    let stride = frame.stride(0);
+    let luma = frame.data_mut(0);
+    for y in 0..HEIGHT as usize {
+        for x in 0..WIDTH as usize {
+            luma[y * stride + x] = (((x as u32 + index * 8) / 64 % 2) * 160 + 48) as u8;
+        }
+    }
+
+    for plane in 1..3 {
+        frame.data_mut(plane).fill(128);
+    }
+}
@@ -167,6 +167,7 @@ impl MakeCapturePipeline for screen_capture::Direct3DCapture {
                     output_size,
                     shared_pause_state,
                     disk_space_callback: None,
+                    retention: None,
                 })
                 .await
         } else {
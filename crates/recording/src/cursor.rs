@@ -1,6 +1,6 @@
 use cap_cursor_capture::CursorCropBounds;
 use cap_cursor_info::CursorShape;
-use cap_project::{CursorClickEvent, CursorEvents, CursorMoveEvent, XY};
+use cap_project::{CursorClickEvent, CursorEvents, CursorMoveEvent, KeyboardEvent, XY};
 use cap_timestamp::Timestamps;
 use futures::{FutureExt, future::Shared};
 use std::{
@@ -28,6 +28,7 @@ pub struct CursorActorResponse {
     pub next_cursor_id: u32,
     pub moves: Vec<CursorMoveEvent>,
     pub clicks: Vec<CursorClickEvent>,
+    pub keys: Vec<KeyboardEvent>,
 }
 
 pub struct CursorActor {
@@ -43,10 +44,11 @@ impl CursorActor {
 
 const CURSOR_FLUSH_INTERVAL_SECS: u64 = 5;
 
-fn flush_cursor_data(output_path: &Path, moves: &[CursorMoveEvent], clicks: &[CursorClickEvent]) {
+fn flush_cursor_data(output_path: &Path, response: &CursorActorResponse) {
     let events = CursorEvents {
-        clicks: clicks.to_vec(),
-        moves: moves.to_vec(),
+        clicks: response.clicks.clone(),
+        moves: response.moves.clone(),
+        keys: response.keys.clone(),
     };
     if let Ok(json) = serde_json::to_string_pretty(&events)
         && let Err(e) = std::fs::write(output_path, json)
@@ -68,6 +70,7 @@ pub fn spawn_cursor_recorder(
     next_cursor_id: u32,
     start_time: Timestamps,
     output_path: Option<PathBuf>,
+    capture_keyboard: bool,
 ) -> CursorActor {
     use cap_utils::spawn_actor;
     use device_query::{DeviceQuery, DeviceState};
@@ -83,6 +86,9 @@ pub fn spawn_cursor_recorder(
     spawn_actor(async move {
         let device_state = DeviceState::new();
         let mut last_mouse_state = device_state.get_mouse();
+        let mut last_keys: Vec<String> = vec![];
+        // keys whose key down was recorded, so their key up is recorded too
+        let mut recorded_keys: Vec<String> = vec![];
 
         let mut last_position = cap_cursor_capture::RawCursorPosition::get();
 
@@ -93,6 +99,7 @@ pub fn spawn_cursor_recorder(
             next_cursor_id,
             moves: vec![],
             clicks: vec![],
+            keys: vec![],
         };
 
         let mut last_flush = Instant::now();
@@ -203,10 +210,61 @@ pub fn spawn_cursor_recorder(
 
             last_mouse_state = mouse_state;
 
+            if capture_keyboard {
+                let keys = device_state
+                    .get_keys()
+                    .iter()
+                    .filter_map(|key| key_name(&format!("{key:?}")))
+                    .fold(Vec::<String>::new(), |mut keys, key| {
+                        if !keys.contains(&key) {
+                            keys.push(key);
+                        }
+                        keys
+                    });
+                let active_modifiers = keys
+                    .iter()
+                    .filter(|key| cap_project::MODIFIER_KEYS.contains(&key.as_str()))
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                for key in last_keys.iter().filter(|key| !keys.contains(key)) {
+                    let Some(i) = recorded_keys.iter().position(|k| k == key) else {
+                        continue;
+                    };
+                    recorded_keys.remove(i);
+
+                    response.keys.push(KeyboardEvent {
+                        active_modifiers: active_modifiers.clone(),
+                        key: key.clone(),
+                        time_ms: elapsed,
+                        down: false,
+                    });
+                }
+
+                for key in keys.iter().filter(|key| !last_keys.contains(key)) {
+                    let event = KeyboardEvent {
+                        active_modifiers: active_modifiers.clone(),
+                        key: key.clone(),
+                        time_ms: elapsed,
+                        down: true,
+                    };
+
+                    // typed text is never recorded, only shortcuts and special keys
+                    if event.is_private() {
+                        continue;
+                    }
+
+                    recorded_keys.push(key.clone());
+                    response.keys.push(event);
+                }
+
+                last_keys = keys;
+            }
+
             if let Some(ref path) = output_path
                 && last_flush.elapsed() >= flush_interval
             {
-                flush_cursor_data(path, &response.moves, &response.clicks);
+                flush_cursor_data(path, &response);
                 last_flush = Instant::now();
             }
         }
@@ -214,7 +272,7 @@ pub fn spawn_cursor_recorder(
         info!("cursor recorder done");
 
         if let Some(ref path) = output_path {
-            flush_cursor_data(path, &response.moves, &response.clicks);
+            flush_cursor_data(path, &response);
         }
 
         let _ = tx.send(response);
@@ -226,6 +284,44 @@ pub fn spawn_cursor_recorder(
     }
 }
 
+/// Maps a `device_query` keycode to the key name stored in [`KeyboardEvent`],
+/// merging the left and right variants of modifiers.
+fn key_name(keycode: &str) -> Option<String> {
+    let name = match keycode {
+        "LControl" | "RControl" => "Control",
+        "LAlt" | "RAlt" | "LOption" | "ROption" => "Alt",
+        "LShift" | "RShift" => "Shift",
+        "LMeta" | "RMeta" | "Command" | "RCommand" => "Meta",
+        "Grave" => "`",
+        "Minus" | "NumpadSubtract" => "-",
+        "Equal" | "NumpadEquals" => "=",
+        "LeftBracket" => "[",
+        "RightBracket" => "]",
+        "BackSlash" => "\\",
+        "Semicolon" => ";",
+        "Apostrophe" => "'",
+        "Comma" => ",",
+        "Dot" | "NumpadDecimal" => ".",
+        "Slash" | "NumpadDivide" => "/",
+        "NumpadAdd" => "+",
+        "NumpadMultiply" => "*",
+        "NumpadEnter" => "Enter",
+        "" => return None,
+        keycode => {
+            return Some(
+                keycode
+                    .strip_prefix("Numpad")
+                    .or_else(|| keycode.strip_prefix("Key"))
+                    .filter(|digit| digit.len() == 1)
+                    .unwrap_or(keycode)
+                    .to_string(),
+            );
+        }
+    };
+
+    Some(name.to_string())
+}
+
 #[derive(Debug)]
 struct CursorData {
     image: Vec<u8>,
@@ -7,6 +7,7 @@ pub mod fragmentation;
 pub mod instant_recording;
 mod output_pipeline;
 pub mod recovery;
+pub mod replay_buffer;
 mod resolution_limits;
 pub mod screenshot;
 pub mod sources;
@@ -38,6 +39,8 @@ pub enum RecordingMode {
     Studio,
     Instant,
     Screenshot,
+    /// Keeps the last few minutes in a rolling buffer, saved as a studio project on stop
+    Replay,
 }
 
 #[derive(specta::Type, Serialize, Deserialize, Clone, Debug)]
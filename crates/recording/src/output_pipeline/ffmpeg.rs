@@ -235,6 +235,8 @@ pub struct SegmentedAudioMuxer {
 pub struct SegmentedAudioMuxerConfig {
     pub segment_duration: Duration,
     pub shared_pause_state: Option<SharedPauseState>,
+    /// Deletes the oldest fragments once the newer ones cover at least this long
+    pub retention: Option<Duration>,
 }
 
 impl Default for SegmentedAudioMuxerConfig {
@@ -242,6 +244,7 @@ impl Default for SegmentedAudioMuxerConfig {
         Self {
             segment_duration: Duration::from_secs(3),
             shared_pause_state: None,
+            retention: None,
         }
     }
 }
@@ -263,13 +266,13 @@ impl Muxer for SegmentedAudioMuxer {
         let audio_config =
             audio_config.ok_or_else(|| anyhow!("No audio configuration provided"))?;
 
+        let mut encoder =
+            SegmentedAudioEncoder::init(output_path, audio_config, config.segment_duration)
+                .map_err(|e| anyhow!("Failed to initialize segmented audio encoder: {e}"))?;
+        encoder.set_retention(config.retention);
+
         Ok(Self {
-            encoder: SegmentedAudioEncoder::init(
-                output_path,
-                audio_config,
-                config.segment_duration,
-            )
-            .map_err(|e| anyhow!("Failed to initialize segmented audio encoder: {e}"))?,
+            encoder,
             pause: config.shared_pause_state,
         })
     }
@@ -577,6 +580,7 @@ impl SegmentedVideoMuxer {
             preset: self.preset,
             bpp: H264EncoderBuilder::QUALITY_BPP,
             output_size: self.output_size,
+            retention: None,
         };
 
         let encoder =
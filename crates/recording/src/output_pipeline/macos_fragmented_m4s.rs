@@ -105,6 +105,7 @@ pub struct MacOSFragmentedM4SMuxer {
     frame_drops: FrameDropTracker,
     started: bool,
     disk_space_callback: Option<DiskSpaceCallback>,
+    retention: Option<Duration>,
 }
 
 pub struct MacOSFragmentedM4SMuxerConfig {
@@ -113,6 +114,8 @@ pub struct MacOSFragmentedM4SMuxerConfig {
     pub output_size: Option<(u32, u32)>,
     pub shared_pause_state: Option<SharedPauseState>,
     pub disk_space_callback: Option<DiskSpaceCallback>,
+    /// Deletes the oldest segments once the newer ones cover at least this long
+    pub retention: Option<Duration>,
 }
 
 impl Default for MacOSFragmentedM4SMuxerConfig {
@@ -123,6 +126,7 @@ impl Default for MacOSFragmentedM4SMuxerConfig {
             output_size: None,
             shared_pause_state: None,
             disk_space_callback: None,
+            retention: None,
         }
     }
 }
@@ -162,6 +166,7 @@ impl Muxer for MacOSFragmentedM4SMuxer {
             frame_drops: FrameDropTracker::new(),
             started: false,
             disk_space_callback: config.disk_space_callback,
+            retention: config.retention,
         })
     }
 
@@ -245,6 +250,7 @@ impl MacOSFragmentedM4SMuxer {
             preset: self.preset,
             bpp: H264EncoderBuilder::QUALITY_BPP,
             output_size: self.output_size,
+            retention: self.retention,
         };
 
         let mut encoder =
@@ -741,6 +747,7 @@ impl MacOSFragmentedM4SCameraMuxer {
             preset: self.preset,
             bpp: H264EncoderBuilder::QUALITY_BPP,
             output_size: self.output_size,
+            retention: None,
         };
 
         let mut encoder =
@@ -3,12 +3,16 @@ mod core;
 pub mod ffmpeg;
 #[cfg(target_os = "macos")]
 mod macos_fragmented_m4s;
+mod stream;
+mod tee;
 
 pub use async_camera::*;
 pub use core::*;
 pub use ffmpeg::*;
 #[cfg(target_os = "macos")]
 pub use macos_fragmented_m4s::*;
+pub use stream::*;
+pub use tee::*;
 
 #[cfg(target_os = "macos")]
 mod macos;
@@ -0,0 +1,639 @@
+use crate::{
+    SharedPauseState, TaskPool,
+    output_pipeline::{AudioFrame, AudioMuxer, FFmpegVideoFrame, Muxer, VideoMuxer},
+};
+use anyhow::{Context, anyhow};
+use cap_enc_ffmpeg::{aac::AACEncoder, h264::*};
+use cap_media_info::{AudioInfo, VideoInfo};
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{Receiver, SyncSender, TrySendError, sync_channel},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+use tracing::*;
+
+/// How long a network read or write may block before the connection counts as lost
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Frames buffered for the network thread before new ones are dropped
+const STREAM_BUFFER_SIZE: usize = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamProtocol {
+    /// FLV over RTMP(S)
+    Rtmp,
+    /// MPEG-TS over SRT
+    Srt,
+}
+
+impl StreamProtocol {
+    pub fn from_url(url: &str) -> Option<Self> {
+        let (scheme, _) = url.split_once("://")?;
+
+        match scheme.to_ascii_lowercase().as_str() {
+            "rtmp" | "rtmps" => Some(Self::Rtmp),
+            "srt" => Some(Self::Srt),
+            _ => None,
+        }
+    }
+
+    fn format_name(self) -> &'static str {
+        match self {
+            Self::Rtmp => "flv",
+            Self::Srt => "mpegts",
+        }
+    }
+}
+
+/// Exponential backoff between attempts to reconnect a dropped stream
+#[derive(Debug, Clone, Copy)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    /// Give up after this many failed attempts in a row, or never if `None`
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// The delay before retrying after `attempt` failed attempts in a row
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX))
+            .min(self.max_delay)
+    }
+}
+
+/// Counters for a live stream, shared between the muxer and whoever is showing its health
+#[derive(Debug, Default)]
+pub struct StreamStats {
+    connected: AtomicBool,
+    reconnects: AtomicU64,
+    sent_video_frames: AtomicU64,
+    dropped_video_frames: AtomicU64,
+    sent_audio_frames: AtomicU64,
+    dropped_audio_frames: AtomicU64,
+}
+
+impl StreamStats {
+    pub fn connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects.load(Ordering::Relaxed)
+    }
+
+    pub fn sent_video_frames(&self) -> u64 {
+        self.sent_video_frames.load(Ordering::Relaxed)
+    }
+
+    /// Video frames that were never sent, because the network couldn't keep up or the stream
+    /// was disconnected
+    pub fn dropped_video_frames(&self) -> u64 {
+        self.dropped_video_frames.load(Ordering::Relaxed)
+    }
+
+    pub fn sent_audio_frames(&self) -> u64 {
+        self.sent_audio_frames.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped_audio_frames(&self) -> u64 {
+        self.dropped_audio_frames.load(Ordering::Relaxed)
+    }
+
+    fn record(&self, video: bool, sent: bool) {
+        let counter = match (video, sent) {
+            (true, true) => &self.sent_video_frames,
+            (true, false) => &self.dropped_video_frames,
+            (false, true) => &self.sent_audio_frames,
+            (false, false) => &self.dropped_audio_frames,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub struct StreamMuxerConfig {
+    /// `rtmp://`, `rtmps://` or `srt://` URL to publish to
+    pub url: String,
+    pub preset: H264Preset,
+    pub bpp: f32,
+    pub output_size: Option<(u32, u32)>,
+    pub reconnect: ReconnectPolicy,
+    pub stats: Arc<StreamStats>,
+    pub shared_pause_state: Option<SharedPauseState>,
+}
+
+impl StreamMuxerConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            preset: H264Preset::Ultrafast,
+            bpp: H264EncoderBuilder::QUALITY_BPP,
+            output_size: None,
+            reconnect: ReconnectPolicy::default(),
+            stats: Arc::default(),
+            shared_pause_state: None,
+        }
+    }
+}
+
+enum StreamMessage {
+    Video(ffmpeg::frame::Video, Duration),
+    Audio(ffmpeg::frame::Audio, Duration),
+}
+
+impl StreamMessage {
+    fn is_video(&self) -> bool {
+        matches!(self, Self::Video(..))
+    }
+}
+
+/// Publishes H264/AAC to a media server over RTMP or SRT.
+///
+/// Encoding and network writes happen on a dedicated thread, so a slow connection drops frames
+/// instead of stalling the pipeline. If the connection is lost the encoders are rebuilt on a new
+/// connection, which starts on a keyframe. The output path passed to the pipeline isn't used.
+pub struct StreamMuxer {
+    tx: Option<SyncSender<StreamMessage>>,
+    thread: Option<JoinHandle<()>>,
+    pause: SharedPauseState,
+    stats: Arc<StreamStats>,
+}
+
+impl Muxer for StreamMuxer {
+    type Config = StreamMuxerConfig;
+
+    async fn setup(
+        config: Self::Config,
+        _: PathBuf,
+        video_config: Option<VideoInfo>,
+        audio_config: Option<AudioInfo>,
+        pause_flag: Arc<AtomicBool>,
+        _: &mut TaskPool,
+    ) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let protocol = StreamProtocol::from_url(&config.url)
+            .ok_or_else(|| anyhow!("Unsupported stream URL '{}'", config.url))?;
+        if video_config.is_none() && audio_config.is_none() {
+            return Err(anyhow!("Nothing to stream"));
+        }
+
+        ffmpeg::format::network::init();
+
+        let settings = StreamSettings {
+            url: config.url,
+            protocol,
+            video_config,
+            audio_config,
+            preset: config.preset,
+            bpp: config.bpp,
+            output_size: config.output_size,
+        };
+
+        // connect up front so a bad URL or unreachable server fails the pipeline immediately
+        let (settings, connection) = tokio::task::spawn_blocking(move || {
+            let connection = StreamConnection::open(&settings);
+            (settings, connection)
+        })
+        .await?;
+        let connection = connection.context("connect")?;
+        config.stats.connected.store(true, Ordering::Relaxed);
+        info!(url = %settings.url, ?protocol, "Connected to stream");
+
+        let (tx, rx) = sync_channel(STREAM_BUFFER_SIZE);
+        let stats = config.stats.clone();
+        let reconnect = config.reconnect;
+        let thread = std::thread::Builder::new()
+            .name("stream-muxer".to_string())
+            .spawn(move || {
+                run_stream(
+                    connection,
+                    || StreamConnection::open(&settings),
+                    reconnect,
+                    stats,
+                    rx,
+                )
+            })?;
+
+        Ok(Self {
+            tx: Some(tx),
+            thread: Some(thread),
+            pause: config
+                .shared_pause_state
+                .unwrap_or_else(|| SharedPauseState::new(pause_flag)),
+            stats: config.stats,
+        })
+    }
+
+    fn stop(&mut self) {
+        self.tx.take();
+    }
+
+    fn finish(&mut self, _: Duration) -> anyhow::Result<anyhow::Result<()>> {
+        self.tx.take();
+
+        if let Some(thread) = self.thread.take()
+            && thread.join().is_err()
+        {
+            return Ok(Err(anyhow!("Stream thread panicked")));
+        }
+
+        let stats = &self.stats;
+        info!(
+            sent_video_frames = stats.sent_video_frames(),
+            dropped_video_frames = stats.dropped_video_frames(),
+            sent_audio_frames = stats.sent_audio_frames(),
+            dropped_audio_frames = stats.dropped_audio_frames(),
+            reconnects = stats.reconnects(),
+            "Stream finished"
+        );
+
+        Ok(Ok(()))
+    }
+}
+
+impl StreamMuxer {
+    fn send(&mut self, message: StreamMessage) {
+        let Some(tx) = &self.tx else {
+            return;
+        };
+
+        match tx.try_send(message) {
+            Ok(()) => {}
+            Err(TrySendError::Full(message)) => self.stats.record(message.is_video(), false),
+            Err(TrySendError::Disconnected(message)) => {
+                self.stats.record(message.is_video(), false);
+                self.tx = None;
+            }
+        }
+    }
+}
+
+impl VideoMuxer for StreamMuxer {
+    type VideoFrame = FFmpegVideoFrame;
+
+    fn send_video_frame(
+        &mut self,
+        frame: Self::VideoFrame,
+        timestamp: Duration,
+    ) -> anyhow::Result<()> {
+        if let Some(timestamp) = self.pause.adjust(timestamp)? {
+            self.send(StreamMessage::Video(frame.inner, timestamp));
+        }
+
+        Ok(())
+    }
+}
+
+impl AudioMuxer for StreamMuxer {
+    fn send_audio_frame(&mut self, frame: AudioFrame, timestamp: Duration) -> anyhow::Result<()> {
+        if let Some(timestamp) = self.pause.adjust(timestamp)? {
+            self.send(StreamMessage::Audio(frame.inner, timestamp));
+        }
+
+        Ok(())
+    }
+}
+
+struct StreamSettings {
+    url: String,
+    protocol: StreamProtocol,
+    video_config: Option<VideoInfo>,
+    audio_config: Option<AudioInfo>,
+    preset: H264Preset,
+    bpp: f32,
+    output_size: Option<(u32, u32)>,
+}
+
+/// Somewhere [`run_stream`] writes to, which is replaced when a write fails
+trait StreamSink {
+    fn write(&mut self, message: StreamMessage) -> anyhow::Result<()>;
+
+    fn finish(self);
+}
+
+struct StreamConnection {
+    output: ffmpeg::format::context::Output,
+    video: Option<H264Encoder>,
+    audio: Option<AACEncoder>,
+}
+
+impl StreamConnection {
+    fn open(settings: &StreamSettings) -> anyhow::Result<Self> {
+        let mut options = ffmpeg::Dictionary::new();
+        options.set("rw_timeout", &IO_TIMEOUT.as_micros().to_string());
+
+        let mut output = ffmpeg::format::output_as_with(
+            &settings.url,
+            settings.protocol.format_name(),
+            options,
+        )?;
+
+        let video = settings
+            .video_config
+            .map(|video_config| {
+                let mut builder = H264Encoder::builder(video_config)
+                    .with_preset(settings.preset)
+                    .with_bpp(settings.bpp);
+                if let Some((width, height)) = settings.output_size {
+                    builder = builder.with_output_size(width, height)?;
+                }
+                if settings.protocol == StreamProtocol::Rtmp {
+                    builder = builder.with_global_header();
+                }
+                builder.build(&mut output)
+            })
+            .transpose()
+            .context("video encoder")?;
+
+        let audio = settings
+            .audio_config
+            .map(|config| AACEncoder::init(config, &mut output))
+            .transpose()
+            .context("audio encoder")?;
+
+        output.write_header()?;
+
+        Ok(Self {
+            output,
+            video,
+            audio,
+        })
+    }
+}
+
+impl StreamSink for StreamConnection {
+    fn write(&mut self, message: StreamMessage) -> anyhow::Result<()> {
+        match message {
+            StreamMessage::Video(frame, timestamp) => {
+                if let Some(video) = &mut self.video {
+                    video.queue_frame(frame, timestamp, &mut self.output)?;
+                }
+            }
+            StreamMessage::Audio(frame, timestamp) => {
+                if let Some(audio) = &mut self.audio {
+                    audio.send_frame(frame, timestamp, &mut self.output)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self) {
+        if let Some(video) = &mut self.video
+            && let Err(e) = video.flush(&mut self.output)
+        {
+            warn!("Failed to flush stream video encoder: {e}");
+        }
+        if let Some(audio) = &mut self.audio
+            && let Err(e) = audio.flush(&mut self.output)
+        {
+            warn!("Failed to flush stream audio encoder: {e}");
+        }
+        if let Err(e) = self.output.write_trailer() {
+            warn!("Failed to write stream trailer: {e}");
+        }
+    }
+}
+
+fn run_stream<S: StreamSink>(
+    connection: S,
+    mut open: impl FnMut() -> anyhow::Result<S>,
+    reconnect: ReconnectPolicy,
+    stats: Arc<StreamStats>,
+    rx: Receiver<StreamMessage>,
+) {
+    let mut connection = Some(connection);
+    let mut failed_attempts = 0;
+    let mut retry_at = Instant::now();
+
+    while let Ok(message) = rx.recv() {
+        if connection.is_none() && Instant::now() >= retry_at {
+            match open() {
+                Ok(new_connection) => {
+                    info!(failed_attempts, "Reconnected to stream");
+                    stats.connected.store(true, Ordering::Relaxed);
+                    stats.reconnects.fetch_add(1, Ordering::Relaxed);
+                    failed_attempts = 0;
+                    connection = Some(new_connection);
+                }
+                Err(e) => {
+                    failed_attempts += 1;
+                    if reconnect
+                        .max_attempts
+                        .is_some_and(|max| failed_attempts >= max)
+                    {
+                        error!("Giving up on stream after {failed_attempts} attempts: {e:#}");
+                        stats.record(message.is_video(), false);
+                        return;
+                    }
+
+                    let delay = reconnect.delay(failed_attempts - 1);
+                    warn!("Failed to reconnect to stream, retrying in {delay:?}: {e:#}");
+                    retry_at = Instant::now() + delay;
+                }
+            }
+        }
+
+        let Some(active) = &mut connection else {
+            stats.record(message.is_video(), false);
+            continue;
+        };
+
+        let is_video = message.is_video();
+        let sent = match active.write(message) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Lost connection to stream: {e:#}");
+                stats.connected.store(false, Ordering::Relaxed);
+                connection = None;
+                retry_at = Instant::now() + reconnect.delay(0);
+                false
+            }
+        };
+        stats.record(is_video, sent);
+    }
+
+    if let Some(connection) = connection {
+        connection.finish();
+    }
+    stats.connected.store(false, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_protocol_from_url() {
+        assert_eq!(
+            StreamProtocol::from_url("rtmp://localhost/live/cap"),
+            Some(StreamProtocol::Rtmp)
+        );
+        assert_eq!(
+            StreamProtocol::from_url("RTMPS://a.rtmp.youtube.com/live2/key"),
+            Some(StreamProtocol::Rtmp)
+        );
+        assert_eq!(
+            StreamProtocol::from_url("srt://127.0.0.1:9000?mode=caller"),
+            Some(StreamProtocol::Srt)
+        );
+        assert_eq!(StreamProtocol::from_url("https://cap.so"), None);
+        assert_eq!(StreamProtocol::from_url("/tmp/out.flv"), None);
+    }
+
+    struct FakeSink {
+        fail_writes: bool,
+        writes: Arc<AtomicU64>,
+    }
+
+    impl StreamSink for FakeSink {
+        fn write(&mut self, _: StreamMessage) -> anyhow::Result<()> {
+            if self.fail_writes {
+                return Err(anyhow!("Broken pipe"));
+            }
+            self.writes.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        fn finish(self) {}
+    }
+
+    fn video_message() -> StreamMessage {
+        StreamMessage::Video(ffmpeg::frame::Video::empty(), Duration::ZERO)
+    }
+
+    fn audio_message() -> StreamMessage {
+        StreamMessage::Audio(ffmpeg::frame::Audio::empty(), Duration::ZERO)
+    }
+
+    #[test]
+    fn counts_frames_dropped_while_the_network_thread_is_behind() {
+        let (tx, rx) = sync_channel(1);
+        let stats = Arc::new(StreamStats::default());
+        let mut muxer = StreamMuxer {
+            tx: Some(tx),
+            thread: None,
+            pause: SharedPauseState::new(Arc::default()),
+            stats: stats.clone(),
+        };
+
+        muxer.send(video_message());
+        muxer.send(video_message());
+        muxer.send(audio_message());
+        assert_eq!(stats.dropped_video_frames(), 1);
+        assert_eq!(stats.dropped_audio_frames(), 1);
+
+        // frames only count as sent once the network thread writes them
+        assert_eq!(stats.sent_video_frames(), 0);
+
+        drop(rx);
+        muxer.send(video_message());
+        assert_eq!(stats.dropped_video_frames(), 2);
+        assert!(muxer.tx.is_none());
+    }
+
+    #[test]
+    fn reconnects_after_a_failed_write() {
+        let (tx, rx) = sync_channel(8);
+        for _ in 0..3 {
+            tx.send(video_message()).unwrap();
+        }
+        tx.send(audio_message()).unwrap();
+        drop(tx);
+
+        let stats = Arc::new(StreamStats::default());
+        let writes = Arc::new(AtomicU64::new(0));
+        let mut opened = 0;
+        run_stream(
+            FakeSink {
+                fail_writes: true,
+                writes: writes.clone(),
+            },
+            || {
+                opened += 1;
+                Ok(FakeSink {
+                    fail_writes: false,
+                    writes: writes.clone(),
+                })
+            },
+            ReconnectPolicy {
+                initial_delay: Duration::ZERO,
+                ..Default::default()
+            },
+            stats.clone(),
+            rx,
+        );
+
+        assert_eq!(opened, 1);
+        assert_eq!(stats.reconnects(), 1);
+        assert_eq!(writes.load(Ordering::Relaxed), 3);
+        assert_eq!(stats.dropped_video_frames(), 1);
+        assert_eq!(stats.sent_video_frames(), 2);
+        assert_eq!(stats.sent_audio_frames(), 1);
+        assert!(!stats.connected());
+    }
+
+    #[test]
+    fn gives_up_after_max_reconnect_attempts() {
+        let (tx, rx) = sync_channel(8);
+        for _ in 0..4 {
+            tx.send(video_message()).unwrap();
+        }
+        drop(tx);
+
+        let stats = Arc::new(StreamStats::default());
+        let mut opened = 0;
+        run_stream(
+            FakeSink {
+                fail_writes: true,
+                writes: Arc::default(),
+            },
+            || {
+                opened += 1;
+                Err(anyhow!("Connection refused"))
+            },
+            ReconnectPolicy {
+                initial_delay: Duration::ZERO,
+                max_attempts: Some(2),
+                ..Default::default()
+            },
+            stats.clone(),
+            rx,
+        );
+
+        assert_eq!(opened, 2);
+        assert_eq!(stats.reconnects(), 0);
+        assert_eq!(stats.dropped_video_frames(), 3);
+        assert_eq!(stats.sent_video_frames(), 0);
+    }
+
+    #[test]
+    fn reconnect_delay_backs_off_up_to_max() {
+        let policy = ReconnectPolicy::default();
+
+        assert_eq!(policy.delay(0), Duration::from_secs(1));
+        assert_eq!(policy.delay(1), Duration::from_secs(2));
+        assert_eq!(policy.delay(4), Duration::from_secs(16));
+        assert_eq!(policy.delay(5), Duration::from_secs(30));
+        assert_eq!(policy.delay(100), Duration::from_secs(30));
+    }
+}
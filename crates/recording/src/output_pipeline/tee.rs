@@ -0,0 +1,534 @@
+use crate::{
+    SharedPauseState, TaskPool,
+    output_pipeline::{AudioFrame, AudioMuxer, FFmpegVideoFrame, Muxer, VideoMuxer},
+};
+use anyhow::{Context, anyhow};
+use cap_enc_ffmpeg::{PacketTap, aac::AACEncoder, h264::*};
+use cap_media_info::{AudioInfo, FFRational, VideoInfo};
+use ffmpeg::Packet;
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{Receiver, SyncSender, TrySendError, sync_channel},
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+use tracing::*;
+
+/// Packets buffered for each sink before new ones are dropped
+const SINK_BUFFER_SIZE: usize = 240;
+
+/// A secondary output of a [`TeeMuxer`], written with the packets encoded for the primary file
+pub struct TeeSink {
+    /// File path or URL to write to
+    pub url: String,
+    /// FFmpeg muxer to use, guessed from `url` if not set
+    pub format: Option<String>,
+    pub options: Vec<(String, String)>,
+    pub stats: Arc<TeeSinkStats>,
+}
+
+impl TeeSink {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            format: None,
+            options: vec![],
+            stats: Arc::default(),
+        }
+    }
+
+    /// Publishes FLV to an RTMP server. Unlike [`StreamMuxer`](super::StreamMuxer) the sink
+    /// doesn't reconnect, it stops and reports the error.
+    pub fn rtmp(url: impl Into<String>) -> Self {
+        Self::new(url)
+            .with_format("flv")
+            .with_option("rw_timeout", "5000000")
+    }
+
+    /// Writes a live HLS playlist, keeping only the most recent segments on disk
+    pub fn hls(playlist_path: impl Into<String>) -> Self {
+        Self::new(playlist_path)
+            .with_format("hls")
+            .with_option("hls_time", "2")
+            .with_option("hls_list_size", "6")
+            .with_option("hls_flags", "delete_segments")
+    }
+
+    pub fn with_format(mut self, format: impl Into<String>) -> Self {
+        self.format = Some(format.into());
+        self
+    }
+
+    pub fn with_option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.push((key.into(), value.into()));
+        self
+    }
+}
+
+/// Counters for a [`TeeSink`], and the error it stopped with if it failed
+#[derive(Debug, Default)]
+pub struct TeeSinkStats {
+    written_packets: AtomicU64,
+    dropped_packets: AtomicU64,
+    error: Mutex<Option<String>>,
+}
+
+impl TeeSinkStats {
+    pub fn written_packets(&self) -> u64 {
+        self.written_packets.load(Ordering::Relaxed)
+    }
+
+    /// Packets skipped because the sink couldn't keep up. After a dropped video packet the sink
+    /// skips ahead to the next keyframe, so those are counted too.
+    pub fn dropped_packets(&self) -> u64 {
+        self.dropped_packets.load(Ordering::Relaxed)
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().ok().and_then(|error| error.clone())
+    }
+
+    fn fail(&self, url: &str, error: anyhow::Error) {
+        error!(
+            url,
+            "Tee sink failed, primary recording continues: {error:#}"
+        );
+        if let Ok(mut slot) = self.error.lock() {
+            *slot = Some(format!("{error:#}"));
+        }
+    }
+}
+
+pub struct TeeMuxerConfig {
+    pub sinks: Vec<TeeSink>,
+    /// Write the primary file as fragmented MP4, so it stays playable if recording is interrupted
+    pub fragmented: bool,
+    pub preset: H264Preset,
+    pub bpp: f32,
+    pub output_size: Option<(u32, u32)>,
+    pub shared_pause_state: Option<SharedPauseState>,
+}
+
+impl Default for TeeMuxerConfig {
+    fn default() -> Self {
+        Self {
+            sinks: vec![],
+            fragmented: true,
+            preset: H264Preset::Ultrafast,
+            bpp: H264EncoderBuilder::QUALITY_BPP,
+            output_size: None,
+            shared_pause_state: None,
+        }
+    }
+}
+
+struct SinkThread {
+    url: String,
+    stats: Arc<TeeSinkStats>,
+    handle: JoinHandle<()>,
+}
+
+/// Encodes once into an MP4 file and copies the encoded packets to any number of [`TeeSink`]s.
+///
+/// Each sink is written on its own thread. A sink that fails to open or write is reported through
+/// its [`TeeSinkStats`] and dropped, without affecting the primary file or the other sinks.
+pub struct TeeMuxer {
+    output: ffmpeg::format::context::Output,
+    video_encoder: Option<H264Encoder>,
+    audio_encoder: Option<AACEncoder>,
+    sinks: Vec<SinkThread>,
+    pause: SharedPauseState,
+}
+
+impl Muxer for TeeMuxer {
+    type Config = TeeMuxerConfig;
+
+    async fn setup(
+        config: Self::Config,
+        output_path: PathBuf,
+        video_config: Option<VideoInfo>,
+        audio_config: Option<AudioInfo>,
+        pause_flag: Arc<AtomicBool>,
+        _: &mut TaskPool,
+    ) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut output = if config.fragmented {
+            let mut options = ffmpeg::Dictionary::new();
+            options.set("movflags", "frag_keyframe+empty_moov+default_base_moof");
+            ffmpeg::format::output_as_with(&output_path, "mp4", options)?
+        } else {
+            ffmpeg::format::output(&output_path)?
+        };
+
+        let mut video_encoder = video_config
+            .map(|video_config| {
+                // sinks like FLV need the SPS/PPS up front rather than in-band
+                let mut builder = H264Encoder::builder(video_config)
+                    .with_preset(config.preset)
+                    .with_bpp(config.bpp)
+                    .with_global_header();
+                if let Some((width, height)) = config.output_size {
+                    builder = builder.with_output_size(width, height)?;
+                }
+                builder.build(&mut output)
+            })
+            .transpose()
+            .context("video encoder")?;
+
+        let mut audio_encoder = audio_config
+            .map(|config| AACEncoder::init(config, &mut output))
+            .transpose()
+            .context("audio encoder")?;
+
+        output.write_header()?;
+
+        let time_bases = output
+            .streams()
+            .map(|stream| stream.time_base())
+            .collect::<Vec<_>>();
+
+        // sinks can be network URLs, so connecting to them mustn't block the runtime
+        let (output, opened_sinks) = tokio::task::spawn_blocking(move || {
+            let opened_sinks = config
+                .sinks
+                .into_iter()
+                .map(|sink| {
+                    let sink_output = open_sink(&sink, &output);
+                    (sink, sink_output)
+                })
+                .collect::<Vec<_>>();
+            (output, opened_sinks)
+        })
+        .await?;
+
+        let mut sinks = vec![];
+        let mut senders = vec![];
+        for (sink, sink_output) in opened_sinks {
+            let sink_output = match sink_output {
+                Ok(sink_output) => sink_output,
+                Err(e) => {
+                    sink.stats.fail(&sink.url, e);
+                    continue;
+                }
+            };
+            info!(url = %sink.url, "Opened tee sink");
+
+            let (tx, rx) = sync_channel(SINK_BUFFER_SIZE);
+            let stats = sink.stats.clone();
+            let url = sink.url.clone();
+            let time_bases = time_bases.clone();
+            let handle = std::thread::Builder::new()
+                .name("tee-sink".to_string())
+                .spawn(move || {
+                    if let Err(e) = write_sink(sink_output, &time_bases, &stats, rx) {
+                        stats.fail(&url, e);
+                    }
+                })?;
+
+            senders.push(SinkSender::new(tx, sink.stats.clone()));
+            sinks.push(SinkThread {
+                url: sink.url,
+                stats: sink.stats,
+                handle,
+            });
+        }
+
+        if let Some(video_encoder) = &mut video_encoder {
+            video_encoder.set_packet_tap(packet_tap(senders.clone()));
+        }
+        if let Some(audio_encoder) = &mut audio_encoder {
+            audio_encoder.set_packet_tap(packet_tap(senders));
+        }
+
+        Ok(Self {
+            output,
+            video_encoder,
+            audio_encoder,
+            sinks,
+            pause: config
+                .shared_pause_state
+                .unwrap_or_else(|| SharedPauseState::new(pause_flag)),
+        })
+    }
+
+    fn finish(&mut self, _: Duration) -> anyhow::Result<anyhow::Result<()>> {
+        let video_result = self
+            .video_encoder
+            .as_mut()
+            .map(|enc| enc.flush(&mut self.output))
+            .unwrap_or(Ok(()));
+
+        let audio_result = self
+            .audio_encoder
+            .as_mut()
+            .map(|enc| enc.flush(&mut self.output))
+            .unwrap_or(Ok(()));
+
+        let trailer_result = self.output.write_trailer();
+
+        // the encoders' packet taps hold the sinks' senders, so dropping them lets the sinks finish
+        self.video_encoder.take();
+        self.audio_encoder.take();
+
+        for sink in self.sinks.drain(..) {
+            if sink.handle.join().is_err() {
+                sink.stats
+                    .fail(&sink.url, anyhow!("Tee sink thread panicked"));
+            }
+            info!(
+                url = %sink.url,
+                written_packets = sink.stats.written_packets(),
+                dropped_packets = sink.stats.dropped_packets(),
+                error = ?sink.stats.error(),
+                "Tee sink finished"
+            );
+        }
+
+        trailer_result.context("write_trailer")?;
+
+        if video_result.is_ok() && audio_result.is_ok() {
+            return Ok(Ok(()));
+        }
+
+        Ok(Err(anyhow!(
+            "Video: {video_result:#?}, Audio: {audio_result:#?}"
+        )))
+    }
+}
+
+impl VideoMuxer for TeeMuxer {
+    type VideoFrame = FFmpegVideoFrame;
+
+    fn send_video_frame(
+        &mut self,
+        frame: Self::VideoFrame,
+        timestamp: Duration,
+    ) -> anyhow::Result<()> {
+        let Some(timestamp) = self.pause.adjust(timestamp)? else {
+            return Ok(());
+        };
+
+        if let Some(video_encoder) = self.video_encoder.as_mut() {
+            video_encoder.queue_frame(frame.inner, timestamp, &mut self.output)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl AudioMuxer for TeeMuxer {
+    fn send_audio_frame(&mut self, frame: AudioFrame, timestamp: Duration) -> anyhow::Result<()> {
+        let Some(timestamp) = self.pause.adjust(timestamp)? else {
+            return Ok(());
+        };
+
+        if let Some(audio_encoder) = self.audio_encoder.as_mut() {
+            audio_encoder.send_frame(frame.inner, timestamp, &mut self.output)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Opens `sink` with a copy of each of the primary output's streams
+fn open_sink(
+    sink: &TeeSink,
+    primary: &ffmpeg::format::context::Output,
+) -> anyhow::Result<ffmpeg::format::context::Output> {
+    let mut options = ffmpeg::Dictionary::new();
+    for (key, value) in &sink.options {
+        options.set(key, value);
+    }
+
+    let mut output = match &sink.format {
+        Some(format) => ffmpeg::format::output_as_with(&sink.url, format, options)?,
+        None => ffmpeg::format::output_with(&sink.url, options)?,
+    };
+
+    for primary_stream in primary.streams() {
+        let mut stream = output.add_stream(None)?;
+        stream.set_parameters(primary_stream.parameters());
+        unsafe {
+            (*stream.as_mut_ptr()).time_base = (*primary_stream.as_ptr()).time_base;
+            // the primary container's codec tag may not be valid in the sink's
+            (*(*stream.as_mut_ptr()).codecpar).codec_tag = 0;
+        }
+    }
+
+    output.write_header()?;
+
+    Ok(output)
+}
+
+fn write_sink(
+    mut output: ffmpeg::format::context::Output,
+    time_bases: &[FFRational],
+    stats: &TeeSinkStats,
+    rx: Receiver<Packet>,
+) -> anyhow::Result<()> {
+    for mut packet in rx {
+        let index = packet.stream();
+        let output_time_base = output
+            .stream(index)
+            .ok_or_else(|| anyhow!("Packet for unknown stream {index}"))?
+            .time_base();
+
+        packet.rescale_ts(time_bases[index], output_time_base);
+        packet.set_position(-1);
+        packet.write_interleaved(&mut output)?;
+
+        stats.written_packets.fetch_add(1, Ordering::Relaxed);
+    }
+
+    output.write_trailer()?;
+
+    Ok(())
+}
+
+#[derive(Clone)]
+struct SinkSender {
+    tx: SyncSender<Packet>,
+    stats: Arc<TeeSinkStats>,
+    waiting_for_keyframe: bool,
+    closed: bool,
+}
+
+impl SinkSender {
+    fn new(tx: SyncSender<Packet>, stats: Arc<TeeSinkStats>) -> Self {
+        Self {
+            tx,
+            stats,
+            waiting_for_keyframe: true,
+            closed: false,
+        }
+    }
+
+    fn send(&mut self, packet: &Packet) {
+        if self.closed {
+            return;
+        }
+
+        // packets after a dropped one can't be decoded until the next keyframe
+        if self.waiting_for_keyframe && !packet.is_key() {
+            self.stats.dropped_packets.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        match self.tx.try_send(packet.clone()) {
+            Ok(()) => self.waiting_for_keyframe = false,
+            Err(TrySendError::Full(_)) => {
+                self.stats.dropped_packets.fetch_add(1, Ordering::Relaxed);
+                self.waiting_for_keyframe = true;
+            }
+            // the sink failed and has already reported why
+            Err(TrySendError::Disconnected(_)) => self.closed = true,
+        }
+    }
+}
+
+/// Each encoder gets its own tap, so a dropped audio packet doesn't hold back video
+fn packet_tap(mut senders: Vec<SinkSender>) -> PacketTap {
+    Box::new(move |packet| {
+        for sender in &mut senders {
+            sender.send(packet);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(key: bool) -> Packet {
+        let mut packet = Packet::new(4);
+        if key {
+            packet.set_flags(ffmpeg::packet::Flags::KEY);
+        }
+        packet
+    }
+
+    #[test]
+    fn skips_to_next_keyframe_after_a_drop() {
+        let stats = Arc::new(TeeSinkStats::default());
+        let (tx, rx) = sync_channel(2);
+        let mut sender = SinkSender::new(tx, stats.clone());
+
+        sender.send(&packet(true));
+        sender.send(&packet(false));
+        // the channel is full, so this is dropped
+        sender.send(&packet(false));
+        rx.recv().unwrap();
+        rx.recv().unwrap();
+        sender.send(&packet(false));
+        sender.send(&packet(true));
+
+        assert_eq!(stats.dropped_packets(), 2);
+        assert!(rx.recv().unwrap().is_key());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failing_sink_leaves_the_primary_output_intact() {
+        use cap_media_info::Pixel;
+        use cap_timestamp::Timestamp;
+        use std::os::fd::IntoRawFd;
+
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("output.mp4");
+
+        // nothing reads from the pipe, so every write to the sink fails
+        let (reader, writer) = std::io::pipe().unwrap();
+        drop(reader);
+        let sink = TeeSink::new(format!("pipe:{}", writer.into_raw_fd())).with_format("mpegts");
+        let sink_stats = sink.stats.clone();
+
+        let mut muxer = TeeMuxer::setup(
+            TeeMuxerConfig {
+                sinks: vec![sink],
+                ..Default::default()
+            },
+            output_path.clone(),
+            Some(VideoInfo::from_raw_ffmpeg(Pixel::YUV420P, 64, 64, 30)),
+            None,
+            Arc::default(),
+            &mut TaskPool::default(),
+        )
+        .await
+        .unwrap();
+
+        for i in 0..60 {
+            let frame = FFmpegVideoFrame {
+                inner: ffmpeg::frame::Video::new(Pixel::YUV420P, 64, 64),
+                timestamp: Timestamp::Instant(std::time::Instant::now()),
+            };
+            muxer
+                .send_video_frame(frame, Duration::from_secs_f64(i as f64 / 30.0))
+                .unwrap();
+        }
+        muxer.finish(Duration::from_secs(2)).unwrap().unwrap();
+
+        assert!(sink_stats.error().is_some());
+        assert!(std::fs::metadata(&output_path).unwrap().len() > 0);
+    }
+
+    #[test]
+    fn stops_sending_once_the_sink_is_gone() {
+        let stats = Arc::new(TeeSinkStats::default());
+        let (tx, rx) = sync_channel(2);
+        let mut sender = SinkSender::new(tx, stats.clone());
+
+        drop(rx);
+        sender.send(&packet(true));
+        sender.send(&packet(true));
+
+        assert!(sender.closed);
+        assert_eq!(stats.dropped_packets(), 0);
+    }
+}
@@ -110,6 +110,7 @@ pub struct WindowsFragmentedM4SMuxer {
     frame_drops: FrameDropTracker,
     started: bool,
     disk_space_callback: Option<DiskSpaceCallback>,
+    retention: Option<Duration>,
 }
 
 pub struct WindowsFragmentedM4SMuxerConfig {
@@ -118,6 +119,8 @@ pub struct WindowsFragmentedM4SMuxerConfig {
     pub output_size: Option<(u32, u32)>,
     pub shared_pause_state: Option<SharedPauseState>,
     pub disk_space_callback: Option<DiskSpaceCallback>,
+    /// Deletes the oldest segments once the newer ones cover at least this long
+    pub retention: Option<Duration>,
 }
 
 impl Default for WindowsFragmentedM4SMuxerConfig {
@@ -128,6 +131,7 @@ impl Default for WindowsFragmentedM4SMuxerConfig {
             output_size: None,
             shared_pause_state: None,
             disk_space_callback: None,
+            retention: None,
         }
     }
 }
@@ -167,6 +171,7 @@ impl Muxer for WindowsFragmentedM4SMuxer {
             frame_drops: FrameDropTracker::new(),
             started: false,
             disk_space_callback: config.disk_space_callback,
+            retention: config.retention,
         };
 
         muxer.start_encoder()?;
@@ -254,6 +259,7 @@ impl WindowsFragmentedM4SMuxer {
             preset: self.preset,
             bpp: H264EncoderBuilder::QUALITY_BPP,
             output_size: self.output_size,
+            retention: self.retention,
         };
 
         let mut encoder =
@@ -688,6 +694,7 @@ impl WindowsFragmentedM4SCameraMuxer {
             preset: self.preset,
             bpp: H264EncoderBuilder::QUALITY_BPP,
             output_size: self.output_size,
+            retention: None,
         };
 
         let mut encoder =
@@ -36,12 +36,44 @@ pub struct RecoverableSegment {
     pub cursor_path: Option<PathBuf>,
 }
 
+impl RecoverableSegment {
+    /// Probes the segment's display fragments for their total duration.
+    pub fn estimated_duration(&self) -> Duration {
+        RecoveryManager::estimate_fragments_duration(&self.display_fragments).unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RecoveredRecording {
     pub project_path: PathBuf,
     pub meta: StudioRecordingMeta,
 }
 
+impl RecoveredRecording {
+    /// Saves the first frame of the display recording as the project's screenshot,
+    /// as the recording never got to save one itself.
+    pub fn save_screenshot(&self) -> Result<(), String> {
+        let display_path = match &self.meta {
+            StudioRecordingMeta::SingleSegment { segment } => {
+                segment.display.path.to_path(&self.project_path)
+            }
+            StudioRecordingMeta::MultipleSegments { inner, .. } => {
+                inner.segments[0].display.path.to_path(&self.project_path)
+            }
+        };
+
+        let screenshots_dir = self.project_path.join("screenshots");
+        std::fs::create_dir_all(&screenshots_dir)
+            .map_err(|e| format!("Failed to create screenshots directory: {e}"))?;
+
+        crate::screenshot::save_first_video_frame(
+            &display_path,
+            &screenshots_dir.join("display.jpg"),
+            None,
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 struct FragmentsInfo {
     fragments: Vec<PathBuf>,
@@ -943,6 +975,7 @@ impl RecoveryManager {
             scene_segments: Vec::new(),
             mask_segments: Vec::new(),
             text_segments: Vec::new(),
+            markers: Vec::new(),
         });
 
         config
@@ -0,0 +1,571 @@
+use crate::{
+    H264_MAX_DIMENSION, RecordingBaseInputs, calculate_gpu_compatible_size,
+    capture_pipeline::{ScreenCaptureMethod, target_to_display_and_crop},
+    feeds::microphone::MicrophoneFeedLock,
+    ffmpeg::{SegmentedAudioMuxer, SegmentedAudioMuxerConfig},
+    output_pipeline::{DoneFut, FinishedOutputPipeline, OutputPipeline},
+    sources::{
+        self,
+        screen_capture::{self, ScreenCaptureConfig, ScreenCaptureTarget},
+    },
+    studio_recording::CompletedRecording,
+};
+
+#[cfg(target_os = "macos")]
+use crate::output_pipeline::{MacOSFragmentedM4SMuxer, MacOSFragmentedM4SMuxerConfig};
+
+#[cfg(windows)]
+use crate::output_pipeline::{WindowsFragmentedM4SMuxer, WindowsFragmentedM4SMuxerConfig};
+
+use anyhow::{Context as _, anyhow, bail};
+use cap_enc_ffmpeg::remux::{
+    concatenate_audio_to_ogg, concatenate_trailing_m4s_segments_with_init,
+};
+use cap_project::{
+    AudioMeta, MultipleSegment, MultipleSegments, StudioRecordingMeta, StudioRecordingStatus,
+    VideoMeta,
+};
+use cap_timestamp::{Timestamp, Timestamps};
+use cap_utils::ensure_dir;
+use futures::future::OptionFuture;
+use kameo::{Actor as _, prelude::*};
+use relative_path::RelativePathBuf;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tracing::*;
+
+/// How much recording the buffer keeps when no duration is given
+pub const DEFAULT_REPLAY_DURATION: Duration = Duration::from_secs(5 * 60);
+
+const SEGMENT_DIR: &str = "content/segments/segment-0";
+
+struct Pipeline {
+    start_time: Timestamps,
+    screen: OutputPipeline,
+    microphone: Option<OutputPipeline>,
+    system_audio: Option<OutputPipeline>,
+}
+
+struct FinishedPipeline {
+    start_time: Timestamps,
+    screen: FinishedOutputPipeline,
+    microphone: Option<FinishedOutputPipeline>,
+    system_audio: Option<FinishedOutputPipeline>,
+}
+
+impl Pipeline {
+    async fn stop(self) -> anyhow::Result<FinishedPipeline> {
+        let (screen, microphone, system_audio) = futures::join!(
+            self.screen.stop(),
+            OptionFuture::from(self.microphone.map(|s| s.stop())),
+            OptionFuture::from(self.system_audio.map(|s| s.stop()))
+        );
+
+        let system_audio = match system_audio.transpose() {
+            Ok(value) => value,
+            Err(err) => {
+                warn!("system audio pipeline failed during stop: {err:#}");
+                None
+            }
+        };
+
+        Ok(FinishedPipeline {
+            start_time: self.start_time,
+            screen: screen.context("screen")?,
+            microphone: microphone.transpose().context("microphone")?,
+            system_audio,
+        })
+    }
+}
+
+pub struct ActorHandle {
+    actor_ref: kameo::actor::ActorRef<Actor>,
+    pub capture_target: ScreenCaptureTarget,
+    done_fut: DoneFut,
+}
+
+impl ActorHandle {
+    /// Stops buffering and turns what's left of the buffer into a studio project
+    pub async fn save(&self) -> anyhow::Result<CompletedRecording> {
+        Ok(self.actor_ref.ask(Save).await?)
+    }
+
+    /// Stops buffering and deletes the buffered segments
+    pub async fn cancel(&self) -> anyhow::Result<()> {
+        Ok(self.actor_ref.ask(Cancel).await?)
+    }
+
+    pub fn done_fut(&self) -> DoneFut {
+        self.done_fut.clone()
+    }
+}
+
+impl Drop for ActorHandle {
+    fn drop(&mut self) {
+        let actor_ref = self.actor_ref.clone();
+        tokio::spawn(async move {
+            let _ = actor_ref.tell(Cancel).await;
+        });
+    }
+}
+
+#[derive(kameo::Actor)]
+pub struct Actor {
+    recording_dir: PathBuf,
+    mic_device_id: Option<String>,
+    pipeline: Option<Pipeline>,
+}
+
+pub struct Save;
+
+impl Message<Save> for Actor {
+    type Reply = anyhow::Result<CompletedRecording>;
+
+    async fn handle(&mut self, _: Save, _: &mut Context<Self, Self::Reply>) -> Self::Reply {
+        let pipeline = self
+            .pipeline
+            .take()
+            .ok_or_else(|| anyhow!("Replay buffer is not running"))?;
+
+        let pipeline = pipeline.stop().await?;
+
+        let recording_dir = self.recording_dir.clone();
+        let mic_device_id = self.mic_device_id.clone();
+        let segment = tokio::task::spawn_blocking(move || {
+            finish_segment(&recording_dir, pipeline, mic_device_id)
+        })
+        .await??;
+
+        Ok(CompletedRecording {
+            project_path: self.recording_dir.clone(),
+            meta: StudioRecordingMeta::MultipleSegments {
+                inner: MultipleSegments {
+                    segments: vec![segment],
+                    cursors: Default::default(),
+                    status: Some(StudioRecordingStatus::Complete),
+                },
+            },
+            cursor_data: Default::default(),
+        })
+    }
+}
+
+pub struct Cancel;
+
+impl Message<Cancel> for Actor {
+    type Reply = anyhow::Result<()>;
+
+    async fn handle(&mut self, _: Cancel, _: &mut Context<Self, Self::Reply>) -> Self::Reply {
+        if let Some(pipeline) = self.pipeline.take() {
+            if let Err(e) = pipeline.stop().await {
+                warn!("Pipeline stop error during cancel: {e:#}");
+            }
+
+            if let Err(e) = std::fs::remove_dir_all(self.recording_dir.join(SEGMENT_DIR)) {
+                warn!("Failed to remove replay buffer segments: {e}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Remuxes the segments left in the buffer into the files a studio recording has.
+/// Each stream's start time is when its oldest remaining segment started,
+/// so the streams stay in sync even though their oldest segments were deleted at different times.
+fn finish_segment(
+    recording_dir: &Path,
+    pipeline: FinishedPipeline,
+    mic_device_id: Option<String>,
+) -> anyhow::Result<MultipleSegment> {
+    let segment_dir = recording_dir.join(SEGMENT_DIR);
+    let to_start_time =
+        |timestamp: Timestamp| timestamp.signed_duration_since_secs(pipeline.start_time);
+
+    let display_dir = segment_dir.join("display");
+    let display_segments = complete_segments(&display_dir, "segments");
+    if display_segments.is_empty() {
+        bail!("Replay buffer has no complete display segments");
+    }
+
+    let display_offset = concatenate_trailing_m4s_segments_with_init(
+        &display_dir.join("init.mp4"),
+        &display_segments
+            .iter()
+            .map(|s| s.path.clone())
+            .collect::<Vec<_>>(),
+        &segment_dir.join("display.mp4"),
+    )
+    .context("display remux")?;
+    remove_buffer_dir(&display_dir);
+
+    let display_start =
+        to_start_time(pipeline.screen.first_timestamp) + display_offset.as_secs_f64();
+    let mic_start = match pipeline.microphone {
+        Some(_) => concatenate_audio(&segment_dir, "audio-input").context("microphone remux")?,
+        None => None,
+    };
+    let system_audio_start = match pipeline.system_audio {
+        Some(_) => concatenate_audio(&segment_dir, "system_audio").context("system audio remux")?,
+        None => None,
+    };
+
+    let earliest_start = [mic_start, system_audio_start]
+        .into_iter()
+        .flatten()
+        .fold(display_start, f64::min);
+
+    Ok(MultipleSegment {
+        display: VideoMeta {
+            path: RelativePathBuf::from(format!("{SEGMENT_DIR}/display.mp4")),
+            fps: pipeline.screen.video_info.map(|v| v.fps()).unwrap_or(30),
+            start_time: Some(display_start - earliest_start),
+            device_id: None,
+        },
+        camera: None,
+        mic: mic_start.map(|start| AudioMeta {
+            path: RelativePathBuf::from(format!("{SEGMENT_DIR}/audio-input.ogg")),
+            start_time: Some(start - earliest_start),
+            device_id: mic_device_id,
+        }),
+        system_audio: system_audio_start.map(|start| AudioMeta {
+            path: RelativePathBuf::from(format!("{SEGMENT_DIR}/system_audio.ogg")),
+            start_time: Some(start - earliest_start),
+            device_id: None,
+        }),
+        cursor: None,
+    })
+}
+
+/// Joins the fragments in `segment_dir/{name}` into `segment_dir/{name}.ogg`,
+/// returning when the first remaining fragment started.
+fn concatenate_audio(segment_dir: &Path, name: &str) -> anyhow::Result<Option<f64>> {
+    let dir = segment_dir.join(name);
+    let fragments = complete_segments(&dir, "fragments");
+    let Some(start) = fragments.first().map(|f| f.start) else {
+        warn!("No complete {name} fragments in the replay buffer");
+        return Ok(None);
+    };
+
+    concatenate_audio_to_ogg(
+        &fragments.into_iter().map(|f| f.path).collect::<Vec<_>>(),
+        &segment_dir.join(format!("{name}.ogg")),
+    )?;
+    remove_buffer_dir(&dir);
+
+    Ok(Some(start))
+}
+
+fn remove_buffer_dir(dir: &Path) {
+    if let Err(e) = std::fs::remove_dir_all(dir) {
+        debug!("Failed to clean up replay buffer dir {:?}: {e}", dir);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct BufferedSegment {
+    path: PathBuf,
+    start: f64,
+}
+
+/// Reads the complete segments still on disk from the manifest in `dir`, oldest first
+fn complete_segments(dir: &Path, entries_key: &str) -> Vec<BufferedSegment> {
+    let Some(manifest) = std::fs::read_to_string(dir.join("manifest.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+    else {
+        return vec![];
+    };
+
+    let Some(entries) = manifest.get(entries_key).and_then(|e| e.as_array()) else {
+        return vec![];
+    };
+
+    let mut segments = entries
+        .iter()
+        .filter(|e| {
+            e.get("is_complete")
+                .and_then(|c| c.as_bool())
+                .unwrap_or(false)
+        })
+        .filter_map(|e| {
+            let path = dir.join(e.get("path")?.as_str()?);
+            let index = e.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+            let start = e.get("start").and_then(|s| s.as_f64()).unwrap_or(0.0);
+            path.exists()
+                .then_some((index, BufferedSegment { path, start }))
+        })
+        .collect::<Vec<_>>();
+
+    segments.sort_by_key(|(index, _)| *index);
+    segments.into_iter().map(|(_, segment)| segment).collect()
+}
+
+impl Actor {
+    pub fn builder(output: PathBuf, capture_target: ScreenCaptureTarget) -> ActorBuilder {
+        ActorBuilder::new(output, capture_target)
+    }
+}
+
+pub struct ActorBuilder {
+    output_path: PathBuf,
+    capture_target: ScreenCaptureTarget,
+    duration: Duration,
+    system_audio: bool,
+    mic_feed: Option<Arc<MicrophoneFeedLock>>,
+    max_fps: u32,
+    #[cfg(target_os = "macos")]
+    excluded_windows: Vec<scap_targets::WindowId>,
+}
+
+impl ActorBuilder {
+    pub fn new(output: PathBuf, capture_target: ScreenCaptureTarget) -> Self {
+        Self {
+            output_path: output,
+            capture_target,
+            duration: DEFAULT_REPLAY_DURATION,
+            system_audio: false,
+            mic_feed: None,
+            max_fps: 60,
+            #[cfg(target_os = "macos")]
+            excluded_windows: Vec::new(),
+        }
+    }
+
+    /// How much of the most recent recording to keep. Older segments are deleted as new ones
+    /// are written, so the buffer holds at most one segment more than this.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn with_system_audio(mut self, system_audio: bool) -> Self {
+        self.system_audio = system_audio;
+        self
+    }
+
+    pub fn with_mic_feed(mut self, mic_feed: Arc<MicrophoneFeedLock>) -> Self {
+        self.mic_feed = Some(mic_feed);
+        self
+    }
+
+    pub fn with_max_fps(mut self, max_fps: u32) -> Self {
+        self.max_fps = max_fps.clamp(1, 120);
+        self
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn with_excluded_windows(mut self, excluded_windows: Vec<scap_targets::WindowId>) -> Self {
+        self.excluded_windows = excluded_windows;
+        self
+    }
+
+    pub async fn build(
+        self,
+        #[cfg(target_os = "macos")] shareable_content: cidre::arc::R<cidre::sc::ShareableContent>,
+    ) -> anyhow::Result<ActorHandle> {
+        spawn_replay_buffer_actor(
+            self.output_path,
+            RecordingBaseInputs {
+                capture_target: self.capture_target,
+                capture_system_audio: self.system_audio,
+                mic_feed: self.mic_feed,
+                camera_feed: None,
+                #[cfg(target_os = "macos")]
+                shareable_content,
+                #[cfg(target_os = "macos")]
+                excluded_windows: self.excluded_windows,
+            },
+            self.duration,
+            self.max_fps,
+        )
+        .await
+    }
+}
+
+#[tracing::instrument("replay_buffer", skip_all)]
+async fn spawn_replay_buffer_actor(
+    recording_dir: PathBuf,
+    inputs: RecordingBaseInputs,
+    duration: Duration,
+    max_fps: u32,
+) -> anyhow::Result<ActorHandle> {
+    ensure_dir(&recording_dir)?;
+
+    let mic_device_id = inputs
+        .mic_feed
+        .as_ref()
+        .map(|f| f.device_name().to_string());
+
+    let pipeline = create_pipeline(&recording_dir, inputs.clone(), duration, max_fps).await?;
+
+    let done_fut = pipeline.screen.done_fut();
+    let actor_ref = Actor::spawn(Actor {
+        recording_dir,
+        mic_device_id,
+        pipeline: Some(pipeline),
+    });
+
+    Ok(ActorHandle {
+        actor_ref,
+        capture_target: inputs.capture_target,
+        done_fut,
+    })
+}
+
+async fn create_pipeline(
+    recording_dir: &Path,
+    base_inputs: RecordingBaseInputs,
+    retention: Duration,
+    max_fps: u32,
+) -> anyhow::Result<Pipeline> {
+    let start_time = Timestamps::now();
+
+    #[cfg(windows)]
+    let d3d_device = crate::capture_pipeline::create_d3d_device()
+        .context("D3D11 device creation failed - this may happen in VMs, RDP sessions, or systems without GPU drivers")?;
+
+    let (display, crop) =
+        target_to_display_and_crop(&base_inputs.capture_target).context("target_display_crop")?;
+
+    let screen_config = ScreenCaptureConfig::<ScreenCaptureMethod>::init(
+        display,
+        crop,
+        true,
+        max_fps,
+        start_time.system_time(),
+        base_inputs.capture_system_audio,
+        #[cfg(windows)]
+        d3d_device,
+        #[cfg(target_os = "macos")]
+        base_inputs.shareable_content,
+        #[cfg(target_os = "macos")]
+        base_inputs.excluded_windows,
+    )
+    .await
+    .context("screen capture init")?;
+
+    let screen_info = screen_config.info();
+    let output_size =
+        calculate_gpu_compatible_size(screen_info.width, screen_info.height, H264_MAX_DIMENSION);
+
+    let (capture_source, system_audio) = screen_config.to_sources().await?;
+
+    let dir = ensure_dir(&recording_dir.join(SEGMENT_DIR))?;
+
+    #[cfg(target_os = "macos")]
+    let screen = OutputPipeline::builder(dir.join("display"))
+        .with_video::<screen_capture::VideoSource>(capture_source)
+        .with_timestamps(start_time)
+        .build::<MacOSFragmentedM4SMuxer>(MacOSFragmentedM4SMuxerConfig {
+            output_size,
+            retention: Some(retention),
+            ..Default::default()
+        })
+        .instrument(error_span!("screen-out"))
+        .await
+        .context("screen pipeline setup")?;
+
+    #[cfg(windows)]
+    let screen = OutputPipeline::builder(dir.join("display"))
+        .with_video::<screen_capture::VideoSource>(capture_source)
+        .with_timestamps(start_time)
+        .build::<WindowsFragmentedM4SMuxer>(WindowsFragmentedM4SMuxerConfig {
+            output_size,
+            retention: Some(retention),
+            ..Default::default()
+        })
+        .instrument(error_span!("screen-out"))
+        .await
+        .context("screen pipeline setup")?;
+
+    let microphone = if let Some(mic_feed) = base_inputs.mic_feed {
+        Some(
+            OutputPipeline::builder(dir.join("audio-input"))
+                .with_audio_source::<sources::Microphone>(mic_feed)
+                .with_timestamps(start_time)
+                .build::<SegmentedAudioMuxer>(SegmentedAudioMuxerConfig {
+                    retention: Some(retention),
+                    ..Default::default()
+                })
+                .instrument(error_span!("mic-out"))
+                .await
+                .context("microphone pipeline setup")?,
+        )
+    } else {
+        None
+    };
+
+    let system_audio = if let Some(system_audio_source) = system_audio {
+        Some(
+            OutputPipeline::builder(dir.join("system_audio"))
+                .with_audio_source::<screen_capture::SystemAudioSource>(system_audio_source)
+                .with_timestamps(start_time)
+                .build::<SegmentedAudioMuxer>(SegmentedAudioMuxerConfig {
+                    retention: Some(retention),
+                    ..Default::default()
+                })
+                .instrument(error_span!("system-audio-out"))
+                .await
+                .context("system audio pipeline setup")?,
+        )
+    } else {
+        None
+    };
+
+    info!("replay buffer pipeline playing");
+
+    Ok(Pipeline {
+        start_time,
+        screen,
+        microphone,
+        system_audio,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_complete_segments_still_on_disk_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["segment_004.m4s", "segment_005.m4s", "segment_006.m4s"] {
+            std::fs::write(dir.path().join(name), b"data").unwrap();
+        }
+        std::fs::write(
+            dir.path().join("manifest.json"),
+            serde_json::json!({
+                "version": 5,
+                "type": "m4s_segments",
+                "segments": [
+                    { "path": "segment_005.m4s", "index": 5, "start": 12.0, "duration": 3.0, "is_complete": true },
+                    { "path": "segment_003.m4s", "index": 3, "start": 6.0, "duration": 3.0, "is_complete": true },
+                    { "path": "segment_004.m4s", "index": 4, "start": 9.0, "duration": 3.0, "is_complete": true },
+                    { "path": "segment_006.m4s", "index": 6, "start": 15.0, "duration": 0.0, "is_complete": false },
+                ],
+                "is_complete": false,
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            complete_segments(dir.path(), "segments"),
+            [
+                BufferedSegment {
+                    path: dir.path().join("segment_004.m4s"),
+                    start: 9.0,
+                },
+                BufferedSegment {
+                    path: dir.path().join("segment_005.m4s"),
+                    start: 12.0,
+                },
+            ]
+        );
+        assert!(complete_segments(dir.path(), "fragments").is_empty());
+    }
+}
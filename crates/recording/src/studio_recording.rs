@@ -96,6 +96,7 @@ impl Actor {
                 serde_json::to_string_pretty(&CursorEvents {
                     clicks: res.clicks,
                     moves: res.moves,
+                    keys: res.keys,
                 })?,
             )?;
 
@@ -477,6 +478,7 @@ pub struct ActorBuilder {
     mic_feed: Option<Arc<MicrophoneFeedLock>>,
     camera_feed: Option<Arc<CameraFeedLock>>,
     custom_cursor: bool,
+    keyboard_capture: bool,
     fragmented: bool,
     max_fps: u32,
     #[cfg(target_os = "macos")]
@@ -492,6 +494,7 @@ impl ActorBuilder {
             mic_feed: None,
             camera_feed: None,
             custom_cursor: false,
+            keyboard_capture: false,
             fragmented: false,
             max_fps: 60,
             #[cfg(target_os = "macos")]
@@ -519,6 +522,13 @@ impl ActorBuilder {
         self
     }
 
+    /// Records shortcuts and special keys pressed during the recording, for the keystroke overlay.
+    /// Typed text is never recorded.
+    pub fn with_keyboard_capture(mut self, keyboard_capture: bool) -> Self {
+        self.keyboard_capture = keyboard_capture;
+        self
+    }
+
     pub fn with_fragmented(mut self, fragmented: bool) -> Self {
         self.fragmented = fragmented;
         self
@@ -552,6 +562,7 @@ impl ActorBuilder {
                 excluded_windows: self.excluded_windows,
             },
             self.custom_cursor,
+            self.keyboard_capture,
             self.fragmented,
             self.max_fps,
         )
@@ -564,6 +575,7 @@ async fn spawn_studio_recording_actor(
     recording_dir: PathBuf,
     base_inputs: RecordingBaseInputs,
     custom_cursor_capture: bool,
+    keyboard_capture: bool,
     fragmented: bool,
     max_fps: u32,
 ) -> anyhow::Result<ActorHandle> {
@@ -593,6 +605,7 @@ async fn spawn_studio_recording_actor(
         cursors_dir,
         base_inputs.clone(),
         custom_cursor_capture,
+        keyboard_capture,
         fragmented,
         max_fps,
         completion_tx.clone(),
@@ -814,6 +827,7 @@ struct SegmentPipelineFactory {
     cursors_dir: PathBuf,
     base_inputs: RecordingBaseInputs,
     custom_cursor_capture: bool,
+    keyboard_capture: bool,
     fragmented: bool,
     max_fps: u32,
     index: u32,
@@ -829,6 +843,7 @@ impl SegmentPipelineFactory {
         cursors_dir: PathBuf,
         base_inputs: RecordingBaseInputs,
         custom_cursor_capture: bool,
+        keyboard_capture: bool,
         fragmented: bool,
         max_fps: u32,
         completion_tx: watch::Sender<Option<Result<(), PipelineDoneError>>>,
@@ -838,6 +853,7 @@ impl SegmentPipelineFactory {
             cursors_dir,
             base_inputs,
             custom_cursor_capture,
+            keyboard_capture,
             fragmented,
             max_fps,
             index: 0,
@@ -861,6 +877,7 @@ impl SegmentPipelineFactory {
             cursors,
             next_cursors_id,
             self.custom_cursor_capture,
+            self.keyboard_capture,
             self.fragmented,
             self.max_fps,
             segment_start_time,
@@ -945,6 +962,7 @@ async fn create_segment_pipeline(
     prev_cursors: Cursors,
     next_cursors_id: u32,
     custom_cursor_capture: bool,
+    keyboard_capture: bool,
     fragmented: bool,
     max_fps: u32,
     start_time: Timestamps,
@@ -1143,6 +1161,7 @@ async fn create_segment_pipeline(
                 next_cursors_id,
                 start_time,
                 incremental_output,
+                keyboard_capture,
             );
 
             Ok::<_, CreateSegmentPipelineError>(CursorPipeline {
@@ -139,7 +139,8 @@ impl AudioSource for SyntheticAudioSource {
     }
 }
 
-fn generate_audio_samples(
+/// Interleaved f32 samples from `generator`, starting `sample_offset` samples into the signal
+pub fn generate_audio_samples(
     generator: &AudioGenerator,
     sample_rate: u32,
     channels: usize,
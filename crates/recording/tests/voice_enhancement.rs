@@ -0,0 +1,88 @@
+use cap_recording::test_sources::{AudioGenerator, generate_audio_samples};
+
+const SAMPLE_RATE: u32 = 48_000;
+const SECONDS: usize = 5;
+
+/// 50ms beeps every 500ms standing in for speech, over white noise about 30dB below them
+fn noisy_beeps() -> Vec<f32> {
+    let count = SAMPLE_RATE as usize * SECONDS;
+    let beeps = generate_audio_samples(
+        &AudioGenerator::TimestampBeeps {
+            beep_interval_ms: 500,
+        },
+        SAMPLE_RATE,
+        1,
+        0,
+        count,
+    );
+    let noise = generate_audio_samples(&AudioGenerator::WhiteNoise, SAMPLE_RATE, 1, 0, count);
+
+    beeps
+        .iter()
+        .zip(&noise)
+        .map(|(beep, noise)| beep + noise * 0.1)
+        .collect()
+}
+
+fn energy_db(samples: &[f32], include: impl Fn(usize) -> bool) -> f32 {
+    let energy = samples
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| include(*i))
+        .map(|(_, s)| s * s)
+        .sum::<f32>();
+    10.0 * energy.log10()
+}
+
+fn position_in_interval(i: usize) -> usize {
+    i % (SAMPLE_RATE as usize / 2)
+}
+
+fn is_beep(i: usize) -> bool {
+    position_in_interval(i) < SAMPLE_RATE as usize / 20
+}
+
+/// Between beeps, leaving room for the compressor and suppressor to settle
+fn is_gap(i: usize) -> bool {
+    (SAMPLE_RATE as usize * 3 / 20..SAMPLE_RATE as usize * 9 / 20)
+        .contains(&position_in_interval(i))
+}
+
+#[test]
+fn enhancement_suppresses_noise_and_keeps_voice() {
+    let input = noisy_beeps();
+    let output = cap_audio::enhance_voice(&input, 1, SAMPLE_RATE);
+
+    assert_eq!(output.len(), input.len());
+
+    let noise_reduction = energy_db(&input, is_gap) - energy_db(&output, is_gap);
+    let voice_loss = energy_db(&input, is_beep) - energy_db(&output, is_beep);
+
+    assert!(
+        noise_reduction > 12.0,
+        "noise only dropped by {noise_reduction}dB"
+    );
+    assert!(voice_loss < 9.0, "voice dropped by {voice_loss}dB");
+}
+
+#[test]
+fn enhancement_is_deterministic() {
+    let input = noisy_beeps();
+
+    assert_eq!(
+        cap_audio::enhance_voice(&input, 1, SAMPLE_RATE),
+        cap_audio::enhance_voice(&input, 1, SAMPLE_RATE)
+    );
+}
+
+#[test]
+fn enhancement_processes_channels_independently() {
+    let mono = noisy_beeps();
+    let stereo = mono.iter().flat_map(|s| [*s, 0.0]).collect::<Vec<_>>();
+
+    let output = cap_audio::enhance_voice(&stereo, 2, SAMPLE_RATE);
+
+    let left = output.iter().step_by(2).copied().collect::<Vec<_>>();
+    assert_eq!(left, cap_audio::enhance_voice(&mono, 1, SAMPLE_RATE));
+    assert!(output.iter().skip(1).step_by(2).all(|s| *s == 0.0));
+}
@@ -6,8 +6,9 @@ use glyphon::{
     TextArea, TextAtlas, TextBounds, TextRenderer, Viewport, Weight,
 };
 use log::warn;
-use wgpu::{Device, Queue, include_wgsl, util::DeviceExt};
+use wgpu::{Device, Queue, util::DeviceExt};
 
+use super::text_background::{TextBackground, TextBackgroundUniforms};
 use crate::{DecodedSegmentFrames, ProjectUniforms, RenderVideoConstants, parse_color_component};
 
 #[derive(Debug, Clone)]
@@ -56,16 +57,6 @@ impl Default for CaptionSettings {
     }
 }
 
-#[repr(C)]
-#[derive(Copy, Clone, Pod, Zeroable, Debug)]
-struct CaptionBackgroundUniforms {
-    rect: [f32; 4],
-    color: [f32; 4],
-    radius: f32,
-    _padding: [f32; 3],
-    _padding2: [f32; 4],
-}
-
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CaptionPosition {
     TopLeft,
@@ -244,9 +235,7 @@ pub struct CaptionsLayer {
     current_segment_start: f32,
     current_segment_end: f32,
     viewport: Viewport,
-    background_pipeline: wgpu::RenderPipeline,
-    background_bind_group: wgpu::BindGroup,
-    background_uniform_buffer: wgpu::Buffer,
+    background: TextBackground,
     background_scissor: Option<[u32; 4]>,
     output_size: (u32, u32),
     has_caption: bool,
@@ -276,84 +265,6 @@ impl CaptionsLayer {
         let metrics = Metrics::new(24.0, 24.0 * 1.2);
         let text_buffer = Buffer::new_empty(metrics);
 
-        let background_uniforms = CaptionBackgroundUniforms {
-            rect: [0.0; 4],
-            color: [0.0; 4],
-            radius: 0.0,
-            _padding: [0.0; 3],
-            _padding2: [0.0; 4],
-        };
-
-        let background_uniform_buffer =
-            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Caption Background Uniform Buffer"),
-                contents: bytemuck::bytes_of(&background_uniforms),
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            });
-
-        let background_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Caption Background Bind Group Layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
-            });
-
-        let background_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Caption Background Bind Group"),
-            layout: &background_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: background_uniform_buffer.as_entire_binding(),
-            }],
-        });
-
-        let background_shader =
-            device.create_shader_module(include_wgsl!("../shaders/caption_bg.wgsl"));
-
-        let background_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Caption Background Pipeline Layout"),
-                bind_group_layouts: &[&background_bind_group_layout],
-                push_constant_ranges: &[],
-            });
-
-        let background_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Caption Background Pipeline"),
-            layout: Some(&background_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &background_shader,
-                entry_point: Some("vs_main"),
-                buffers: &[],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &background_shader,
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: wgpu::TextureFormat::Rgba8Unorm,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                ..Default::default()
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-            cache: None,
-        });
-
         Self {
             _settings_buffer: settings_buffer,
             font_system,
@@ -365,9 +276,7 @@ impl CaptionsLayer {
             current_segment_start: 0.0,
             current_segment_end: 0.0,
             viewport,
-            background_pipeline,
-            background_bind_group,
-            background_uniform_buffer,
+            background: TextBackground::new(device, "Caption"),
             background_scissor: None,
             output_size: (0, 0),
             has_caption: false,
@@ -767,28 +676,24 @@ impl CaptionsLayer {
             Err(e) => warn!("Error preparing text: {e:?}"),
         }
 
-        let rect = CaptionBackgroundUniforms {
-            rect: [
-                background_left.max(0.0),
-                background_top.max(0.0),
-                box_width,
-                box_height,
-            ],
-            color: [
-                background_color_rgb[0],
-                background_color_rgb[1],
-                background_color_rgb[2],
-                background_alpha,
-            ],
-            radius: corner_radius.min(box_width / 2.0).min(box_height / 2.0),
-            _padding: [0.0; 3],
-            _padding2: [0.0; 4],
-        };
-
-        queue.write_buffer(
-            &self.background_uniform_buffer,
-            0,
-            bytemuck::bytes_of(&rect),
+        self.background.update(
+            queue,
+            TextBackgroundUniforms {
+                rect: [
+                    background_left.max(0.0),
+                    background_top.max(0.0),
+                    box_width,
+                    box_height,
+                ],
+                color: [
+                    background_color_rgb[0],
+                    background_color_rgb[1],
+                    background_color_rgb[2],
+                    background_alpha,
+                ],
+                radius: corner_radius.min(box_width / 2.0).min(box_height / 2.0),
+                ..Default::default()
+            },
         );
 
         let scissor_padding = 4.0;
@@ -831,10 +736,7 @@ impl CaptionsLayer {
 
         if let Some([x, y, width, height]) = self.background_scissor {
             pass.set_scissor_rect(x, y, width, height);
-            pass.set_pipeline(&self.background_pipeline);
-            pass.set_bind_group(0, &self.background_bind_group, &[]);
-            pass.draw(0..6, 0..1);
-            pass.set_scissor_rect(x, y, width, height);
+            self.background.render(pass);
         } else if self.output_size.0 > 0 && self.output_size.1 > 0 {
             pass.set_scissor_rect(0, 0, self.output_size.0, self.output_size.1);
         }
@@ -0,0 +1,337 @@
+use cap_project::{CaptionPosition, CursorEvents, Keystroke};
+use glyphon::cosmic_text::LayoutRunIter;
+use glyphon::{
+    Attrs, Buffer, Cache, Color, Family, FontSystem, Metrics, Resolution, Shaping, SwashCache,
+    TextArea, TextAtlas, TextBounds, TextRenderer, Viewport, Weight,
+};
+use log::warn;
+use wgpu::{Device, Queue};
+
+use super::text_background::{TextBackground, TextBackgroundUniforms};
+use crate::{DecodedSegmentFrames, ProjectUniforms, RenderVideoConstants};
+
+const BACKGROUND_ALPHA: f32 = 0.8;
+
+/// Draws the most recent keystroke as a badge, using the same background shader as captions.
+pub struct KeystrokesLayer {
+    font_system: FontSystem,
+    swash_cache: SwashCache,
+    text_atlas: TextAtlas,
+    text_renderer: TextRenderer,
+    text_buffer: Buffer,
+    viewport: Viewport,
+    background: TextBackground,
+    background_scissor: Option<[u32; 4]>,
+    keystrokes: Option<KeystrokesCache>,
+    output_size: (u32, u32),
+    has_keystroke: bool,
+}
+
+impl KeystrokesLayer {
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let font_system = FontSystem::new();
+        let swash_cache = SwashCache::new();
+        let cache = Cache::new(device);
+        let viewport = Viewport::new(device, &cache);
+        let mut text_atlas = TextAtlas::new(device, queue, &cache, wgpu::TextureFormat::Rgba8Unorm);
+        let text_renderer = TextRenderer::new(
+            &mut text_atlas,
+            device,
+            wgpu::MultisampleState::default(),
+            None,
+        );
+
+        let text_buffer = Buffer::new_empty(Metrics::new(32.0, 32.0 * 1.2));
+
+        Self {
+            font_system,
+            swash_cache,
+            text_atlas,
+            text_renderer,
+            text_buffer,
+            viewport,
+            background: TextBackground::new(device, "Keystroke"),
+            background_scissor: None,
+            keystrokes: None,
+            output_size: (0, 0),
+            has_keystroke: false,
+        }
+    }
+
+    pub fn prepare(
+        &mut self,
+        uniforms: &ProjectUniforms,
+        segment_frames: &DecodedSegmentFrames,
+        cursor: &CursorEvents,
+        constants: &RenderVideoConstants,
+    ) {
+        self.has_keystroke = false;
+        self.background_scissor = None;
+
+        let (width, height) = uniforms.output_size;
+        self.output_size = (width, height);
+
+        let settings = &uniforms.project.hotkeys;
+        if !settings.show || cursor.keys.is_empty() || width == 0 || height == 0 {
+            return;
+        }
+
+        let current_time_ms = segment_frames.recording_time as f64 * 1000.0;
+        let keystrokes =
+            KeystrokesCache::get(&mut self.keystrokes, cursor, settings.combine_chords);
+        let Some((keystroke, opacity)) = visible_keystroke(
+            keystrokes,
+            current_time_ms,
+            settings.display_duration as f64 * 1000.0,
+            settings.fade_duration as f64 * 1000.0,
+        ) else {
+            return;
+        };
+
+        let label = keystroke.label(constants.recording_meta.platform.as_ref());
+        let device = &constants.device;
+        let queue = &constants.queue;
+
+        let font_size = settings.size as f32 * (height as f32 / 1080.0);
+        let margin = height as f32 * 0.05;
+        let padding = font_size * 0.5;
+
+        let mut buffer = Buffer::new(
+            &mut self.font_system,
+            Metrics::new(font_size, font_size * 1.2),
+        );
+        buffer.set_size(
+            &mut self.font_system,
+            Some((width as f32 - margin * 2.0 - padding * 2.0).max(font_size)),
+            None,
+        );
+        let color = Color::rgba(255, 255, 255, (opacity * 255.0) as u8);
+        let attrs = Attrs::new()
+            .family(Family::SansSerif)
+            .weight(Weight::SEMIBOLD)
+            .color(color);
+        buffer.set_text(&mut self.font_system, &label, &attrs, Shaping::Advanced);
+        buffer.shape_until_scroll(&mut self.font_system, false);
+
+        let mut text_width: f32 = 0.0;
+        let mut text_height: f32 = 0.0;
+        for run in LayoutRunIter::new(&buffer) {
+            text_width = text_width.max(run.line_w);
+            text_height = text_height.max(run.line_top + run.line_height);
+        }
+        if text_height == 0.0 {
+            return;
+        }
+
+        let box_width = (text_width + padding * 2.0).min(width as f32);
+        let box_height = (text_height + padding * 2.0).min(height as f32);
+
+        let background_left = match settings.position {
+            CaptionPosition::TopLeft | CaptionPosition::BottomLeft => margin,
+            CaptionPosition::TopRight | CaptionPosition::BottomRight => {
+                width as f32 - margin - box_width
+            }
+            CaptionPosition::TopCenter | CaptionPosition::BottomCenter => {
+                (width as f32 - box_width) / 2.0
+            }
+        }
+        .max(0.0);
+        let background_top = match settings.position {
+            CaptionPosition::TopLeft | CaptionPosition::TopCenter | CaptionPosition::TopRight => {
+                margin
+            }
+            _ => height as f32 - margin - box_height,
+        }
+        .max(0.0);
+
+        let text_left = background_left + padding;
+        let text_top = background_top + padding;
+
+        self.text_buffer = buffer;
+        self.viewport.update(queue, Resolution { width, height });
+
+        let text_area = TextArea {
+            buffer: &self.text_buffer,
+            left: text_left,
+            top: text_top,
+            scale: 1.0,
+            bounds: TextBounds {
+                left: (text_left - 2.0).floor() as i32,
+                top: (text_top - 2.0).floor() as i32,
+                right: (text_left + text_width + 2.0).ceil() as i32,
+                bottom: (text_top + text_height + 2.0).ceil() as i32,
+            },
+            default_color: color,
+            custom_glyphs: &[],
+        };
+
+        if let Err(e) = self.text_renderer.prepare(
+            device,
+            queue,
+            &mut self.font_system,
+            &mut self.text_atlas,
+            &self.viewport,
+            [text_area],
+            &mut self.swash_cache,
+        ) {
+            warn!("Error preparing keystroke text: {e:?}");
+            return;
+        }
+
+        let corner_radius = font_size * 0.4;
+        self.background.update(
+            queue,
+            TextBackgroundUniforms {
+                rect: [background_left, background_top, box_width, box_height],
+                color: [0.0, 0.0, 0.0, BACKGROUND_ALPHA * opacity],
+                radius: corner_radius.min(box_width / 2.0).min(box_height / 2.0),
+                ..Default::default()
+            },
+        );
+
+        let scissor_padding = 4.0;
+        let scissor_x = (background_left - scissor_padding).max(0.0).floor() as u32;
+        let scissor_y = (background_top - scissor_padding).max(0.0).floor() as u32;
+        let scissor_width = ((box_width + scissor_padding * 2.0).ceil() as u32)
+            .min(width.saturating_sub(scissor_x));
+        let scissor_height = ((box_height + scissor_padding * 2.0).ceil() as u32)
+            .min(height.saturating_sub(scissor_y));
+
+        if scissor_width == 0 || scissor_height == 0 {
+            return;
+        }
+
+        self.background_scissor = Some([scissor_x, scissor_y, scissor_width, scissor_height]);
+        self.has_keystroke = true;
+    }
+
+    pub fn has_content(&self) -> bool {
+        self.has_keystroke
+    }
+
+    pub fn render<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+        let Some([x, y, width, height]) = self.background_scissor.filter(|_| self.has_keystroke)
+        else {
+            return;
+        };
+
+        pass.set_scissor_rect(x, y, width, height);
+        self.background.render(pass);
+
+        if let Err(e) = self
+            .text_renderer
+            .render(&self.text_atlas, &self.viewport, pass)
+        {
+            warn!("Error rendering keystroke text: {e:?}");
+        }
+
+        pass.set_scissor_rect(0, 0, self.output_size.0, self.output_size.1);
+    }
+}
+
+/// A cursor's keystrokes, which are only rebuilt when the cursor or the chord setting changes.
+/// Cursors are told apart by their address and number of key events.
+struct KeystrokesCache {
+    cursor: usize,
+    key_count: usize,
+    combine_chords: bool,
+    keystrokes: Vec<Keystroke>,
+}
+
+impl KeystrokesCache {
+    fn get<'a>(
+        cache: &'a mut Option<Self>,
+        cursor: &CursorEvents,
+        combine_chords: bool,
+    ) -> &'a [Keystroke] {
+        let address = std::ptr::from_ref(cursor) as usize;
+
+        let cache = match cache.take() {
+            Some(cached)
+                if cached.cursor == address
+                    && cached.key_count == cursor.keys.len()
+                    && cached.combine_chords == combine_chords =>
+            {
+                cache.insert(cached)
+            }
+            _ => cache.insert(Self {
+                cursor: address,
+                key_count: cursor.keys.len(),
+                combine_chords,
+                keystrokes: cursor.keystrokes(combine_chords),
+            }),
+        };
+
+        &cache.keystrokes
+    }
+}
+
+/// The latest keystroke pressed by `current_time_ms` that's still on screen, and its opacity.
+/// A keystroke stays visible for `display_duration_ms` after its keys are released, or until
+/// the next one is pressed.
+fn visible_keystroke(
+    keystrokes: &[Keystroke],
+    current_time_ms: f64,
+    display_duration_ms: f64,
+    fade_duration_ms: f64,
+) -> Option<(&Keystroke, f32)> {
+    let keystroke = keystrokes
+        .iter()
+        .rev()
+        .find(|keystroke| keystroke.start_ms <= current_time_ms)?;
+
+    let hide_ms = keystroke.end_ms + display_duration_ms;
+    if current_time_ms >= hide_ms {
+        return None;
+    }
+
+    if fade_duration_ms <= 0.0 {
+        return Some((keystroke, 1.0));
+    }
+
+    let fade_in = (current_time_ms - keystroke.start_ms) / fade_duration_ms;
+    let fade_out = (hide_ms - current_time_ms) / fade_duration_ms;
+
+    Some((keystroke, fade_in.min(fade_out).clamp(0.0, 1.0) as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keystroke(start_ms: f64, end_ms: f64) -> Keystroke {
+        Keystroke {
+            keys: vec!["Enter".to_string()],
+            start_ms,
+            end_ms,
+        }
+    }
+
+    #[test]
+    fn keystroke_fades_out_after_display_duration() {
+        let keystrokes = [keystroke(1000.0, 1100.0)];
+
+        assert!(visible_keystroke(&keystrokes, 900.0, 1000.0, 100.0).is_none());
+        assert_eq!(
+            visible_keystroke(&keystrokes, 1050.0, 1000.0, 100.0).map(|(_, o)| o),
+            Some(0.5)
+        );
+        assert_eq!(
+            visible_keystroke(&keystrokes, 1500.0, 1000.0, 100.0).map(|(_, o)| o),
+            Some(1.0)
+        );
+        assert_eq!(
+            visible_keystroke(&keystrokes, 2050.0, 1000.0, 100.0).map(|(_, o)| o),
+            Some(0.5)
+        );
+        assert!(visible_keystroke(&keystrokes, 2100.0, 1000.0, 100.0).is_none());
+    }
+
+    #[test]
+    fn next_keystroke_replaces_previous() {
+        let keystrokes = [keystroke(0.0, 100.0), keystroke(500.0, 600.0)];
+
+        let (visible, _) = visible_keystroke(&keystrokes, 550.0, 1000.0, 0.0).unwrap();
+        assert_eq!(visible.start_ms, 500.0);
+    }
+}
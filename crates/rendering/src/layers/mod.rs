@@ -4,8 +4,10 @@ mod camera;
 mod captions;
 mod cursor;
 mod display;
+mod keystrokes;
 mod mask;
 mod text;
+mod text_background;
 
 pub use background::*;
 pub use blur::*;
@@ -13,5 +15,6 @@ pub use camera::*;
 pub use captions::*;
 pub use cursor::*;
 pub use display::*;
+pub use keystrokes::*;
 pub use mask::*;
 pub use text::*;
@@ -12,7 +12,7 @@ use futures::FutureExt;
 use futures::future::OptionFuture;
 use layers::{
     Background, BackgroundLayer, BlurLayer, CameraLayer, CaptionsLayer, CursorLayer, DisplayLayer,
-    MaskLayer, TextLayer,
+    KeystrokesLayer, MaskLayer, TextLayer,
 };
 use specta::Type;
 use spring_mass_damper::SpringMassDamperSimulationConfig;
@@ -263,6 +263,36 @@ pub async fn render_video_to_channel(
     fps: u32,
     resolution_base: XY<u32>,
     recordings: &ProjectRecordingsMeta,
+) -> Result<(), RenderingError> {
+    render_video_range_to_channel(
+        constants,
+        project,
+        sender,
+        recording_meta,
+        meta,
+        render_segments,
+        fps,
+        resolution_base,
+        recordings,
+        0..u32::MAX,
+    )
+    .await
+}
+
+/// Renders only the timeline frames within `frame_range`, clamped to the timeline's duration.
+/// Frames are sent with their timeline frame number, not their index within the range.
+#[allow(clippy::too_many_arguments)]
+pub async fn render_video_range_to_channel(
+    constants: &RenderVideoConstants,
+    project: &ProjectConfiguration,
+    sender: mpsc::Sender<(RenderedFrame, u32)>,
+    recording_meta: &RecordingMeta,
+    meta: &StudioRecordingMeta,
+    render_segments: Vec<RenderSegment>,
+    fps: u32,
+    resolution_base: XY<u32>,
+    recordings: &ProjectRecordingsMeta,
+    frame_range: std::ops::Range<u32>,
 ) -> Result<(), RenderingError> {
     ffmpeg::init().unwrap();
 
@@ -270,7 +300,7 @@ pub async fn render_video_to_channel(
 
     let duration = get_duration(recordings, recording_meta, meta, project);
 
-    let total_frames = (fps as f64 * duration).ceil() as u32;
+    let total_frames = ((fps as f64 * duration).ceil() as u32).min(frame_range.end);
 
     let cursor_smoothing =
         (!project.cursor.raw).then_some(spring_mass_damper::SpringMassDamperSimulationConfig {
@@ -293,7 +323,7 @@ pub async fn render_video_to_channel(
         })
         .collect();
 
-    let mut frame_number = 0;
+    let mut frame_number = frame_range.start;
 
     let mut frame_renderer = FrameRenderer::new(constants);
 
@@ -466,8 +496,9 @@ pub async fn render_video_to_channel(
     }
 
     let total_time = start_time.elapsed();
-    println!(
-        "Render complete. Processed {frame_number} frames in {:?} seconds",
+    tracing::info!(
+        "Render complete. Processed {} frames in {:?} seconds",
+        frame_number.saturating_sub(frame_range.start),
         total_time.as_secs_f32()
     );
 
@@ -1776,6 +1807,7 @@ pub struct RendererLayers {
     mask: MaskLayer,
     text: TextLayer,
     captions: CaptionsLayer,
+    keystrokes: KeystrokesLayer,
 }
 
 impl RendererLayers {
@@ -1798,6 +1830,7 @@ impl RendererLayers {
             mask: MaskLayer::new(device),
             text: TextLayer::new(device, queue),
             captions: CaptionsLayer::new(device, queue),
+            keystrokes: KeystrokesLayer::new(device, queue),
         }
     }
 
@@ -1875,6 +1908,9 @@ impl RendererLayers {
             constants,
         );
 
+        self.keystrokes
+            .prepare(uniforms, segment_frames, cursor, constants);
+
         Ok(())
     }
 
@@ -1966,6 +2002,11 @@ impl RendererLayers {
             let mut pass = render_pass!(session.current_texture_view(), wgpu::LoadOp::Load);
             self.captions.render(&mut pass);
         }
+
+        if self.keystrokes.has_content() {
+            let mut pass = render_pass!(session.current_texture_view(), wgpu::LoadOp::Load);
+            self.keystrokes.render(&mut pass);
+        }
     }
 }
 
@@ -2118,6 +2159,7 @@ mod project_uniforms_tests {
         let events = CursorEvents {
             clicks: vec![],
             moves: vec![],
+            keys: vec![],
         };
 
         let focus = ProjectUniforms::auto_zoom_focus(&events, 0.3, None, None);
@@ -2135,6 +2177,7 @@ mod project_uniforms_tests {
                 cursor_move(200.0, 0.55, 0.5),
                 cursor_move(400.0, 0.6, 0.5),
             ],
+            keys: vec![],
         };
 
         let smoothing = Some(default_smoothing());
@@ -2155,6 +2198,7 @@ mod project_uniforms_tests {
         let events = CursorEvents {
             clicks: vec![],
             moves: vec![cursor_move(0.0, 0.1, 0.5), cursor_move(40.0, 0.9, 0.5)],
+            keys: vec![],
         };
 
         let smoothing = Some(default_smoothing());
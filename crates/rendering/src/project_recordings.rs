@@ -3,7 +3,9 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use cap_project::{AudioMeta, StudioRecordingMeta, VideoMeta};
+use cap_project::{
+    AudioMeta, StudioRecordingMeta, TimelineConfiguration, TimelineSegment, VideoMeta,
+};
 use serde::Serialize;
 use specta::Type;
 
@@ -217,6 +219,44 @@ impl ProjectRecordingsMeta {
         self.segments.iter().map(|s| s.duration()).sum()
     }
 
+    /// The time each segment starts at when the segments are played back to back.
+    pub fn segment_offsets(&self) -> Vec<f64> {
+        self.segments
+            .iter()
+            .scan(0.0, |offset, segment| {
+                let start = *offset;
+                *offset += segment.duration();
+                Some(start)
+            })
+            .collect()
+    }
+
+    /// A timeline that plays every segment in full, for projects that haven't been edited yet.
+    pub fn timeline_segments(&self) -> Vec<TimelineSegment> {
+        self.segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| TimelineSegment {
+                recording_clip: i as u32,
+                start: 0.0,
+                end: segment.duration(),
+                timescale: 1.0,
+            })
+            .collect()
+    }
+
+    /// The timeline of a project that hasn't been edited yet.
+    pub fn default_timeline(&self) -> TimelineConfiguration {
+        TimelineConfiguration {
+            segments: self.timeline_segments(),
+            zoom_segments: vec![],
+            scene_segments: vec![],
+            mask_segments: vec![],
+            text_segments: vec![],
+            markers: vec![],
+        }
+    }
+
     pub fn get_source_duration(&self, path: &PathBuf) -> Result<f64, String> {
         Video::new(path, 0.0).map(|v| v.duration)
     }
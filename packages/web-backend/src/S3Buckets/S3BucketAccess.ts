@@ -294,6 +294,25 @@ export const createS3BucketAccess = Effect.gen(function* () {
 						),
 					),
 				),
+			listParts: (
+				key: string,
+				uploadId: string,
+				args?: Omit<S3.ListPartsCommandInput, "Key" | "Bucket" | "UploadId">,
+			) =>
+				wrapS3Promise(
+					provider.getInternal.pipe(
+						Effect.map((client) =>
+							client.send(
+								new S3.ListPartsCommand({
+									...args,
+									Bucket: provider.bucket,
+									Key: key,
+									UploadId: uploadId,
+								}),
+							),
+						),
+					),
+				),
 			complete: (
 				key: string,
 				uploadId: string,